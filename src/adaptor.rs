@@ -0,0 +1,282 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Schnorr adaptor signatures in the Decaf group.
+//!
+//! A `Signature` `(R, s)` on `m` under `X = G*x` satisfies
+//! `G*s = R + X*c`, with `c = H(X, R, m)`.  A `PreSignature` is such a
+//! signature encrypted to an adaptor point `T = G*t`:
+//!
+//! 1. the holder of `t` publishes an `AdaptorPoint`, `T` with a proof
+//!    of knowledge of `t` in a transcript labelled `"zkp adaptor"`
+//!    after the caller's context, for the statement
+//!    `T = (G * t)`;
+//! 2. the signer checks the proof, picks a nonce `k`, and sends the
+//!    pre-signature `(R, s') = (G*k + T, k + c*x)`, with the
+//!    challenge `c` over the *final* nonce `R`;
+//! 3. anyone can check that `G*s' = R - T + X*c`, i.e. that adding `t`
+//!    to `s'` yields a valid signature, without learning it;
+//! 4. the holder of `t` completes the signature `(R, s' + t)`, and
+//!    whoever has the pre-signature extracts `t = s - s'` from the
+//!    published signature.
+//!
+//! Completing a signature thus reveals the adaptor secret, and
+//! learning the secret lets one complete every pre-signature on it,
+//! which is what atomic swaps and payment channels are built on: two
+//! pre-signatures under the same `T` are completed together or not at
+//! all.
+//!
+//! The proof of knowledge of `t` stops a party from choosing `T` as a
+//! function of the signer's key, e.g. `T = T' - X*c`, which would let
+//! it complete the pre-signature without knowing `t`.  The consistency
+//! of a pre-signature with `T` needs no further proof: step 3 is a
+//! linear check, like verifying the signature itself.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use transcript::Transcript;
+
+create_nipk!{adaptor, (t), (T, G) : T = (G * t) }
+
+/// The signer's key `(x, X = G*x)`.
+pub struct SigningKey {
+    secret: Scalar,
+    public: DecafPoint,
+}
+
+/// A Schnorr signature `(R, s)`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Signature {
+    pub R: DecafPoint,
+    pub s: Scalar,
+}
+
+/// A signature `(R, s')` encrypted to an adaptor point.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PreSignature {
+    pub R: DecafPoint,
+    pub s: Scalar,
+}
+
+/// The adaptor secret `t`.
+pub struct AdaptorSecret {
+    secret: Scalar,
+    point: DecafPoint,
+}
+
+/// The adaptor point `T = G*t`, with a proof of knowledge of `t`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AdaptorPoint {
+    pub point: DecafPoint,
+    pub proof: adaptor::Proof,
+}
+
+fn challenge(public: &DecafPoint, nonce: &DecafPoint, message: &[u8]) -> Scalar {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp adaptor signature");
+    hash.input(public.compress().as_bytes());
+    hash.input(nonce.compress().as_bytes());
+    hash.input(message);
+    Scalar::from_hash(hash)
+}
+
+fn adaptor_transcript(context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"zkp adaptor");
+    transcript.append_message(b"context", context);
+    transcript
+}
+
+impl SigningKey {
+    /// Generate a fresh signing key.
+    pub fn generate<R: Rng>(csprng: &mut R) -> SigningKey {
+        SigningKey::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct a signing key from an existing secret scalar.
+    pub fn from_secret(secret: Scalar) -> SigningKey {
+        let public = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        SigningKey{ secret: secret, public: public }
+    }
+
+    /// The public verification key `X = G*x`.
+    pub fn verification_key(&self) -> DecafPoint {
+        self.public
+    }
+
+    /// Sign `message`.
+    pub fn sign<R: Rng>(&self, csprng: &mut R, message: &[u8]) -> Signature {
+        let k = Scalar::random(csprng);
+        let R = &dalek_constants::DECAF_ED25519_BASEPOINT * &k;
+        let c = challenge(&self.public, &R, message);
+        Signature{ R: R, s: Scalar::multiply_add(&c, &self.secret, &k) }
+    }
+
+    /// Pre-sign `message`, encrypting the signature to `adaptor`.
+    ///
+    /// Returns `Err(())` if the adaptor point's proof does not verify
+    /// with `context`.
+    pub fn pre_sign<R: Rng>(
+        &self,
+        csprng: &mut R,
+        message: &[u8],
+        adaptor: &AdaptorPoint,
+        context: &[u8],
+    ) -> Result<PreSignature,()> {
+        adaptor.verify(context)?;
+        let k = Scalar::random(csprng);
+        let R = &(&dalek_constants::DECAF_ED25519_BASEPOINT * &k) + &adaptor.point;
+        let c = challenge(&self.public, &R, message);
+        Ok(PreSignature{ R: R, s: Scalar::multiply_add(&c, &self.secret, &k) })
+    }
+}
+
+impl Signature {
+    /// Verify the signature on `message` under `public`.
+    pub fn verify(&self, public: &DecafPoint, message: &[u8]) -> Result<(),()> {
+        let c = challenge(public, &self.R, message);
+        let expected = &self.R + &(public * &c);
+        if (&dalek_constants::DECAF_ED25519_BASEPOINT * &self.s).compress() == expected.compress() {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl PreSignature {
+    /// Check that the pre-signature on `message` under `public`
+    /// completes to a signature with the secret of `adaptor`.
+    pub fn verify(&self, public: &DecafPoint, message: &[u8], adaptor: &DecafPoint) -> Result<(),()> {
+        let c = challenge(public, &self.R, message);
+        let expected = &(&self.R - adaptor) + &(public * &c);
+        if (&dalek_constants::DECAF_ED25519_BASEPOINT * &self.s).compress() == expected.compress() {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Complete the pre-signature with the adaptor secret.
+    pub fn adapt(&self, secret: &AdaptorSecret) -> Signature {
+        Signature{ R: self.R, s: &self.s + &secret.secret }
+    }
+
+    /// Extract the secret of `adaptor` from the completed `signature`.
+    ///
+    /// Returns `Err(())` if `signature` is not the completion of this
+    /// pre-signature with the secret of `adaptor`.
+    pub fn extract(&self, signature: &Signature, adaptor: &DecafPoint) -> Result<AdaptorSecret,()> {
+        if signature.R.compress() != self.R.compress() {
+            return Err(());
+        }
+        let secret = AdaptorSecret::from_secret(&signature.s - &self.s);
+        if secret.point.compress() != adaptor.compress() {
+            return Err(());
+        }
+        Ok(secret)
+    }
+}
+
+impl AdaptorSecret {
+    /// Generate a fresh adaptor secret.
+    pub fn generate<R: Rng>(csprng: &mut R) -> AdaptorSecret {
+        AdaptorSecret::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct the adaptor secret for an existing scalar.
+    pub fn from_secret(secret: Scalar) -> AdaptorSecret {
+        let point = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        AdaptorSecret{ secret: secret, point: point }
+    }
+
+    pub fn secret(&self) -> &Scalar {
+        &self.secret
+    }
+
+    /// Publish the adaptor point, with a proof of knowledge of the
+    /// secret bound to `context`.
+    pub fn publish<R: Rng>(&self, csprng: &mut R, context: &[u8]) -> AdaptorPoint {
+        let proof = adaptor::Proof::create_from_transcript(
+            csprng,
+            &mut adaptor_transcript(context),
+            adaptor::Publics{ T: &self.point, G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+            adaptor::Secrets{ t: &self.secret },
+        );
+        AdaptorPoint{ point: self.point, proof: proof }
+    }
+}
+
+impl AdaptorPoint {
+    /// Verify the proof of knowledge of the adaptor secret.
+    pub fn verify(&self, context: &[u8]) -> Result<(),()> {
+        self.proof.verify_from_transcript(
+            &mut adaptor_transcript(context),
+            adaptor::Publics{ T: &self.point, G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn atomic_swap() {
+        let mut csprng = OsRng::new().unwrap();
+        let alice = SigningKey::generate(&mut csprng);
+        let bob = SigningKey::generate(&mut csprng);
+        let secret = AdaptorSecret::generate(&mut csprng);
+        let adaptor = secret.publish(&mut csprng, b"swap 1");
+
+        // Each pays the other, pre-signed under Alice's adaptor point.
+        let to_alice = bob.pre_sign(&mut csprng, b"bob pays alice", &adaptor, b"swap 1").unwrap();
+        let to_bob = alice.pre_sign(&mut csprng, b"alice pays bob", &adaptor, b"swap 1").unwrap();
+        assert!(to_alice.verify(&bob.verification_key(), b"bob pays alice", &adaptor.point).is_ok());
+        assert!(to_bob.verify(&alice.verification_key(), b"alice pays bob", &adaptor.point).is_ok());
+        assert!(to_bob.verify(&alice.verification_key(), b"alice pays carol", &adaptor.point).is_err());
+
+        // A pre-signature is not a signature.
+        let unadapted = Signature{ R: to_alice.R, s: to_alice.s };
+        assert!(unadapted.verify(&bob.verification_key(), b"bob pays alice").is_err());
+
+        // Alice claims her payment, revealing the secret to Bob.
+        let claimed = to_alice.adapt(&secret);
+        assert!(claimed.verify(&bob.verification_key(), b"bob pays alice").is_ok());
+        let extracted = to_alice.extract(&claimed, &adaptor.point).unwrap();
+        assert_eq!(extracted.secret(), secret.secret());
+        let bobs = to_bob.adapt(&extracted);
+        assert!(bobs.verify(&alice.verification_key(), b"alice pays bob").is_ok());
+
+        // Extraction needs the completion of this pre-signature.
+        let other = bob.sign(&mut csprng, b"bob pays alice");
+        assert!(other.verify(&bob.verification_key(), b"bob pays alice").is_ok());
+        assert!(to_alice.extract(&other, &adaptor.point).is_err());
+    }
+
+    #[test]
+    fn adaptor_point_proof() {
+        let mut csprng = OsRng::new().unwrap();
+        let signer = SigningKey::generate(&mut csprng);
+        let secret = AdaptorSecret::generate(&mut csprng);
+        let adaptor = secret.publish(&mut csprng, b"channel 1");
+        assert!(adaptor.verify(b"channel 1").is_ok());
+        assert!(signer.pre_sign(&mut csprng, b"msg", &adaptor, b"channel 2").is_err());
+
+        // A point chosen without knowing its discrete log is refused.
+        let forged = AdaptorPoint{ point: DecafPoint::random(&mut csprng), proof: adaptor.proof };
+        assert!(signer.pre_sign(&mut csprng, b"msg", &forged, b"channel 1").is_err());
+    }
+}
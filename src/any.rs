@@ -0,0 +1,231 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs of different statements behind one trait object.
+//!
+//! Every `Proof` generated by `create_nipk!` implements `AnyProof`,
+//! which only uses byte strings, so proofs of different statements
+//! can be kept together as `Box<dyn AnyProof>`.  The publics are
+//! passed as in `Publics::to_bytes`, the concatenation of their
+//! compressed points.
+//!
+//! `AnyProof::to_tagged_bytes` writes the proof after its statement
+//! name:
+//!
+//! ```text
+//! len(name) || name || Proof::to_bytes()
+//! ```
+//!
+//! with the length as a single byte, so names and the identifiers of
+//! a `Registry` are at most `MAX_ID_LEN` bytes.  A `Registry` of the statements
+//! an application knows parses these back, dispatching on the name:
+//!
+//! ```rust,ignore
+//! let mut registry = Registry::new();
+//! registry.register::<dlog::Proof>()?;
+//! registry.register::<dleq::Proof>()?;
+//!
+//! let log: Vec<Box<dyn AnyProof>> = entries.iter()
+//!     .map(|bytes| registry.from_tagged_bytes(bytes))
+//!     .collect::<Result<_,()>>()?;
+//! ```
+//!
+//! Each generated module can also add itself, with
+//! `dleq::register(&mut registry)`.  Statements are registered under
+//! their name by default; `register_as` chooses another identifier,
+//! which stays the same when a module is renamed or when two modules
+//! have the same name.  A service receiving proofs of many statements
+//! on one endpoint dispatches on the identifier sent with them:
+//!
+//! ```rust,ignore
+//! registry.register_as::<v1::dleq::Proof>("dleq-v1")?;
+//! registry.verify_any(&request.id, &request.publics, &request.proof)?;
+//! ```
+//!
+//! Tagged proofs are tagged with the statement name, so they are only
+//! parsed by a registry with the statement under its name.
+
+use {NizkProof, ProofBytes, Version};
+
+/// The longest statement name or identifier, in bytes.
+pub const MAX_ID_LEN: usize = 0xff;
+
+/// A proof of some statement, with byte-string publics.
+pub trait AnyProof {
+    /// The name of the module generated by `create_nipk!`.
+    fn statement(&self) -> &'static str;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Verify the proof against publics encoded as in
+    /// `Publics::to_bytes`.
+    ///
+    /// Returns `Err(())` if the publics cannot be decoded.
+    fn verify_bytes(&self, publics: &[u8]) -> Result<(),()>;
+
+    /// Verify a proof made by `Proof::create_with_context` with
+    /// `context`, whose challenge was computed with `version`, as
+    /// `verify_bytes` does.
+    fn verify_bytes_with_context(&self, version: Version, context: &[u8], publics: &[u8]) -> Result<(),()>;
+
+    /// The proof, prefixed by its statement name.
+    ///
+    /// Returns `Err(())` if the name is longer than `MAX_ID_LEN`.
+    fn to_tagged_bytes(&self) -> Result<Vec<u8>,()> {
+        let name = self.statement().as_bytes();
+        if name.len() > MAX_ID_LEN {
+            return Err(());
+        }
+        let mut bytes = vec![name.len() as u8];
+        bytes.extend_from_slice(name);
+        bytes.extend_from_slice(&self.to_bytes());
+        Ok(bytes)
+    }
+}
+
+fn parse<P: AnyProof + ProofBytes + 'static>(bytes: &[u8]) -> Result<Box<dyn AnyProof>,()> {
+    Ok(Box::new(P::from_bytes(bytes)?))
+}
+
+/// The statements whose tagged proofs can be parsed.
+pub struct Registry {
+    parsers: Vec<(&'static str, fn(&[u8]) -> Result<Box<dyn AnyProof>,()>)>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry{ parsers: Vec::new() }
+    }
+
+    /// Add the statement of the proof type `P`, under its name.
+    ///
+    /// Returns `Err(())` if a statement with the same name is already
+    /// registered, or the name is longer than `MAX_ID_LEN`.
+    pub fn register<P: NizkProof + AnyProof + 'static>(&mut self) -> Result<(),()> {
+        self.register_as::<P>(P::NAME)
+    }
+
+    /// Add the statement of the proof type `P`, under the identifier
+    /// `id`.
+    ///
+    /// Returns `Err(())` if a statement is already registered under
+    /// `id`, or `id` is longer than `MAX_ID_LEN`.
+    pub fn register_as<P: AnyProof + ProofBytes + 'static>(&mut self, id: &'static str) -> Result<(),()> {
+        if id.len() > MAX_ID_LEN || self.contains(id) {
+            return Err(());
+        }
+        self.parsers.push((id, parse::<P>));
+        Ok(())
+    }
+
+    /// Whether a statement is registered under `id`.
+    pub fn contains(&self, id: &str) -> bool {
+        self.parsers.iter().any(|&(name, _)| name == id)
+    }
+
+    /// Parse a proof of the statement registered under `statement`.
+    pub fn from_bytes(&self, statement: &str, bytes: &[u8]) -> Result<Box<dyn AnyProof>,()> {
+        let &(_, parse) = self.parsers.iter().find(|&&(name, _)| name == statement).ok_or(())?;
+        parse(bytes)
+    }
+
+    /// Verify a proof, encoded as in `Proof::to_bytes`, of the
+    /// statement registered under `id`, against publics encoded as in
+    /// `Publics::to_bytes`.
+    ///
+    /// Returns `Err(())` if no statement is registered under `id`,
+    /// either encoding is invalid, or the proof does not verify.
+    pub fn verify_any(&self, id: &str, publics: &[u8], proof: &[u8]) -> Result<(),()> {
+        self.from_bytes(id, proof)?.verify_bytes(publics)
+    }
+
+    /// Parse a proof written by `AnyProof::to_tagged_bytes`.
+    pub fn from_tagged_bytes(&self, bytes: &[u8]) -> Result<Box<dyn AnyProof>,()> {
+        let (&length, rest) = bytes.split_first().ok_or(())?;
+        if rest.len() < length as usize {
+            return Err(());
+        }
+        let (name, proof) = rest.split_at(length as usize);
+        let name = ::std::str::from_utf8(name).map_err(|_| ())?;
+        self.from_bytes(name, proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+    create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+    #[test]
+    fn registry() {
+        let mut csprng = OsRng::new().unwrap();
+        let mut registry = Registry::new();
+        registry.register::<dlog::Proof>().unwrap();
+        registry.register::<dleq::Proof>().unwrap();
+        assert!(registry.register::<dlog::Proof>().is_err());
+
+        let (dlog_publics, dlog_secrets) = dlog::random_instance(&mut csprng);
+        let (dleq_publics, dleq_secrets) = dleq::random_instance(&mut csprng);
+        let proofs: Vec<Box<dyn AnyProof>> = vec![
+            Box::new(dlog::Proof::create(&mut csprng, dlog_publics.as_publics(), dlog_secrets.as_secrets())),
+            Box::new(dleq::Proof::create(&mut csprng, dleq_publics.as_publics(), dleq_secrets.as_secrets())),
+        ];
+        let publics = vec![dlog_publics.as_publics().to_bytes(), dleq_publics.as_publics().to_bytes()];
+
+        let log: Vec<Vec<u8>> = proofs.iter().map(|proof| proof.to_tagged_bytes().unwrap()).collect();
+        assert_eq!(&log[0][..5], b"\x04dlog");
+        let parsed: Vec<Box<dyn AnyProof>> = log.iter()
+            .map(|bytes| registry.from_tagged_bytes(bytes))
+            .collect::<Result<_,()>>()
+            .unwrap();
+        assert_eq!(parsed[1].statement(), "dleq");
+        for (proof, publics) in parsed.iter().zip(publics.iter()) {
+            assert!(proof.verify_bytes(publics).is_ok());
+        }
+        assert!(parsed[0].verify_bytes(&publics[1]).is_err());
+        assert!(parsed[1].verify_bytes(&publics[0]).is_err());
+
+        assert!(registry.from_tagged_bytes(&log[0][..10]).is_err());
+        assert!(registry.from_tagged_bytes(&[]).is_err());
+        assert!(Registry::new().from_tagged_bytes(&log[0]).is_err());
+    }
+
+    #[test]
+    fn verify_any() {
+        let mut csprng = OsRng::new().unwrap();
+        let mut registry = Registry::new();
+        dlog::register(&mut registry).unwrap();
+        registry.register_as::<dleq::Proof>("dleq-v1").unwrap();
+        assert!(registry.register_as::<dleq::Proof>("dlog").is_err());
+        assert!(registry.contains("dlog"));
+        assert!(!registry.contains("dleq"));
+        let long = "x".repeat(MAX_ID_LEN + 1);
+        let long: &'static str = Box::leak(long.into_boxed_str());
+        assert!(registry.register_as::<dleq::Proof>(&long[..MAX_ID_LEN]).is_ok());
+        assert!(registry.register_as::<dleq::Proof>(long).is_err());
+
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let proof = dleq::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets()).to_bytes();
+        let publics = publics.as_publics().to_bytes();
+        assert!(registry.verify_any("dleq-v1", &publics, &proof).is_ok());
+        assert!(registry.verify_any("dleq", &publics, &proof).is_err());
+        assert!(registry.verify_any("dlog", &publics, &proof).is_err());
+        assert!(registry.verify_any("dleq-v1", &publics[..64], &proof).is_err());
+        assert!(registry.verify_any("dleq-v1", &publics, &proof[..32]).is_err());
+
+        let (publics, secrets) = dlog::random_instance(&mut csprng);
+        let proof = dlog::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets()).to_bytes();
+        assert!(registry.verify_any("dlog", &publics.as_publics().to_bytes(), &proof).is_ok());
+    }
+}
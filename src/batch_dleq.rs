@@ -0,0 +1,126 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! One proof that a single key `k`, with `K = G*k`, links many pairs
+//! `(P_i, Q_i = P_i*k)`, as in the batched DLEQ proofs of Privacy Pass
+//! and VOPRFs.
+//!
+//! The pairs are combined with weights `c_i` which both sides derive
+//! from a transcript labelled `"zkp batch dleq"`, to which `G`, `K`
+//! and all the pairs are appended, with one `challenge_scalar` per
+//! pair.  The proof is then a `zkp::protocols::dleq` proof, in the
+//! same transcript, of
+//!
+//! ```text
+//! K = G*k
+//! Z = M*k
+//! ```
+//!
+//! where `M = sum_i c_i*P_i` and `Z = sum_i c_i*Q_i`.  Since the weights
+//! are fixed by the pairs, a cheating prover would have to choose pairs
+//! with `Z = M*k` by chance.  The proof has constant size, however many
+//! pairs it covers.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+use protocols::dleq;
+use transcript::Transcript;
+
+/// A proof that one key links all of a batch of pairs.
+pub use protocols::dleq::Proof as BatchDleqProof;
+
+/// Append the batch to a fresh transcript, and compute the combined
+/// points `(M, Z)`.
+fn combine(
+    G: &DecafPoint,
+    K: &DecafPoint,
+    P: &[DecafPoint],
+    Q: &[DecafPoint],
+) -> Result<(Transcript, DecafPoint, DecafPoint), ()> {
+    if P.is_empty() || P.len() != Q.len() {
+        return Err(());
+    }
+    let mut transcript = Transcript::new(b"zkp batch dleq");
+    transcript.append_point(b"G", G);
+    transcript.append_point(b"K", K);
+    for (P_i, Q_i) in P.iter().zip(Q.iter()) {
+        transcript.append_point(b"P", P_i);
+        transcript.append_point(b"Q", Q_i);
+    }
+    let c: Vec<Scalar> = P.iter().map(|_| transcript.challenge_scalar(b"weight")).collect();
+    let mut M = &P[0] * &c[0];
+    let mut Z = &Q[0] * &c[0];
+    for i in 1..P.len() {
+        M = &M + &(&P[i] * &c[i]);
+        Z = &Z + &(&Q[i] * &c[i]);
+    }
+    Ok((transcript, M, Z))
+}
+
+/// Prove that `K = G*k` and `Q[i] = P[i]*k` for every `i`.
+///
+/// Returns `Err(())` if `P` is empty or the slices have different
+/// lengths.  (The proof is only valid if the equations really hold.)
+pub fn prove<R: Rng>(
+    csprng: &mut R,
+    G: &DecafPoint,
+    K: &DecafPoint,
+    k: &Scalar,
+    P: &[DecafPoint],
+    Q: &[DecafPoint],
+) -> Result<BatchDleqProof, ()> {
+    let (mut transcript, M, Z) = combine(G, K, P, Q)?;
+    let publics = dleq::Publics{ A: K, B: &Z, G: G, H: &M };
+    Ok(BatchDleqProof::create_from_transcript(csprng, &mut transcript, publics, dleq::Secrets{ x: k }))
+}
+
+/// Verify a proof that `K = G*k` and `Q[i] = P[i]*k` for every `i`.
+pub fn verify(
+    G: &DecafPoint,
+    K: &DecafPoint,
+    P: &[DecafPoint],
+    Q: &[DecafPoint],
+    proof: &BatchDleqProof,
+) -> Result<(),()> {
+    let (mut transcript, M, Z) = combine(G, K, P, Q)?;
+    proof.verify_from_transcript(&mut transcript, dleq::Publics{ A: K, B: &Z, G: G, H: &M })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use rand::OsRng;
+
+    #[test]
+    fn prove_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let k = Scalar::random(&mut csprng);
+        let K = G * &k;
+
+        let P: Vec<DecafPoint> = (0..5).map(|_| DecafPoint::random(&mut csprng)).collect();
+        let mut Q: Vec<DecafPoint> = P.iter().map(|P_i| P_i * &k).collect();
+
+        let proof = prove(&mut csprng, G, &K, &k, &P, &Q).unwrap();
+        assert!(verify(G, &K, &P, &Q, &proof).is_ok());
+        assert!(verify(G, &K, &P[1..], &Q[1..], &proof).is_err());
+        assert!(verify(G, &K, &P, &Q[1..], &proof).is_err());
+        assert!(prove(&mut csprng, G, &K, &k, &[], &[]).is_err());
+
+        // One pair evaluated with another key.
+        Q[3] = &P[3] * &Scalar::random(&mut csprng);
+        let proof = prove(&mut csprng, G, &K, &k, &P, &Q).unwrap();
+        assert!(verify(G, &K, &P, &Q, &proof).is_err());
+    }
+}
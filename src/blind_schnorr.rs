@@ -0,0 +1,233 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Blind Schnorr signatures in the Decaf group.
+//!
+//! The protocol is the classic three-move blind Schnorr signature:
+//!
+//! 1. the signer picks a nonce `k` and sends its `Commitment` `R = G*k`;
+//! 2. the user blinds the commitment as `R' = R + G*alpha + X*beta`,
+//!    computes `c' = H(X, R', m)`, and sends the `BlindedChallenge`
+//!    `c = c' + beta`;
+//! 3. the signer sends the `Response` `s = k + c*x`, and the user
+//!    unblinds it to `s' = s + alpha`.
+//!
+//! The resulting `Signature` `(c', s')` verifies as an ordinary
+//! Schnorr signature on `m`, but the signer learns nothing about `m`
+//! or the final signature.
+//!
+//! The hash `H` is the challenge of the proof of knowledge
+//!
+//! ```rust,ignore
+//! create_nipk!{schnorr, (x), (X, G) : X = (G * x) }
+//! ```
+//!
+//! made from a transcript labelled `"zkp blind schnorr"` to which the
+//! message is appended, so a signature is such a proof, with the
+//! commitment `R'`, and `Signature::to_proof` converts it.
+//!
+//! Each side of the protocol is an explicit state type whose methods
+//! consume `self`, so a signer nonce cannot be used to answer two
+//! challenges.  Note that blind Schnorr signatures are **not** secure
+//! when a signer runs many sessions concurrently (the ROS attack);
+//! signers should complete each session before opening the next one.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+use transcript::Transcript;
+
+create_nipk!{schnorr, (x), (X, G) : X = (G * x) }
+
+/// The signer's long-term key `(x, X = G*x)`.
+pub struct SigningKey {
+    secret: Scalar,
+    public: DecafPoint,
+}
+
+/// The signer's first message, `R = G*k`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Commitment(pub DecafPoint);
+
+/// The user's blinded challenge `c = c' + beta`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct BlindedChallenge(pub Scalar);
+
+/// The signer's response `s = k + c*x`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Response(pub Scalar);
+
+/// An unblinded Schnorr signature `(c', s')`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Signature {
+    challenge: Scalar,
+    response: Scalar,
+}
+
+/// Signer state after sending a `Commitment`, waiting for a challenge.
+pub struct SignerSession<'a> {
+    key: &'a SigningKey,
+    nonce: Scalar,
+}
+
+/// User state after sending a `BlindedChallenge`, waiting for a
+/// response.
+pub struct UserSession {
+    public: DecafPoint,
+    commitment: DecafPoint,
+    alpha: Scalar,
+    blinded: Scalar,
+    challenge: Scalar,
+}
+
+/// The transcript of a signature on `message`.
+fn message_transcript(message: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"zkp blind schnorr");
+    transcript.append_message(b"message", message);
+    transcript
+}
+
+fn challenge(public: &DecafPoint, commitment: &DecafPoint, message: &[u8]) -> Scalar {
+    schnorr::challenge_from_transcript(
+        &message_transcript(message),
+        schnorr::Publics{ X: public, G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+        &[*commitment],
+    )
+}
+
+impl SigningKey {
+    /// Generate a fresh signing key.
+    pub fn generate<R: Rng>(csprng: &mut R) -> SigningKey {
+        SigningKey::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct a signing key from an existing secret scalar.
+    pub fn from_secret(secret: Scalar) -> SigningKey {
+        let public = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        SigningKey{ secret: secret, public: public }
+    }
+
+    /// The public verification key `X = G*x`.
+    pub fn verification_key(&self) -> DecafPoint {
+        self.public
+    }
+
+    /// Start a signing session, producing the first protocol message.
+    pub fn commit<'a, R: Rng>(&'a self, csprng: &mut R) -> (SignerSession<'a>, Commitment) {
+        let nonce = Scalar::random(csprng);
+        let commitment = &dalek_constants::DECAF_ED25519_BASEPOINT * &nonce;
+        (SignerSession{ key: self, nonce: nonce }, Commitment(commitment))
+    }
+}
+
+impl<'a> SignerSession<'a> {
+    /// Answer the user's blinded challenge, ending the session.
+    pub fn respond(self, challenge: &BlindedChallenge) -> Response {
+        Response(Scalar::multiply_add(&challenge.0, &self.key.secret, &self.nonce))
+    }
+}
+
+impl UserSession {
+    /// Blind the signer's `commitment` for a signature on `message`
+    /// under `public`, producing the challenge to send to the signer.
+    pub fn blind<R: Rng>(
+        csprng: &mut R,
+        public: &DecafPoint,
+        message: &[u8],
+        commitment: &Commitment,
+    ) -> (UserSession, BlindedChallenge) {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let alpha = Scalar::random(csprng);
+        let beta = Scalar::random(csprng);
+
+        let blinded = &(&commitment.0 + &(G * &alpha)) + &(public * &beta);
+        let c_prime = challenge(public, &blinded, message);
+        let c = &c_prime + &beta;
+
+        let session = UserSession{
+            public: *public,
+            commitment: commitment.0,
+            alpha: alpha,
+            blinded: c,
+            challenge: c_prime,
+        };
+        (session, BlindedChallenge(c))
+    }
+
+    /// Check the signer's response and unblind it into a signature.
+    ///
+    /// Returns `Err(())` if the response does not match the signer's
+    /// commitment and public key.
+    pub fn unblind(self, response: &Response) -> Result<Signature, ()> {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let expected = &self.commitment + &(&self.public * &self.blinded);
+        if (G * &response.0).compress() != expected.compress() {
+            return Err(());
+        }
+        Ok(Signature{ challenge: self.challenge, response: &response.0 + &self.alpha })
+    }
+}
+
+impl Signature {
+    /// Verify the signature on `message` under `public`.
+    pub fn verify(&self, public: &DecafPoint, message: &[u8]) -> Result<(),()> {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let commitment = &(G * &self.response) - &(public * &self.challenge);
+        if challenge(public, &commitment, message) == self.challenge { Ok(()) } else { Err(()) }
+    }
+
+    /// The signature as a proof of knowledge of the secret key, which
+    /// verifies with `proof.verify_from_transcript` on the transcript
+    /// of the message.
+    pub fn to_proof(&self) -> schnorr::Proof {
+        schnorr::Proof::from_parts(self.challenge, schnorr::Responses::from_array([self.response]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn blind_sign_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = SigningKey::generate(&mut csprng);
+        let public = key.verification_key();
+
+        let (signer, commitment) = key.commit(&mut csprng);
+        let (user, challenge) = UserSession::blind(&mut csprng, &public, b"msg", &commitment);
+        let response = signer.respond(&challenge);
+        let signature = user.unblind(&response).unwrap();
+
+        assert!(signature.verify(&public, b"msg").is_ok());
+        assert!(signature.verify(&public, b"other msg").is_err());
+
+        let publics = schnorr::Publics{ X: &public, G: &dalek_constants::DECAF_ED25519_BASEPOINT };
+        let proof = signature.to_proof();
+        assert!(proof.verify_from_transcript(&mut message_transcript(b"msg"), publics).is_ok());
+        assert!(proof.verify_from_transcript(&mut message_transcript(b"other msg"), publics).is_err());
+    }
+
+    #[test]
+    fn unblind_rejects_bad_response() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = SigningKey::generate(&mut csprng);
+        let public = key.verification_key();
+
+        let (_signer, commitment) = key.commit(&mut csprng);
+        let (user, _challenge) = UserSession::blind(&mut csprng, &public, b"msg", &commitment);
+
+        assert!(user.unblind(&Response(Scalar::random(&mut csprng))).is_err());
+    }
+}
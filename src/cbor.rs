@@ -0,0 +1,192 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! A canonical CBOR encoding of proofs.
+//!
+//! The Serde encoding of a proof depends on the serializer: CBOR
+//! libraries may choose different integer widths, map orders or
+//! (indefinite) lengths, and some write scalars as hex strings.
+//! `Proof::to_canonical_cbor()` instead writes the map
+//!
+//! ```text
+//! { "challenge": h'...', "responses": { "x": h'...', ... } }
+//! ```
+//!
+//! with each scalar as a 32-byte byte string, following the core
+//! deterministic encoding requirements of RFC 8949, section 4.2.1:
+//! lengths are definite and in their shortest form, and the keys of
+//! each map are sorted by the bytewise order of their encodings, so
+//! shorter secret names come first.  `Proof::from_canonical_cbor`
+//! rejects any other encoding, including the same proof with a
+//! different key order or length form, so that each proof has exactly
+//! one encoding and its bytes can safely be hashed.
+
+const BYTES: u8 = 2 << 5;
+const TEXT: u8 = 3 << 5;
+const MAP: u8 = 5 << 5;
+
+/// Appends the shortest head for `major` and `n`.
+fn put_head(out: &mut Vec<u8>, major: u8, n: usize) {
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n < 0x100 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n < 0x10000 {
+        out.push(major | 25);
+        out.push((n >> 8) as u8);
+        out.push(n as u8);
+    } else {
+        out.push(major | 26);
+        for i in (0..4).rev() {
+            out.push((n >> (8 * i)) as u8);
+        }
+    }
+}
+
+fn put_text(out: &mut Vec<u8>, text: &str) {
+    put_head(out, TEXT, text.len());
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn put_scalar(out: &mut Vec<u8>, scalar: &[u8; 32]) {
+    put_head(out, BYTES, 32);
+    out.extend_from_slice(&scalar[..]);
+}
+
+fn encoded_text(text: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    put_text(&mut out, text);
+    out
+}
+
+/// The indices of `names`, in the canonical order of their encodings.
+fn canonical_order(names: &[&str]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..names.len()).collect();
+    order.sort_by_key(|&i| encoded_text(names[i]));
+    order
+}
+
+/// Encodes a proof, given its challenge and its responses with the
+/// names of their secrets, in the order they were declared.
+#[doc(hidden)]
+pub fn __encode(challenge: &[u8; 32], names: &[&str], responses: &[&[u8; 32]]) -> Vec<u8> {
+    let mut out = Vec::new();
+    put_head(&mut out, MAP, 2);
+    put_text(&mut out, "challenge");
+    put_scalar(&mut out, challenge);
+    put_text(&mut out, "responses");
+    put_head(&mut out, MAP, names.len());
+    for i in canonical_order(names) {
+        put_text(&mut out, names[i]);
+        put_scalar(&mut out, responses[i]);
+    }
+    out
+}
+
+/// Consumes `expected` from the front of `bytes`.
+fn expect(bytes: &mut &[u8], expected: &[u8]) -> Result<(),()> {
+    if bytes.len() < expected.len() || &bytes[..expected.len()] != expected {
+        return Err(());
+    }
+    *bytes = &bytes[expected.len()..];
+    Ok(())
+}
+
+fn get_scalar<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8],()> {
+    expect(bytes, &[BYTES | 24, 32])?;
+    if bytes.len() < 32 {
+        return Err(());
+    }
+    let (scalar, rest) = bytes.split_at(32);
+    *bytes = rest;
+    Ok(scalar)
+}
+
+/// Decodes a proof encoded by `__encode`, returning the challenge
+/// followed by the responses in the order of `names`, concatenated as
+/// in `Proof::to_bytes`.
+///
+/// Since the canonical encoding of a proof of a given statement is
+/// determined by its scalars, this checks each head and key against
+/// the one `__encode` writes, rather than parsing general CBOR.
+#[doc(hidden)]
+pub fn __decode(mut bytes: &[u8], names: &[&str]) -> Result<Vec<u8>,()> {
+    let mut head = Vec::new();
+    put_head(&mut head, MAP, 2);
+    expect(&mut bytes, &head)?;
+    expect(&mut bytes, &encoded_text("challenge"))?;
+    let mut proof = get_scalar(&mut bytes)?.to_vec();
+    expect(&mut bytes, &encoded_text("responses"))?;
+    let mut head = Vec::new();
+    put_head(&mut head, MAP, names.len());
+    expect(&mut bytes, &head)?;
+    let mut responses = vec![&[][..]; names.len()];
+    for i in canonical_order(names) {
+        expect(&mut bytes, &encoded_text(names[i]))?;
+        responses[i] = get_scalar(&mut bytes)?;
+    }
+    if !bytes.is_empty() {
+        return Err(());
+    }
+    for response in responses {
+        proof.extend_from_slice(response);
+    }
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    create_nipk!{sum, (blinding, x), (C, G, H) : C = (G * x + H * blinding) }
+
+    #[test]
+    fn canonical() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = &(G * &Scalar::from_u64(7));
+        let (x, blinding) = (Scalar::random(&mut csprng), Scalar::random(&mut csprng));
+        let C = &(G * &x) + &(H * &blinding);
+        let publics = sum::Publics{C: &C, G: G, H: H};
+        let proof = sum::Proof::create(&mut csprng, publics, sum::Secrets{blinding: &blinding, x: &x});
+
+        let cbor = proof.to_canonical_cbor();
+        assert_eq!(cbor.len(), 1 + 10 + 34 + 10 + 1 + 2 + 34 + 9 + 34);
+        // The shorter name comes first.
+        assert_eq!(&cbor[56..58], &[TEXT | 1, b'x']);
+        let parsed = sum::Proof::from_canonical_cbor(&cbor).unwrap();
+        assert_eq!(parsed.to_bytes(), proof.to_bytes());
+        assert!(parsed.verify(publics).is_ok());
+
+        let mut trailing = cbor.clone();
+        trailing.push(0);
+        assert!(sum::Proof::from_canonical_cbor(&trailing).is_err());
+
+        // The keys of the outer map in the wrong order.
+        let mut swapped = vec![MAP | 2];
+        swapped.extend_from_slice(&cbor[45..]);
+        swapped.extend_from_slice(&cbor[1..45]);
+        assert!(sum::Proof::from_canonical_cbor(&swapped).is_err());
+
+        // A non-minimal length for the first key, and an indefinite map.
+        let mut long = vec![MAP | 2, TEXT | 24, 9];
+        long.extend_from_slice(&cbor[2..]);
+        assert!(sum::Proof::from_canonical_cbor(&long).is_err());
+        let mut indefinite = vec![MAP | 31];
+        indefinite.extend_from_slice(&cbor[1..]);
+        indefinite.push(0xff);
+        assert!(sum::Proof::from_canonical_cbor(&indefinite).is_err());
+    }
+}
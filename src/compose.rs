@@ -0,0 +1,554 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! AND, OR and threshold compositions of statements, chosen at
+//! runtime.
+//!
+//! Every module generated by `create_nipk!` implements `Statement`
+//! for its `Publics`, and `Witness` for its `Instance`, which pairs
+//! the publics with the secrets.  A `Composition` is a tree of these,
+//! combined with `and`, `or` and `threshold`:
+//!
+//! ```rust,ignore
+//! // Either both of A and B, or C.
+//! let policy = Composition::or(vec![
+//!     Composition::and(vec![Composition::witness(&a), Composition::witness(&b)]),
+//!     Composition::statement(&c_publics),
+//! ]);
+//! let proof = policy.prove(&mut csprng, b"my protocol")?;
+//! ```
+//!
+//! and the verifier builds the same tree from the publics alone.
+//!
+//! The proof is a single sigma protocol, made non-interactive with
+//! one Fiat-Shamir challenge.  It uses the Cramer-Damgård-Schoenmakers
+//! composition: the challenges of the `n` parts of a `t`-of-`n`
+//! threshold are the values at `1, ..., n` of a polynomial of degree
+//! `n - t` whose value at `0` is the challenge of the whole, so a
+//! prover who knows `t` of the parts can simulate the other `n - t`
+//! with challenges of its choice.  An AND is the `n`-of-`n` threshold,
+//! in which every part has the same challenge, and an OR the
+//! `1`-of-`n` threshold.  The challenge hashes
+//!
+//! ```text
+//! "zkp composition" || len(context) || context || policy || commitments
+//! ```
+//!
+//! where the policy is the tree in depth-first order, with each
+//! threshold as `"T" || t || n` and each statement as `"S" ||
+//! len(name) || name || len(publics) || publics`, lengths and counts
+//! being 8-byte little-endian integers.  The proof holds the challenge,
+//! then in depth-first order the challenges of the first `n - t` parts
+//! of each threshold and the responses of each statement.
+//!
+//! The prover's running time depends on which parts it knows, so
+//! `prove` is compiled out under the `ct-audit` feature.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use transcript::Transcript;
+
+/// A statement with its publics, as a sigma protocol.
+pub trait Statement {
+    /// The name of the module generated by `create_nipk!`.
+    fn name(&self) -> &'static str;
+
+    /// The publics, as in `Publics::to_bytes`.
+    fn public_bytes(&self) -> Vec<u8>;
+
+    /// The number of responses in a proof.
+    fn responses_len(&self) -> usize;
+
+    /// Simulate a proof for `challenge`, returning its responses and
+    /// commitments.
+    fn simulate(&self, csprng: &mut dyn Rng, challenge: &Scalar) -> (Vec<Scalar>, Vec<DecafPoint>);
+
+    /// The commitments for which `responses` answer `challenge`.
+    ///
+    /// Returns `Err(())` if there are the wrong number of responses,
+    /// or they do not satisfy the relations of the statement.
+    fn commitments(&self, challenge: &Scalar, responses: &[Scalar]) -> Result<Vec<DecafPoint>,()>;
+}
+
+/// A statement together with its secrets.
+pub trait Witness {
+    fn statement(&self) -> &dyn Statement;
+
+    /// The responses `challenge * secret + nonce`, for nonces from
+    /// `simulate` with a zero challenge.
+    fn respond(&self, nonces: &[Scalar], challenge: &Scalar) -> Vec<Scalar>;
+}
+
+enum Node<'a> {
+    Statement(&'a dyn Statement),
+    Witness(&'a dyn Witness),
+    Threshold(usize, Vec<Composition<'a>>),
+}
+
+/// A tree of statements, combined with thresholds.
+pub struct Composition<'a> {
+    node: Node<'a>,
+}
+
+/// A proof of a `Composition`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CompositeProof {
+    challenge: Scalar,
+    scalars: Vec<Scalar>,
+}
+
+impl CompositeProof {
+    /// The challenge followed by the other scalars, as 32 bytes each.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 * (1 + self.scalars.len()));
+        bytes.extend_from_slice(self.challenge.as_bytes());
+        for scalar in &self.scalars {
+            bytes.extend_from_slice(scalar.as_bytes());
+        }
+        bytes
+    }
+
+    /// Parse a proof encoded by `to_bytes`, rejecting non-canonical
+    /// scalars.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CompositeProof,()> {
+        if bytes.len() < 32 || bytes.len() % 32 != 0 {
+            return Err(());
+        }
+        let mut scalars = bytes.chunks(32).map(::__decode_scalar).collect::<Result<Vec<_>,()>>()?;
+        let challenge = scalars.remove(0);
+        Ok(CompositeProof{ challenge: challenge, scalars: scalars })
+    }
+}
+
+fn put_length(hash: &mut Sha512, n: usize) {
+    hash.input(&::length_bytes(n));
+}
+
+/// The value at `x` of the polynomial of least degree through `points`.
+fn interpolate(points: &[(u64, Scalar)], x: u64) -> Scalar {
+    let x = Scalar::from_u64(x);
+    let mut value = Scalar::zero();
+    for (i, &(xi, ref yi)) in points.iter().enumerate() {
+        let xi = Scalar::from_u64(xi);
+        let mut numerator = Scalar::one();
+        let mut denominator = Scalar::one();
+        for (j, &(xj, _)) in points.iter().enumerate() {
+            if i != j {
+                let xj = Scalar::from_u64(xj);
+                numerator = &numerator * &(&x - &xj);
+                denominator = &denominator * &(&xi - &xj);
+            }
+        }
+        value = &value + &(&(yi * &numerator) * &denominator.invert());
+    }
+    value
+}
+
+/// The challenges of all `n` parts, given the challenge of the whole and
+/// the challenges of the parts in `known`, of which there must be `n - t`.
+fn part_challenges(challenge: &Scalar, known: &[(u64, Scalar)], n: usize) -> Vec<Scalar> {
+    let mut points = vec![(0, *challenge)];
+    points.extend_from_slice(known);
+    (1..n as u64 + 1).map(|i| {
+        match known.iter().find(|&&(x, _)| x == i) {
+            Some(&(_, ref e)) => *e,
+            None => interpolate(&points, i),
+        }
+    }).collect()
+}
+
+/// The prover's state between committing and responding.
+#[cfg(not(feature = "ct-audit"))]
+enum Pending {
+    /// A simulated part, with its challenge and proof scalars.
+    Simulated(Scalar, Vec<Scalar>),
+    /// The nonces of a statement with a witness.
+    Nonces(Vec<Scalar>),
+    /// A threshold, with the state of each part.
+    Threshold(Vec<Pending>),
+}
+
+impl<'a> Composition<'a> {
+    /// A statement for which the prover does not know the secrets, or
+    /// which is only verified.
+    pub fn statement(statement: &'a dyn Statement) -> Composition<'a> {
+        Composition{ node: Node::Statement(statement) }
+    }
+
+    /// A statement for which the prover knows the secrets.
+    pub fn witness(witness: &'a dyn Witness) -> Composition<'a> {
+        Composition{ node: Node::Witness(witness) }
+    }
+
+    /// All of `parts`.
+    pub fn and(parts: Vec<Composition<'a>>) -> Composition<'a> {
+        let n = parts.len();
+        Composition::threshold(n, parts)
+    }
+
+    /// At least one of `parts`.
+    pub fn or(parts: Vec<Composition<'a>>) -> Composition<'a> {
+        Composition::threshold(1, parts)
+    }
+
+    /// At least `t` of `parts`.
+    ///
+    /// Panics unless `0 < t <= parts.len()`.
+    pub fn threshold(t: usize, parts: Vec<Composition<'a>>) -> Composition<'a> {
+        assert!(0 < t && t <= parts.len(), "invalid threshold");
+        Composition{ node: Node::Threshold(t, parts) }
+    }
+
+    /// Whether the witnesses in the tree suffice to prove it.
+    pub fn is_provable(&self) -> bool {
+        match self.node {
+            Node::Statement(_) => false,
+            Node::Witness(_) => true,
+            Node::Threshold(t, ref parts) => parts.iter().filter(|p| p.is_provable()).count() >= t,
+        }
+    }
+
+    /// Prove the composition, bound to `context`.
+    ///
+    /// Returns `Err(())` if the witnesses do not suffice.
+    #[cfg(not(feature = "ct-audit"))]
+    pub fn prove<R: Rng>(&self, csprng: &mut R, context: &[u8]) -> Result<CompositeProof,()> {
+        if !self.is_provable() {
+            return Err(());
+        }
+        let mut commitments = Vec::new();
+        let pending = self.commit(csprng, &mut commitments);
+        let challenge = self.challenge(context, &commitments);
+        let mut scalars = Vec::new();
+        self.respond(pending, &challenge, &mut scalars);
+        Ok(CompositeProof{ challenge: challenge, scalars: scalars })
+    }
+
+    /// Prove the composition as the next step of `transcript`, and then
+    /// add the proof to the transcript.
+    #[cfg(not(feature = "ct-audit"))]
+    pub fn prove_from_transcript<R: Rng>(
+        &self,
+        csprng: &mut R,
+        transcript: &mut Transcript,
+    ) -> Result<CompositeProof,()> {
+        let proof = self.prove(csprng, transcript.__state())?;
+        transcript.append_message(b"zkp composition", &proof.to_bytes());
+        Ok(proof)
+    }
+
+    /// Verify a proof of the composition, bound to `context`.
+    pub fn verify(&self, context: &[u8], proof: &CompositeProof) -> Result<(),()> {
+        let mut commitments = Vec::new();
+        let mut scalars = &proof.scalars[..];
+        self.recompute(&proof.challenge, &mut scalars, &mut commitments)?;
+        if !scalars.is_empty() {
+            return Err(());
+        }
+        if self.challenge(context, &commitments).as_bytes() == proof.challenge.as_bytes() {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Verify a proof made by `prove_from_transcript`, and add it to the
+    /// transcript if it is valid.
+    pub fn verify_from_transcript(
+        &self,
+        transcript: &mut Transcript,
+        proof: &CompositeProof,
+    ) -> Result<(),()> {
+        self.verify(transcript.__state(), proof)?;
+        transcript.append_message(b"zkp composition", &proof.to_bytes());
+        Ok(())
+    }
+
+    fn challenge(&self, context: &[u8], commitments: &[DecafPoint]) -> Scalar {
+        let mut hash = Sha512::default();
+        hash.input(b"zkp composition");
+        put_length(&mut hash, context.len());
+        hash.input(context);
+        self.hash_policy(&mut hash);
+        for commitment in commitments {
+            hash.input(commitment.compress().as_bytes());
+        }
+        Scalar::from_hash(hash)
+    }
+
+    fn hash_policy(&self, hash: &mut Sha512) {
+        match self.node {
+            Node::Threshold(t, ref parts) => {
+                hash.input(b"T");
+                put_length(hash, t);
+                put_length(hash, parts.len());
+                for part in parts {
+                    part.hash_policy(hash);
+                }
+            }
+            _ => {
+                let statement = self.as_statement();
+                let publics = statement.public_bytes();
+                hash.input(b"S");
+                put_length(hash, statement.name().len());
+                hash.input(statement.name().as_bytes());
+                put_length(hash, publics.len());
+                hash.input(&publics);
+            }
+        }
+    }
+
+    fn as_statement(&self) -> &dyn Statement {
+        match self.node {
+            Node::Statement(statement) => statement,
+            Node::Witness(witness) => witness.statement(),
+            Node::Threshold(..) => unreachable!(),
+        }
+    }
+
+    /// Commit to a provable part, simulating the parts it does not
+    /// need, and append its commitments.
+    #[cfg(not(feature = "ct-audit"))]
+    fn commit(&self, mut csprng: &mut dyn Rng, commitments: &mut Vec<DecafPoint>) -> Pending {
+        match self.node {
+            Node::Witness(witness) => {
+                let (nonces, rs) = witness.statement().simulate(csprng, &Scalar::zero());
+                commitments.extend(rs);
+                Pending::Nonces(nonces)
+            }
+            Node::Threshold(t, ref parts) => {
+                // Simulate every part which cannot be proven, and
+                // then the last provable ones, up to `n - t` parts.
+                let mut spare = parts.len() - t
+                    - parts.iter().filter(|p| !p.is_provable()).count();
+                let mut simulate = vec![false; parts.len()];
+                for (i, part) in parts.iter().enumerate().rev() {
+                    if !part.is_provable() {
+                        simulate[i] = true;
+                    } else if spare > 0 {
+                        simulate[i] = true;
+                        spare -= 1;
+                    }
+                }
+                let mut pending = Vec::with_capacity(parts.len());
+                for (part, simulate) in parts.iter().zip(simulate) {
+                    if simulate {
+                        let e = Scalar::random(&mut csprng);
+                        let mut scalars = Vec::new();
+                        part.simulate(csprng, &e, &mut scalars, commitments);
+                        pending.push(Pending::Simulated(e, scalars));
+                    } else {
+                        pending.push(part.commit(csprng, commitments));
+                    }
+                }
+                Pending::Threshold(pending)
+            }
+            Node::Statement(_) => unreachable!(),
+        }
+    }
+
+    /// Answer `challenge` for a part committed to by `commit`, and
+    /// append its proof scalars.
+    #[cfg(not(feature = "ct-audit"))]
+    fn respond(&self, pending: Pending, challenge: &Scalar, scalars: &mut Vec<Scalar>) {
+        match (&self.node, pending) {
+            (&Node::Witness(witness), Pending::Nonces(nonces)) => {
+                scalars.extend(witness.respond(&nonces, challenge));
+            }
+            (&Node::Threshold(_, ref parts), Pending::Threshold(pending)) => {
+                let known: Vec<(u64, Scalar)> = pending.iter().enumerate().filter_map(|(i, p)| {
+                    match *p {
+                        Pending::Simulated(ref e, _) => Some((i as u64 + 1, *e)),
+                        _ => None,
+                    }
+                }).collect();
+                let challenges = part_challenges(challenge, &known, parts.len());
+                scalars.extend_from_slice(&challenges[..known.len()]);
+                for ((part, pending), e) in parts.iter().zip(pending).zip(challenges.iter()) {
+                    match pending {
+                        Pending::Simulated(_, simulated) => scalars.extend(simulated),
+                        pending => part.respond(pending, e, scalars),
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Simulate a proof of this part for `challenge`, appending its
+    /// proof scalars and commitments.
+    #[cfg(not(feature = "ct-audit"))]
+    fn simulate(
+        &self,
+        mut csprng: &mut dyn Rng,
+        challenge: &Scalar,
+        scalars: &mut Vec<Scalar>,
+        commitments: &mut Vec<DecafPoint>,
+    ) {
+        match self.node {
+            Node::Threshold(t, ref parts) => {
+                let known: Vec<(u64, Scalar)> = (1..(parts.len() - t) as u64 + 1)
+                    .map(|i| (i, Scalar::random(&mut csprng)))
+                    .collect();
+                let challenges = part_challenges(challenge, &known, parts.len());
+                scalars.extend_from_slice(&challenges[..known.len()]);
+                for (part, e) in parts.iter().zip(challenges.iter()) {
+                    part.simulate(csprng, e, scalars, commitments);
+                }
+            }
+            _ => {
+                let (responses, rs) = self.as_statement().simulate(csprng, challenge);
+                scalars.extend(responses);
+                commitments.extend(rs);
+            }
+        }
+    }
+
+    /// Recompute the commitments of this part from its proof scalars,
+    /// consuming them from the front of `scalars`.
+    fn recompute(
+        &self,
+        challenge: &Scalar,
+        scalars: &mut &[Scalar],
+        commitments: &mut Vec<DecafPoint>,
+    ) -> Result<(),()> {
+        match self.node {
+            Node::Threshold(t, ref parts) => {
+                let d = parts.len() - t;
+                if scalars.len() < d {
+                    return Err(());
+                }
+                let known: Vec<(u64, Scalar)> = scalars[..d].iter().enumerate()
+                    .map(|(i, e)| (i as u64 + 1, *e))
+                    .collect();
+                *scalars = &scalars[d..];
+                let challenges = part_challenges(challenge, &known, parts.len());
+                for (part, e) in parts.iter().zip(challenges.iter()) {
+                    part.recompute(e, scalars, commitments)?;
+                }
+                Ok(())
+            }
+            _ => {
+                let statement = self.as_statement();
+                let n = statement.responses_len();
+                if scalars.len() < n {
+                    return Err(());
+                }
+                commitments.extend(statement.commitments(challenge, &scalars[..n])?);
+                *scalars = &scalars[n..];
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "ct-audit")))]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+    create_nipk!{sum, (a, b, r), (C, D, G, H)
+                 : D = (G * b), (C - D) = (G * a + H * r)
+                 ; b = ((3) * a + (1)) }
+
+    #[test]
+    fn interpolation() {
+        let points = [(0, Scalar::from_u64(5)), (1, Scalar::from_u64(7))];
+        assert_eq!(interpolate(&points, 3).as_bytes(), Scalar::from_u64(11).as_bytes());
+        let e = Scalar::from_u64(9);
+        let challenges = part_challenges(&e, &[], 3);
+        assert!(challenges.iter().all(|c| c.as_bytes() == e.as_bytes()));
+    }
+
+    #[test]
+    fn compositions() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::random(&mut csprng);
+        let xs: Vec<Scalar> = (0..3).map(|_| Scalar::random(&mut csprng)).collect();
+        let As: Vec<DecafPoint> = xs.iter().map(|x| G * x).collect();
+        let publics: Vec<dlog::Publics> = As.iter().map(|A| dlog::Publics{A: A, G: G}).collect();
+        let instances: Vec<dlog::Instance> = publics.iter().zip(xs.iter())
+            .map(|(p, x)| dlog::Instance{ publics: *p, secrets: dlog::Secrets{x: x} })
+            .collect();
+
+        let (a, r) = (Scalar::random(&mut csprng), Scalar::random(&mut csprng));
+        let b = &(&Scalar::from_u64(3) * &a) + &Scalar::one();
+        let D = G * &b;
+        let C = &(&D + &(G * &a)) + &(&H * &r);
+        let sum_publics = sum::Publics{C: &C, D: &D, G: G, H: &H};
+        let sum_instance = sum::Instance{
+            publics: sum_publics,
+            secrets: sum::Secrets{a: &a, b: &b, r: &r},
+        };
+
+        // The verifier's view of `(A0 and sum) or (2 of A0, A1, A2)`.
+        let verifier = || Composition::or(vec![
+            Composition::and(vec![Composition::statement(&publics[0]), Composition::statement(&sum_publics)]),
+            Composition::threshold(2, publics.iter().map(|p| Composition::statement(p as &dyn Statement)).collect()),
+        ]);
+
+        // Each of these sets of known witnesses satisfies the policy.
+        let provers = vec![
+            [true, false, false, true],
+            [false, true, true, false],
+            [true, false, true, false],
+            [true, true, true, true],
+        ];
+        for known in provers {
+            let leaf = |i: usize| if known[i] {
+                Composition::witness(&instances[i])
+            } else {
+                Composition::statement(&publics[i])
+            };
+            let prover = Composition::or(vec![
+                Composition::and(vec![
+                    leaf(0),
+                    if known[3] { Composition::witness(&sum_instance) } else { Composition::statement(&sum_publics) },
+                ]),
+                Composition::threshold(2, (0..3).map(|i| leaf(i)).collect()),
+            ]);
+            let proof = prover.prove(&mut csprng, b"policy").unwrap();
+            assert!(verifier().verify(b"policy", &proof).is_ok());
+            assert!(verifier().verify(b"other", &proof).is_err());
+            let parsed = CompositeProof::from_bytes(&proof.to_bytes()).unwrap();
+            assert!(verifier().verify(b"policy", &parsed).is_ok());
+
+            // A different policy over the same statements.
+            let and = Composition::and(publics.iter().map(|p| Composition::statement(p as &dyn Statement)).collect());
+            assert!(and.verify(b"policy", &proof).is_err());
+        }
+
+        // One of the three is not enough for the threshold.
+        let prover = Composition::threshold(2, vec![
+            Composition::witness(&instances[0]),
+            Composition::statement(&publics[1]),
+            Composition::statement(&publics[2]),
+        ]);
+        assert!(!prover.is_provable());
+        assert!(prover.prove(&mut csprng, b"policy").is_err());
+
+        // Proofs on a shared transcript.
+        let prover = Composition::and(vec![Composition::witness(&instances[1]), Composition::witness(&sum_instance)]);
+        let mut transcript = Transcript::new(b"session");
+        let proof = prover.prove_from_transcript(&mut csprng, &mut transcript).unwrap();
+        let verifier = Composition::and(vec![Composition::statement(&publics[1]), Composition::statement(&sum_publics)]);
+        let mut replay = Transcript::new(b"session");
+        assert!(verifier.verify_from_transcript(&mut replay, &proof).is_ok());
+        assert_eq!(replay.__state(), transcript.__state());
+        assert!(verifier.verify_from_transcript(&mut Transcript::new(b"other"), &proof).is_err());
+    }
+}
@@ -0,0 +1,391 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Keyed-verification anonymous credentials, following Chase,
+//! Meiklejohn and Zaverucha ("Algebraic MACs and Keyed-Verification
+//! Anonymous Credentials", CCS 2014), with the `MAC_GGM` algebraic
+//! MAC.
+//!
+//! The issuer's secret key is `(x_0, x~_0, x_1, ..., x_n)`, and its
+//! public parameters are `C_x0 = G*x_0 + H*x~_0` and `X_i = H*x_i`.
+//! A credential on attributes `m_1, ..., m_n` is a MAC
+//! `(P, Q = P*(x_0 + sum_i x_i*m_i))` for a random point `P`.
+//!
+//! * Issuance: `Issuer::issue` computes the MAC on attributes known to
+//!   the issuer, with an `IssuanceProof` that it used the key committed
+//!   to in the public parameters, which the user checks with
+//!   `verify_issuance`.  This prevents the issuer from tagging users
+//!   with per-user keys.
+//! * Presentation: the user rerandomizes the MAC to `(P' = P*t,
+//!   Q' = Q*t)`, commits to each attribute as `C_i = P'*m_i + H*z_i`
+//!   and to `Q'` as `C_Q = Q' + G*r`, and proves knowledge of
+//!   `m_i, z_i, r` with
+//!
+//!   ```text
+//!   C_i = P'*m_i + H*z_i
+//!   V   = sum_i X_i*z_i - G*r
+//!   ```
+//!
+//!   The issuer, which alone can compute
+//!   `V = P'*x_0 + sum_i C_i*x_i - C_Q` (keyed verification), checks
+//!   the proof with `Issuer::verify_presentation`.  Presentations of
+//!   the same credential are unlinkable, and reveal nothing about the
+//!   attributes; the commitments `C_i` can be used in further proofs
+//!   about them.
+//!
+//! The statements have one equation per attribute, so unlike the
+//! fixed statements in the rest of the crate they are proved by hand
+//! here, with the same Fiat-Shamir structure as `create_nipk!`.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use __is_identity;
+use pedersen::PedersenGenerators;
+
+/// The issuer's public parameters.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IssuerParameters {
+    pub C_x0: DecafPoint,
+    pub X: Vec<DecafPoint>,
+}
+
+/// An issuer, holding the secret MAC key.
+pub struct Issuer {
+    gens: PedersenGenerators,
+    x0: Scalar,
+    x0_blinding: Scalar,
+    x: Vec<Scalar>,
+    parameters: IssuerParameters,
+}
+
+/// A MAC `(P, Q)` on a list of attributes.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Credential {
+    pub P: DecafPoint,
+    pub Q: DecafPoint,
+}
+
+/// A proof that a credential was made with the key committed to in
+/// the issuer's parameters.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IssuanceProof {
+    challenge: Scalar,
+    x0: Scalar,
+    x0_blinding: Scalar,
+    x: Vec<Scalar>,
+}
+
+/// A proof of knowledge of the committed attributes and blinding
+/// factors in a `Presentation`.
+#[derive(Clone, Serialize, Deserialize)]
+struct PresentationProof {
+    challenge: Scalar,
+    m: Vec<Scalar>,
+    z: Vec<Scalar>,
+    r: Scalar,
+}
+
+/// An unlinkable presentation of a credential.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Presentation {
+    /// The rerandomized `P' = P*t`.
+    pub P: DecafPoint,
+    /// The commitments `C_i = P'*m_i + H*z_i` to the attributes.
+    pub C: Vec<DecafPoint>,
+    /// The commitment `C_Q = Q' + G*r`.
+    pub C_Q: DecafPoint,
+    proof: PresentationProof,
+}
+
+/// A random scalar which is not zero.
+fn nonzero_scalar<R: Rng>(csprng: &mut R) -> Scalar {
+    loop {
+        let s = Scalar::random(csprng);
+        if s != Scalar::zero() {
+            return s;
+        }
+    }
+}
+
+/// Compute `sum_i scalars[i] * points[i]`, which must be non-empty.
+fn linear_combination(scalars: &[Scalar], points: &[DecafPoint]) -> DecafPoint {
+    let mut sum = &points[0] * &scalars[0];
+    for (a, P) in scalars.iter().zip(points.iter()).skip(1) {
+        sum = &sum + &(P * a);
+    }
+    sum
+}
+
+fn challenge(label: &[u8], points: &[&DecafPoint], scalars: &[Scalar]) -> Scalar {
+    let mut hash = Sha512::default();
+    hash.input(label);
+    for P in points {
+        hash.input(P.compress().as_bytes());
+    }
+    for s in scalars {
+        hash.input(s.as_bytes());
+    }
+    Scalar::from_hash(hash)
+}
+
+fn issuance_challenge(
+    gens: &PedersenGenerators,
+    parameters: &IssuerParameters,
+    attributes: &[Scalar],
+    credential: &Credential,
+    T_C: &DecafPoint,
+    T_X: &[DecafPoint],
+    T_Q: &DecafPoint,
+) -> Scalar {
+    let mut points = vec![&gens.G, &gens.H, &parameters.C_x0];
+    points.extend(parameters.X.iter());
+    points.push(&credential.P);
+    points.push(&credential.Q);
+    points.push(T_C);
+    points.extend(T_X.iter());
+    points.push(T_Q);
+    challenge(b"zkp credential issuance", &points, attributes)
+}
+
+fn presentation_challenge(
+    gens: &PedersenGenerators,
+    parameters: &IssuerParameters,
+    P: &DecafPoint,
+    C: &[DecafPoint],
+    C_Q: &DecafPoint,
+    T: &[DecafPoint],
+    T_V: &DecafPoint,
+) -> Scalar {
+    let mut points = vec![&gens.G, &gens.H, &parameters.C_x0];
+    points.extend(parameters.X.iter());
+    points.push(P);
+    points.extend(C.iter());
+    points.push(C_Q);
+    points.extend(T.iter());
+    points.push(T_V);
+    challenge(b"zkp credential presentation", &points, &[])
+}
+
+impl Issuer {
+    /// Generate a key for credentials with `n` attributes.
+    ///
+    /// Returns `Err(())` if `n` is zero.
+    pub fn new<R: Rng>(
+        csprng: &mut R,
+        gens: &PedersenGenerators,
+        n: usize,
+    ) -> Result<Issuer, ()> {
+        if n == 0 {
+            return Err(());
+        }
+        let x0 = Scalar::random(csprng);
+        let x0_blinding = Scalar::random(csprng);
+        let x: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let parameters = IssuerParameters{
+            C_x0: &(&gens.G * &x0) + &(&gens.H * &x0_blinding),
+            X: x.iter().map(|x_i| &gens.H * x_i).collect(),
+        };
+        Ok(Issuer{
+            gens: *gens,
+            x0: x0,
+            x0_blinding: x0_blinding,
+            x: x,
+            parameters: parameters,
+        })
+    }
+
+    /// The public parameters, to be given to users.
+    pub fn parameters(&self) -> &IssuerParameters {
+        &self.parameters
+    }
+
+    /// Issue a credential on `attributes`.
+    ///
+    /// Returns `Err(())` if the number of attributes is wrong.
+    pub fn issue<R: Rng>(
+        &self,
+        csprng: &mut R,
+        attributes: &[Scalar],
+    ) -> Result<(Credential, IssuanceProof), ()> {
+        if attributes.len() != self.x.len() {
+            return Err(());
+        }
+        let P = &self.gens.G * &nonzero_scalar(csprng);
+        // x_0 + sum_i x_i*m_i
+        let mut exponent = self.x0;
+        for (x_i, m_i) in self.x.iter().zip(attributes.iter()) {
+            exponent = Scalar::multiply_add(x_i, m_i, &exponent);
+        }
+        let credential = Credential{ P: P, Q: &P * &exponent };
+
+        let k0 = Scalar::random(csprng);
+        let k0_blinding = Scalar::random(csprng);
+        let k: Vec<Scalar> = self.x.iter().map(|_| Scalar::random(csprng)).collect();
+        let T_C = &(&self.gens.G * &k0) + &(&self.gens.H * &k0_blinding);
+        let T_X: Vec<DecafPoint> = k.iter().map(|k_i| &self.gens.H * k_i).collect();
+        let mut k_exponent = k0;
+        for (k_i, m_i) in k.iter().zip(attributes.iter()) {
+            k_exponent = Scalar::multiply_add(k_i, m_i, &k_exponent);
+        }
+        let T_Q = &P * &k_exponent;
+
+        let c = issuance_challenge(
+            &self.gens, &self.parameters, attributes, &credential, &T_C, &T_X, &T_Q,
+        );
+        let proof = IssuanceProof{
+            challenge: c,
+            x0: Scalar::multiply_add(&c, &self.x0, &k0),
+            x0_blinding: Scalar::multiply_add(&c, &self.x0_blinding, &k0_blinding),
+            x: self.x.iter().zip(k.iter()).map(|(x_i, k_i)| {
+                Scalar::multiply_add(&c, x_i, k_i)
+            }).collect(),
+        };
+        Ok((credential, proof))
+    }
+
+    /// Verify a presentation of a credential issued with this key.
+    pub fn verify_presentation(&self, presentation: &Presentation) -> Result<(),()> {
+        let n = self.x.len();
+        let proof = &presentation.proof;
+        if presentation.C.len() != n || proof.m.len() != n || proof.z.len() != n {
+            return Err(());
+        }
+        let P = &presentation.P;
+        if __is_identity(P) {
+            return Err(());
+        }
+        let c = &proof.challenge;
+        let gens = &self.gens;
+
+        // V = P'*x_0 + sum_i C_i*x_i - C_Q
+        let V = &(&(P * &self.x0) + &linear_combination(&self.x, &presentation.C))
+            - &presentation.C_Q;
+
+        let T: Vec<DecafPoint> = (0..n).map(|i| {
+            &(&(P * &proof.m[i]) + &(&gens.H * &proof.z[i])) - &(&presentation.C[i] * c)
+        }).collect();
+        let T_V = &(&linear_combination(&proof.z, &self.parameters.X) + &(&gens.G * &proof.r))
+            - &(&V * c);
+
+        let expected = presentation_challenge(
+            gens, &self.parameters, P, &presentation.C, &presentation.C_Q, &T, &T_V,
+        );
+        if expected == *c { Ok(()) } else { Err(()) }
+    }
+}
+
+/// Check that `credential` is a MAC on `attributes` under the key
+/// committed to in `parameters`.
+pub fn verify_issuance(
+    gens: &PedersenGenerators,
+    parameters: &IssuerParameters,
+    attributes: &[Scalar],
+    credential: &Credential,
+    proof: &IssuanceProof,
+) -> Result<(),()> {
+    let n = parameters.X.len();
+    if attributes.len() != n || proof.x.len() != n || __is_identity(&credential.P) {
+        return Err(());
+    }
+    let c = &proof.challenge;
+    let T_C = &(&(&gens.G * &proof.x0) + &(&gens.H * &proof.x0_blinding))
+        - &(&parameters.C_x0 * c);
+    let T_X: Vec<DecafPoint> = (0..n).map(|i| {
+        &(&gens.H * &proof.x[i]) - &(&parameters.X[i] * c)
+    }).collect();
+    let mut s_exponent = proof.x0;
+    for (s_i, m_i) in proof.x.iter().zip(attributes.iter()) {
+        s_exponent = Scalar::multiply_add(s_i, m_i, &s_exponent);
+    }
+    let T_Q = &(&credential.P * &s_exponent) - &(&credential.Q * c);
+
+    let expected = issuance_challenge(gens, parameters, attributes, credential, &T_C, &T_X, &T_Q);
+    if expected == *c { Ok(()) } else { Err(()) }
+}
+
+/// Present `credential` on `attributes`, without revealing them.
+///
+/// Returns `Err(())` if the number of attributes is wrong.  (The
+/// presentation is only valid if the credential really is a MAC on
+/// `attributes`.)
+pub fn present<R: Rng>(
+    csprng: &mut R,
+    gens: &PedersenGenerators,
+    parameters: &IssuerParameters,
+    credential: &Credential,
+    attributes: &[Scalar],
+) -> Result<Presentation, ()> {
+    let n = parameters.X.len();
+    if attributes.len() != n {
+        return Err(());
+    }
+    let t = nonzero_scalar(csprng);
+    let P = &credential.P * &t;
+    let Q = &credential.Q * &t;
+    let z: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+    let r = Scalar::random(csprng);
+    let C: Vec<DecafPoint> = (0..n).map(|i| {
+        &(&P * &attributes[i]) + &(&gens.H * &z[i])
+    }).collect();
+    let C_Q = &Q + &(&gens.G * &r);
+    let minus_r = -&r;
+
+    let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+    let b: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+    let e = Scalar::random(csprng);
+    let T: Vec<DecafPoint> = (0..n).map(|i| &(&P * &a[i]) + &(&gens.H * &b[i])).collect();
+    let T_V = &linear_combination(&b, &parameters.X) + &(&gens.G * &e);
+
+    let c = presentation_challenge(gens, parameters, &P, &C, &C_Q, &T, &T_V);
+    let proof = PresentationProof{
+        challenge: c,
+        m: (0..n).map(|i| Scalar::multiply_add(&c, &attributes[i], &a[i])).collect(),
+        z: (0..n).map(|i| Scalar::multiply_add(&c, &z[i], &b[i])).collect(),
+        r: Scalar::multiply_add(&c, &minus_r, &e),
+    };
+    Ok(Presentation{ P: P, C: C, C_Q: C_Q, proof: proof })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn issue_and_present() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+        let issuer = Issuer::new(&mut csprng, &gens, 3).unwrap();
+        let parameters = issuer.parameters().clone();
+
+        let attributes = [Scalar::from_u64(1), Scalar::from_u64(20), Scalar::from_u64(300)];
+        let (credential, proof) = issuer.issue(&mut csprng, &attributes).unwrap();
+        assert!(verify_issuance(&gens, &parameters, &attributes, &credential, &proof).is_ok());
+
+        let other = [Scalar::from_u64(1), Scalar::from_u64(21), Scalar::from_u64(300)];
+        assert!(verify_issuance(&gens, &parameters, &other, &credential, &proof).is_err());
+
+        let presentation = present(&mut csprng, &gens, &parameters, &credential, &attributes).unwrap();
+        assert!(issuer.verify_presentation(&presentation).is_ok());
+
+        // Presenting the wrong attributes, or to another issuer, fails.
+        let presentation = present(&mut csprng, &gens, &parameters, &credential, &other).unwrap();
+        assert!(issuer.verify_presentation(&presentation).is_err());
+        let presentation = present(&mut csprng, &gens, &parameters, &credential, &attributes).unwrap();
+        let issuer2 = Issuer::new(&mut csprng, &gens, 3).unwrap();
+        assert!(issuer2.verify_presentation(&presentation).is_err());
+
+        assert!(present(&mut csprng, &gens, &parameters, &credential, &attributes[1..]).is_err());
+    }
+}
@@ -0,0 +1,731 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs that public keys in two different groups have the same
+//! secret key.
+//!
+//! For a secret integer `x`, with public keys `P = G*x` in one group
+//! and `Q = G'*x` in another, such as decaf and secp256k1, a
+//! `CrossGroupProof` shows that both have the same discrete logarithm.
+//! A single sigma protocol cannot show this, since its responses are
+//! reduced modulo the order of its group, so the proof goes through
+//! the bits of `x`, as in `zkp::range`.  The prover commits to each bit
+//! `b_i` in both groups, as
+//!
+//! ```text
+//! C_i = G*b_i + H*r_i        D_i = G'*b_i + H'*s_i
+//! ```
+//!
+//! choosing the blindings so that `sum_i 2^i C_i = P` and
+//! `sum_i 2^i D_i = Q`, and proves for each bit the OR-statement
+//!
+//! ```text
+//! (C_i = H*r_i and D_i = H'*s_i) or (C_i - G = H*r_i and D_i - G' = H'*s_i)
+//! ```
+//!
+//! The challenges are 248-bit strings rather than scalars, which are
+//! split between the branches of each OR-proof by XOR, so that they
+//! are the same integer in both groups.  All the OR-proofs share one
+//! Fiat-Shamir challenge, the first 31 bytes of the SHA-512 hash of
+//!
+//! ```text
+//! "zkp cross-group dleq" || len(context) || context
+//!     || len(name) || name || len(name') || name' || P || Q
+//!     || for each bit: C_i || D_i || the commitments of both branches in both groups
+//! ```
+//!
+//! with the lengths as 8-byte little-endian integers.  The context
+//! should name the application, e.g. the swap the keys are for.
+//!
+//! The secret must be less than `2^BITS`, so both groups must have
+//! order greater than `2^BITS`.  Proof creation is constant-time with
+//! respect to the secret, as in `zkp::range`.
+//!
+//! The groups are given by the `Group` trait, which `Decaf` implements
+//! for this crate's group, and `Secp256k1`, with the `secp256k1`
+//! feature, for secp256k1 using the `k256` crate:
+//!
+//! ```rust,ignore
+//! let proof = CrossGroupProof::<Decaf, Secp256k1>::create(&mut csprng, b"swap 1234", &x)?;
+//! proof.verify(&P, &Q, b"swap 1234")?;
+//! ```
+//!
+//! Other groups are added by implementing `Group` for a type wrapping
+//! their library.
+//!
+//! Proofs are large: `CrossGroupProof::serialized_size()` is about
+//! 64 kB for decaf and secp256k1.
+
+use std::marker::PhantomData;
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use {__decode_point, __decode_scalar};
+use pedersen::PedersenGenerators;
+
+/// The bit length of the secrets.
+pub const BITS: usize = 252;
+
+/// The length of the challenges, in bytes.
+const CHALLENGE_SIZE: usize = 31;
+
+/// A prime-order group, with a second generator whose discrete
+/// logarithm nobody knows.
+pub trait Group {
+    type Scalar: Copy;
+    type Point: Copy;
+
+    /// The name of the group, hashed into the challenge.
+    const NAME: &'static str;
+    /// The length of `encode_scalar`.
+    const SCALAR_SIZE: usize;
+    /// The length of `encode_point`.
+    const POINT_SIZE: usize;
+
+    /// The generator of the public keys.
+    fn basepoint() -> Self::Point;
+    /// The generator of the blindings.
+    fn blinding_point() -> Self::Point;
+
+    fn random_scalar<R: Rng>(csprng: &mut R) -> Self::Scalar;
+    /// The integer with the little-endian encoding `bytes`, which is
+    /// less than `2^BITS`, as a scalar.
+    fn scalar_from_le_bytes(bytes: &[u8; 32]) -> Self::Scalar;
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_sub(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+
+    fn add(a: &Self::Point, b: &Self::Point) -> Self::Point;
+    fn sub(a: &Self::Point, b: &Self::Point) -> Self::Point;
+    fn mul(point: &Self::Point, scalar: &Self::Scalar) -> Self::Point;
+
+    fn encode_scalar(scalar: &Self::Scalar, out: &mut Vec<u8>);
+    /// Parse a scalar, rejecting non-canonical encodings.
+    fn decode_scalar(bytes: &[u8]) -> Result<Self::Scalar,()>;
+    fn encode_point(point: &Self::Point, out: &mut Vec<u8>);
+    /// Parse a point, rejecting invalid encodings.
+    fn decode_point(bytes: &[u8]) -> Result<Self::Point,()>;
+}
+
+/// The decaf group, with the generators of
+/// `PedersenGenerators::default()`.
+pub struct Decaf;
+
+impl Group for Decaf {
+    type Scalar = Scalar;
+    type Point = DecafPoint;
+
+    const NAME: &'static str = "decaf ed25519";
+    const SCALAR_SIZE: usize = 32;
+    const POINT_SIZE: usize = 32;
+
+    fn basepoint() -> DecafPoint {
+        dalek_constants::DECAF_ED25519_BASEPOINT
+    }
+
+    fn blinding_point() -> DecafPoint {
+        PedersenGenerators::default().H
+    }
+
+    fn random_scalar<R: Rng>(csprng: &mut R) -> Scalar {
+        Scalar::random(csprng)
+    }
+
+    fn scalar_from_le_bytes(bytes: &[u8; 32]) -> Scalar {
+        Scalar::from_bytes_mod_order(*bytes)
+    }
+
+    fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_sub(a: &Scalar, b: &Scalar) -> Scalar {
+        a - b
+    }
+
+    fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn add(a: &DecafPoint, b: &DecafPoint) -> DecafPoint {
+        a + b
+    }
+
+    fn sub(a: &DecafPoint, b: &DecafPoint) -> DecafPoint {
+        a - b
+    }
+
+    fn mul(point: &DecafPoint, scalar: &Scalar) -> DecafPoint {
+        point * scalar
+    }
+
+    fn encode_scalar(scalar: &Scalar, out: &mut Vec<u8>) {
+        out.extend_from_slice(scalar.as_bytes());
+    }
+
+    fn decode_scalar(bytes: &[u8]) -> Result<Scalar,()> {
+        __decode_scalar(bytes)
+    }
+
+    fn encode_point(point: &DecafPoint, out: &mut Vec<u8>) {
+        out.extend_from_slice(point.compress().as_bytes());
+    }
+
+    fn decode_point(bytes: &[u8]) -> Result<DecafPoint,()> {
+        __decode_point(bytes)
+    }
+}
+
+/// secp256k1, with the `secp256k1` feature.
+///
+/// The public keys are multiples of its standard generator.  The
+/// blinding generator is the first point with even `y` whose `x` is
+/// the first 32 bytes of the SHA-512 hash of `"zkp cross-group
+/// secp256k1 blinding" || i`, for a counter byte `i = 0, 1, ...`, so
+/// nobody knows its discrete logarithm.  Scalars are encoded as 32
+/// big-endian bytes and points in compressed SEC 1 form, as 33 bytes,
+/// with the identity as 33 zero bytes.
+#[cfg(feature = "secp256k1")]
+pub struct Secp256k1;
+
+#[cfg(feature = "secp256k1")]
+impl Group for Secp256k1 {
+    type Scalar = k256::Scalar;
+    type Point = k256::ProjectivePoint;
+
+    const NAME: &'static str = "secp256k1";
+    const SCALAR_SIZE: usize = 32;
+    const POINT_SIZE: usize = 33;
+
+    fn basepoint() -> k256::ProjectivePoint {
+        k256::ProjectivePoint::GENERATOR
+    }
+
+    fn blinding_point() -> k256::ProjectivePoint {
+        for i in 0..=255u8 {
+            let mut hash = Sha512::default();
+            hash.input(b"zkp cross-group secp256k1 blinding");
+            hash.input(&[i]);
+            let mut encoding = [2u8; 33];
+            encoding[1..].copy_from_slice(&hash.result()[..32]);
+            if let Ok(point) = Secp256k1::decode_point(&encoding) {
+                return point;
+            }
+        }
+        unreachable!("no valid x-coordinate among 256 hashes")
+    }
+
+    fn random_scalar<R: Rng>(csprng: &mut R) -> k256::Scalar {
+        // Rejection sampling, since the order is just below 2^256.
+        loop {
+            let mut bytes = [0u8; 32];
+            csprng.fill_bytes(&mut bytes);
+            if let Ok(scalar) = Secp256k1::decode_scalar(&bytes) {
+                return scalar;
+            }
+        }
+    }
+
+    fn scalar_from_le_bytes(bytes: &[u8; 32]) -> k256::Scalar {
+        let mut encoding = *bytes;
+        encoding.reverse();
+        Secp256k1::decode_scalar(&encoding).expect("less than 2^BITS, so less than the order")
+    }
+
+    fn scalar_add(a: &k256::Scalar, b: &k256::Scalar) -> k256::Scalar {
+        *a + *b
+    }
+
+    fn scalar_sub(a: &k256::Scalar, b: &k256::Scalar) -> k256::Scalar {
+        *a - *b
+    }
+
+    fn scalar_mul(a: &k256::Scalar, b: &k256::Scalar) -> k256::Scalar {
+        *a * *b
+    }
+
+    fn add(a: &k256::ProjectivePoint, b: &k256::ProjectivePoint) -> k256::ProjectivePoint {
+        *a + *b
+    }
+
+    fn sub(a: &k256::ProjectivePoint, b: &k256::ProjectivePoint) -> k256::ProjectivePoint {
+        *a - *b
+    }
+
+    fn mul(point: &k256::ProjectivePoint, scalar: &k256::Scalar) -> k256::ProjectivePoint {
+        *point * *scalar
+    }
+
+    fn encode_scalar(scalar: &k256::Scalar, out: &mut Vec<u8>) {
+        use k256::elliptic_curve::ff::PrimeField;
+        out.extend_from_slice(&scalar.to_repr());
+    }
+
+    fn decode_scalar(bytes: &[u8]) -> Result<k256::Scalar,()> {
+        use k256::elliptic_curve::ff::PrimeField;
+        if bytes.len() != 32 {
+            return Err(());
+        }
+        Option::from(k256::Scalar::from_repr(*k256::FieldBytes::from_slice(bytes))).ok_or(())
+    }
+
+    fn encode_point(point: &k256::ProjectivePoint, out: &mut Vec<u8>) {
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+        let encoded = point.to_affine().to_encoded_point(true);
+        if encoded.len() == 33 {
+            out.extend_from_slice(encoded.as_bytes());
+        } else {
+            out.extend_from_slice(&[0u8; 33]);
+        }
+    }
+
+    fn decode_point(bytes: &[u8]) -> Result<k256::ProjectivePoint,()> {
+        use k256::elliptic_curve::sec1::FromEncodedPoint;
+        if bytes.len() != 33 {
+            return Err(());
+        }
+        if bytes.iter().all(|&byte| byte == 0) {
+            return Ok(k256::ProjectivePoint::IDENTITY);
+        }
+        let encoded = k256::EncodedPoint::from_bytes(bytes).map_err(|_| ())?;
+        let point: Option<k256::AffinePoint> = k256::AffinePoint::from_encoded_point(&encoded).into();
+        point.map(k256::ProjectivePoint::from).ok_or(())
+    }
+}
+
+/// A proof that one bit is committed to in both groups.
+struct BitProof<G1: Group, G2: Group> {
+    commitments: (G1::Point, G2::Point),
+    challenges: [[u8; CHALLENGE_SIZE]; 2],
+    responses: [(G1::Scalar, G2::Scalar); 2],
+}
+
+/// A proof that `G1::basepoint() * x` and `G2::basepoint() * x` have
+/// the same discrete logarithm `x`.
+pub struct CrossGroupProof<G1: Group, G2: Group> {
+    bits: Vec<BitProof<G1, G2>>,
+    groups: PhantomData<(G1, G2)>,
+}
+
+/// The integer `n` as a scalar.
+fn small<G: Group>(n: u8) -> G::Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[0] = n;
+    G::scalar_from_le_bytes(&bytes)
+}
+
+/// A challenge, as a scalar.
+fn challenge_scalar<G: Group>(challenge: &[u8; CHALLENGE_SIZE]) -> G::Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[..CHALLENGE_SIZE].copy_from_slice(challenge);
+    G::scalar_from_le_bytes(&bytes)
+}
+
+/// Pick blindings `r_1, ..., r_{n-1}` at random, and
+/// `r_0 = - sum 2^i r_i`, so that the bit commitments add up to the
+/// public key.
+fn blindings<G: Group, R: Rng>(csprng: &mut R) -> Vec<G::Scalar> {
+    let mut blindings: Vec<G::Scalar> = (0..BITS).map(|_| G::random_scalar(csprng)).collect();
+    let mut r_0 = small::<G>(0);
+    let mut power = small::<G>(1);
+    for r_i in blindings.iter().skip(1) {
+        power = G::scalar_add(&power, &power);
+        r_0 = G::scalar_sub(&r_0, &G::scalar_mul(&power, r_i));
+    }
+    blindings[0] = r_0;
+    blindings
+}
+
+/// `b ? x : y`, for `b` zero or one.
+fn select<G: Group>(b: &G::Scalar, not_b: &G::Scalar, x: &G::Point, y: &G::Point) -> G::Point {
+    G::add(&G::mul(x, b), &G::mul(y, not_b))
+}
+
+/// `b ? x : y`, for `b` zero or one.
+fn select_scalar<G: Group>(b: &G::Scalar, not_b: &G::Scalar, x: &G::Scalar, y: &G::Scalar) -> G::Scalar {
+    G::scalar_add(&G::scalar_mul(x, b), &G::scalar_mul(y, not_b))
+}
+
+fn input_length(hash: &mut Sha512, length: usize) {
+    hash.input(&::length_bytes(length));
+}
+
+fn input_point<G: Group>(hash: &mut Sha512, point: &G::Point) {
+    let mut bytes = Vec::with_capacity(G::POINT_SIZE);
+    G::encode_point(point, &mut bytes);
+    hash.input(&bytes);
+}
+
+/// Start the challenge hash, with everything but the bits.
+fn start_hash<G1: Group, G2: Group>(context: &[u8], P: &G1::Point, Q: &G2::Point) -> Sha512 {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp cross-group dleq");
+    input_length(&mut hash, context.len());
+    hash.input(context);
+    input_length(&mut hash, G1::NAME.len());
+    hash.input(G1::NAME.as_bytes());
+    input_length(&mut hash, G2::NAME.len());
+    hash.input(G2::NAME.as_bytes());
+    input_point::<G1>(&mut hash, P);
+    input_point::<G2>(&mut hash, Q);
+    hash
+}
+
+/// Add one bit's commitment, and the commitments of both branches of
+/// its OR-proof in both groups, to the challenge hash.
+fn input_bit<G1: Group, G2: Group>(
+    hash: &mut Sha512,
+    commitments: &(G1::Point, G2::Point),
+    branches: &[(G1::Point, G2::Point); 2],
+) {
+    input_point::<G1>(hash, &commitments.0);
+    input_point::<G2>(hash, &commitments.1);
+    for branch in branches.iter() {
+        input_point::<G1>(hash, &branch.0);
+        input_point::<G2>(hash, &branch.1);
+    }
+}
+
+fn finish_hash(hash: Sha512) -> [u8; CHALLENGE_SIZE] {
+    let mut challenge = [0u8; CHALLENGE_SIZE];
+    challenge.copy_from_slice(&hash.result()[..CHALLENGE_SIZE]);
+    challenge
+}
+
+fn xor(a: &[u8; CHALLENGE_SIZE], b: &[u8; CHALLENGE_SIZE]) -> [u8; CHALLENGE_SIZE] {
+    let mut out = [0u8; CHALLENGE_SIZE];
+    for i in 0..CHALLENGE_SIZE {
+        out[i] = a[i] ^ b[i];
+    }
+    out
+}
+
+/// `mask ? x : y`, for `mask` either `0xff` or zero.
+fn select_bytes(mask: u8, x: &[u8; CHALLENGE_SIZE], y: &[u8; CHALLENGE_SIZE]) -> [u8; CHALLENGE_SIZE] {
+    let mut out = [0u8; CHALLENGE_SIZE];
+    for i in 0..CHALLENGE_SIZE {
+        out[i] = (x[i] & mask) | (y[i] & !mask);
+    }
+    out
+}
+
+/// The commitment of branch `j` of an OR-proof in one group, from its
+/// challenge and response.
+fn branch<G: Group>(
+    commitment: &G::Point,
+    j: u8,
+    challenge: &[u8; CHALLENGE_SIZE],
+    response: &G::Scalar,
+) -> G::Point {
+    let mut target = *commitment;
+    if j == 1 {
+        target = G::sub(&target, &G::basepoint());
+    }
+    G::sub(
+        &G::mul(&G::blinding_point(), response),
+        &G::mul(&target, &challenge_scalar::<G>(challenge)),
+    )
+}
+
+/// The nonces of one bit's OR-proof in one group.
+struct Nonces<G: Group> {
+    b: G::Scalar,
+    not_b: G::Scalar,
+    k: G::Scalar,
+    response: G::Scalar,
+}
+
+/// Commit to bit `b` in `G`, for the OR-proof with (real or simulated)
+/// challenge `simulated` on the branch the prover cannot prove.
+fn commit_bit<G: Group, R: Rng>(
+    csprng: &mut R,
+    bit: u8,
+    blinding: &G::Scalar,
+    simulated: &[u8; CHALLENGE_SIZE],
+) -> (G::Point, [G::Point; 2], Nonces<G>) {
+    let b = small::<G>(bit);
+    let not_b = G::scalar_sub(&small::<G>(1), &b);
+    let C = G::add(&G::mul(&G::basepoint(), &b), &G::mul(&G::blinding_point(), blinding));
+
+    // The real branch commits to H*k; the other branch is simulated
+    // from the challenge `simulated` and a random response.
+    let k = G::random_scalar(csprng);
+    let response = G::random_scalar(csprng);
+    let real = G::mul(&G::blinding_point(), &k);
+    let sim0 = branch::<G>(&C, 0, simulated, &response);
+    let sim1 = branch::<G>(&C, 1, simulated, &response);
+
+    // A0 = b ? sim0 : real, A1 = b ? real : sim1
+    let A0 = select::<G>(&b, &not_b, &sim0, &real);
+    let A1 = select::<G>(&b, &not_b, &real, &sim1);
+
+    (C, [A0, A1], Nonces{ b: b, not_b: not_b, k: k, response: response })
+}
+
+/// The responses of both branches of one bit's OR-proof in one group.
+fn respond_bit<G: Group>(
+    nonces: &Nonces<G>,
+    blinding: &G::Scalar,
+    real: &[u8; CHALLENGE_SIZE],
+) -> [G::Scalar; 2] {
+    let response = G::scalar_add(
+        &G::scalar_mul(&challenge_scalar::<G>(real), blinding),
+        &nonces.k,
+    );
+    [
+        select_scalar::<G>(&nonces.b, &nonces.not_b, &nonces.response, &response),
+        select_scalar::<G>(&nonces.b, &nonces.not_b, &response, &nonces.response),
+    ]
+}
+
+impl<G1: Group, G2: Group> CrossGroupProof<G1, G2> {
+    /// Prove that the public keys for the secret `x`, given as 32
+    /// little-endian bytes, in `G1` and `G2` have the same secret key,
+    /// bound to `context`.
+    ///
+    /// Returns `Err(())` if `x` is not less than `2^BITS`.
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        context: &[u8],
+        x: &[u8; 32],
+    ) -> Result<CrossGroupProof<G1, G2>,()> {
+        if x[31] >> (BITS - 248) != 0 {
+            return Err(());
+        }
+        let P = G1::mul(&G1::basepoint(), &G1::scalar_from_le_bytes(x));
+        let Q = G2::mul(&G2::basepoint(), &G2::scalar_from_le_bytes(x));
+        let r = blindings::<G1, R>(csprng);
+        let s = blindings::<G2, R>(csprng);
+
+        let mut hash = start_hash::<G1, G2>(context, &P, &Q);
+        let mut pending = Vec::with_capacity(BITS);
+        for i in 0..BITS {
+            let bit = (x[i / 8] >> (i % 8)) & 1;
+            let mut simulated = [0u8; CHALLENGE_SIZE];
+            csprng.fill_bytes(&mut simulated);
+            let (C, A, n1) = commit_bit::<G1, R>(csprng, bit, &r[i], &simulated);
+            let (D, B, n2) = commit_bit::<G2, R>(csprng, bit, &s[i], &simulated);
+            input_bit::<G1, G2>(&mut hash, &(C, D), &[(A[0], B[0]), (A[1], B[1])]);
+            pending.push((bit, (C, D), simulated, n1, n2));
+        }
+        let challenge = finish_hash(hash);
+
+        let bits = pending.into_iter().enumerate().map(|(i, (bit, commitments, simulated, n1, n2))| {
+            let real = xor(&challenge, &simulated);
+            let mask = 0u8.wrapping_sub(bit);
+            let z1 = respond_bit::<G1>(&n1, &r[i], &real);
+            let z2 = respond_bit::<G2>(&n2, &s[i], &real);
+            BitProof{
+                commitments: commitments,
+                challenges: [
+                    select_bytes(mask, &simulated, &real),
+                    select_bytes(mask, &real, &simulated),
+                ],
+                responses: [(z1[0], z2[0]), (z1[1], z2[1])],
+            }
+        }).collect();
+
+        Ok(CrossGroupProof{ bits: bits, groups: PhantomData })
+    }
+
+    /// Verify that `P` in `G1` and `Q` in `G2` have the same secret
+    /// key, less than `2^BITS`, for a proof bound to `context`.
+    pub fn verify(&self, P: &G1::Point, Q: &G2::Point, context: &[u8]) -> Result<(),()> {
+        if self.bits.len() != BITS {
+            return Err(());
+        }
+
+        // Check that the bit commitments add up to the public keys.
+        let mut sum = self.bits[BITS - 1].commitments;
+        for bit in self.bits.iter().rev().skip(1) {
+            sum.0 = G1::add(&G1::add(&sum.0, &sum.0), &bit.commitments.0);
+            sum.1 = G2::add(&G2::add(&sum.1, &sum.1), &bit.commitments.1);
+        }
+        if encode::<G1>(&sum.0) != encode::<G1>(P) || encode::<G2>(&sum.1) != encode::<G2>(Q) {
+            return Err(());
+        }
+
+        let mut hash = start_hash::<G1, G2>(context, P, Q);
+        for bit in &self.bits {
+            let mut branches = [(G1::basepoint(), G2::basepoint()); 2];
+            for j in 0..2 {
+                branches[j] = (
+                    branch::<G1>(&bit.commitments.0, j as u8, &bit.challenges[j], &bit.responses[j].0),
+                    branch::<G2>(&bit.commitments.1, j as u8, &bit.challenges[j], &bit.responses[j].1),
+                );
+            }
+            input_bit::<G1, G2>(&mut hash, &bit.commitments, &branches);
+        }
+        let challenge = finish_hash(hash);
+
+        for bit in &self.bits {
+            if xor(&bit.challenges[0], &bit.challenges[1]) != challenge {
+                return Err(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The length of `to_bytes`.
+    pub fn serialized_size() -> usize {
+        BITS * (G1::POINT_SIZE + G2::POINT_SIZE
+                + 2 * (CHALLENGE_SIZE + G1::SCALAR_SIZE + G2::SCALAR_SIZE))
+    }
+
+    /// For each bit, its commitments in `G1` and `G2`, then for each
+    /// branch of its OR-proof, the challenge and the responses in `G1`
+    /// and `G2`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CrossGroupProof::<G1, G2>::serialized_size());
+        for bit in &self.bits {
+            G1::encode_point(&bit.commitments.0, &mut bytes);
+            G2::encode_point(&bit.commitments.1, &mut bytes);
+            for j in 0..2 {
+                bytes.extend_from_slice(&bit.challenges[j]);
+                G1::encode_scalar(&bit.responses[j].0, &mut bytes);
+                G2::encode_scalar(&bit.responses[j].1, &mut bytes);
+            }
+        }
+        bytes
+    }
+
+    /// Parse the encoding written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CrossGroupProof<G1, G2>,()> {
+        if bytes.len() != CrossGroupProof::<G1, G2>::serialized_size() {
+            return Err(());
+        }
+        let mut rest = bytes;
+        let mut take = |n: usize| {
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            head
+        };
+        let mut bits = Vec::with_capacity(BITS);
+        for _ in 0..BITS {
+            let commitments = (G1::decode_point(take(G1::POINT_SIZE))?, G2::decode_point(take(G2::POINT_SIZE))?);
+            let mut challenges = [[0u8; CHALLENGE_SIZE]; 2];
+            let mut responses = [(small::<G1>(0), small::<G2>(0)); 2];
+            for j in 0..2 {
+                challenges[j].copy_from_slice(take(CHALLENGE_SIZE));
+                responses[j] = (G1::decode_scalar(take(G1::SCALAR_SIZE))?, G2::decode_scalar(take(G2::SCALAR_SIZE))?);
+            }
+            bits.push(BitProof{ commitments: commitments, challenges: challenges, responses: responses });
+        }
+        Ok(CrossGroupProof{ bits: bits, groups: PhantomData })
+    }
+}
+
+fn encode<G: Group>(point: &G::Point) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(G::POINT_SIZE);
+    G::encode_point(point, &mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators;
+    use rand::OsRng;
+
+    /// Decaf with other generators, standing in for a second group.
+    struct OtherDecaf;
+
+    impl Group for OtherDecaf {
+        type Scalar = Scalar;
+        type Point = DecafPoint;
+
+        const NAME: &'static str = "other decaf";
+        const SCALAR_SIZE: usize = 32;
+        const POINT_SIZE: usize = 32;
+
+        fn basepoint() -> DecafPoint {
+            generators::derive_one(b"cross-group test", 0)
+        }
+
+        fn blinding_point() -> DecafPoint {
+            generators::derive_one(b"cross-group test", 1)
+        }
+
+        fn random_scalar<R: Rng>(csprng: &mut R) -> Scalar { Decaf::random_scalar(csprng) }
+        fn scalar_from_le_bytes(bytes: &[u8; 32]) -> Scalar { Decaf::scalar_from_le_bytes(bytes) }
+        fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar { Decaf::scalar_add(a, b) }
+        fn scalar_sub(a: &Scalar, b: &Scalar) -> Scalar { Decaf::scalar_sub(a, b) }
+        fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar { Decaf::scalar_mul(a, b) }
+        fn add(a: &DecafPoint, b: &DecafPoint) -> DecafPoint { Decaf::add(a, b) }
+        fn sub(a: &DecafPoint, b: &DecafPoint) -> DecafPoint { Decaf::sub(a, b) }
+        fn mul(point: &DecafPoint, scalar: &Scalar) -> DecafPoint { Decaf::mul(point, scalar) }
+        fn encode_scalar(scalar: &Scalar, out: &mut Vec<u8>) { Decaf::encode_scalar(scalar, out) }
+        fn decode_scalar(bytes: &[u8]) -> Result<Scalar,()> { Decaf::decode_scalar(bytes) }
+        fn encode_point(point: &DecafPoint, out: &mut Vec<u8>) { Decaf::encode_point(point, out) }
+        fn decode_point(bytes: &[u8]) -> Result<DecafPoint,()> { Decaf::decode_point(bytes) }
+    }
+
+    type Proof = CrossGroupProof<Decaf, OtherDecaf>;
+
+    #[test]
+    fn same_secret() {
+        let mut csprng = OsRng::new().unwrap();
+        let mut x = [0u8; 32];
+        csprng.fill_bytes(&mut x);
+        x[31] &= 0x0f;
+        let secret = Scalar::from_bytes_mod_order(x);
+        let P = &Decaf::basepoint() * &secret;
+        let Q = &OtherDecaf::basepoint() * &secret;
+
+        let proof = Proof::create(&mut csprng, b"swap 1", &x).unwrap();
+        assert!(proof.verify(&P, &Q, b"swap 1").is_ok());
+        assert!(proof.verify(&P, &Q, b"swap 2").is_err());
+        assert!(proof.verify(&P, &(&Q + &OtherDecaf::basepoint()), b"swap 1").is_err());
+        assert!(proof.verify(&Q, &P, b"swap 1").is_err());
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), Proof::serialized_size());
+        let parsed = Proof::from_bytes(&bytes).unwrap();
+        assert!(parsed.verify(&P, &Q, b"swap 1").is_ok());
+        assert!(Proof::from_bytes(&bytes[1..]).is_err());
+
+        x[31] = 0x10;
+        assert!(Proof::create(&mut csprng, b"swap 1", &x).is_err());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn secp256k1() {
+        let mut csprng = OsRng::new().unwrap();
+        let mut x = [0u8; 32];
+        csprng.fill_bytes(&mut x);
+        x[31] &= 0x0f;
+        let P = Decaf::mul(&Decaf::basepoint(), &Decaf::scalar_from_le_bytes(&x));
+        let Q = Secp256k1::mul(&Secp256k1::basepoint(), &Secp256k1::scalar_from_le_bytes(&x));
+
+        let proof = CrossGroupProof::<Decaf, Secp256k1>::create(&mut csprng, b"swap 1", &x).unwrap();
+        assert!(proof.verify(&P, &Q, b"swap 1").is_ok());
+        let other = Secp256k1::add(&Q, &Secp256k1::basepoint());
+        assert!(proof.verify(&P, &other, b"swap 1").is_err());
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), CrossGroupProof::<Decaf, Secp256k1>::serialized_size());
+        let parsed = CrossGroupProof::<Decaf, Secp256k1>::from_bytes(&bytes).unwrap();
+        assert!(parsed.verify(&P, &Q, b"swap 1").is_ok());
+
+        // Encodings round-trip, and non-canonical scalars are refused.
+        let H = Secp256k1::blinding_point();
+        assert_eq!(encode::<Secp256k1>(&Secp256k1::decode_point(&encode::<Secp256k1>(&H)).unwrap()),
+                   encode::<Secp256k1>(&H));
+        assert_ne!(encode::<Secp256k1>(&H), encode::<Secp256k1>(&Secp256k1::basepoint()));
+        assert!(Secp256k1::decode_scalar(&[0xff; 32]).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! DER encodings of proofs, for embedding in X.509 extensions and CMS
+//! structures.
+//!
+//! With the `der` feature, each `create_nipk!` module has
+//! `Proof::to_der(algorithm)` and `Proof::from_der(bytes, algorithm)`,
+//! using the ASN.1 type
+//!
+//! ```text
+//! ZkpProof ::= SEQUENCE {
+//!     algorithm   AlgorithmIdentifier,
+//!     challenge   OCTET STRING (SIZE (32)),
+//!     responses   OCTET STRING (SIZE (32)) ...
+//! }
+//!
+//! AlgorithmIdentifier ::= SEQUENCE {
+//!     algorithm   OBJECT IDENTIFIER,
+//!     parameters  UTF8String
+//! }
+//! ```
+//!
+//! with one response for each secret, in the order they were declared,
+//! and the name of the `create_nipk!` module as the parameters.  This
+//! crate has no registered object identifier, so the `algorithm` is
+//! given by the caller, as a list of arcs (e.g. `&[1, 3, 6, 1, 4, 1,
+//! ...]`), and identifies the protocol the proof belongs to.
+//!
+//! Decoding is strict: it rejects indefinite and non-minimal lengths,
+//! trailing data, a different algorithm or statement, and the wrong
+//! number of responses, so each proof has exactly one encoding.
+
+const SEQUENCE: u8 = 0x30;
+const OCTET_STRING: u8 = 0x04;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+
+fn put(out: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+    out.push(tag);
+    let length = contents.len();
+    if length < 0x80 {
+        out.push(length as u8);
+    } else {
+        let mut bytes = Vec::new();
+        let mut n = length;
+        while n > 0 {
+            bytes.insert(0, n as u8);
+            n >>= 8;
+        }
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+    out.extend_from_slice(contents);
+}
+
+/// Reads one element with the given tag, returning its contents.
+fn get<'a>(bytes: &mut &'a [u8], tag: u8) -> Result<&'a [u8],()> {
+    if bytes.len() < 2 || bytes[0] != tag {
+        return Err(());
+    }
+    let (length, header) = if bytes[1] < 0x80 {
+        (bytes[1] as usize, 2)
+    } else {
+        let n = (bytes[1] & 0x7f) as usize;
+        // Indefinite lengths (n = 0) are not DER.
+        if n == 0 || n > 8 || bytes.len() < 2 + n || bytes[2] == 0 {
+            return Err(());
+        }
+        let length = bytes[2..2 + n].iter().fold(0u64, |l, &b| (l << 8) | b as u64);
+        // The short form must be used when it fits.
+        if length < 0x80 || length > (bytes.len() - 2 - n) as u64 {
+            return Err(());
+        }
+        (length as usize, 2 + n)
+    };
+    if bytes.len() < header + length {
+        return Err(());
+    }
+    let contents = &bytes[header..header + length];
+    *bytes = &bytes[header + length..];
+    Ok(contents)
+}
+
+/// Appends one arc of an object identifier, in base 128 with the
+/// high bit set on all but the last digit.
+fn put_arc(out: &mut Vec<u8>, arc: u64) {
+    let mut digits = vec![(arc & 0x7f) as u8];
+    let mut value = arc >> 7;
+    while value > 0 {
+        digits.insert(0, (value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend_from_slice(&digits);
+}
+
+/// Encodes the arcs of an object identifier.
+///
+/// Returns `Err(())` if `arcs` is not a valid object identifier.
+fn encode_oid(arcs: &[u64]) -> Result<Vec<u8>,()> {
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] >= 40) {
+        return Err(());
+    }
+    let first = arcs[0].checked_mul(40).and_then(|arc| arc.checked_add(arcs[1])).ok_or(())?;
+    let mut out = Vec::new();
+    put_arc(&mut out, first);
+    for &arc in &arcs[2..] {
+        put_arc(&mut out, arc);
+    }
+    Ok(out)
+}
+
+fn algorithm_identifier(algorithm: &[u64], statement: &str) -> Result<Vec<u8>,()> {
+    let mut contents = Vec::new();
+    put(&mut contents, OBJECT_IDENTIFIER, &encode_oid(algorithm)?);
+    put(&mut contents, UTF8_STRING, statement.as_bytes());
+    Ok(contents)
+}
+
+/// Encodes a proof, given as the 32-byte chunks of `Proof::to_bytes`.
+#[doc(hidden)]
+pub fn __encode_proof(algorithm: &[u64], statement: &str, chunks: &[&[u8; 32]]) -> Result<Vec<u8>,()> {
+    let mut contents = Vec::new();
+    put(&mut contents, SEQUENCE, &algorithm_identifier(algorithm, statement)?);
+    for chunk in chunks {
+        put(&mut contents, OCTET_STRING, &chunk[..]);
+    }
+    let mut out = Vec::new();
+    put(&mut out, SEQUENCE, &contents);
+    Ok(out)
+}
+
+/// Decodes a proof with `scalars` octet strings, returning their
+/// concatenation for `Proof::from_bytes`.
+#[doc(hidden)]
+pub fn __decode_proof(
+    mut bytes: &[u8],
+    algorithm: &[u64],
+    statement: &str,
+    scalars: usize,
+) -> Result<Vec<u8>,()> {
+    let mut contents = get(&mut bytes, SEQUENCE)?;
+    if !bytes.is_empty() {
+        return Err(());
+    }
+    if get(&mut contents, SEQUENCE)? != &algorithm_identifier(algorithm, statement)?[..] {
+        return Err(());
+    }
+    let mut proof = Vec::with_capacity(32 * scalars);
+    for _ in 0..scalars {
+        let scalar = get(&mut contents, OCTET_STRING)?;
+        if scalar.len() != 32 {
+            return Err(());
+        }
+        proof.extend_from_slice(scalar);
+    }
+    if !contents.is_empty() {
+        return Err(());
+    }
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+    const ALGORITHM: &'static [u64] = &[1, 3, 6, 1, 4, 1, 99999, 1];
+
+    #[test]
+    fn oid() {
+        assert_eq!(encode_oid(&[1, 2, 840, 113549]).unwrap(), vec![0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d]);
+        assert_eq!(encode_oid(&[2, 999, 3]).unwrap(), vec![0x88, 0x37, 0x03]);
+        assert!(encode_oid(&[1]).is_err());
+        assert!(encode_oid(&[3, 1]).is_err());
+        assert!(encode_oid(&[1, 40]).is_err());
+        assert!(encode_oid(&[2, u64::max_value()]).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+        let proof = dlog::Proof::create(&mut csprng, dlog::Publics{A: &A, G: G}, dlog::Secrets{x: &x});
+
+        let der = proof.to_der(ALGORITHM).unwrap();
+        assert_eq!(&der[..2], &[SEQUENCE, (der.len() - 2) as u8]);
+        let parsed = dlog::Proof::from_der(&der, ALGORITHM).unwrap();
+        assert_eq!(parsed.to_bytes(), proof.to_bytes());
+
+        assert!(dlog::Proof::from_der(&der, &[1, 3, 6, 1, 4, 1, 99999, 2]).is_err());
+        assert!(proof.to_der(&[2, u64::max_value()]).is_err());
+        assert!(dlog::Proof::from_der(&der, &[2, u64::max_value()]).is_err());
+        assert!(dlog::Proof::from_der(&der[..der.len() - 1], ALGORITHM).is_err());
+        let mut trailing = der.clone();
+        trailing.push(0);
+        assert!(dlog::Proof::from_der(&trailing, ALGORITHM).is_err());
+
+        // A non-minimal length of the outer sequence.
+        let mut long = vec![SEQUENCE, 0x81];
+        long.extend_from_slice(&der[1..]);
+        assert!(dlog::Proof::from_der(&long, ALGORITHM).is_err());
+    }
+}
@@ -0,0 +1,367 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Complaints and justifications for Pedersen distributed key
+//! generation.
+//!
+//! Each participant has a long-term `Keypair`, and deals its
+//! contribution with `zkp::vss::deal`, broadcasting the commitment and
+//! sending each share encrypted to its recipient under a key made for
+//! that share alone: the dealer picks a fresh `e`, and sends `E = G*e`
+//! with
+//!
+//! ```text
+//! c = s + H(session, E, K)
+//! ```
+//!
+//! where `K = X_r*e = E*x_r` is the Diffie-Hellman key of `E` and the
+//! recipient's public key `X_r`.  The dealer keeps `e`, as a
+//! `ShareKey`, until the dealing is settled.
+//!
+//! If a recipient's share does not match the dealer's commitment, it
+//! broadcasts a `Complaint`, revealing `K` with a proof that it is
+//! `E*x_r`, so that everyone can decrypt the share and see that it is
+//! invalid.  The dealer answers a complaint it disputes, or preempts
+//! one, with a `Justification`: the same `K`, with a proof that it is
+//! `X_r*e`, which shows that the share it sent was valid.  Both prove
+//! the statement
+//!
+//! ```rust,ignore
+//! create_nipk!{dh_key, (x), (X, K, G, Y) : X = (G * x), K = (Y * x) }
+//! ```
+//!
+//! in a transcript labelled `"zkp dkg"`, to which the `Session` (the
+//! DKG's session identifier, and the dealer's and recipient's indices
+//! and public keys), every point of the dealer's commitment, the
+//! encrypted share, and the role (complaint or justification) are
+//! appended.  A complaint or justification therefore cannot be moved
+//! to another run of the protocol, another pair of participants,
+//! another dealing, or the other role.
+//!
+//! Since `K` depends on `e`, revealing it only reveals the one share
+//! encrypted with `E`, and nothing about the long-term keys or other
+//! shares: if it is invalid the dealer is disqualified, and if it is
+//! valid the recipient keeps it, now that it is public.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+use keypair::Keypair;
+use secret::Secret;
+use transcript::Transcript;
+use vss::{SecretShare, VssCommitment};
+
+create_nipk!{dh_key, (x), (X, K, G, Y) : X = (G * x), K = (Y * x) }
+
+/// The public part of one dealer-to-recipient exchange in a DKG run.
+#[derive(Copy, Clone)]
+pub struct Session<'a> {
+    /// The identifier of the DKG run, unique to its participants and
+    /// parameters.
+    pub id: &'a [u8],
+    pub dealer: u32,
+    pub dealer_public: &'a DecafPoint,
+    pub recipient: u32,
+    pub recipient_public: &'a DecafPoint,
+}
+
+/// A share `s`, encrypted to its recipient as `c = s + H(.., E, K)`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct EncryptedShare {
+    /// The dealer's key `E = G*e` for this share.
+    pub ephemeral: DecafPoint,
+    /// The encrypted share `c`.
+    pub value: Scalar,
+}
+
+/// The dealer's secret `e` for one encrypted share, kept to justify
+/// it.
+#[derive(Debug)]
+pub struct ShareKey(Secret<Scalar>);
+
+/// A recipient's claim that its share from a dealer is invalid.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Complaint {
+    pub key: DecafPoint,
+    pub proof: dh_key::Proof,
+}
+
+/// A dealer's demonstration that the share it sent a recipient is
+/// valid.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Justification {
+    pub key: DecafPoint,
+    pub proof: dh_key::Proof,
+}
+
+fn index_bytes(index: u32) -> [u8; 4] {
+    [index as u8, (index >> 8) as u8, (index >> 16) as u8, (index >> 24) as u8]
+}
+
+fn session_transcript(label: &[u8], session: &Session) -> Transcript {
+    let mut transcript = Transcript::new(label);
+    transcript.append_message(b"session", session.id);
+    transcript.append_message(b"dealer", &index_bytes(session.dealer));
+    transcript.append_point(b"X_d", session.dealer_public);
+    transcript.append_message(b"recipient", &index_bytes(session.recipient));
+    transcript.append_point(b"X_r", session.recipient_public);
+    transcript
+}
+
+/// The pad `H(session, E, K)` for a share.
+fn pad(session: &Session, ephemeral: &DecafPoint, key: &DecafPoint) -> Scalar {
+    let mut transcript = session_transcript(b"zkp dkg share encryption", session);
+    transcript.append_point(b"E", ephemeral);
+    transcript.append_point(b"K", key);
+    transcript.challenge_scalar(b"pad")
+}
+
+fn dispute_transcript(
+    session: &Session,
+    commitment: &VssCommitment,
+    encrypted: &EncryptedShare,
+    role: &[u8],
+) -> Transcript {
+    let mut transcript = session_transcript(b"zkp dkg", session);
+    for A_j in commitment.points() {
+        transcript.append_point(b"A", A_j);
+    }
+    transcript.append_point(b"E", &encrypted.ephemeral);
+    transcript.append_message(b"c", encrypted.value.as_bytes());
+    transcript.append_message(b"role", role);
+    transcript
+}
+
+/// Prove that `key = other * secret`, where `public = G * secret`.
+fn prove_key<R: Rng>(
+    csprng: &mut R,
+    transcript: &mut Transcript,
+    secret: &Scalar,
+    public: &DecafPoint,
+    other: &DecafPoint,
+) -> (DecafPoint, dh_key::Proof) {
+    let K = other * secret;
+    let proof = dh_key::Proof::create_from_transcript(
+        csprng,
+        transcript,
+        dh_key::Publics{
+            X: public,
+            K: &K,
+            G: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            Y: other,
+        },
+        dh_key::Secrets{ x: secret },
+    );
+    (K, proof)
+}
+
+/// Check a revealed key and decrypt the share with it.
+fn open(
+    transcript: &mut Transcript,
+    session: &Session,
+    prover: &DecafPoint,
+    other: &DecafPoint,
+    encrypted: &EncryptedShare,
+    key: &DecafPoint,
+    proof: &dh_key::Proof,
+) -> Result<SecretShare,()> {
+    proof.verify_from_transcript(
+        transcript,
+        dh_key::Publics{
+            X: prover,
+            K: key,
+            G: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            Y: other,
+        },
+    )?;
+    let mask = pad(session, &encrypted.ephemeral, key);
+    Ok(SecretShare::from_parts(session.recipient, &encrypted.value - &mask))
+}
+
+/// Encrypt the dealer's `share` for the recipient of `session`, under
+/// a fresh key, returning the encrypted share and the dealer's secret
+/// for it.
+///
+/// Panics if `share` is not the recipient's.
+pub fn encrypt_share<R: Rng>(
+    csprng: &mut R,
+    session: &Session,
+    share: &SecretShare,
+) -> (EncryptedShare, ShareKey) {
+    assert_eq!(share.index(), session.recipient);
+    let e = Scalar::random(csprng);
+    let E = &dalek_constants::DECAF_ED25519_BASEPOINT * &e;
+    let K = session.recipient_public * &e;
+    let encrypted = EncryptedShare{ ephemeral: E, value: share.value() + &pad(session, &E, &K) };
+    (encrypted, ShareKey(Secret::new(e)))
+}
+
+/// Decrypt the recipient's share from `encrypted`.
+///
+/// The share should then be checked with `VssCommitment::verify_share`,
+/// and a `Complaint` made if it is invalid.
+pub fn decrypt_share(session: &Session, recipient: &Keypair, encrypted: &EncryptedShare) -> SecretShare {
+    let K = &encrypted.ephemeral * recipient.secret();
+    let mask = pad(session, &encrypted.ephemeral, &K);
+    SecretShare::from_parts(session.recipient, &encrypted.value - &mask)
+}
+
+impl Complaint {
+    /// Complain, as the recipient of `session`, that `encrypted` is not
+    /// a valid share of the dealing with `commitment`.
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        session: &Session,
+        recipient: &Keypair,
+        commitment: &VssCommitment,
+        encrypted: &EncryptedShare,
+    ) -> Complaint {
+        let (key, proof) = prove_key(
+            csprng,
+            &mut dispute_transcript(session, commitment, encrypted, b"complaint"),
+            recipient.secret(),
+            recipient.public(),
+            &encrypted.ephemeral,
+        );
+        Complaint{ key: key, proof: proof }
+    }
+
+    /// Check the complaint, returning whether it is upheld, i.e.
+    /// whether the dealer sent an invalid share and is disqualified.
+    ///
+    /// Returns `Err(())` if the complaint's proof does not verify, in
+    /// which case it should be ignored, or the complainant disqualified.
+    pub fn verify(
+        &self,
+        session: &Session,
+        commitment: &VssCommitment,
+        encrypted: &EncryptedShare,
+    ) -> Result<bool,()> {
+        let share = open(
+            &mut dispute_transcript(session, commitment, encrypted, b"complaint"),
+            session,
+            session.recipient_public,
+            &encrypted.ephemeral,
+            encrypted,
+            &self.key,
+            &self.proof,
+        )?;
+        Ok(commitment.verify_share(&share).is_err())
+    }
+}
+
+impl Justification {
+    /// Justify, as the dealer of `session`, the share `encrypted`
+    /// sent to the recipient, with the `ShareKey` returned by
+    /// `encrypt_share`.
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        session: &Session,
+        key: &ShareKey,
+        commitment: &VssCommitment,
+        encrypted: &EncryptedShare,
+    ) -> Justification {
+        let (key, proof) = prove_key(
+            csprng,
+            &mut dispute_transcript(session, commitment, encrypted, b"justification"),
+            key.0.expose_secret(),
+            &encrypted.ephemeral,
+            session.recipient_public,
+        );
+        Justification{ key: key, proof: proof }
+    }
+
+    /// Check the justification, returning the recipient's share, which
+    /// is public from now on.
+    ///
+    /// Returns `Err(())` if the proof does not verify or the share is
+    /// invalid, in which case the dealer is disqualified.
+    pub fn verify(
+        &self,
+        session: &Session,
+        commitment: &VssCommitment,
+        encrypted: &EncryptedShare,
+    ) -> Result<SecretShare,()> {
+        let share = open(
+            &mut dispute_transcript(session, commitment, encrypted, b"justification"),
+            session,
+            &encrypted.ephemeral,
+            session.recipient_public,
+            encrypted,
+            &self.key,
+            &self.proof,
+        )?;
+        commitment.verify_share(&share)?;
+        Ok(share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+    use vss;
+
+    #[test]
+    fn complaints_and_justifications() {
+        let mut csprng = OsRng::new().unwrap();
+        let dealer = Keypair::generate(&mut csprng);
+        let recipient = Keypair::generate(&mut csprng);
+        let secret = Scalar::random(&mut csprng);
+        let (commitment, shares) = vss::deal(&mut csprng, &secret, 2, 3);
+        let session = Session{
+            id: b"dkg run 1",
+            dealer: 1,
+            dealer_public: dealer.public(),
+            recipient: 2,
+            recipient_public: recipient.public(),
+        };
+
+        // An honest share decrypts, and a complaint about it is rejected.
+        let (encrypted, share_key) = encrypt_share(&mut csprng, &session, &shares[1]);
+        let share = decrypt_share(&session, &recipient, &encrypted);
+        assert!(commitment.verify_share(&share).is_ok());
+        let complaint = Complaint::create(&mut csprng, &session, &recipient, &commitment, &encrypted);
+        assert_eq!(complaint.verify(&session, &commitment, &encrypted), Ok(false));
+        let justification = Justification::create(&mut csprng, &session, &share_key, &commitment, &encrypted);
+        let revealed = justification.verify(&session, &commitment, &encrypted).unwrap();
+        assert_eq!(revealed.value(), share.value());
+
+        // A bad share is caught, and cannot be justified.
+        let bad = EncryptedShare{ value: &encrypted.value + &Scalar::one(), ..encrypted };
+        assert!(commitment.verify_share(&decrypt_share(&session, &recipient, &bad)).is_err());
+        let complaint = Complaint::create(&mut csprng, &session, &recipient, &commitment, &bad);
+        assert_eq!(complaint.verify(&session, &commitment, &bad), Ok(true));
+        let justification = Justification::create(&mut csprng, &session, &share_key, &commitment, &bad);
+        assert!(justification.verify(&session, &commitment, &bad).is_err());
+
+        // Complaints are bound to the session, the dealing and the role.
+        let other_session = Session{ id: b"dkg run 2", ..session };
+        assert!(complaint.verify(&other_session, &commitment, &bad).is_err());
+        let swapped = Session{ recipient: 3, ..session };
+        assert!(complaint.verify(&swapped, &commitment, &bad).is_err());
+        let (other_commitment, _) = vss::deal(&mut csprng, &secret, 2, 3);
+        assert!(complaint.verify(&session, &other_commitment, &bad).is_err());
+        assert!(complaint.verify(&session, &commitment, &encrypted).is_err());
+        let as_justification = Justification{ key: complaint.key, proof: complaint.proof };
+        assert!(as_justification.verify(&session, &commitment, &bad).is_err());
+
+        // Each share has its own key, so revealing one reveals no other.
+        let (again, _) = encrypt_share(&mut csprng, &session, &shares[1]);
+        assert!(again.ephemeral.compress() != encrypted.ephemeral.compress());
+        let key = &again.ephemeral * recipient.secret();
+        assert!(key.compress() != complaint.key.compress());
+        let share = decrypt_share(&session, &recipient, &again);
+        assert_eq!(share.value(), shares[1].value());
+    }
+}
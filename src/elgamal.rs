@@ -0,0 +1,528 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! ElGamal encryption in the Decaf group, with proofs of correct
+//! encryption.
+//!
+//! A plaintext point `M` is encrypted under the public key `PK = B*x`
+//! as `(C1, C2) = (B*r, M + PK*r)`, where `B` is the Decaf basepoint.
+//! Scalar values `m` are encrypted "in the exponent" as `M = G*m` for
+//! the value generator `G` of a `PedersenGenerators`.
+//!
+//! Five proofs are provided:
+//!
+//! * an `EncryptionProof` that a ciphertext encrypts a known plaintext
+//!   point, proving knowledge of `r` with `C1 = B*r`, `C2 - M = PK*r`;
+//! * a `CommittedEncryptionProof` that a ciphertext encrypts the value
+//!   committed to by a Pedersen commitment `C = G*m + H*s`, without
+//!   revealing `m`;
+//! * a `DecryptionProof` that `S = C1*x` is the decryption share of a
+//!   ciphertext for the key `PK = B*x`, proving `PK = B*x`, `S = C1*x`
+//!   (Chaum-Pedersen), so that `M = C2 - S` is its correct decryption.
+//!   The proof is made in a transcript labelled
+//!   `"zkp elgamal decryption"` with the whole ciphertext appended, so
+//!   it cannot be reused for another ciphertext with the same `C1`.
+//!   With a key shared between trustees, each proves its own share
+//!   `S_i = C1*x_i` against its public key `B*x_i`;
+//! * a `ReencryptionProof` that a ciphertext `(C1', C2')` is a
+//!   re-randomization of `(C1, C2)` under the same key, proving
+//!   knowledge of `r` with `C1' - C1 = B*r`, `C2' - C2 = PK*r`.  As for
+//!   decryption proofs, both ciphertexts are appended to a transcript
+//!   labelled `"zkp elgamal reencryption"`, so that a mix-net's proof
+//!   for one pair of ciphertexts cannot be reused for another;
+//! * a `PlaintextEquivalenceProof` that ciphertexts `(C1, C2)` under
+//!   `PK` and `(C1', C2')` under `PK'` encrypt the same plaintext,
+//!   given the randomness of both, proving knowledge of `r` and `r'`
+//!   with `C1 = B*r`, `C1' = B*r'` and `C2 - C2' = PK*r - PK'*r'`.  Both
+//!   keys and ciphertexts are appended to a transcript labelled
+//!   `"zkp elgamal plaintext equivalence"`.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+use pedersen::{Opening, PedersenCommitment, PedersenGenerators};
+use transcript::Transcript;
+
+create_nipk!{known, (r), (C1, D, B, PK) : C1 = (B * r), D = (PK * r) }
+create_nipk!{decryption, (x), (PK, S, B, C1) : PK = (B * x), S = (C1 * x) }
+create_nipk!{reencryption, (r), (D1, D2, B, PK) : D1 = (B * r), D2 = (PK * r) }
+create_nipk!{equivalence, (r1, r2), (C1, E1, D, B, PK1, PK2)
+             : C1 = (B * r1), E1 = (B * r2), D = (PK1 * r1 - PK2 * r2) }
+create_nipk!{committed, (m, r, s), (C1, C2, C, B, G, H, PK)
+             : C1 = (B * r), C2 = (G * m + PK * r), C = (G * m + H * s) }
+
+/// A proof that a ciphertext encrypts a known plaintext point.
+pub use self::known::Proof as EncryptionProof;
+/// A proof that a ciphertext encrypts a Pedersen-committed value.
+pub use self::committed::Proof as CommittedEncryptionProof;
+/// A proof that a decryption share is correct.
+pub use self::decryption::Proof as DecryptionProof;
+/// A proof that a ciphertext is a re-randomization of another.
+pub use self::reencryption::Proof as ReencryptionProof;
+/// A proof that two ciphertexts under different keys have the same
+/// plaintext.
+pub use self::equivalence::Proof as PlaintextEquivalenceProof;
+
+/// The transcript for a decryption proof, bound to the ciphertext.
+fn decryption_transcript(ciphertext: &Ciphertext) -> Transcript {
+    let mut transcript = Transcript::new(b"zkp elgamal decryption");
+    transcript.append_point(b"C1", &ciphertext.C1);
+    transcript.append_point(b"C2", &ciphertext.C2);
+    transcript
+}
+
+/// The transcript for a re-encryption proof, bound to both
+/// ciphertexts.
+fn reencryption_transcript(original: &Ciphertext, reencrypted: &Ciphertext) -> Transcript {
+    let mut transcript = Transcript::new(b"zkp elgamal reencryption");
+    transcript.append_point(b"C1", &original.C1);
+    transcript.append_point(b"C2", &original.C2);
+    transcript.append_point(b"C1'", &reencrypted.C1);
+    transcript.append_point(b"C2'", &reencrypted.C2);
+    transcript
+}
+
+/// The transcript for a plaintext equivalence proof, bound to both
+/// keys and ciphertexts.
+fn equivalence_transcript(
+    pk1: &PublicKey,
+    ct1: &Ciphertext,
+    pk2: &PublicKey,
+    ct2: &Ciphertext,
+) -> Transcript {
+    let mut transcript = Transcript::new(b"zkp elgamal plaintext equivalence");
+    transcript.append_point(b"PK", &pk1.0);
+    transcript.append_point(b"C1", &ct1.C1);
+    transcript.append_point(b"C2", &ct1.C2);
+    transcript.append_point(b"PK'", &pk2.0);
+    transcript.append_point(b"C1'", &ct2.C1);
+    transcript.append_point(b"C2'", &ct2.C2);
+    transcript
+}
+
+/// Prove that `ct1`, encrypted under `pk1` with randomness `r1`, and
+/// `ct2`, encrypted under `pk2` with randomness `r2`, have the same
+/// plaintext.
+pub fn prove_plaintext_equivalence<R: Rng>(
+    csprng: &mut R,
+    (pk1, ct1, r1): (&PublicKey, &Ciphertext, &Scalar),
+    (pk2, ct2, r2): (&PublicKey, &Ciphertext, &Scalar),
+) -> PlaintextEquivalenceProof {
+    let D = &ct1.C2 - &ct2.C2;
+    let publics = equivalence::Publics{
+        C1: &ct1.C1,
+        E1: &ct2.C1,
+        D: &D,
+        B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+        PK1: &pk1.0,
+        PK2: &pk2.0,
+    };
+    PlaintextEquivalenceProof::create_from_transcript(
+        csprng,
+        &mut equivalence_transcript(pk1, ct1, pk2, ct2),
+        publics,
+        equivalence::Secrets{ r1: r1, r2: r2 },
+    )
+}
+
+/// Verify a proof that `ct1` under `pk1` and `ct2` under `pk2` have
+/// the same plaintext.
+pub fn verify_plaintext_equivalence(
+    (pk1, ct1): (&PublicKey, &Ciphertext),
+    (pk2, ct2): (&PublicKey, &Ciphertext),
+    proof: &PlaintextEquivalenceProof,
+) -> Result<(),()> {
+    let D = &ct1.C2 - &ct2.C2;
+    let publics = equivalence::Publics{
+        C1: &ct1.C1,
+        E1: &ct2.C1,
+        D: &D,
+        B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+        PK1: &pk1.0,
+        PK2: &pk2.0,
+    };
+    proof.verify_from_transcript(&mut equivalence_transcript(pk1, ct1, pk2, ct2), publics)
+}
+
+/// An ElGamal secret key `x`.
+pub struct SecretKey(Scalar);
+
+/// An ElGamal public key `PK = B*x`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PublicKey(pub DecafPoint);
+
+/// An ElGamal ciphertext `(C1, C2) = (B*r, M + PK*r)`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Ciphertext {
+    pub C1: DecafPoint,
+    pub C2: DecafPoint,
+}
+
+impl SecretKey {
+    /// Generate a fresh secret key.
+    pub fn generate<R: Rng>(csprng: &mut R) -> SecretKey {
+        SecretKey(Scalar::random(csprng))
+    }
+
+    /// Construct a secret key from an existing scalar.
+    pub fn from_scalar(x: Scalar) -> SecretKey {
+        SecretKey(x)
+    }
+
+    /// The public key `PK = B*x`.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(&dalek_constants::DECAF_ED25519_BASEPOINT * &self.0)
+    }
+
+    /// Decrypt a ciphertext to its plaintext point `M = C2 - C1*x`.
+    pub fn decrypt(&self, ciphertext: &Ciphertext) -> DecafPoint {
+        &ciphertext.C2 - &(&ciphertext.C1 * &self.0)
+    }
+
+    /// Compute the decryption share `S = C1*x` of a ciphertext, and
+    /// prove that it is correct.
+    pub fn prove_decryption_share<R: Rng>(
+        &self,
+        csprng: &mut R,
+        ciphertext: &Ciphertext,
+    ) -> (DecafPoint, DecryptionProof) {
+        let PK = self.public_key().0;
+        let S = &ciphertext.C1 * &self.0;
+        let publics = decryption::Publics{
+            PK: &PK,
+            S: &S,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            C1: &ciphertext.C1,
+        };
+        let proof = DecryptionProof::create_from_transcript(
+            csprng,
+            &mut decryption_transcript(ciphertext),
+            publics,
+            decryption::Secrets{ x: &self.0 },
+        );
+        (S, proof)
+    }
+
+    /// Decrypt a ciphertext to its plaintext point, and prove that the
+    /// decryption is correct.
+    pub fn prove_decryption<R: Rng>(
+        &self,
+        csprng: &mut R,
+        ciphertext: &Ciphertext,
+    ) -> (DecafPoint, DecryptionProof) {
+        let (S, proof) = self.prove_decryption_share(csprng, ciphertext);
+        (&ciphertext.C2 - &S, proof)
+    }
+}
+
+impl PublicKey {
+    /// Encrypt the point `M` using the randomness `r`.
+    pub fn encrypt_with(&self, M: &DecafPoint, r: &Scalar) -> Ciphertext {
+        Ciphertext{
+            C1: &dalek_constants::DECAF_ED25519_BASEPOINT * r,
+            C2: M + &(&self.0 * r),
+        }
+    }
+
+    /// Encrypt the point `M`, returning the ciphertext together with
+    /// the randomness used, which is needed to prove correctness.
+    pub fn encrypt<R: Rng>(&self, csprng: &mut R, M: &DecafPoint) -> (Ciphertext, Scalar) {
+        let r = Scalar::random(csprng);
+        (self.encrypt_with(M, &r), r)
+    }
+
+    /// Re-randomize a ciphertext under this key using the randomness
+    /// `r`, producing a fresh encryption of the same plaintext.
+    pub fn rerandomize(&self, ciphertext: &Ciphertext, r: &Scalar) -> Ciphertext {
+        Ciphertext{
+            C1: &ciphertext.C1 + &(&dalek_constants::DECAF_ED25519_BASEPOINT * r),
+            C2: &ciphertext.C2 + &(&self.0 * r),
+        }
+    }
+
+    /// Prove that `reencrypted` is `rerandomize(original, r)`.
+    pub fn prove_reencryption<R: Rng>(
+        &self,
+        csprng: &mut R,
+        original: &Ciphertext,
+        reencrypted: &Ciphertext,
+        r: &Scalar,
+    ) -> ReencryptionProof {
+        let D1 = &reencrypted.C1 - &original.C1;
+        let D2 = &reencrypted.C2 - &original.C2;
+        let publics = reencryption::Publics{
+            D1: &D1,
+            D2: &D2,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            PK: &self.0,
+        };
+        ReencryptionProof::create_from_transcript(
+            csprng,
+            &mut reencryption_transcript(original, reencrypted),
+            publics,
+            reencryption::Secrets{ r: r },
+        )
+    }
+
+    /// Verify a proof that `reencrypted` is a re-randomization of
+    /// `original` under this key.
+    pub fn verify_reencryption(
+        &self,
+        original: &Ciphertext,
+        reencrypted: &Ciphertext,
+        proof: &ReencryptionProof,
+    ) -> Result<(),()> {
+        let D1 = &reencrypted.C1 - &original.C1;
+        let D2 = &reencrypted.C2 - &original.C2;
+        let publics = reencryption::Publics{
+            D1: &D1,
+            D2: &D2,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            PK: &self.0,
+        };
+        proof.verify_from_transcript(&mut reencryption_transcript(original, reencrypted), publics)
+    }
+
+    /// Encrypt the value `m` as the point `G*m`.
+    pub fn encrypt_value<R: Rng>(
+        &self,
+        csprng: &mut R,
+        gens: &PedersenGenerators,
+        m: &Scalar,
+    ) -> (Ciphertext, Scalar) {
+        self.encrypt(csprng, &(&gens.G * m))
+    }
+
+    /// Prove that `ciphertext` is an encryption of `M` with
+    /// randomness `r`.
+    pub fn prove_encryption<R: Rng>(
+        &self,
+        csprng: &mut R,
+        ciphertext: &Ciphertext,
+        M: &DecafPoint,
+        r: &Scalar,
+    ) -> EncryptionProof {
+        let D = &ciphertext.C2 - M;
+        let publics = known::Publics{
+            C1: &ciphertext.C1,
+            D: &D,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            PK: &self.0,
+        };
+        EncryptionProof::create(csprng, publics, known::Secrets{ r: r })
+    }
+
+    /// Verify a proof that `ciphertext` is an encryption of `M`.
+    pub fn verify_encryption(
+        &self,
+        ciphertext: &Ciphertext,
+        M: &DecafPoint,
+        proof: &EncryptionProof,
+    ) -> Result<(),()> {
+        let D = &ciphertext.C2 - M;
+        proof.verify(known::Publics{
+            C1: &ciphertext.C1,
+            D: &D,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            PK: &self.0,
+        })
+    }
+
+    /// Verify a proof that `S` is the decryption share of `ciphertext`
+    /// for this key.
+    pub fn verify_decryption_share(
+        &self,
+        ciphertext: &Ciphertext,
+        S: &DecafPoint,
+        proof: &DecryptionProof,
+    ) -> Result<(),()> {
+        let publics = decryption::Publics{
+            PK: &self.0,
+            S: S,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            C1: &ciphertext.C1,
+        };
+        proof.verify_from_transcript(&mut decryption_transcript(ciphertext), publics)
+    }
+
+    /// Verify a proof that `M` is the decryption of `ciphertext` under
+    /// this key.
+    pub fn verify_decryption(
+        &self,
+        ciphertext: &Ciphertext,
+        M: &DecafPoint,
+        proof: &DecryptionProof,
+    ) -> Result<(),()> {
+        self.verify_decryption_share(ciphertext, &(&ciphertext.C2 - M), proof)
+    }
+
+    /// Prove that `ciphertext` encrypts the value committed to by
+    /// `commitment`, given the encryption randomness `r` and the
+    /// commitment `opening`.
+    pub fn prove_committed_encryption<R: Rng>(
+        &self,
+        csprng: &mut R,
+        gens: &PedersenGenerators,
+        ciphertext: &Ciphertext,
+        r: &Scalar,
+        commitment: &PedersenCommitment,
+        opening: &Opening,
+    ) -> CommittedEncryptionProof {
+        let publics = committed::Publics{
+            C1: &ciphertext.C1,
+            C2: &ciphertext.C2,
+            C: &commitment.0,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            G: &gens.G,
+            H: &gens.H,
+            PK: &self.0,
+        };
+        let secrets = committed::Secrets{ m: &opening.value, r: r, s: &opening.blinding };
+        CommittedEncryptionProof::create(csprng, publics, secrets)
+    }
+
+    /// Verify a proof that `ciphertext` encrypts the value committed
+    /// to by `commitment`.
+    pub fn verify_committed_encryption(
+        &self,
+        gens: &PedersenGenerators,
+        ciphertext: &Ciphertext,
+        commitment: &PedersenCommitment,
+        proof: &CommittedEncryptionProof,
+    ) -> Result<(),()> {
+        proof.verify(committed::Publics{
+            C1: &ciphertext.C1,
+            C2: &ciphertext.C2,
+            C: &commitment.0,
+            B: &dalek_constants::DECAF_ED25519_BASEPOINT,
+            G: &gens.G,
+            H: &gens.H,
+            PK: &self.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn encrypt_decrypt_and_prove() {
+        let mut csprng = OsRng::new().unwrap();
+        let sk = SecretKey::generate(&mut csprng);
+        let pk = sk.public_key();
+
+        let M = DecafPoint::random(&mut csprng);
+        let (ct, r) = pk.encrypt(&mut csprng, &M);
+        assert_eq!(sk.decrypt(&ct).compress(), M.compress());
+
+        let proof = pk.prove_encryption(&mut csprng, &ct, &M, &r);
+        assert!(pk.verify_encryption(&ct, &M, &proof).is_ok());
+
+        let other = DecafPoint::random(&mut csprng);
+        assert!(pk.verify_encryption(&ct, &other, &proof).is_err());
+    }
+
+    #[test]
+    fn reencryption() {
+        let mut csprng = OsRng::new().unwrap();
+        let sk = SecretKey::generate(&mut csprng);
+        let pk = sk.public_key();
+
+        let M = DecafPoint::random(&mut csprng);
+        let (ct, _) = pk.encrypt(&mut csprng, &M);
+        let r = Scalar::random(&mut csprng);
+        let reencrypted = pk.rerandomize(&ct, &r);
+        assert_eq!(sk.decrypt(&reencrypted).compress(), M.compress());
+
+        let proof = pk.prove_reencryption(&mut csprng, &ct, &reencrypted, &r);
+        assert!(pk.verify_reencryption(&ct, &reencrypted, &proof).is_ok());
+        assert!(pk.verify_reencryption(&reencrypted, &ct, &proof).is_err());
+
+        // A different plaintext, with the same randomness.
+        let N = DecafPoint::random(&mut csprng);
+        let (other, _) = pk.encrypt(&mut csprng, &N);
+        let forged = Ciphertext{ C1: reencrypted.C1, C2: other.C2 };
+        let forged_proof = pk.prove_reencryption(&mut csprng, &ct, &forged, &r);
+        assert!(pk.verify_reencryption(&ct, &forged, &forged_proof).is_err());
+
+        let other_pk = SecretKey::generate(&mut csprng).public_key();
+        assert!(other_pk.verify_reencryption(&ct, &reencrypted, &proof).is_err());
+    }
+
+    #[test]
+    fn plaintext_equivalence() {
+        let mut csprng = OsRng::new().unwrap();
+        let sk1 = SecretKey::generate(&mut csprng);
+        let sk2 = SecretKey::generate(&mut csprng);
+        let (pk1, pk2) = (sk1.public_key(), sk2.public_key());
+
+        let M = DecafPoint::random(&mut csprng);
+        let (ct1, r1) = pk1.encrypt(&mut csprng, &M);
+        let (ct2, r2) = pk2.encrypt(&mut csprng, &M);
+        let proof = prove_plaintext_equivalence(&mut csprng, (&pk1, &ct1, &r1), (&pk2, &ct2, &r2));
+        assert!(verify_plaintext_equivalence((&pk1, &ct1), (&pk2, &ct2), &proof).is_ok());
+        assert!(verify_plaintext_equivalence((&pk2, &ct2), (&pk1, &ct1), &proof).is_err());
+        assert!(verify_plaintext_equivalence((&pk1, &ct1), (&pk1, &ct2), &proof).is_err());
+
+        let N = DecafPoint::random(&mut csprng);
+        let (ct3, r3) = pk2.encrypt(&mut csprng, &N);
+        let proof = prove_plaintext_equivalence(&mut csprng, (&pk1, &ct1, &r1), (&pk2, &ct3, &r3));
+        assert!(verify_plaintext_equivalence((&pk1, &ct1), (&pk2, &ct3), &proof).is_err());
+    }
+
+    #[test]
+    fn committed_encryption() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+        let pk = SecretKey::generate(&mut csprng).public_key();
+
+        let m = Scalar::from_u64(42);
+        let opening = Opening::random(&mut csprng, m);
+        let commitment = gens.commit(&opening);
+        let (ct, r) = pk.encrypt_value(&mut csprng, &gens, &m);
+
+        let proof = pk.prove_committed_encryption(
+            &mut csprng, &gens, &ct, &r, &commitment, &opening,
+        );
+        assert!(pk.verify_committed_encryption(&gens, &ct, &commitment, &proof).is_ok());
+
+        let other = gens.commit(&Opening::random(&mut csprng, Scalar::from_u64(43)));
+        assert!(pk.verify_committed_encryption(&gens, &ct, &other, &proof).is_err());
+    }
+
+    #[test]
+    fn decryption() {
+        let mut csprng = OsRng::new().unwrap();
+        let sk = SecretKey::generate(&mut csprng);
+        let pk = sk.public_key();
+
+        let M = DecafPoint::random(&mut csprng);
+        let (ct, _) = pk.encrypt(&mut csprng, &M);
+        let (decrypted, proof) = sk.prove_decryption(&mut csprng, &ct);
+        assert_eq!(decrypted.compress(), M.compress());
+        assert!(pk.verify_decryption(&ct, &M, &proof).is_ok());
+
+        let other = DecafPoint::random(&mut csprng);
+        assert!(pk.verify_decryption(&ct, &other, &proof).is_err());
+
+        // The proof is bound to the whole ciphertext, not just `C1`.
+        let moved = Ciphertext{ C1: ct.C1, C2: &ct.C2 + &other };
+        let moved_M = &M + &other;
+        assert!(pk.verify_decryption(&moved, &moved_M, &proof).is_err());
+
+        let other_pk = SecretKey::generate(&mut csprng).public_key();
+        assert!(other_pk.verify_decryption(&ct, &M, &proof).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Hex and base64 encodings of proofs, with the `text-encoding`
+//! feature.
+//!
+//! These encode the output of `Proof::to_bytes`.  Hex is written in
+//! lowercase, and accepted in either case.  Base64 uses the standard
+//! alphabet with `=` padding (RFC 4648, section 4), and decoding is
+//! strict: it rejects missing or extra padding, whitespace, and
+//! non-zero trailing bits, so each proof has exactly one base64
+//! encoding.
+//!
+//! ```rust,ignore
+//! use zkp::encoding::TextEncoding;
+//!
+//! let text = proof.to_base64();
+//! let parsed = dleq::Proof::from_base64(&text).unwrap();
+//! ```
+
+use hex;
+use ProofBytes;
+
+const BASE64: &'static [u8; 64]
+    = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(4 * ((bytes.len() + 2) / 3));
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn base64_digit(c: u8) -> Result<u32,()> {
+    BASE64.iter().position(|&d| d == c).map(|i| i as u32).ok_or(())
+}
+
+fn base64_decode(s: &str) -> Result<Vec<u8>,()> {
+    let s = s.as_bytes();
+    if s.len() % 4 != 0 {
+        return Err(());
+    }
+    let mut bytes = Vec::with_capacity(3 * (s.len() / 4));
+    let groups = s.len() / 4;
+    for (i, group) in s.chunks(4).enumerate() {
+        // Padding may only appear at the end of the last group.
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && i + 1 != groups) {
+            return Err(());
+        }
+        let mut n = 0u32;
+        for &c in &group[..4 - padding] {
+            n = (n << 6) | base64_digit(c)?;
+        }
+        n <<= 6 * padding as u32;
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        // The bits beyond the last byte must be zero.
+        if (padding == 1 && decoded[2] != 0) || (padding == 2 && decoded[1] != 0) {
+            return Err(());
+        }
+        bytes.extend_from_slice(&decoded[..3 - padding]);
+    }
+    Ok(bytes)
+}
+
+/// Hex and base64 encodings, for any proof with a byte encoding.
+pub trait TextEncoding: ProofBytes {
+    fn to_hex(&self) -> String {
+        hex::encode(&self.to_bytes())
+    }
+
+    fn from_hex(s: &str) -> Result<Self,()> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+
+    fn to_base64(&self) -> String {
+        base64_encode(&self.to_bytes())
+    }
+
+    fn from_base64(s: &str) -> Result<Self,()> {
+        Self::from_bytes(&base64_decode(s)?)
+    }
+}
+
+impl<T: ProofBytes> TextEncoding for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    #[test]
+    fn base64_vectors() {
+        // From RFC 4648, section 10.
+        let vectors = [
+            ("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy"),
+        ];
+        for &(plain, encoded) in vectors.iter() {
+            assert_eq!(base64_encode(plain.as_bytes()), encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), plain.as_bytes());
+        }
+        assert!(base64_decode("Zg=").is_err());
+        assert!(base64_decode("Zh==").is_err());
+        assert!(base64_decode("Zg==Zg==").is_err());
+        assert!(base64_decode("Zm9v\n").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+        let publics = dlog::Publics{A: &A, G: G};
+        let proof = dlog::Proof::create(&mut csprng, publics, dlog::Secrets{x: &x});
+
+        let hex = proof.to_hex();
+        assert_eq!(hex.len(), 128);
+        assert!(dlog::Proof::from_hex(&hex).unwrap() == proof);
+        assert!(dlog::Proof::from_hex(&hex.to_uppercase()).unwrap() == proof);
+
+        let base64 = proof.to_base64();
+        assert_eq!(base64.len(), 88);
+        assert!(dlog::Proof::from_base64(&base64).unwrap() == proof);
+        assert!(dlog::Proof::from_base64(&base64[4..]).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs which carry what they are proofs of.
+//!
+//! The bytes of a proof do not say which statement they prove, nor
+//! the version and context of their challenge, so a proof kept with
+//! this information out of band can be checked against the wrong
+//! statement.  An `Envelope` keeps them together: the identifier of
+//! the statement in a `zkp::any::Registry`, the `Version` of the
+//! challenge, the application context given to
+//! `Proof::create_with_context`, and the proof as in `Proof::to_bytes`.
+//!
+//! ```rust,ignore
+//! let proof = dleq::Proof::create_with_context(&mut csprng, b"my app", publics, secrets);
+//! let bytes = Envelope::new("dleq", b"my app", &proof).to_bytes();
+//!
+//! let envelope = Envelope::from_bytes(&bytes)?;
+//! let proof = envelope.open(&registry, "dleq", &publics_bytes)?;
+//! ```
+//!
+//! `open` refuses an envelope for any statement but the expected one,
+//! or for any version but `Version::CURRENT`, rather than trusting the
+//! identifier and version inside it; `open_version` accepts an older
+//! version chosen by the caller.  Envelopes are
+//! serialized with Serde, or as
+//!
+//! ```text
+//! len(statement_id) || statement_id || version || len(context) || context || proof
+//! ```
+//!
+//! with the lengths as 8-byte little-endian integers and the version
+//! as a single byte (0 for `Version::V0`, 1 for `Version::V1` and 2
+//! for `Version::V2`).
+
+use any::{AnyProof, Registry};
+use Version;
+
+/// A proof with its statement, version and context.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// The identifier of the statement in a `Registry`.
+    pub statement_id: String,
+    /// The version of the challenge.
+    pub version: Version,
+    /// The application context the proof is bound to.
+    pub context: Vec<u8>,
+    /// The proof, as in `Proof::to_bytes`.
+    pub proof_bytes: Vec<u8>,
+}
+
+fn version_byte(version: Version) -> u8 {
+    match version {
+        Version::V0 => 0,
+        Version::V1 => 1,
+        Version::V2 => 2,
+    }
+}
+
+fn version_from_byte(byte: u8) -> Result<Version,()> {
+    match byte {
+        0 => Ok(Version::V0),
+        1 => Ok(Version::V1),
+        2 => Ok(Version::V2),
+        _ => Err(()),
+    }
+}
+
+/// Split `bytes` after a string prefixed by its length, as an 8-byte
+/// little-endian integer.
+fn take_prefixed(bytes: &[u8]) -> Result<(&[u8], &[u8]),()> {
+    if bytes.len() < 8 {
+        return Err(());
+    }
+    let (length, rest) = bytes.split_at(8);
+    let mut n = 0u64;
+    for i in 0..8 {
+        n |= (length[i] as u64) << (8 * i);
+    }
+    if (rest.len() as u64) < n {
+        return Err(());
+    }
+    Ok(rest.split_at(n as usize))
+}
+
+impl Envelope {
+    /// An envelope for `proof`, made by `Proof::create_with_context`
+    /// with `context`, of the statement registered under
+    /// `statement_id`.
+    pub fn new(statement_id: &str, context: &[u8], proof: &dyn AnyProof) -> Envelope {
+        Envelope{
+            statement_id: statement_id.to_string(),
+            version: Version::CURRENT,
+            context: context.to_vec(),
+            proof_bytes: proof.to_bytes(),
+        }
+    }
+
+    /// Parse and verify the proof, as a proof of the statement
+    /// registered under `expected` in `registry`, against publics
+    /// encoded as in `Publics::to_bytes`.
+    ///
+    /// Returns `Err(())` if the envelope is for another statement or
+    /// for a version other than `Version::CURRENT`, `expected` is not
+    /// registered, or the proof is invalid.
+    pub fn open(&self, registry: &Registry, expected: &str, publics: &[u8]) -> Result<Box<dyn AnyProof>,()> {
+        self.open_version(registry, expected, Version::CURRENT, publics)
+    }
+
+    /// Like `open`, but for an envelope of an older `version`.
+    ///
+    /// The version inside an envelope is chosen by whoever made it,
+    /// so the version to accept is given by the caller.
+    pub fn open_version(
+        &self,
+        registry: &Registry,
+        expected: &str,
+        version: Version,
+        publics: &[u8],
+    ) -> Result<Box<dyn AnyProof>,()> {
+        if self.statement_id != expected || self.version != version {
+            return Err(());
+        }
+        let proof = registry.from_bytes(expected, &self.proof_bytes)?;
+        proof.verify_bytes_with_context(version, &self.context, publics)?;
+        Ok(proof)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let id = self.statement_id.as_bytes();
+        let mut bytes = Vec::with_capacity(17 + id.len() + self.context.len() + self.proof_bytes.len());
+        bytes.extend_from_slice(&::length_bytes(id.len()));
+        bytes.extend_from_slice(id);
+        bytes.push(version_byte(self.version));
+        bytes.extend_from_slice(&::length_bytes(self.context.len()));
+        bytes.extend_from_slice(&self.context);
+        bytes.extend_from_slice(&self.proof_bytes);
+        bytes
+    }
+
+    /// Parse an envelope encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Envelope,()> {
+        let (id, rest) = take_prefixed(bytes)?;
+        let statement_id = ::std::str::from_utf8(id).map_err(|_| ())?.to_string();
+        let (&version, rest) = rest.split_first().ok_or(())?;
+        let version = version_from_byte(version)?;
+        let (context, proof) = take_prefixed(rest)?;
+        Ok(Envelope{
+            statement_id: statement_id,
+            version: version,
+            context: context.to_vec(),
+            proof_bytes: proof.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+    create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+    #[test]
+    fn open() {
+        let mut csprng = OsRng::new().unwrap();
+        let mut registry = Registry::new();
+        dlog::register(&mut registry).unwrap();
+        dleq::register(&mut registry).unwrap();
+
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let proof = dleq::Proof::create_with_context(
+            &mut csprng, b"my app", publics.as_publics(), secrets.as_secrets());
+        let publics = publics.as_publics().to_bytes();
+        let envelope = Envelope::new("dleq", b"my app", &proof);
+        let parsed = Envelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(parsed, envelope);
+        assert_eq!(parsed.open(&registry, "dleq", &publics).unwrap().statement(), "dleq");
+
+        // The wrong statement, even if named inside the envelope.
+        assert!(envelope.open(&registry, "dlog", &publics).is_err());
+        let relabelled = Envelope{ statement_id: "dlog".to_string(), ..envelope.clone() };
+        assert!(relabelled.open(&registry, "dlog", &publics).is_err());
+        assert!(envelope.open(&Registry::new(), "dleq", &publics).is_err());
+
+        // The wrong context or version.
+        let other = Envelope{ context: b"other app".to_vec(), ..envelope.clone() };
+        assert!(other.open(&registry, "dleq", &publics).is_err());
+        let old = Envelope{ version: Version::V1, ..envelope.clone() };
+        assert!(old.open(&registry, "dleq", &publics).is_err());
+        assert!(envelope.open_version(&registry, "dleq", Version::V1, &publics).is_err());
+        assert!(Envelope::from_bytes(&old.to_bytes()).unwrap().version == Version::V1);
+
+        let bytes = envelope.to_bytes();
+        assert!(Envelope::from_bytes(&bytes[..10]).is_err());
+        assert!(Envelope::from_bytes(&bytes[..20]).is_err());
+        let mut bad_version = bytes.clone();
+        bad_version[12] = 3;
+        assert!(Envelope::from_bytes(&bad_version).is_err());
+
+        // The version inside the envelope is not trusted: a proof of
+        // the current version relabelled as V0 is refused, even when
+        // asked for the current version.
+        let v0 = Envelope{ version: Version::V0, ..envelope.clone() };
+        assert!(v0.open(&registry, "dleq", &publics).is_err());
+        assert!(v0.open_version(&registry, "dleq", Version::CURRENT, &publics).is_err());
+        assert!(envelope.open_version(&registry, "dleq", Version::CURRENT, &publics).is_ok());
+
+        // Identifiers of any length are encoded.
+        let long = Envelope{ statement_id: "x".repeat(300), ..envelope.clone() };
+        assert_eq!(Envelope::from_bytes(&long.to_bytes()).unwrap(), long);
+    }
+}
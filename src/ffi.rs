@@ -0,0 +1,109 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Return codes and helpers for the C functions exported by
+//! `create_nipk_ffi!`.
+
+use std::slice;
+
+/// The call succeeded (and for `verify`, the proof is valid).
+pub const ZKP_OK: i32 = 0;
+/// A pointer argument was null.
+pub const ZKP_ERR_NULL: i32 = -1;
+/// An input buffer had the wrong length or an invalid encoding.
+pub const ZKP_ERR_ENCODING: i32 = -2;
+/// The output buffer was too small; the required length is written
+/// to `*proof_len`.
+pub const ZKP_ERR_BUFFER_TOO_SMALL: i32 = -3;
+/// The proof did not verify.
+pub const ZKP_ERR_VERIFICATION: i32 = -4;
+/// The operating system's random number generator was unavailable.
+pub const ZKP_ERR_RNG: i32 = -5;
+
+/// Borrow `len` bytes at `ptr`, or return `ZKP_ERR_NULL`.
+#[doc(hidden)]
+pub unsafe fn __slice<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], i32> {
+    if ptr.is_null() {
+        return Err(ZKP_ERR_NULL);
+    }
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+/// Copy `bytes` into the buffer `out` of capacity `*out_len`, setting
+/// `*out_len` to `bytes.len()`.
+#[doc(hidden)]
+pub unsafe fn __copy_out(bytes: &[u8], out: *mut u8, out_len: *mut usize) -> i32 {
+    if out.is_null() || out_len.is_null() {
+        return ZKP_ERR_NULL;
+    }
+    let capacity = *out_len;
+    *out_len = bytes.len();
+    if capacity < bytes.len() {
+        return ZKP_ERR_BUFFER_TOO_SMALL;
+    }
+    slice::from_raw_parts_mut(out, bytes.len()).copy_from_slice(bytes);
+    ZKP_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+    use std::ptr;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+    create_nipk_ffi!{dlog, zkp_test_dlog_create, zkp_test_dlog_verify}
+
+    #[test]
+    fn create_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+
+        let mut publics = Vec::new();
+        publics.extend_from_slice(A.compress().as_bytes());
+        publics.extend_from_slice(G.compress().as_bytes());
+        let secrets = x.as_bytes().to_vec();
+
+        unsafe {
+            let mut proof = [0u8; 64];
+            let mut proof_len = 10;
+            assert_eq!(zkp_test_dlog_create(
+                publics.as_ptr(), publics.len(), secrets.as_ptr(), secrets.len(),
+                proof.as_mut_ptr(), &mut proof_len,
+            ), ZKP_ERR_BUFFER_TOO_SMALL);
+            assert_eq!(proof_len, 64);
+
+            assert_eq!(zkp_test_dlog_create(
+                publics.as_ptr(), publics.len(), secrets.as_ptr(), secrets.len(),
+                proof.as_mut_ptr(), &mut proof_len,
+            ), ZKP_OK);
+            assert_eq!(zkp_test_dlog_verify(
+                proof.as_ptr(), proof_len, publics.as_ptr(), publics.len(),
+            ), ZKP_OK);
+
+            proof[40] ^= 1;
+            assert_eq!(zkp_test_dlog_verify(
+                proof.as_ptr(), proof_len, publics.as_ptr(), publics.len(),
+            ), ZKP_ERR_VERIFICATION);
+
+            assert_eq!(zkp_test_dlog_verify(
+                proof.as_ptr(), proof_len, publics.as_ptr(), 32,
+            ), ZKP_ERR_ENCODING);
+            assert_eq!(zkp_test_dlog_verify(
+                ptr::null(), 0, publics.as_ptr(), publics.len(),
+            ), ZKP_ERR_NULL);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Nothing-up-my-sleeve generators, derived from a label.
+//!
+//! `derive(label, n)` hashes to the group with `DecafPoint::hash_from_bytes`
+//! over SHA-512, with the `i`-th generator the hash of
+//!
+//! ```text
+//! "zkp generators" || len(label) || label || i
+//! ```
+//!
+//! where the length and the index are 8-byte little-endian integers.
+//! Since the generators are hashes, nobody knows a discrete logarithm
+//! of one to the base of another, or of the basepoint.  The index is
+//! part of each hash, so the first `n` generators for a label do not
+//! depend on how many are derived, and the length prefix keeps
+//! different labels apart.
+//!
+//! Protocols which agree on a label thus agree on their generators:
+//!
+//! ```rust,ignore
+//! let gens = generators::derive(b"my protocol pedersen", 2);
+//! let pedersen = PedersenGenerators{ G: gens[0], H: gens[1] };
+//! ```
+//!
+//! which is what `PedersenGenerators::derive(label)` does.
+//!
+//! A common reference string which must be shipped to all parties can
+//! be kept in a `GeneratorSet`, which names each generator, has a
+//! Serde encoding, and can be checked with `validate` after it is
+//! received.  Its points are borrowed by name to fill in `Publics`:
+//!
+//! ```rust,ignore
+//! let crs = GeneratorSet::derive(b"my protocol", &["G", "H"]);
+//! crs.validate()?;
+//! let publics = representation::Publics{ C: &C, G: &crs["G"], H: &crs["H"] };
+//! ```
+
+use std::ops::Index;
+
+use curve25519_dalek::decaf::DecafPoint;
+use sha2::Sha512;
+
+use __is_identity;
+
+fn encode_u64(n: u64) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    for i in 0..8 {
+        bytes[i] = (n >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+/// The generator with the given `index` for `label`.
+pub fn derive_one(label: &[u8], index: u64) -> DecafPoint {
+    let mut input = Vec::with_capacity(14 + 8 + label.len() + 8);
+    input.extend_from_slice(b"zkp generators");
+    input.extend_from_slice(&encode_u64(label.len() as u64));
+    input.extend_from_slice(label);
+    input.extend_from_slice(&encode_u64(index));
+    DecafPoint::hash_from_bytes::<Sha512>(&input)
+}
+
+/// The first `n` generators for `label`.
+pub fn derive(label: &[u8], n: usize) -> Vec<DecafPoint> {
+    (0..n as u64).map(|i| derive_one(label, i)).collect()
+}
+
+/// A list of named generators, such as a common reference string.
+///
+/// The generators keep the order in which they were added, and are
+/// serialized in that order.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeneratorSet {
+    generators: Vec<(String, DecafPoint)>,
+}
+
+impl GeneratorSet {
+    /// An empty set.
+    pub fn new() -> GeneratorSet {
+        GeneratorSet{ generators: Vec::new() }
+    }
+
+    /// Derive one generator for each of `names` from `label`, as
+    /// `derive(label, names.len())`.
+    pub fn derive(label: &[u8], names: &[&str]) -> GeneratorSet {
+        let points = derive(label, names.len());
+        GeneratorSet{
+            generators: names.iter().map(|name| name.to_string()).zip(points).collect(),
+        }
+    }
+
+    /// Add a generator.
+    ///
+    /// Returns `Err(())` if there is already a generator called `name`.
+    pub fn insert(&mut self, name: &str, point: DecafPoint) -> Result<(),()> {
+        if self.get(name).is_some() {
+            return Err(());
+        }
+        self.generators.push((name.to_string(), point));
+        Ok(())
+    }
+
+    /// The generator called `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&DecafPoint> {
+        self.generators.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref point)| point)
+    }
+
+    /// The names and generators, in order.
+    pub fn generators(&self) -> &[(String, DecafPoint)] {
+        &self.generators
+    }
+
+    pub fn len(&self) -> usize {
+        self.generators.len()
+    }
+
+    /// Check that no generator is the identity, and that the names
+    /// and the generators are distinct.
+    ///
+    /// A deserialized set has not been checked, so this should be
+    /// called before using a set received from someone else.
+    pub fn validate(&self) -> Result<(),()> {
+        for (i, &(ref name, ref point)) in self.generators.iter().enumerate() {
+            if __is_identity(point) {
+                return Err(());
+            }
+            let encoding = point.compress();
+            for &(ref other_name, ref other) in &self.generators[..i] {
+                if name == other_name || encoding == other.compress() {
+                    return Err(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Index<&'a str> for GeneratorSet {
+    type Output = DecafPoint;
+
+    /// The generator called `name`.
+    ///
+    /// Panics if there is no such generator.
+    fn index(&self, name: &'a str) -> &DecafPoint {
+        self.get(name).expect("no generator with this name")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::decaf::CompressedDecaf;
+
+    fn encodings(points: &[DecafPoint]) -> Vec<CompressedDecaf> {
+        points.iter().map(|P| P.compress()).collect()
+    }
+
+    #[test]
+    fn derive_generators() {
+        let gens = encodings(&derive(b"label", 4));
+        assert_eq!(gens, encodings(&derive(b"label", 4)));
+        assert_eq!(&gens[..2], &encodings(&derive(b"label", 2))[..]);
+        assert_eq!(gens[3], derive_one(b"label", 3).compress());
+
+        let mut all = gens.clone();
+        all.extend(encodings(&derive(b"label2", 4)));
+        all.push(dalek_constants::DECAF_ED25519_BASEPOINT.compress());
+        for i in 0..all.len() {
+            for j in 0..i {
+                assert!(all[i] != all[j]);
+            }
+        }
+        assert!(derive(b"", 0).is_empty());
+    }
+
+    #[test]
+    fn generator_set() {
+        extern crate serde_json;
+        use curve25519_dalek::traits::Identity;
+
+        let set = GeneratorSet::derive(b"crs", &["G", "H", "K"]);
+        assert!(set.validate().is_ok());
+        assert_eq!(set.len(), 3);
+        assert_eq!(set["H"].compress(), derive_one(b"crs", 1).compress());
+        assert!(set.get("J").is_none());
+        assert_eq!(set.generators().iter().map(|g| &g.0[..]).collect::<Vec<_>>(), vec!["G", "H", "K"]);
+
+        let json = serde_json::to_string(&set).unwrap();
+        let parsed: GeneratorSet = serde_json::from_str(&json).unwrap();
+        assert!(parsed.validate().is_ok());
+        assert_eq!(parsed["K"].compress(), set["K"].compress());
+
+        let mut bad = set.clone();
+        assert!(bad.insert("G", set["H"]).is_err());
+        bad.insert("L", DecafPoint::identity()).unwrap();
+        assert!(bad.validate().is_err());
+
+        let mut bad = set.clone();
+        bad.insert("L", set["G"]).unwrap();
+        assert!(bad.validate().is_err());
+
+        // Duplicate names can only come from a deserialized set.
+        let duplicate = json.replace("\"K\"", "\"G\"");
+        let parsed: GeneratorSet = serde_json::from_str(&duplicate).unwrap();
+        assert!(parsed.validate().is_err());
+    }
+}
@@ -0,0 +1,175 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The groups `create_nipk!` statements can be made over.
+//!
+//! A `create_nipk!` module works with points of type `DecafPoint`
+//! unless another type is given after its challenge hash, as in
+//! `create_nipk!{dleq<Sha512, MyPoint>, ...}`.  The generated code
+//! uses the point type as it would `DecafPoint`, so the type needs the
+//! same API as curve25519-dalek's points:
+//!
+//! * the arithmetic operators `&P + &P`, `&P - &P`, `-&P` and
+//!   `&P * &Scalar`, with curve25519-dalek's `Scalar`, so the group
+//!   must have the same prime order (as Ristretto does);
+//! * serde's `Serialize` and `Deserialize`;
+//! * `P::random(csprng)`, and `p.compress()` returning the
+//!   `PrimeGroup::Compressed` encoding, which has `as_bytes()` and
+//!   `decompress()`;
+//!
+//! and an implementation of `PrimeGroup`, for what the crate's own
+//! helpers need.
+
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::traits::Identity;
+
+/// A group of prime order `ℓ`, with 32-byte point encodings.
+pub trait PrimeGroup: Copy + Send + Sync {
+    /// The compressed encoding of a point.
+    type Compressed: Copy;
+
+    fn identity() -> Self;
+
+    /// The 32-byte encoding of the point.
+    fn to_bytes(&self) -> [u8; 32];
+
+    /// Decode a point, returning `None` if `bytes` is not a valid
+    /// encoding.
+    fn from_bytes(bytes: &[u8; 32]) -> Option<Self>;
+
+    /// Wrap 32 bytes as a compressed encoding, without checking them.
+    fn compressed(bytes: [u8; 32]) -> Self::Compressed;
+}
+
+impl PrimeGroup for DecafPoint {
+    type Compressed = CompressedDecaf;
+
+    fn identity() -> DecafPoint {
+        <DecafPoint as Identity>::identity()
+    }
+
+    fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(self.compress().as_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; 32]) -> Option<DecafPoint> {
+        CompressedDecaf(*bytes).decompress()
+    }
+
+    fn compressed(bytes: [u8; 32]) -> CompressedDecaf {
+        CompressedDecaf(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use std::ops::{Add, Mul, Sub};
+    use rand::{OsRng, Rng};
+
+    /// Decaf points under another name, standing in for a second
+    /// backend.
+    #[derive(Copy, Clone, Serialize, Deserialize)]
+    struct OtherPoint(DecafPoint);
+
+    #[derive(Copy, Clone, PartialEq)]
+    struct OtherCompressed(CompressedDecaf);
+
+    impl OtherPoint {
+        fn random<R: Rng>(csprng: &mut R) -> OtherPoint {
+            OtherPoint(DecafPoint::random(csprng))
+        }
+
+        fn compress(&self) -> OtherCompressed {
+            OtherCompressed(self.0.compress())
+        }
+    }
+
+    impl OtherCompressed {
+        fn as_bytes(&self) -> &[u8; 32] {
+            self.0.as_bytes()
+        }
+
+        fn decompress(&self) -> Option<OtherPoint> {
+            self.0.decompress().map(OtherPoint)
+        }
+    }
+
+    impl<'a, 'b> Add<&'b OtherPoint> for &'a OtherPoint {
+        type Output = OtherPoint;
+        fn add(self, other: &'b OtherPoint) -> OtherPoint {
+            OtherPoint(&self.0 + &other.0)
+        }
+    }
+
+    impl<'a, 'b> Sub<&'b OtherPoint> for &'a OtherPoint {
+        type Output = OtherPoint;
+        fn sub(self, other: &'b OtherPoint) -> OtherPoint {
+            OtherPoint(&self.0 - &other.0)
+        }
+    }
+
+    impl<'a, 'b> Mul<&'b Scalar> for &'a OtherPoint {
+        type Output = OtherPoint;
+        fn mul(self, scalar: &'b Scalar) -> OtherPoint {
+            OtherPoint(&self.0 * scalar)
+        }
+    }
+
+    impl PrimeGroup for OtherPoint {
+        type Compressed = OtherCompressed;
+
+        fn identity() -> OtherPoint {
+            OtherPoint(<DecafPoint as PrimeGroup>::identity())
+        }
+
+        fn to_bytes(&self) -> [u8; 32] {
+            self.0.to_bytes()
+        }
+
+        fn from_bytes(bytes: &[u8; 32]) -> Option<OtherPoint> {
+            <DecafPoint as PrimeGroup>::from_bytes(bytes).map(OtherPoint)
+        }
+
+        fn compressed(bytes: [u8; 32]) -> OtherCompressed {
+            OtherCompressed(CompressedDecaf(bytes))
+        }
+    }
+
+    #[test]
+    fn other_point_type() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{decaf, (x, y), (A, B, G, H) : A = (G * x), B = (G * x + H * y) }
+        create_nipk!{other<::sha2::Sha512, OtherPoint>, (x, y), (A, B, G, H)
+                     : A = (G * x), B = (G * x + H * y) }
+
+        let (publics, secrets) = other::random_instance(&mut csprng);
+        let _: &OtherPoint = &publics.A;
+        let proof = other::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify(publics.as_publics()).is_ok());
+        assert!(proof.verify_strict(publics.as_publics()).is_ok());
+
+        let compressed = other::CompressedPublicsOwned{
+            A: publics.A.compress(), B: publics.B.compress(),
+            G: publics.G.compress(), H: publics.H.compress(),
+        };
+        assert!(proof.verify_compressed(compressed.as_compressed_publics()).is_ok());
+
+        // The same statement over the same encodings agrees with Decaf.
+        let decaf_publics = decaf::PublicsOwned::from_bytes(&publics.as_publics().to_bytes()).unwrap();
+        let decaf_proof = decaf::Proof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(decaf_proof.verify(decaf_publics.as_publics()).is_ok());
+    }
+}
@@ -0,0 +1,58 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Lowercase hex encoding of byte strings.
+
+const DIGITS: &'static [u8; 16] = b"0123456789abcdef";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        s.push(DIGITS[(byte >> 4) as usize] as char);
+        s.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+    s
+}
+
+fn digit(c: u8) -> Result<u8,()> {
+    if b'0' <= c && c <= b'9' {
+        Ok(c - b'0')
+    } else if b'a' <= c && c <= b'f' {
+        Ok(c - b'a' + 10)
+    } else if b'A' <= c && c <= b'F' {
+        Ok(c - b'A' + 10)
+    } else {
+        Err(())
+    }
+}
+
+/// Decode a hex string, in either case.
+pub fn decode(s: &str) -> Result<Vec<u8>,()> {
+    let s = s.as_bytes();
+    if s.len() % 2 != 0 {
+        return Err(());
+    }
+    s.chunks(2).map(|pair| Ok((digit(pair[0])? << 4) | digit(pair[1])?)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let bytes = [0x00, 0x01, 0x7f, 0x80, 0xab, 0xff];
+        assert_eq!(encode(&bytes), "00017f80abff");
+        assert_eq!(decode("00017F80abff").unwrap(), bytes.to_vec());
+        assert!(decode("0").is_err());
+        assert!(decode("0g").is_err());
+    }
+}
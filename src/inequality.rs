@@ -0,0 +1,108 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs that two discrete logarithms are *not* equal.
+//!
+//! Given `Y = G*x`, an `InequalityProof` shows that `Z != H*x`
+//! without revealing `x`, following Camenisch and Shoup.  The prover
+//! picks a random `r` and publishes the auxiliary commitment
+//! `C = (H*x - Z)*r`, then proves knowledge of `a = x*r` and `b = -r`
+//! with
+//!
+//! ```text
+//! C = H*a + Z*b
+//! 0 = G*a + Y*b
+//! ```
+//!
+//! The second equation forces `a = x*(-b)`, so `C = (H*x - Z)*(-b)`;
+//! the verifier additionally checks that `C` is not the identity,
+//! which holds exactly when `Z != H*x`.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::Rng;
+
+create_nipk!{dlne, (a, b), (C, O, G, Y, H, Z) : C = (H * a + Z * b), O = (G * a + Y * b) }
+
+/// A proof that `log_G(Y) != log_H(Z)`.
+#[derive(Serialize, Deserialize)]
+pub struct InequalityProof {
+    C: DecafPoint,
+    proof: dlne::Proof,
+}
+
+impl InequalityProof {
+    /// Prove that `Z != H*x`, where `Y = G*x`.
+    ///
+    /// Returns `Err(())` if in fact `Z = H*x`.
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        G: &DecafPoint,
+        Y: &DecafPoint,
+        H: &DecafPoint,
+        Z: &DecafPoint,
+        x: &Scalar,
+    ) -> Result<InequalityProof, ()> {
+        let O = DecafPoint::identity();
+        let r = Scalar::random(csprng);
+        let C = &(&(H * x) - Z) * &r;
+        if C.compress() == O.compress() {
+            return Err(());
+        }
+
+        let a = x * &r;
+        let b = -&r;
+        let publics = dlne::Publics{ C: &C, O: &O, G: G, Y: Y, H: H, Z: Z };
+        let secrets = dlne::Secrets{ a: &a, b: &b };
+        Ok(InequalityProof{ C: C, proof: dlne::Proof::create(csprng, publics, secrets) })
+    }
+
+    /// Verify that `log_G(Y) != log_H(Z)`.
+    pub fn verify(
+        &self,
+        G: &DecafPoint,
+        Y: &DecafPoint,
+        H: &DecafPoint,
+        Z: &DecafPoint,
+    ) -> Result<(),()> {
+        let O = DecafPoint::identity();
+        if self.C.compress() == O.compress() {
+            return Err(());
+        }
+        self.proof.verify(dlne::Publics{ C: &self.C, O: &O, G: G, Y: Y, H: H, Z: Z })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use rand::OsRng;
+
+    #[test]
+    fn create_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::random(&mut csprng);
+
+        let x = Scalar::random(&mut csprng);
+        let Y = G * &x;
+        let Z = &H * &Scalar::random(&mut csprng);
+
+        let proof = InequalityProof::create(&mut csprng, G, &Y, &H, &Z, &x).unwrap();
+        assert!(proof.verify(G, &Y, &H, &Z).is_ok());
+
+        let other = &H * &x;
+        assert!(proof.verify(G, &Y, &H, &other).is_err());
+        assert!(InequalityProof::create(&mut csprng, G, &Y, &H, &other, &x).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Keypairs with proofs of possession.
+//!
+//! A `Keypair` is a secret scalar `x` together with its public key
+//! `A = B*x`, where `B` is the Decaf basepoint.  Keys registered with
+//! someone else should come with a `ProofOfPossession`, proving
+//! knowledge of `x` for the statement
+//!
+//! ```rust,ignore
+//! create_nipk!{possession, (x), (A, B) : A = (B * x) }
+//! ```
+//!
+//! in a transcript labelled `"zkp proof of possession"`, after the
+//! caller's context as a message.  The context should name the
+//! protocol and whatever the key is registered for, so that a proof
+//! cannot be replayed elsewhere:
+//!
+//! ```rust,ignore
+//! let keypair = Keypair::generate(&mut csprng);
+//! let pop = keypair.prove_possession(&mut csprng, b"my protocol signer 7");
+//! verify_possession(keypair.public(), b"my protocol signer 7", &pop)?;
+//! ```
+//!
+//! `Keypair::to_bytes` writes the secret followed by the compressed
+//! public key, and `Keypair::from_bytes` checks that they match.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+create_nipk!{possession, (x), (A, B) : A = (B * x) }
+
+/// A proof of knowledge of the secret key for a public key.
+pub use self::possession::Proof as ProofOfPossession;
+
+/// A secret key `x` and its public key `A = B*x`.
+#[derive(Clone)]
+pub struct Keypair {
+    secret: Scalar,
+    public: DecafPoint,
+}
+
+impl Keypair {
+    /// Generate a fresh keypair.
+    pub fn generate<R: Rng>(csprng: &mut R) -> Keypair {
+        Keypair::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct the keypair for an existing secret key.
+    pub fn from_secret(secret: Scalar) -> Keypair {
+        let public = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        Keypair{ secret: secret, public: public }
+    }
+
+    pub fn secret(&self) -> &Scalar {
+        &self.secret
+    }
+
+    pub fn public(&self) -> &DecafPoint {
+        &self.public
+    }
+
+    /// The secret key followed by the compressed public key.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.secret.as_bytes());
+        bytes[32..].copy_from_slice(self.public.compress().as_bytes());
+        bytes
+    }
+
+    /// Parse the encoding written by `to_bytes`.
+    ///
+    /// Returns `Err(())` if the secret key is not canonical, or the
+    /// public key is not the one for the secret key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Keypair,()> {
+        if bytes.len() != 64 {
+            return Err(());
+        }
+        let mut encoding = [0u8; 32];
+        encoding.copy_from_slice(&bytes[..32]);
+        let secret = Scalar::from_bytes_mod_order(encoding);
+        if secret.as_bytes() != &encoding {
+            return Err(());
+        }
+        let keypair = Keypair::from_secret(secret);
+        if keypair.public.compress().as_bytes()[..] != bytes[32..] {
+            return Err(());
+        }
+        Ok(keypair)
+    }
+
+    /// Prove possession of the secret key, bound to `context`.
+    pub fn prove_possession<R: Rng>(&self, csprng: &mut R, context: &[u8]) -> ProofOfPossession {
+        ProofOfPossession::prove_possession(
+            csprng,
+            context,
+            possession::Publics{ A: &self.public, B: &dalek_constants::DECAF_ED25519_BASEPOINT },
+            possession::Secrets{ x: &self.secret },
+        )
+    }
+}
+
+/// Verify a proof of possession of the secret key for `public`, bound
+/// to `context`.
+///
+/// This also rejects the identity, whose secret key is known to
+/// everyone.
+pub fn verify_possession(
+    public: &DecafPoint,
+    context: &[u8],
+    proof: &ProofOfPossession,
+) -> Result<(),()> {
+    proof.verify_possession(
+        context,
+        possession::Publics{ A: public, B: &dalek_constants::DECAF_ED25519_BASEPOINT },
+    )
+}
+
+/// Verify a proof of possession for a compressed public key, returning
+/// the decompressed key.
+pub fn verify_possession_compressed(
+    public: &CompressedDecaf,
+    context: &[u8],
+    proof: &ProofOfPossession,
+) -> Result<DecafPoint,()> {
+    let public = public.decompress().ok_or(())?;
+    verify_possession(&public, context, proof)?;
+    Ok(public)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn possession() {
+        let mut csprng = OsRng::new().unwrap();
+        let keypair = Keypair::generate(&mut csprng);
+        let proof = keypair.prove_possession(&mut csprng, b"signer 1");
+        assert!(verify_possession(keypair.public(), b"signer 1", &proof).is_ok());
+        assert!(verify_possession(keypair.public(), b"signer 2", &proof).is_err());
+        let other = Keypair::generate(&mut csprng);
+        assert!(verify_possession(other.public(), b"signer 1", &proof).is_err());
+
+        let compressed = keypair.public().compress();
+        let public = verify_possession_compressed(&compressed, b"signer 1", &proof).unwrap();
+        assert_eq!(public.compress(), compressed);
+
+        let parsed = ProofOfPossession::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(verify_possession(keypair.public(), b"signer 1", &parsed).is_ok());
+    }
+
+    #[test]
+    fn keypair_bytes() {
+        let mut csprng = OsRng::new().unwrap();
+        let keypair = Keypair::generate(&mut csprng);
+        let bytes = keypair.to_bytes();
+        let parsed = Keypair::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.secret().as_bytes(), keypair.secret().as_bytes());
+        assert_eq!(parsed.public().compress(), keypair.public().compress());
+
+        let other = Keypair::generate(&mut csprng);
+        let mut mismatched = bytes;
+        mismatched[32..].copy_from_slice(&other.to_bytes()[32..]);
+        assert!(Keypair::from_bytes(&mismatched).is_err());
+        assert!(Keypair::from_bytes(&bytes[..63]).is_err());
+
+        // The group order plus one is not a canonical scalar.
+        let mut unreduced = bytes;
+        unreduced[..32].copy_from_slice(&[0xee, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58,
+                                            0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+                                            0, 0, 0, 0, 0, 0, 0, 0,
+                                            0, 0, 0, 0, 0, 0, 0, 0x10]);
+        assert!(Keypair::from_bytes(&unreduced).is_err());
+    }
+}
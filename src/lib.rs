@@ -20,6 +20,11 @@
 //! well as this library*, are currently **UNFINISHED, UNREVIEWED, AND
 //! EXPERIMENTAL**.  (I haven't actually checked carefully that the
 //! proofs are sound, for instance...)
+//!
+//! Each module generated by `create_nipk!`, and each optional feature,
+//! is documented on the items it defines.  Creating, simulating and
+//! verifying a single proof make no heap allocations, which the tests
+//! check, so they can be used where there is no allocator.
 #![allow(non_snake_case)]
 #![feature(test)]
 
@@ -34,377 +39,4740 @@ pub extern crate curve25519_dalek;
 pub extern crate rand;
 #[doc(hidden)]
 pub extern crate sha2;
+#[doc(hidden)]
+pub extern crate subtle;
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+extern crate rayon;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub extern crate arbitrary;
+#[cfg(feature = "postcard-encoding")]
+#[doc(hidden)]
+pub extern crate postcard;
+#[cfg(feature = "property-testing")]
+extern crate proptest;
+#[cfg(feature = "secp256k1")]
+extern crate k256;
+#[cfg(feature = "mobile")]
+#[doc(hidden)]
+pub extern crate uniffi;
+#[cfg(feature = "wasm")]
+#[doc(hidden)]
+pub extern crate wasm_bindgen;
+
+#[cfg(feature = "mobile")]
+uniffi::setup_scaffolding!("zkp");
+
+use std::{error, fmt, io, ops};
+
+use curve25519_dalek::scalar::Scalar;
+
+use group::PrimeGroup;
 
-/// compute_formula_consttime!((publics, scalars) (A*a + B*b + ...))
-/// returns
 /// The input to this macro is of the form
 ///
-///   (publics, scalars) (A*a + B*b + ...)
+///   (publics, scalars) (A*a + B*b - C*c ...)
 ///
 /// where `publics` is the name of a struct with members `A, B, ...`
 /// of type `&DecafPoint`, and `scalars` is the name of a struct with
 /// members `a, b, ...` of type `Scalar`.
 ///
-/// It expands to an expression of the form
+/// It expands to a single multiscalar multiplication over all the
+/// terms,
 ///
-///   &(publics.A * &scalars.a) + &( &(publics.B * &scalars.b) + &(...))
+///   __multiscalar_mult(&[scalars.a, scalars.b, -&scalars.c, ...],
+///                      &[publics.A, publics.B, publics.C, ...])
 ///
-/// All these operations are constant-time.
+/// where each `-` negates the scalar of the term following it.  A term
+/// may also carry a public coefficient, as in `(2) * A * a`, which is
+/// multiplied into the scalar.  All these operations are
+/// constant-time.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __compute_formula_consttime {
-    // Unbracket a statement
-    (($publics:ident, $scalars:ident) ($($x:tt)*)) => {
-        __compute_formula_consttime!(($publics,$scalars) $($x)*)
+    // Add the next term, with a coefficient
+    (@acc ($publics:ident, $scalars:ident) ($($s:expr),*) ($($p:expr),*)
+     + ($coeff:expr) * $point:ident * $scalar:ident $($x:tt)*) => {
+        __compute_formula_consttime!(@acc ($publics, $scalars)
+            ($($s,)* __compute_formula_consttime!(@scalar $scalars ($coeff) $scalar))
+            ($($p,)* $publics.$point) $($x)*)
+    };
+    // Subtract the next term, with a coefficient
+    (@acc ($publics:ident, $scalars:ident) ($($s:expr),*) ($($p:expr),*)
+     - ($coeff:expr) * $point:ident * $scalar:ident $($x:tt)*) => {
+        __compute_formula_consttime!(@acc ($publics, $scalars)
+            ($($s,)* -&__compute_formula_consttime!(@scalar $scalars ($coeff) $scalar))
+            ($($p,)* $publics.$point) $($x)*)
+    };
+    // Add the next term
+    (@acc ($publics:ident, $scalars:ident) ($($s:expr),*) ($($p:expr),*)
+     + $point:ident * $scalar:ident $($x:tt)*) => {
+        __compute_formula_consttime!(@acc ($publics, $scalars)
+            ($($s,)* $scalars.$scalar) ($($p,)* $publics.$point) $($x)*)
+    };
+    // Subtract the next term
+    (@acc ($publics:ident, $scalars:ident) ($($s:expr),*) ($($p:expr),*)
+     - $point:ident * $scalar:ident $($x:tt)*) => {
+        __compute_formula_consttime!(@acc ($publics, $scalars)
+            ($($s,)* -&$scalars.$scalar) ($($p,)* $publics.$point) $($x)*)
     };
-    // Multi-part statement
-    (($publics:ident, $scalars:ident)
-     $point:ident * $scalar:ident + $($x:tt)*) => {
-        &($publics.$point * &$scalars.$scalar) +
-        &( __compute_formula_consttime!(($publics,$scalars) $($x)*) )
+    // End of statement
+    (@acc ($publics:ident, $scalars:ident) ($($s:expr),*) ($($p:expr),*)) => {
+        $crate::__multiscalar_mult(&[ $($s),* ], &[ $($p),* ])
     };
-    // Single-part statement / end of statement
-    (($publics:ident, $scalars:ident)
-     $point:ident * $scalar:ident ) => {
-        $publics.$point * &$scalars.$scalar
+    // The scalar of a term with a coefficient
+    (@scalar $scalars:ident ($coeff:expr) $scalar:ident) => {
+        (&$crate::Coefficient::to_scalar($coeff) * &$scalars.$scalar)
+    };
+    // Unbracket a statement whose first term is negated
+    (($publics:ident, $scalars:ident) (- $($x:tt)*)) => {
+        __compute_formula_consttime!(@acc ($publics, $scalars) () () - $($x)*)
+    };
+    // Unbracket a statement
+    (($publics:ident, $scalars:ident) ($($x:tt)*)) => {
+        __compute_formula_consttime!(@acc ($publics, $scalars) () () + $($x)*)
     };
 }
 
-/// Expands to a constructor for a `Commitments` struct, which
-/// computes (in constant time) commitments based on the input
-/// statements.
+/// Expands to a `Commitments` array, which computes (in constant
+/// time) commitments based on the input statements.  Each commitment
+/// is computed by a closure, so that `__compute_all` can run them on
+/// a thread pool.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __compute_commitments_consttime {
-    (($publics:ident, $scalars:ident) $($lhs:ident = $statement:tt),+) => {
-        Commitments {
-            $( $lhs : __compute_formula_consttime!(($publics, $scalars) $statement) ),+
-        }
-    }
+    (($publics:ident, $scalars:ident) $($statement:tt),+) => {{
+        let jobs: [&(dyn Fn() -> Point + Sync); __count_tts!($($statement)+)] = [
+            $( &|| __compute_formula_consttime!(($publics, $scalars) $statement) ),+
+        ];
+        let mut commitments = [
+            <Point as $crate::group::PrimeGroup>::identity();
+            __count_tts!($($statement)+)
+        ];
+        $crate::__compute_all(&jobs, &mut commitments);
+        commitments
+    }}
 }
 
-/// Creates a module with code required to produce a non-interactive
-/// zero-knowledge proof statement, to serialize it to wire format, to
-/// parse from wire format, and to verify the proof statement.
-///
-/// The statement is specified in an embedded DSL resembling
-/// Camenisch-Stadler notation.  For instance, a proof of knowledge of
-/// two equal discrete logarithms ("DLEQ") is specified as:
-///
-/// ```rust,ignore
-/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
-/// ```
-///
-/// This creates a module `dleq` with code for proving knowledge of a
-/// secret `x: Scalar` such that `A = G*x`, `B = H*x` for public
-/// parameters `A, B, G, H: DecafPoint`.  In general the syntax is
-///
-/// ```rust,ignore
-/// create_nipk!{
-///     module_name, // used to label proof statements
-///     (x,y,z,...), // secret variable names
-///     (A,B,C,...)  // public parameter names
-///     :
-///     LHS = (A * x + B * y + C * z + ... ),  // comma-seperated statements
-///     ...
-/// }
-/// ```
-///
-/// Statements have the form `LHS = (A * x + B * y + C * z + ... )`,
-/// where `LHS` is one of the points listed as a public parameter, and
-/// the right-hand side is a sum of public points multiplied by secret
-/// scalars.
-///
-/// Inside the generated module `module_name`, the macro defines three
-/// structs:
-///
-/// A `Publics` struct corresponding to the public parameters, of the
-/// form
-///
-/// ```rust,ignore
-/// pub struct Publics<'a> { pub A: &'a DecafPoint, ... }
-/// ```
-///
-/// A `Secrets` struct corresponding to the secret parameters, of the
-/// form
+/// The input to this macro is of the form
 ///
-/// ```rust,ignore
-/// pub struct Secrets<'a> { pub x: &'a Scalar, ... }
-/// ```
+///   publics (A + B - C ...)
 ///
-/// A `Proof` struct, of the form
+/// or a single identifier `A`, where `publics` is the name of a
+/// struct with members `A, B, C, ...` of type `&DecafPoint`.  It
+/// expands to the value of the left-hand side of a statement.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compute_lhs {
+    // Add the next point to the accumulator
+    (@acc $publics:ident ($acc:expr) + $point:ident $($x:tt)*) => {
+        __compute_lhs!(@acc $publics (&($acc) + $publics.$point) $($x)*)
+    };
+    // Subtract the next point from the accumulator
+    (@acc $publics:ident ($acc:expr) - $point:ident $($x:tt)*) => {
+        __compute_lhs!(@acc $publics (&($acc) - $publics.$point) $($x)*)
+    };
+    // End of the left-hand side
+    (@acc $publics:ident ($acc:expr)) => {
+        $acc
+    };
+    // Unbracket a left-hand side
+    ($publics:ident ($($x:tt)*)) => {
+        __compute_lhs!($publics $($x)*)
+    };
+    // First point of a left-hand side
+    ($publics:ident $point:ident $($x:tt)*) => {
+        __compute_lhs!(@acc $publics (*$publics.$point) $($x)*)
+    };
+}
+
+/// The input to this macro is of the form
 ///
-/// ```rust,ignore
-/// #[derive(Serialize, Deserialize)]
-/// pub struct Proof { ... }
+///   publics (value) (A + B - C ...)
 ///
-/// impl Proof {
-///     pub fn create<R: Rng>(
-///         csprng: &mut R,
-///         publics: Publics,
-///         secrets: Secrets,
-///     ) -> Proof { ... }
+/// where `publics` is the name of a struct with members `A, B, ...`
+/// of type `DecafPoint`.  It expands to an assignment to the first
+/// point of the left-hand side, `publics.A = value - B + C ...`, so
+/// that the left-hand side equals `value`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __solve_lhs {
+    // Move the next point to the other side, subtracting it
+    (@acc $publics:ident ($acc:expr) + $point:ident $($x:tt)*) => {
+        __solve_lhs!(@acc $publics (&($acc) - &$publics.$point) $($x)*)
+    };
+    // Move the next point to the other side, adding it
+    (@acc $publics:ident ($acc:expr) - $point:ident $($x:tt)*) => {
+        __solve_lhs!(@acc $publics (&($acc) + &$publics.$point) $($x)*)
+    };
+    // End of the left-hand side
+    (@acc $publics:ident ($acc:expr)) => {
+        $acc
+    };
+    // Unbracket a left-hand side
+    ($publics:ident ($value:expr) ($($x:tt)*)) => {
+        __solve_lhs!($publics ($value) $($x)*)
+    };
+    // First point of a left-hand side
+    ($publics:ident ($value:expr) $point:ident $($x:tt)*) => {
+        $publics.$point = __solve_lhs!(@acc $publics ($value) $($x)*);
+    };
+}
+
+/// The input to this macro is of the form
 ///
-///     pub fn verify(&self, publics: Publics) -> Result<(),()> { ... }
-/// }
-/// ```
+///   (scalars, scale) (a + (2) * b - c + (k) ...)
 ///
-/// The `Proof` struct derives the Serde traits, so it can be
-/// serialized and deserialized to various wire formats.
+/// where `scalars` is the name of a struct with members `a, b, ...`
+/// of type `Scalar`, each term is a secret optionally multiplied by a
+/// coefficient, and a bracketed term without a secret is a constant.
+/// It expands to the linear combination of the secrets, plus the sum
+/// of the constants multiplied by `scale`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compute_relation {
+    // Add the next secret, with a coefficient, to the accumulator
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)
+     + ($coeff:expr) * $secret:ident $($x:tt)*) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            (&($acc) + &(&$crate::Coefficient::to_scalar($coeff) * &$scalars.$secret)) $($x)*)
+    };
+    // Subtract the next secret, with a coefficient, from the accumulator
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)
+     - ($coeff:expr) * $secret:ident $($x:tt)*) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            (&($acc) - &(&$crate::Coefficient::to_scalar($coeff) * &$scalars.$secret)) $($x)*)
+    };
+    // Add a constant to the accumulator
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)
+     + ($constant:expr) $($x:tt)*) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            (&($acc) + &(&$crate::Coefficient::to_scalar($constant) * &$scale)) $($x)*)
+    };
+    // Subtract a constant from the accumulator
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)
+     - ($constant:expr) $($x:tt)*) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            (&($acc) - &(&$crate::Coefficient::to_scalar($constant) * &$scale)) $($x)*)
+    };
+    // Add the next secret to the accumulator
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)
+     + $secret:ident $($x:tt)*) => {
+        __compute_relation!(@acc ($scalars, $scale) (&($acc) + &$scalars.$secret) $($x)*)
+    };
+    // Subtract the next secret from the accumulator
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)
+     - $secret:ident $($x:tt)*) => {
+        __compute_relation!(@acc ($scalars, $scale) (&($acc) - &$scalars.$secret) $($x)*)
+    };
+    // End of relation
+    (@acc ($scalars:ident, $scale:expr) ($acc:expr)) => {
+        $acc
+    };
+    // Unbracket a relation starting with a negated term
+    (($scalars:ident, $scale:expr) (- $($x:tt)*)) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            ($crate::curve25519_dalek::scalar::Scalar::zero()) - $($x)*)
+    };
+    // Unbracket a relation
+    (($scalars:ident, $scale:expr) ($($x:tt)*)) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            ($crate::curve25519_dalek::scalar::Scalar::zero()) + $($x)*)
+    };
+    // A relation consisting of a single secret
+    (($scalars:ident, $scale:expr) $secret:ident) => {
+        __compute_relation!(@acc ($scalars, $scale)
+            ($crate::curve25519_dalek::scalar::Scalar::zero()) + $secret)
+    };
+}
+
+/// Expands to statements naming a variant of `DeclaredPublics` or
+/// `DeclaredSecrets` for each identifier used in a statement or
+/// relation, so that an undeclared identifier is a compile error
+/// pointing at its use.
 ///
-/// The `Publics` and `Secrets` structs are used to fake named
-/// arguments in the input to `create` and `verify`.  Proof creation
-/// is done in constant time.
+/// The input is one of `@lhs (A + B ...)`, `@statement (A * a + ...)`
+/// or `@relation (a + (2) * b + ...)`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_declared {
+    // Left-hand sides
+    (@lhs ($($x:tt)*)) => {
+        __check_declared!(@lhs $($x)*);
+    };
+    (@lhs + $($x:tt)*) => {
+        __check_declared!(@lhs $($x)*);
+    };
+    (@lhs - $($x:tt)*) => {
+        __check_declared!(@lhs $($x)*);
+    };
+    (@lhs $point:ident $($x:tt)*) => {
+        let _ = DeclaredPublics::$point;
+        __check_declared!(@lhs $($x)*);
+    };
+    (@lhs) => {};
+    // Right-hand sides of statements
+    (@statement ($($x:tt)*)) => {
+        __check_declared!(@terms $($x)*);
+    };
+    (@terms + $($x:tt)*) => {
+        __check_declared!(@terms $($x)*);
+    };
+    (@terms - $($x:tt)*) => {
+        __check_declared!(@terms $($x)*);
+    };
+    (@terms ($coeff:expr) * $point:ident * $scalar:ident $($x:tt)*) => {
+        __check_declared!(@terms $point * $scalar $($x)*);
+    };
+    (@terms $point:ident * $scalar:ident $($x:tt)*) => {
+        let _ = DeclaredPublics::$point;
+        let _ = DeclaredSecrets::$scalar;
+        __check_declared!(@terms $($x)*);
+    };
+    (@terms) => {};
+    // Right-hand sides of relations
+    (@relation ($($x:tt)*)) => {
+        __check_declared!(@secrets $($x)*);
+    };
+    (@relation $secret:ident) => {
+        let _ = DeclaredSecrets::$secret;
+    };
+    (@secrets + $($x:tt)*) => {
+        __check_declared!(@secrets $($x)*);
+    };
+    (@secrets - $($x:tt)*) => {
+        __check_declared!(@secrets $($x)*);
+    };
+    (@secrets ($coeff:expr) * $secret:ident $($x:tt)*) => {
+        __check_declared!(@secrets $secret $($x)*);
+    };
+    (@secrets ($constant:expr) $($x:tt)*) => {
+        __check_declared!(@secrets $($x)*);
+    };
+    (@secrets $secret:ident $($x:tt)*) => {
+        let _ = DeclaredSecrets::$secret;
+        __check_declared!(@secrets $($x)*);
+    };
+    (@secrets) => {};
+}
+
+/// Expands to a function `check_used` which matches exhaustively on
+/// `DeclaredPublics` and `DeclaredSecrets` using only the identifiers
+/// which appear in the statements and relations, so that a declared
+/// but unused identifier is a compile error.
 ///
-/// As an example, we can create and verify a DLEQ proof as follows:
+/// The input is `@munch (publics...) (secrets...);` followed by a
+/// sequence of `@lhs LHS @statement (RHS)` and `@secret x @relation
+/// (RHS)` items; the identifiers found are accumulated into the two
+/// lists.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_used {
+    // Left-hand sides of statements
+    (@munch $P:tt $S:tt; @lhs ($($x:tt)*) $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @lhs_points $($x)* @done $($rest)*);
+    };
+    (@munch ($($p:ident)*) $S:tt; @lhs $point:ident $($rest:tt)*) => {
+        __check_used!(@munch ($($p)* $point) $S; $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @lhs_points + $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @lhs_points $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @lhs_points - $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @lhs_points $($rest)*);
+    };
+    (@munch ($($p:ident)*) $S:tt; @lhs_points $point:ident $($rest:tt)*) => {
+        __check_used!(@munch ($($p)* $point) $S; @lhs_points $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @lhs_points @done $($rest:tt)*) => {
+        __check_used!(@munch $P $S; $($rest)*);
+    };
+    // Right-hand sides of statements
+    (@munch $P:tt $S:tt; @statement ($($x:tt)*) $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @terms $($x)* @done $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @terms + $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @terms $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @terms - $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @terms $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @terms ($coeff:expr) * $point:ident * $scalar:ident $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @terms $point * $scalar $($rest)*);
+    };
+    (@munch ($($p:ident)*) ($($s:ident)*);
+     @terms $point:ident * $scalar:ident $($rest:tt)*) => {
+        __check_used!(@munch ($($p)* $point) ($($s)* $scalar); @terms $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @terms @done $($rest:tt)*) => {
+        __check_used!(@munch $P $S; $($rest)*);
+    };
+    // Relations
+    (@munch $P:tt ($($s:ident)*); @secret $secret:ident $($rest:tt)*) => {
+        __check_used!(@munch $P ($($s)* $secret); $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @relation ($($x:tt)*) $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @secrets $($x)* @done $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @relation $secret:ident $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @secret $secret $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @secrets + $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @secrets $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @secrets - $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @secrets $($rest)*);
+    };
+    (@munch $P:tt ($($s:ident)*); @secrets ($coeff:expr) * $secret:ident $($rest:tt)*) => {
+        __check_used!(@munch $P ($($s)* $secret); @secrets $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @secrets ($constant:expr) $($rest:tt)*) => {
+        __check_used!(@munch $P $S; @secrets $($rest)*);
+    };
+    (@munch $P:tt ($($s:ident)*); @secrets $secret:ident $($rest:tt)*) => {
+        __check_used!(@munch $P ($($s)* $secret); @secrets $($rest)*);
+    };
+    (@munch $P:tt $S:tt; @secrets @done $($rest:tt)*) => {
+        __check_used!(@munch $P $S; $($rest)*);
+    };
+    // Everything has been collected
+    (@munch ($($p:ident)*) ($($s:ident)*);) => {
+        #[allow(dead_code, unreachable_patterns)]
+        fn check_used(public: DeclaredPublics, secret: DeclaredSecrets) {
+            match public { $( DeclaredPublics::$p )|* => {} }
+            match secret { $( DeclaredSecrets::$s )|* => {} }
+        }
+    };
+}
+
+/// The input to this macro is of the form
 ///
-/// ```
-/// #[macro_use]
-/// extern crate serde_derive;
+///   () @lhs LHS @statement (RHS)
 ///
-/// #[macro_use]
-/// extern crate zkp;
+/// for one statement `LHS = (RHS)`.  It expands to a `&[&str]` of the
+/// names of the publics in the statement, in order of appearance.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __statement_publics {
+    // Left-hand side
+    ($P:tt @lhs ($($x:tt)*) $($rest:tt)*) => {
+        __statement_publics!($P @lhs_points $($x)* @done $($rest)*)
+    };
+    (($($p:ident)*) @lhs $point:ident $($rest:tt)*) => {
+        __statement_publics!(($($p)* $point) $($rest)*)
+    };
+    ($P:tt @lhs_points + $($rest:tt)*) => {
+        __statement_publics!($P @lhs_points $($rest)*)
+    };
+    ($P:tt @lhs_points - $($rest:tt)*) => {
+        __statement_publics!($P @lhs_points $($rest)*)
+    };
+    (($($p:ident)*) @lhs_points $point:ident $($rest:tt)*) => {
+        __statement_publics!(($($p)* $point) @lhs_points $($rest)*)
+    };
+    ($P:tt @lhs_points @done $($rest:tt)*) => {
+        __statement_publics!($P $($rest)*)
+    };
+    // Right-hand side
+    ($P:tt @statement ($($x:tt)*)) => {
+        __statement_publics!($P @terms $($x)*)
+    };
+    ($P:tt @terms + $($rest:tt)*) => {
+        __statement_publics!($P @terms $($rest)*)
+    };
+    ($P:tt @terms - $($rest:tt)*) => {
+        __statement_publics!($P @terms $($rest)*)
+    };
+    ($P:tt @terms ($coeff:expr) * $point:ident * $scalar:ident $($rest:tt)*) => {
+        __statement_publics!($P @terms $point * $scalar $($rest)*)
+    };
+    (($($p:ident)*) @terms $point:ident * $scalar:ident $($rest:tt)*) => {
+        __statement_publics!(($($p)* $point) @terms $($rest)*)
+    };
+    (($($p:ident)*) @terms) => {
+        &[ $( stringify!($p) ),* ]
+    };
+}
+
+/// The input to this macro is of the form
 ///
-/// extern crate curve25519_dalek;
-/// use curve25519_dalek::constants as dalek_constants;
-/// use curve25519_dalek::decaf::DecafPoint;
-/// use curve25519_dalek::scalar::Scalar;
+///   () @lhs LHS @statement (RHS)
 ///
-/// extern crate rand;
-/// use rand::OsRng;
+/// for one statement `LHS = (RHS)`.  It expands to a `&str` label for
+/// the statement in the challenge hash: its tokens separated by single
+/// spaces, as in `"A = G * x + H * y"`, with each coefficient written
+/// as `(c)`.  Only identifiers are stringified, since the spacing of
+/// `stringify!` on longer inputs is not stable.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __statement_label {
+    // Left-hand side
+    ($S:tt @lhs ($($x:tt)*) $($rest:tt)*) => {
+        __statement_label!($S @lhs_points $($x)* @done $($rest)*)
+    };
+    (($($s:tt)*) @lhs $point:ident $($rest:tt)*) => {
+        __statement_label!(($($s)* stringify!($point),) $($rest)*)
+    };
+    (($($s:tt)*) @lhs_points + $($rest:tt)*) => {
+        __statement_label!(($($s)* " + ",) @lhs_points $($rest)*)
+    };
+    (($($s:tt)*) @lhs_points - $($rest:tt)*) => {
+        __statement_label!(($($s)* " - ",) @lhs_points $($rest)*)
+    };
+    (($($s:tt)*) @lhs_points $point:ident $($rest:tt)*) => {
+        __statement_label!(($($s)* stringify!($point),) @lhs_points $($rest)*)
+    };
+    ($S:tt @lhs_points @done $($rest:tt)*) => {
+        __statement_label!($S $($rest)*)
+    };
+    // Right-hand side
+    (($($s:tt)*) @statement (- $($x:tt)*)) => {
+        __statement_label!(($($s)* " = - ",) @terms $($x)*)
+    };
+    (($($s:tt)*) @statement ($($x:tt)*)) => {
+        __statement_label!(($($s)* " = ",) @terms $($x)*)
+    };
+    (($($s:tt)*) @terms + $($rest:tt)*) => {
+        __statement_label!(($($s)* " + ",) @terms $($rest)*)
+    };
+    (($($s:tt)*) @terms - $($rest:tt)*) => {
+        __statement_label!(($($s)* " - ",) @terms $($rest)*)
+    };
+    (($($s:tt)*) @terms ($coeff:expr) * $point:ident * $scalar:ident $($rest:tt)*) => {
+        __statement_label!(($($s)* "(c) * ",) @terms $point * $scalar $($rest)*)
+    };
+    (($($s:tt)*) @terms $point:ident * $scalar:ident $($rest:tt)*) => {
+        __statement_label!(($($s)* stringify!($point), " * ", stringify!($scalar),) @terms $($rest)*)
+    };
+    (($($s:tt)*) @terms) => {
+        concat!($($s)*)
+    };
+}
+
+/// The input to this macro is of the form
 ///
-/// extern crate sha2;
-/// use sha2::Sha256;
+///   () @lhs LHS @statement (RHS)
 ///
-/// extern crate serde_cbor;
+/// for one statement `LHS = (RHS)`.  It expands to a `&str` rendering
+/// the statement multiplicatively, in Camenisch-Stadler notation:
+/// `G * x` becomes `G^x`, `(c) * G * x` becomes `G^(c·x)`, a negated
+/// term `G^-x`, and terms are joined with `·`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cs_statement {
+    // Left-hand side
+    ($S:tt @lhs ($($x:tt)*) $($rest:tt)*) => {
+        __cs_statement!($S @lhs_points $($x)* @done $($rest)*)
+    };
+    (($($s:tt)*) @lhs $point:ident $($rest:tt)*) => {
+        __cs_statement!(($($s)* stringify!($point),) $($rest)*)
+    };
+    (($($s:tt)*) @lhs_points + $point:ident $($rest:tt)*) => {
+        __cs_statement!(($($s)* " · ", stringify!($point),) @lhs_points $($rest)*)
+    };
+    (($($s:tt)*) @lhs_points - $point:ident $($rest:tt)*) => {
+        __cs_statement!(($($s)* " · ", stringify!($point), "^-1",) @lhs_points $($rest)*)
+    };
+    (($($s:tt)*) @lhs_points $point:ident $($rest:tt)*) => {
+        __cs_statement!(($($s)* stringify!($point),) @lhs_points $($rest)*)
+    };
+    ($S:tt @lhs_points @done $($rest:tt)*) => {
+        __cs_statement!($S $($rest)*)
+    };
+    // Right-hand side
+    (($($s:tt)*) @statement (- $($x:tt)*)) => {
+        __cs_statement!(($($s)* " = ",) @term "-" $($x)*)
+    };
+    (($($s:tt)*) @statement ($($x:tt)*)) => {
+        __cs_statement!(($($s)* " = ",) @term "" $($x)*)
+    };
+    (($($s:tt)*) @terms + $($rest:tt)*) => {
+        __cs_statement!(($($s)* " · ",) @term "" $($rest)*)
+    };
+    (($($s:tt)*) @terms - $($rest:tt)*) => {
+        __cs_statement!(($($s)* " · ",) @term "-" $($rest)*)
+    };
+    (($($s:tt)*) @term $sign:tt ($coeff:expr) * $point:ident * $scalar:ident $($rest:tt)*) => {
+        __cs_statement!(($($s)* stringify!($point), "^", $sign, "(", stringify!($coeff), "·",
+                         stringify!($scalar), ")",) @terms $($rest)*)
+    };
+    (($($s:tt)*) @term $sign:tt $point:ident * $scalar:ident $($rest:tt)*) => {
+        __cs_statement!(($($s)* stringify!($point), "^", $sign, stringify!($scalar),) @terms $($rest)*)
+    };
+    (($($s:tt)*) @terms) => {
+        concat!($($s)*)
+    };
+}
+
+/// The input to this macro is of the form
 ///
-/// # fn main() {
-/// let mut csprng = OsRng::new().unwrap();
-/// let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
-/// let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+///   () lhs = (RELATION)
 ///
-/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+/// for one relation between the secrets.  It expands to a `&str`
+/// rendering the relation for `__cs_statement!`'s notation, with
+/// `(c) * x` written `c·x`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cs_relation {
+    (($($s:tt)*) $lhs:ident = (- $($x:tt)*)) => {
+        __cs_relation!(($($s)* stringify!($lhs), " = -",) @term $($x)*)
+    };
+    (($($s:tt)*) $lhs:ident = ($($x:tt)*)) => {
+        __cs_relation!(($($s)* stringify!($lhs), " = ",) @term $($x)*)
+    };
+    (($($s:tt)*) $lhs:ident = $secret:ident) => {
+        concat!($($s)* stringify!($lhs), " = ", stringify!($secret))
+    };
+    (($($s:tt)*) @terms + $($rest:tt)*) => {
+        __cs_relation!(($($s)* " + ",) @term $($rest)*)
+    };
+    (($($s:tt)*) @terms - $($rest:tt)*) => {
+        __cs_relation!(($($s)* " - ",) @term $($rest)*)
+    };
+    (($($s:tt)*) @term ($coeff:expr) * $secret:ident $($rest:tt)*) => {
+        __cs_relation!(($($s)* stringify!($coeff), "·", stringify!($secret),) @terms $($rest)*)
+    };
+    (($($s:tt)*) @term ($constant:expr) $($rest:tt)*) => {
+        __cs_relation!(($($s)* stringify!($constant),) @terms $($rest)*)
+    };
+    (($($s:tt)*) @term $secret:ident $($rest:tt)*) => {
+        __cs_relation!(($($s)* stringify!($secret),) @terms $($rest)*)
+    };
+    (($($s:tt)*) @terms) => {
+        concat!($($s)*)
+    };
+}
+
+/// `length` as an 8-byte little-endian integer, as every length is
+/// hashed in this crate.
+fn length_bytes(length: usize) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    for i in 0..8 {
+        bytes[i] = ((length as u64) >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+/// The versions of the challenge computation of `create_nipk!` proofs.
 ///
-/// let x = Scalar::from_u64(89327492234);
+/// The encoding of a proof does not say which version its challenge
+/// was computed with, so whenever the inputs to the challenge change,
+/// the old computation is kept as a version, and proofs stored before
+/// the change still verify with `Proof::verify_version`.  Proofs are
+/// created, and by default verified, with `Version::CURRENT`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Version {
+    /// The encoding of each public and then of each commitment, with
+    /// nothing else, as hashed by zkp 0.1.
+    ///
+    /// Proofs of zkp 0.1 had no context; here a context is hashed as
+    /// it is, before the publics.
+    V0,
+    /// The context, then each public and each commitment as
+    /// `len(label) || label || bytes`.
+    ///
+    /// The context is hashed as it is, so that only its own encoding
+    /// (empty, `len(context) || context`, or a 64-byte transcript
+    /// state) keeps it apart from the publics.
+    V1,
+    /// The domain `"zkp challenge v2"`, then the context, each public
+    /// and each commitment framed as
+    ///
+    /// ```text
+    /// kind || len(label) || label || len(bytes) || bytes
+    /// ```
+    ///
+    /// where `kind` is one byte, 0 for the context (whose label is
+    /// empty), 1 for a public and 2 for a commitment, and the lengths
+    /// are 8-byte little-endian integers.  Every input can be split
+    /// off the front of the rest unambiguously.
+    V2,
+}
+
+impl Version {
+    /// The version of the challenge of new proofs.
+    pub const CURRENT: Version = Version::V2;
+}
+
+/// The kinds of the framed inputs of a `Version::V2` challenge.
+const FRAME_CONTEXT: u8 = 0;
+const FRAME_PUBLIC: u8 = 1;
+const FRAME_COMMITMENT: u8 = 2;
+
+/// The first bytes of every `Version::V2` challenge.
+const CHALLENGE_V2_DOMAIN: &'static [u8] = b"zkp challenge v2";
+
+/// Feeds the start of a challenge of the given version, up to and
+/// including the context, given as the parts of its encoding, to
+/// `sink`.
+fn absorb_start<F: FnMut(&[u8])>(version: Version, context: &[&[u8]], mut sink: F) {
+    if version == Version::V2 {
+        sink(CHALLENGE_V2_DOMAIN);
+    }
+    absorb_input(version, FRAME_CONTEXT, "", context, sink);
+}
+
+/// Feeds one input of a challenge of the given version, whose
+/// encoding is the concatenation of `parts`, to `sink`.
+fn absorb_input<F: FnMut(&[u8])>(version: Version, kind: u8, label: &str, parts: &[&[u8]], mut sink: F) {
+    match version {
+        Version::V0 => {}
+        Version::V1 => {
+            if kind != FRAME_CONTEXT {
+                sink(&length_bytes(label.len()));
+                sink(label.as_bytes());
+            }
+        }
+        Version::V2 => {
+            sink(&[kind]);
+            sink(&length_bytes(label.len()));
+            sink(label.as_bytes());
+            sink(&length_bytes(parts.iter().map(|part| part.len()).sum()));
+        }
+    }
+    for part in parts {
+        sink(part);
+    }
+}
+
+/// Starts the challenge hash of the given version, in the context of
+/// an application or transcript.
+#[doc(hidden)]
+pub fn __start_challenge<D: sha2::Digest + Default>(version: Version, context: &[&[u8]]) -> D {
+    let mut hash = D::default();
+    absorb_start(version, context, |bytes| hash.input(bytes));
+    hash
+}
+
+/// Hashes the encoding of a public, labelled with its name.
+#[doc(hidden)]
+pub fn __input_public<D: sha2::Digest>(version: Version, hash: &mut D, name: &str, bytes: &[u8; 32]) {
+    absorb_input(version, FRAME_PUBLIC, name, &[&bytes[..]], |bytes| hash.input(bytes));
+}
+
+/// Hashes the encoding of a commitment, labelled with its statement.
+#[doc(hidden)]
+pub fn __input_commitment<D: sha2::Digest>(version: Version, hash: &mut D, label: &str, bytes: &[u8; 32]) {
+    absorb_input(version, FRAME_COMMITMENT, label, &[&bytes[..]], |bytes| hash.input(bytes));
+}
+
+/// The bytes hashed to compute a challenge of the given version, from
+/// the context and the `"public"` and `"commitment"` entries of
+/// `Proof::absorbed`.
+#[doc(hidden)]
+pub fn __challenge_input(version: Version, context: &[u8], entries: &[transcript::Absorbed]) -> Vec<u8> {
+    let mut out = Vec::new();
+    absorb_start(version, &[context], |bytes| out.extend_from_slice(bytes));
+    for entry in entries {
+        let kind = if entry.kind == "public" { FRAME_PUBLIC } else { FRAME_COMMITMENT };
+        absorb_input(version, kind, &entry.label, &[&entry.bytes[..]], |bytes| out.extend_from_slice(bytes));
+    }
+    out
+}
+
+/// The length of an application context, as an 8-byte little-endian
+/// integer: the challenge hashes `len(context) || context`, passed as
+/// these two parts so that no buffer is allocated for them.
+#[doc(hidden)]
+pub fn __context_length(context: &[u8]) -> [u8; 8] {
+    length_bytes(context.len())
+}
+
+/// Writes a proof as `Display`s it: its name, then the challenge and
+/// each response, one per line, in hex.
+#[doc(hidden)]
+pub fn __fmt_proof(
+    f: &mut fmt::Formatter,
+    name: &str,
+    challenge: &[u8; 32],
+    secrets: &[&str],
+    responses: &[&[u8; 32]],
+) -> fmt::Result {
+    write!(f, "{} proof", name)?;
+    write!(f, "\n  challenge: {}", hex::encode(challenge))?;
+    for (secret, response) in secrets.iter().zip(responses.iter()) {
+        write!(f, "\n  {}: {}", secret, hex::encode(*response))?;
+    }
+    Ok(())
+}
+
+/// Whether `name` occurs as an identifier in one of `formulas`.
+#[doc(hidden)]
+pub fn __mentions(formulas: &[&str], name: &str) -> bool {
+    formulas.iter().any(|formula| {
+        formula
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|word| word == name)
+    })
+}
+
+/// Counts the token trees given to it, as a `usize` constant
+/// expression.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __count_tts {
+    () => { 0usize };
+    ($head:tt $($tail:tt)*) => { 1usize + __count_tts!($($tail)*) };
+}
+
+/// The input to this macro is of the form
+///
+///   (0usize) a b c ...
+///
+/// and it expands to the constants `a = 0`, `b = 1`, `c = 2`, ...,
+/// the position of each name in the list.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __indices {
+    (($index:expr)) => {};
+    (($index:expr) $head:ident $($tail:ident)*) => {
+        pub const $head: usize = $index;
+        __indices!{ ($index + 1) $($tail)* }
+    };
+}
+
+/// Implements `arbitrary::Arbitrary` for the `Proof` and
+/// `CompressedPublicsOwned` in scope, given the names of the secrets
+/// and publics.  Like `__check_all`, the choice is made here rather
+/// than in `create_nipk!`: this expands to nothing unless this crate's
+/// `fuzzing` feature is enabled.
+///
+/// Scalars are reduced from 32 arbitrary bytes, so every generated
+/// proof is well-formed; points are arbitrary 32-byte encodings, most
+/// of which do not decompress.
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_arbitrary {
+    (@proof ($($secret:ident),+)) => {
+        impl $crate::arbitrary::Arbitrary for Proof {
+            fn arbitrary<U>(u: &mut U) -> Result<Proof, U::Error>
+                where U: $crate::arbitrary::Unstructured + ?Sized
+            {
+                let challenge = Scalar::from_bytes_mod_order(<[u8; 32] as $crate::arbitrary::Arbitrary>::arbitrary(u)?);
+                let responses = Responses::from(__Scalars{
+                    $( $secret : Scalar::from_bytes_mod_order(<[u8; 32] as $crate::arbitrary::Arbitrary>::arbitrary(u)?), )+
+                });
+                Ok(Proof{ challenge: challenge, responses: responses })
+            }
+        }
+    };
+    (@publics ($($public:ident),+)) => {
+        impl $crate::arbitrary::Arbitrary for CompressedPublicsOwned {
+            fn arbitrary<U>(u: &mut U) -> Result<CompressedPublicsOwned, U::Error>
+                where U: $crate::arbitrary::Unstructured + ?Sized
+            {
+                Ok(CompressedPublicsOwned{
+                    $( $public : <Point as $crate::group::PrimeGroup>::compressed(<[u8; 32] as $crate::arbitrary::Arbitrary>::arbitrary(u)?), )+
+                })
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "fuzzing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_arbitrary {
+    ($($x:tt)*) => {};
+}
+
+/// Implements `to_proto` and `from_proto` for the `Proof` and
+/// `CompressedPublicsOwned` in scope, using the messages in
+/// `zkp::proto`, when this crate's `proto` feature is enabled.
+#[cfg(feature = "proto")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_proto {
+    (@proof ($($secret:ident),+)) => {
+        impl Proof {
+            /// Encode the proof as a `zkp.Proof` protobuf message.
+            #[allow(dead_code)]
+            pub fn to_proto(&self) -> Vec<u8> {
+                $crate::proto::ProofMessage{
+                    statement: NAME.to_string(),
+                    challenge: self.challenge.as_bytes().to_vec(),
+                    responses: vec![ $( self.responses.0[response_index::$secret].as_bytes().to_vec() ),+ ],
+                }.encode()
+            }
+
+            /// Parse a `zkp.Proof` message for this statement,
+            /// rejecting non-canonical scalars.
+            #[allow(dead_code)]
+            pub fn from_proto(bytes: &[u8]) -> Result<Proof,()> {
+                let message = $crate::proto::ProofMessage::decode(bytes)?;
+                if message.statement != NAME || message.responses.len() != SECRETS.len() {
+                    return Err(());
+                }
+                let mut responses = message.responses.iter();
+                Ok(Proof{
+                    challenge: $crate::__decode_scalar(&message.challenge)?,
+                    responses: Responses::from(__Scalars{
+                        $( $secret : $crate::__decode_scalar(responses.next().unwrap())?, )+
+                    }),
+                })
+            }
+        }
+    };
+    (@publics ($($public:ident),+)) => {
+        impl CompressedPublicsOwned {
+            /// Encode the publics as a `zkp.CompressedPublics`
+            /// protobuf message.
+            #[allow(dead_code)]
+            pub fn to_proto(&self) -> Vec<u8> {
+                $crate::proto::CompressedPublicsMessage{
+                    statement: NAME.to_string(),
+                    points: vec![ $( self.$public.as_bytes().to_vec() ),+ ],
+                }.encode()
+            }
+
+            /// Parse a `zkp.CompressedPublics` message for this
+            /// statement.  The points are not decompressed.
+            #[allow(dead_code)]
+            pub fn from_proto(bytes: &[u8]) -> Result<CompressedPublicsOwned,()> {
+                let message = $crate::proto::CompressedPublicsMessage::decode(bytes)?;
+                if message.statement != NAME || message.points.len() != PUBLICS.len() {
+                    return Err(());
+                }
+                let mut points = message.points.iter();
+                Ok(CompressedPublicsOwned{
+                    $( $public : $crate::proto::__compressed_point::<Point>(points.next().unwrap())?, )+
+                })
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "proto"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_proto {
+    ($($x:tt)*) => {};
+}
+
+/// Implements `to_der` and `from_der` for the `Proof` in scope, using
+/// `zkp::der`, when this crate's `der` feature is enabled.
+#[cfg(feature = "der")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_der {
+    () => {
+        impl Proof {
+            /// Encode the proof in DER, with the object identifier
+            /// `algorithm` and the statement name in its algorithm
+            /// identifier.
+            ///
+            /// Returns `Err(())` if `algorithm` is not a valid object
+            /// identifier.
+            #[allow(dead_code)]
+            pub fn to_der(&self, algorithm: &[u64]) -> Result<Vec<u8>,()> {
+                $crate::der::__encode_proof(algorithm, NAME, &self.chunks())
+            }
+
+            /// Parse a proof encoded by `to_der` with the same
+            /// `algorithm`.
+            #[allow(dead_code)]
+            pub fn from_der(bytes: &[u8], algorithm: &[u64]) -> Result<Proof,()> {
+                let bytes = $crate::der::__decode_proof(bytes, algorithm, NAME, 1 + SECRETS.len())?;
+                Proof::from_bytes(&bytes)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "der"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_der {
+    () => {};
+}
+
+/// Implements `to_postcard` and `from_postcard` for the `Proof` in
+/// scope, when this crate's `postcard-encoding` feature is enabled.
+#[cfg(feature = "postcard-encoding")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_postcard {
+    ($($secret:ident)+) => {
+        impl Proof {
+            /// An upper bound on the length of the postcard encoding,
+            /// for sizing stack buffers: each scalar is at most a
+            /// one-byte length and 32 bytes.
+            #[allow(dead_code)]
+            pub const POSTCARD_MAX_SIZE: usize = 33 * (1 + __count_tts!($($secret)+));
+
+            /// Encode the proof with postcard into `buf`, returning
+            /// the part of `buf` which was written.
+            ///
+            /// Returns `Err(())` if `buf` is too short, which cannot
+            /// happen if it is at least `POSTCARD_MAX_SIZE` bytes.
+            #[allow(dead_code)]
+            pub fn to_postcard<'b>(&self, buf: &'b mut [u8]) -> Result<&'b mut [u8], ()> {
+                $crate::postcard::to_slice(self, buf).map_err(|_| ())
+            }
+
+            /// Parse a proof encoded by `to_postcard`, rejecting
+            /// non-canonical scalars and trailing bytes.
+            #[allow(dead_code)]
+            pub fn from_postcard(bytes: &[u8]) -> Result<Proof,()> {
+                match $crate::postcard::take_from_bytes(bytes) {
+                    Ok((proof, rest)) if rest.is_empty() => Ok(proof),
+                    _ => Err(()),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "postcard-encoding"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_postcard {
+    ($($x:tt)*) => {};
+}
+
+/// The number of runs of the sigma protocol in a `FischlinProof`.
+pub const FISCHLIN_REPETITIONS: usize = 16;
+/// The number of bits of the challenge of each run of a
+/// `FischlinProof`.
+pub const FISCHLIN_CHALLENGE_BITS: u32 = 12;
+/// The number of leading zero bits of the hash which accepts a run of
+/// a `FischlinProof`.  A prover who does not know the witness passes
+/// all the runs with probability about
+/// `2^-(FISCHLIN_REPETITIONS * FISCHLIN_WORK_BITS)`, or `2^-128`.
+pub const FISCHLIN_WORK_BITS: u32 = 8;
+
+/// Whether a hash output starts with `FISCHLIN_WORK_BITS` zero bits.
+#[doc(hidden)]
+pub fn __fischlin_accepts(output: &[u8]) -> bool {
+    let bytes = (FISCHLIN_WORK_BITS / 8) as usize;
+    let bits = FISCHLIN_WORK_BITS % 8;
+    output[..bytes].iter().all(|&byte| byte == 0) && (bits == 0 || output[bytes] >> (8 - bits) == 0)
+}
+
+/// Computes `sum_i scalars[i] * points[i]` in constant time.
+///
+/// The terms are multiplied one by one, on the stack, so that no
+/// tables are allocated.
+#[doc(hidden)]
+pub fn __multiscalar_mult<P: PrimeGroup>(scalars: &[Scalar], points: &[&P]) -> P
+    where for<'a, 'b> &'a P: ops::Mul<&'b Scalar, Output = P> + ops::Add<&'b P, Output = P>
+{
+    debug_assert_eq!(scalars.len(), points.len());
+    let mut sum = points[0] * &scalars[0];
+    for (a, P) in scalars.iter().zip(points.iter()).skip(1) {
+        sum = &sum + &(*P * a);
+    }
+    sum
+}
+
+/// Returns `Ok(())` if `check` holds for every pair `(a[i], b[i])`,
+/// and `Err(())` if it fails for any pair or if the slices have
+/// different lengths.
+///
+/// The pairs are checked on a thread pool when this crate's
+/// `parallel` feature is enabled (except on `wasm32`), and
+/// sequentially otherwise.  This
+/// has to be a function rather than part of `create_nipk!`, since a
+/// `cfg` in the expansion would test the features of the calling
+/// crate.
+#[doc(hidden)]
+pub fn __check_all<A, B, F>(a: &[A], b: &[B], check: F) -> Result<(),()>
+    where A: Sync, B: Sync, F: Fn(&A, &B) -> bool + Sync
+{
+    if a.len() != b.len() {
+        return Err(());
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    let ok = {
+        use rayon::prelude::*;
+        a.par_iter().zip(b.par_iter()).all(|(x, y)| check(x, y))
+    };
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    let ok = a.iter().zip(b.iter()).all(|(x, y)| check(x, y));
+
+    if ok { Ok(()) } else { Err(()) }
+}
+
+/// Statements with at least this many equations have their
+/// commitments computed on a thread pool, when this crate's `parallel`
+/// feature is enabled.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const PARALLEL_COMMITMENTS: usize = 8;
+
+/// Sets `out[i]` to the result of `jobs[i]`.
+///
+/// If there are at least `PARALLEL_COMMITMENTS` jobs, and this crate's
+/// `parallel` feature is enabled (except on `wasm32`), they are run on
+/// a thread pool.  As for `__check_all`, this has to be a function
+/// rather than part of `create_nipk!`.
+#[doc(hidden)]
+pub fn __compute_all<P: Send>(jobs: &[&(dyn Fn() -> P + Sync)], out: &mut [P]) {
+    debug_assert_eq!(jobs.len(), out.len());
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    {
+        if jobs.len() >= PARALLEL_COMMITMENTS {
+            use rayon::prelude::*;
+            out.par_iter_mut().zip(jobs.par_iter()).for_each(|(point, job)| *point = job());
+            return;
+        }
+    }
+
+    for (point, job) in out.iter_mut().zip(jobs.iter()) {
+        *point = job();
+    }
+}
+
+/// Compares two scalars in constant time.
+#[doc(hidden)]
+pub fn __scalar_ct_eq(a: &Scalar, b: &Scalar) -> subtle::Choice {
+    use subtle::ConstantTimeEq;
+    a.as_bytes()[..].ct_eq(&b.as_bytes()[..])
+}
+
+/// Decodes a compressed point, failing if `bytes` is not a valid
+/// 32-byte encoding.
+#[doc(hidden)]
+pub fn __decode_point<P: PrimeGroup>(bytes: &[u8]) -> Result<P,()> {
+    if bytes.len() != 32 {
+        return Err(());
+    }
+    let mut encoding = [0u8; 32];
+    encoding.copy_from_slice(bytes);
+    P::from_bytes(&encoding).ok_or(())
+}
+
+/// Checks whether `point` is the identity, the only point of small
+/// order in a prime-order group such as Decaf.
+#[doc(hidden)]
+pub fn __is_identity<P: PrimeGroup>(point: &P) -> bool {
+    point.to_bytes() == P::identity().to_bytes()
+}
+
+/// Decodes a scalar, failing if `bytes` is not the canonical 32-byte
+/// encoding of a scalar.
+#[doc(hidden)]
+pub fn __decode_scalar(bytes: &[u8]) -> Result<Scalar,()> {
+    if bytes.len() != 32 {
+        return Err(());
+    }
+    let mut encoding = [0u8; 32];
+    encoding.copy_from_slice(bytes);
+    let scalar = Scalar::from_bytes_mod_order(encoding);
+    if scalar.as_bytes() == &encoding { Ok(scalar) } else { Err(()) }
+}
+
+/// Serializes scalars as hex strings in human-readable formats such
+/// as JSON, and as usual otherwise.  Used by `create_nipk!` with
+/// `#[serde(with = ...)]`.
+///
+/// In either case, deserialization rejects non-canonical encodings,
+/// so that each proof has exactly one encoding.
+#[doc(hidden)]
+pub mod __serde_scalar {
+    use std::fmt;
+    use curve25519_dalek::scalar::Scalar;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::{Error, SeqAccess, Visitor};
+    use hex;
+
+    struct ScalarVisitor;
+
+    impl<'de> Visitor<'de> for ScalarVisitor {
+        type Value = Scalar;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a canonical 32-byte scalar encoding")
+        }
+
+        fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Scalar, E> {
+            ::__decode_scalar(bytes).map_err(|_| E::custom("invalid scalar"))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Scalar, A::Error> {
+            let mut bytes = Vec::with_capacity(32);
+            while let Some(byte) = seq.next_element::<u8>()? {
+                if bytes.len() == 32 {
+                    return Err(A::Error::custom("invalid scalar"));
+                }
+                bytes.push(byte);
+            }
+            self.visit_bytes(&bytes)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(scalar: &Scalar, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(scalar.as_bytes()))
+        } else {
+            scalar.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Scalar, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            let bytes = hex::decode(&s).map_err(|_| D::Error::custom("invalid hex"))?;
+            ::__decode_scalar(&bytes).map_err(|_| D::Error::custom("invalid scalar"))
+        } else {
+            deserializer.deserialize_bytes(ScalarVisitor)
+        }
+    }
+}
+
+/// Proofs with a fixed binary encoding.  Implemented by every `Proof`
+/// generated by `create_nipk!`.
+pub trait ProofBytes: Sized {
+    fn to_bytes(&self) -> Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Result<Self,()>;
+}
+
+/// Proofs of a statement, for code which is generic over the
+/// statement.  Implemented by every `Proof` generated by
+/// `create_nipk!`, with the `PublicsOwned` and `SecretsOwned` structs
+/// of its module as the parameters.
+pub trait NizkProof: ProofBytes {
+    type Publics;
+    type Secrets;
+
+    /// The name of the module generated by `create_nipk!`, which
+    /// labels the statement.
+    const NAME: &'static str;
+
+    fn create<R: rand::Rng>(csprng: &mut R, publics: &Self::Publics, secrets: &Self::Secrets) -> Self;
+    fn verify(&self, publics: &Self::Publics) -> Result<(),()>;
+}
+
+/// A known coefficient of a term in a `create_nipk!` statement.
+pub trait Coefficient {
+    fn to_scalar(self) -> Scalar;
+}
+
+impl Coefficient for Scalar {
+    fn to_scalar(self) -> Scalar {
+        self
+    }
+}
+
+impl<'a> Coefficient for &'a Scalar {
+    fn to_scalar(self) -> Scalar {
+        *self
+    }
+}
+
+impl Coefficient for u64 {
+    fn to_scalar(self) -> Scalar {
+        Scalar::from_u64(self)
+    }
+}
+
+impl Coefficient for u32 {
+    fn to_scalar(self) -> Scalar {
+        Scalar::from_u64(self as u64)
+    }
+}
+
+impl Coefficient for i64 {
+    fn to_scalar(self) -> Scalar {
+        if self < 0 {
+            -&Scalar::from_u64(self.wrapping_neg() as u64)
+        } else {
+            Scalar::from_u64(self as u64)
+        }
+    }
+}
+
+impl Coefficient for i32 {
+    fn to_scalar(self) -> Scalar {
+        (self as i64).to_scalar()
+    }
+}
+
+/// The operating system's random number generator was unavailable.
+#[derive(Debug)]
+pub struct RngError(pub io::Error);
+
+impl fmt::Display for RngError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OS random number generator unavailable: {}", self.0)
+    }
+}
+
+impl error::Error for RngError {
+    fn description(&self) -> &str {
+        "OS random number generator unavailable"
+    }
+}
+
+/// Opens the operating system's random number generator.
+#[doc(hidden)]
+pub fn __os_rng() -> Result<rand::OsRng, RngError> {
+    rand::OsRng::new().map_err(RngError)
+}
+
+/// Defines the `Point` and `CompressedPoint` types of a `create_nipk!`
+/// module: the point type given as `name<H, P>`, or `DecafPoint`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __group_types {
+    () => {
+        __group_types!{ $crate::curve25519_dalek::decaf::DecafPoint }
+    };
+    ($point:ty) => {
+        /// The type of the points in the statements.
+        #[allow(dead_code)]
+        pub type Point = $point;
+        /// The compressed encoding of a `Point`.
+        #[allow(dead_code)]
+        pub type CompressedPoint = <$point as $crate::group::PrimeGroup>::Compressed;
+    };
+}
+
+/// Defines the `ChallengeHash` of a `create_nipk!` module: the hash
+/// given as `name<H>`, or SHA-512.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __challenge_hash {
+    () => {
+        /// The hash function of the Fiat-Shamir challenge.
+        #[allow(dead_code)]
+        pub type ChallengeHash = $crate::sha2::Sha512;
+    };
+    ($hash:ty) => {
+        /// The hash function of the Fiat-Shamir challenge.
+        #[allow(dead_code)]
+        pub type ChallengeHash = $hash;
+    };
+}
+
+/// Defines the `Publics`, `Secrets` and related types for a
+/// `create_nipk!` module, which all the proofs of a module with
+/// several statements share.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nipk_publics {
+    (($($secret:ident),+) ($($public:ident),+)) => {
+        #[derive(Copy, Clone)]
+        pub struct Secrets<'a> {
+            // Create a parameter for each secret value
+            $(
+                pub $secret : &'a Scalar,
+            )+
+        }
+
+        #[derive(Copy, Clone)]
+        pub struct Publics<'a> {
+            // Create a parameter for each public value
+            $(
+                pub $public : &'a Point,
+            )+
+        }
+
+        /// The public parameters, in compressed form.
+        #[derive(Copy, Clone)]
+        pub struct CompressedPublics<'a> {
+            $(
+                pub $public : &'a CompressedPoint,
+            )+
+        }
+
+        /// The encodings of the public parameters, held by value.
+        #[derive(Copy, Clone)]
+        pub struct CompressedPublicsOwned {
+            $(
+                pub $public : CompressedPoint,
+            )+
+        }
+
+        /// The secret parameters, held by value.
+        #[derive(Clone)]
+        pub struct SecretsOwned {
+            $(
+                pub $secret : Scalar,
+            )+
+        }
+
+        /// The public parameters, held by value.
+        #[derive(Copy, Clone, Serialize, Deserialize)]
+        pub struct PublicsOwned {
+            $(
+                pub $public : Point,
+            )+
+        }
+
+        impl<'a> Publics<'a> {
+            /// The length of the encoding of the publics, as
+            /// compressed points of 32 bytes each.
+            #[allow(dead_code)]
+            pub const SERIALIZED_SIZE: usize = 32 * __count_tts!($($public)+);
+
+            /// Encode the publics as the concatenation of their
+            /// compressed points, in the order they were declared.
+            #[allow(dead_code)]
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = Vec::with_capacity(Publics::SERIALIZED_SIZE);
+                $( bytes.extend_from_slice(self.$public.compress().as_bytes()); )+
+                bytes
+            }
+        }
+
+        impl<'a> Secrets<'a> {
+            /// Encode the secrets as the concatenation of their
+            /// 32-byte encodings, in the order they were declared.
+            #[allow(dead_code)]
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = Vec::new();
+                $( bytes.extend_from_slice(self.$secret.as_bytes()); )+
+                bytes
+            }
+        }
+
+        impl<'a> ::std::fmt::Debug for Secrets<'a> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct("Secrets")
+                    $( .field(stringify!($secret), &$crate::secret::__Redacted) )+
+                    .finish()
+            }
+        }
+
+        impl ::std::fmt::Debug for SecretsOwned {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct("SecretsOwned")
+                    $( .field(stringify!($secret), &$crate::secret::__Redacted) )+
+                    .finish()
+            }
+        }
+
+        impl SecretsOwned {
+            #[allow(dead_code)]
+            pub fn as_secrets<'a>(&'a self) -> Secrets<'a> {
+                Secrets{ $( $secret : &self.$secret, )+ }
+            }
+        }
+
+        impl PublicsOwned {
+            #[allow(dead_code)]
+            pub fn as_publics<'a>(&'a self) -> Publics<'a> {
+                Publics{ $( $public : &self.$public, )+ }
+            }
+        }
+
+        impl CompressedPublicsOwned {
+            #[allow(dead_code)]
+            pub fn as_compressed_publics<'a>(&'a self) -> CompressedPublics<'a> {
+                CompressedPublics{ $( $public : &self.$public, )+ }
+            }
+        }
+
+        impl SecretsOwned {
+            /// Parse the secrets from the concatenation of their
+            /// 32-byte encodings, in the order they were declared.
+            #[allow(dead_code)]
+            pub fn from_bytes(bytes: &[u8]) -> Result<SecretsOwned,()> {
+                if bytes.len() != 32 * __count_tts!($($secret)+) {
+                    return Err(());
+                }
+                let mut chunks = bytes.chunks(32);
+                Ok(SecretsOwned{
+                    $( $secret : $crate::__decode_scalar(chunks.next().unwrap())?, )+
+                })
+            }
+        }
+
+        impl PublicsOwned {
+            /// Parse the publics from the concatenation of their
+            /// 32-byte compressed encodings, in the order they were
+            /// declared.
+            #[allow(dead_code)]
+            pub fn from_bytes(bytes: &[u8]) -> Result<PublicsOwned,()> {
+                if bytes.len() != Publics::SERIALIZED_SIZE {
+                    return Err(());
+                }
+                let mut chunks = bytes.chunks(32);
+                Ok(PublicsOwned{
+                    $( $public : $crate::__decode_point(chunks.next().unwrap())?, )+
+                })
+            }
+        }
+
+        impl<'a> From<Secrets<'a>> for SecretsOwned {
+            fn from(secrets: Secrets<'a>) -> SecretsOwned {
+                SecretsOwned{ $( $secret : *secrets.$secret, )+ }
+            }
+        }
+
+        impl<'a> From<Publics<'a>> for PublicsOwned {
+            fn from(publics: Publics<'a>) -> PublicsOwned {
+                PublicsOwned{ $( $public : *publics.$public, )+ }
+            }
+        }
+
+        // Every identifier used in a statement or relation must
+        // name one of these; see `check_declared`.  In turn, unless
+        // the module has several statements, every one of these must
+        // be used somewhere; see `check_used`.
+        #[allow(dead_code, non_camel_case_types)]
+        enum DeclaredPublics { $($public),+ }
+        #[allow(dead_code, non_camel_case_types)]
+        enum DeclaredSecrets { $($secret),+ }
+
+        /// The names of the secrets, in the order they were declared.
+        #[allow(dead_code)]
+        pub const SECRETS: &'static [&'static str] = &[ $( stringify!($secret) ),+ ];
+        /// The names of the publics, in the order they were declared.
+        #[allow(dead_code)]
+        pub const PUBLICS: &'static [&'static str] = &[ $( stringify!($public) ),+ ];
+
+        __impl_arbitrary!{ @publics ($($public),+) }
+        __impl_proto!{ @publics ($($public),+) }
+    };
+}
+
+/// Defines the `Proof` and everything specific to one statement for
+/// a `create_nipk!` module, given the same input as `create_nipk!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nipk_proof {
+    (
+        $(#[$proof_attr:meta])*
+        $proof_module_name:ident
+        ,
+        ( $($secret:ident),+ )
+        ,
+        ( $($public:ident),+ )
+        :
+        $($lhs:tt = $statement:tt),+
+        ;
+        $($rel_lhs:ident = $relation:tt),*
+    ) => {
+        #[allow(dead_code)]
+        fn check_declared() {
+            $(
+                __check_declared!(@lhs $lhs);
+                __check_declared!(@statement $statement);
+            )+
+            $(
+                let _ = DeclaredSecrets::$rel_lhs;
+                __check_declared!(@relation $relation);
+            )*
+        }
+
+        /// The name of this proof module.
+        #[allow(dead_code)]
+        pub const NAME: &'static str = stringify!($proof_module_name);
+        /// The statements, as written.
+        #[allow(dead_code)]
+        pub const STATEMENTS: &'static [&'static str] = &[ $( stringify!($lhs = $statement) ),+ ];
+        /// The labels of the statements in the challenge hash: each
+        /// statement with its tokens separated by single spaces and
+        /// each coefficient as `(c)`, as in `"A = G * x + (c) * H * y"`,
+        /// so that statements which differ only in the roles of their
+        /// publics have different challenges.
+        #[allow(dead_code)]
+        pub const LABELS: &'static [&'static str] = &[
+            $( __statement_label!(() @lhs $lhs @statement $statement) ),+
+        ];
+        /// For each statement, the names of the publics it uses, in
+        /// order of appearance (with repeats).
+        #[allow(dead_code)]
+        pub const STATEMENT_PUBLICS: &'static [&'static [&'static str]] = &[
+            $( __statement_publics!(() @lhs $lhs @statement $statement) ),+
+        ];
+        /// The relations between the secrets, as written.
+        #[allow(dead_code)]
+        pub const RELATIONS: &'static [&'static str] = &[ $( stringify!($rel_lhs = $relation) ),* ];
+
+        /// The statements and relations in Camenisch-Stadler
+        /// notation, as in `"PK{(x): A = G^x ∧ B = H^x}"`.
+        #[allow(dead_code)]
+        pub fn camenisch_stadler() -> String {
+            let parts: &[&str] = &[
+                $( __cs_statement!(() @lhs $lhs @statement $statement), )+
+                $( __cs_relation!(() $rel_lhs = $relation), )*
+            ];
+            format!("PK{{({}): {}}}", SECRETS.join(", "), parts.join(" ∧ "))
+        }
+
+        /// The prover's commitments, one per statement.
+        pub type Commitments = [Point; __count_tts!($($statement)+)];
+        // Hack because we can't concat identifiers,
+        // so do rand.x instead of rand_x, etc.
+        /// One scalar per secret, by name.
+        #[doc(hidden)]
+        #[derive(Copy, Clone, Serialize, Deserialize)]
+        pub struct __Scalars {
+            $(
+                #[serde(with = "serde_scalar")]
+                pub $secret : Scalar,
+            )+
+        }
+
+        /// The index of each secret's response in `Responses`.
+        #[allow(dead_code, non_upper_case_globals)]
+        mod response_index {
+            __indices!{ (0usize) $($secret)+ }
+        }
+
+        /// The prover's responses, one per secret, in the order the
+        /// secrets were declared.
+        #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+        #[serde(from = "__Scalars", into = "__Scalars")]
+        pub struct Responses([Scalar; __count_tts!($($secret)+)]);
+
+        impl Responses {
+            /// The number of responses, one per secret.
+            #[allow(dead_code)]
+            pub const LEN: usize = __count_tts!($($secret)+);
+
+            $(
+                /// The response for this secret.
+                #[allow(dead_code)]
+                pub fn $secret(&self) -> Scalar {
+                    self.0[response_index::$secret]
+                }
+            )+
+
+            /// The responses, in the order the secrets were declared.
+            #[allow(dead_code)]
+            pub fn to_array(&self) -> [Scalar; __count_tts!($($secret)+)] {
+                self.0
+            }
+
+            /// The responses given in the order the secrets were
+            /// declared.
+            #[allow(dead_code)]
+            pub fn from_array(scalars: [Scalar; __count_tts!($($secret)+)]) -> Responses {
+                Responses(scalars)
+            }
+        }
+
+        impl From<__Scalars> for Responses {
+            fn from(scalars: __Scalars) -> Responses {
+                Responses([ $( scalars.$secret ),+ ])
+            }
+        }
+
+        impl From<Responses> for __Scalars {
+            fn from(responses: Responses) -> __Scalars {
+                __Scalars{ $( $secret : responses.$secret(), )+ }
+            }
+        }
+
+        #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+        $(#[$proof_attr])*
+        pub struct Proof {
+            #[serde(with = "serde_scalar")]
+            challenge: Scalar,
+            responses: Responses,
+        }
+
+        /// Proofs are compared in constant time.
+        impl PartialEq for Proof {
+            fn eq(&self, other: &Proof) -> bool {
+                let mut difference = 0u8;
+                let pairs = [
+                    (&self.challenge, &other.challenge),
+                    $( (&self.responses.0[response_index::$secret], &other.responses.0[response_index::$secret]), )+
+                ];
+                for &(a, b) in pairs.iter() {
+                    for (x, y) in a.as_bytes().iter().zip(b.as_bytes().iter()) {
+                        difference |= x ^ y;
+                    }
+                }
+                difference == 0
+            }
+        }
+
+        impl Eq for Proof {}
+
+        /// Shows the challenge and each named response in hex, one per
+        /// line, for comparing proofs field by field.
+        impl ::std::fmt::Display for Proof {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                $crate::__fmt_proof(
+                    f,
+                    NAME,
+                    self.challenge.as_bytes(),
+                    SECRETS,
+                    &[ $( self.responses.0[response_index::$secret].as_bytes() ),+ ],
+                )
+            }
+        }
+
+        impl Proof {
+            /// The length of the encoding produced by `to_bytes`.
+            #[allow(dead_code)]
+            pub const SERIALIZED_SIZE: usize = 32 * (1 + __count_tts!($($secret)+));
+
+            /// Encode the proof as the challenge followed by the
+            /// responses, in the order the secrets were declared,
+            /// each as 32 bytes.
+            #[allow(dead_code)]
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = Vec::with_capacity(Proof::SERIALIZED_SIZE);
+                bytes.extend_from_slice(self.challenge.as_bytes());
+                $( bytes.extend_from_slice(self.responses.0[response_index::$secret].as_bytes()); )+
+                bytes
+            }
+
+            /// The encoding of `to_bytes`, in an array on the stack.
+            #[allow(dead_code)]
+            pub fn to_byte_array(&self) -> [u8; 32 * (1 + __count_tts!($($secret)+))] {
+                let mut bytes = [0u8; 32 * (1 + __count_tts!($($secret)+))];
+                for (out, chunk) in bytes.chunks_mut(32).zip(self.chunks().iter()) {
+                    out.copy_from_slice(&chunk[..]);
+                }
+                bytes
+            }
+
+            /// The 32-byte chunks of `to_bytes`, without allocating.
+            fn chunks(&self) -> [&[u8; 32]; 1 + __count_tts!($($secret)+)] {
+                [ self.challenge.as_bytes(), $( self.responses.0[response_index::$secret].as_bytes() ),+ ]
+            }
+
+            /// Parse a proof encoded by `to_bytes`, rejecting
+            /// non-canonical scalars.
+            #[allow(dead_code)]
+            pub fn from_bytes(bytes: &[u8]) -> Result<Proof,()> {
+                if bytes.len() != Proof::SERIALIZED_SIZE {
+                    return Err(());
+                }
+                let mut chunks = bytes.chunks(32);
+                let challenge = $crate::__decode_scalar(chunks.next().unwrap())?;
+                let responses = Responses::from(__Scalars{
+                    $( $secret : $crate::__decode_scalar(chunks.next().unwrap())?, )+
+                });
+                Ok(Proof{ challenge: challenge, responses: responses })
+            }
+
+            /// Encode the proof as canonical CBOR, as described in
+            /// `zkp::cbor`.
+            #[allow(dead_code)]
+            pub fn to_canonical_cbor(&self) -> Vec<u8> {
+                $crate::cbor::__encode(
+                    self.challenge.as_bytes(),
+                    SECRETS,
+                    &[ $( self.responses.0[response_index::$secret].as_bytes() ),+ ],
+                )
+            }
+
+            /// Parse a proof encoded by `to_canonical_cbor`,
+            /// rejecting any other encoding of it.
+            #[allow(dead_code)]
+            pub fn from_canonical_cbor(bytes: &[u8]) -> Result<Proof,()> {
+                Proof::from_bytes(&$crate::cbor::__decode(bytes, SECRETS)?)
+            }
+        }
+
+        /// Verifies many proofs against the same publics.
+        ///
+        /// The publics are decompressed (if given compressed) and
+        /// compressed once, and hashed once into a challenge hash
+        /// which is cloned for each proof, so each verification
+        /// only recomputes the commitments.
+        pub struct Verifier {
+            publics: PublicsOwned,
+            hash: ChallengeHash,
+        }
+
+        impl Verifier {
+            #[allow(dead_code)]
+            pub fn new(publics: Publics) -> Verifier {
+                Verifier{
+                    publics: PublicsOwned::from(publics),
+                    hash: hash_publics($crate::Version::CURRENT, &[], &[ $( publics.$public.compress().as_bytes() ),+ ]),
+                }
+            }
+
+            /// Create a verifier from compressed publics, failing
+            /// if any encoding is invalid.
+            #[allow(dead_code)]
+            pub fn from_compressed(publics: CompressedPublics) -> Result<Verifier,()> {
+                Ok(Verifier{
+                    publics: PublicsOwned{
+                        $( $public : publics.$public.decompress().ok_or(())?, )+
+                    },
+                    hash: hash_publics($crate::Version::CURRENT, &[], &[ $( publics.$public.as_bytes() ),+ ]),
+                })
+            }
+
+            /// Verify `proof`, as `proof.verify(publics)` would.
+            #[allow(dead_code)]
+            pub fn verify(&self, proof: &Proof) -> Result<(),()> {
+                proof.verify_with_hash(self.publics.as_publics(), $crate::Version::CURRENT, self.hash.clone())
+            }
+
+            /// Verify each of `proofs`, on a thread pool if `zkp` is
+            /// built with the `parallel` feature.
+            #[allow(dead_code)]
+            pub fn verify_batch(&self, proofs: &[Proof]) -> Result<(),()> {
+                // `__check_all` checks pairs, so pair each proof with itself.
+                $crate::__check_all(proofs, proofs, |proof, _| self.verify(proof).is_ok())
+            }
+
+            #[allow(dead_code)]
+            pub fn publics<'a>(&'a self) -> Publics<'a> {
+                self.publics.as_publics()
+            }
+        }
+
+        /// Queues proofs with their publics for `verify_batch`, in
+        /// buffers which are kept between batches, so that a verifier
+        /// checking batch after batch of about the same size stops
+        /// allocating after the first.
+        #[derive(Default)]
+        pub struct BatchVerifier {
+            proofs: Vec<Proof>,
+            publics: Vec<PublicsOwned>,
+        }
+
+        impl BatchVerifier {
+            #[allow(dead_code)]
+            pub fn new() -> BatchVerifier {
+                BatchVerifier::default()
+            }
+
+            /// Create a batch verifier with room for `n` proofs.
+            #[allow(dead_code)]
+            pub fn with_capacity(n: usize) -> BatchVerifier {
+                BatchVerifier{ proofs: Vec::with_capacity(n), publics: Vec::with_capacity(n) }
+            }
+
+            /// Queue `proof`, to be verified against `publics`.
+            #[allow(dead_code)]
+            pub fn push(&mut self, proof: &Proof, publics: Publics) {
+                self.proofs.push(*proof);
+                self.publics.push(PublicsOwned::from(publics));
+            }
+
+            /// The number of queued proofs.
+            #[allow(dead_code)]
+            pub fn len(&self) -> usize {
+                self.proofs.len()
+            }
+
+            #[allow(dead_code)]
+            pub fn is_empty(&self) -> bool {
+                self.proofs.is_empty()
+            }
+
+            /// Drop the queued proofs, keeping the buffers.
+            #[allow(dead_code)]
+            pub fn clear(&mut self) {
+                self.proofs.clear();
+                self.publics.clear();
+            }
+
+            /// Verify the queued proofs, as `Proof::verify_batch` would,
+            /// and clear the queue for the next batch.
+            ///
+            /// Returns `Err(())` if any proof fails to verify; the queue
+            /// is cleared either way.
+            #[allow(dead_code)]
+            pub fn verify(&mut self) -> Result<(),()> {
+                let result = $crate::__check_all(&self.proofs, &self.publics, |proof, publics| {
+                    proof.verify(publics.as_publics()).is_ok()
+                });
+                self.clear();
+                result
+            }
+        }
+
+        impl $crate::ProofBytes for Proof {
+            fn to_bytes(&self) -> Vec<u8> {
+                Proof::to_bytes(self)
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Result<Proof,()> {
+                Proof::from_bytes(bytes)
+            }
+        }
+
+        impl $crate::NizkProof for Proof {
+            type Publics = PublicsOwned;
+            type Secrets = SecretsOwned;
+
+            const NAME: &'static str = NAME;
+
+            fn create<R: Rng>(csprng: &mut R, publics: &PublicsOwned, secrets: &SecretsOwned) -> Proof {
+                Proof::create(csprng, publics.as_publics(), secrets.as_secrets())
+            }
+
+            fn verify(&self, publics: &PublicsOwned) -> Result<(),()> {
+                Proof::verify(self, publics.as_publics())
+            }
+        }
+
+        /// Add this statement to `registry`, under its `NAME`.
+        #[allow(dead_code)]
+        pub fn register(registry: &mut $crate::any::Registry) -> Result<(),()> {
+            registry.register::<Proof>()
+        }
+
+        impl $crate::any::AnyProof for Proof {
+            fn statement(&self) -> &'static str {
+                NAME
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                Proof::to_bytes(self)
+            }
+
+            fn verify_bytes(&self, publics: &[u8]) -> Result<(),()> {
+                Proof::verify(self, PublicsOwned::from_bytes(publics)?.as_publics())
+            }
+
+            fn verify_bytes_with_context(
+                &self,
+                version: $crate::Version,
+                context: &[u8],
+                publics: &[u8],
+            ) -> Result<(),()> {
+                let publics = PublicsOwned::from_bytes(publics)?;
+                Proof::verify_with_context_version(self, version, context, publics.as_publics())
+            }
+        }
+
+        __impl_arbitrary!{ @proof ($($secret),+) }
+        __impl_proto!{ @proof ($($secret),+) }
+        __impl_der!{}
+        __impl_postcard!{ $($secret)+ }
+        __impl_fischlin!{
+            ($($secret),+) ($($public),+) ($($statement),+) ($($rel_lhs = $relation),*)
+        }
+
+        /// Compute the Fiat-Shamir challenge for the given publics
+        /// and commitments.
+        #[allow(dead_code)]
+        pub fn challenge(publics: Publics, commitments: &Commitments) -> Scalar {
+            challenge_from_bytes(
+                $crate::Version::CURRENT,
+                &[],
+                &[ $( publics.$public.compress().as_bytes() ),+ ],
+                commitments,
+            )
+        }
+
+        /// Compute the challenge which `Proof::create_from_transcript`
+        /// gives the commitments at the current state of `transcript`,
+        /// for protocols which choose the commitments themselves.
+        #[allow(dead_code)]
+        pub fn challenge_from_transcript(
+            transcript: &$crate::transcript::Transcript,
+            publics: Publics,
+            commitments: &Commitments,
+        ) -> Scalar {
+            challenge_from_bytes(
+                $crate::Version::CURRENT,
+                &[ transcript.__state() ],
+                &[ $( publics.$public.compress().as_bytes() ),+ ],
+                commitments,
+            )
+        }
+
+        /// Compute the challenge of the given version from the
+        /// encodings of the publics, in order, prefixed by the state
+        /// of the enclosing transcript, if any.
+        fn challenge_from_bytes(
+            version: $crate::Version,
+            context: &[&[u8]],
+            publics: &[&[u8; 32]],
+            commitments: &Commitments,
+        ) -> Scalar {
+            challenge_from_hash(version, hash_publics(version, context, publics), commitments)
+        }
+
+        /// Start the challenge hash, with the context and the
+        /// encodings of the publics, each labelled with its name.
+        fn hash_publics(version: $crate::Version, context: &[&[u8]], publics: &[&[u8; 32]]) -> ChallengeHash {
+            let mut hash: ChallengeHash = $crate::__start_challenge(version, context);
+            // Add each public point into the hash
+            for (name, public) in PUBLICS.iter().zip(publics.iter()) {
+                $crate::__input_public(version, &mut hash, name, public);
+            }
+            hash
+        }
+
+        /// Finish the challenge hash started by `hash_publics`.
+        fn challenge_from_hash(
+            version: $crate::Version,
+            mut hash: ChallengeHash,
+            commitments: &Commitments,
+        ) -> Scalar {
+            // Add each commitment into the hash, labelled with its
+            // statement
+            for (label, commitment) in LABELS.iter().zip(commitments.iter()) {
+                $crate::__input_commitment(version, &mut hash, label, commitment.compress().as_bytes());
+            }
+
+            Scalar::from_hash(hash)
+        }
+
+        impl Proof {
+            /// Create a proof, with nonces hedged by hashing 64 bytes
+            /// from `csprng` with the secrets and the publics.
+            #[allow(dead_code)]
+            pub fn create<R: Rng>(
+                csprng: &mut R,
+                publics: Publics,
+                secrets: Secrets,
+            ) -> Proof {
+                Proof::create_with_bytes(
+                    csprng,
+                    $crate::Version::CURRENT,
+                    publics,
+                    secrets,
+                    &[],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Create a proof with randomness from the operating
+            /// system's random number generator.
+            ///
+            /// Nothing else in the module uses the operating
+            /// system's entropy, the clock or threads, so it also
+            /// builds for `wasm32-unknown-unknown`.  There `OsRng`
+            /// is unavailable, so seed an `Rng` (e.g.
+            /// `rand::ChaChaRng`) from the browser's
+            /// `crypto.getRandomValues()` and call `create`.
+            #[allow(dead_code)]
+            pub fn create_default(
+                publics: Publics,
+                secrets: Secrets,
+            ) -> Result<Proof, $crate::RngError> {
+                let mut csprng = $crate::__os_rng()?;
+                Ok(Proof::create(&mut csprng, publics, secrets))
+            }
+
+            /// Create a proof with the challenge of an earlier
+            /// `version`, for verifiers which have not yet moved to
+            /// the current one.
+            #[allow(dead_code)]
+            pub fn create_version<R: Rng>(
+                csprng: &mut R,
+                version: $crate::Version,
+                publics: Publics,
+                secrets: Secrets,
+            ) -> Proof {
+                Proof::create_with_bytes(
+                    csprng,
+                    version,
+                    publics,
+                    secrets,
+                    &[],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Create a proof bound to an application context, such
+            /// as the name and deployment of the protocol, which
+            /// only verifies with `verify_with_context` and the
+            /// same context.
+            #[allow(dead_code)]
+            pub fn create_with_context<R: Rng>(
+                csprng: &mut R,
+                context: &[u8],
+                publics: Publics,
+                secrets: Secrets,
+            ) -> Proof {
+                Proof::create_with_bytes(
+                    csprng,
+                    $crate::Version::CURRENT,
+                    publics,
+                    secrets,
+                    &[ &$crate::__context_length(context)[..], context ],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Create a proof as the next step of `transcript`,
+            /// binding its challenge to every proof already in the
+            /// transcript, and then add it to the transcript.
+            #[allow(dead_code)]
+            pub fn create_from_transcript<R: Rng>(
+                csprng: &mut R,
+                transcript: &mut $crate::transcript::Transcript,
+                publics: Publics,
+                secrets: Secrets,
+            ) -> Proof {
+                let proof = Proof::create_with_bytes(
+                    csprng,
+                    $crate::Version::CURRENT,
+                    publics,
+                    secrets,
+                    &[ transcript.__state() ],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                );
+                transcript.__record_challenge(NAME, || proof.absorbed(publics));
+                transcript.__append_proof(NAME, &proof.chunks());
+                proof
+            }
+
+            /// Create one proof for each pair of `publics[i]` and
+            /// `secrets[i]`.
+            ///
+            /// A public point which is the same (by reference) as
+            /// in the previous instance, such as a shared
+            /// generator, is compressed only once.  Returns
+            /// `Err(())` if the slices have different lengths.
+            #[allow(dead_code)]
+            pub fn create_batch<R: Rng>(
+                csprng: &mut R,
+                publics: &[Publics],
+                secrets: &[Secrets],
+            ) -> Result<Vec<Proof>, ()> {
+                if publics.len() != secrets.len() {
+                    return Err(());
+                }
+                let mut proofs = Vec::with_capacity(publics.len());
+                let mut previous: Option<(Publics, CompressedPublicsOwned)> = None;
+                for (p, s) in publics.iter().zip(secrets.iter()) {
+                    let encodings = CompressedPublicsOwned{
+                        $(
+                            $public : match previous {
+                                Some((ref q, ref e)) if ::std::ptr::eq(q.$public, p.$public)
+                                    => e.$public,
+                                _ => p.$public.compress(),
+                            },
+                        )+
+                    };
+                    proofs.push(Proof::create_with_bytes(
+                        csprng,
+                        $crate::Version::CURRENT,
+                        *p,
+                        *s,
+                        &[],
+                        &[ $( encodings.$public.as_bytes() ),+ ],
+                    ));
+                    previous = Some((*p, encodings));
+                }
+                Ok(proofs)
+            }
+
+            /// Create a proof with the challenge of the given version,
+            /// given the transcript state and the encodings of the
+            /// publics to hash.
+            fn create_with_bytes<R: Rng>(
+                csprng: &mut R,
+                version: $crate::Version,
+                publics: Publics,
+                secrets: Secrets,
+                context: &[&[u8]],
+                public_bytes: &[&[u8; 32]],
+            ) -> Proof {
+                // Hedge the nonces: hash the output of the RNG
+                // with the secrets, the publics and the context,
+                // so that they stay secret if the RNG is weak or
+                // repeats, and are fresh if the secrets are reused.
+                let mut seed = [0u8; 64];
+                csprng.fill_bytes(&mut seed);
+                let mut nonce_hash = Sha512::default();
+                nonce_hash.input(b"zkp hedged nonce");
+                nonce_hash.input(&seed[..]);
+                $(
+                    nonce_hash.input(secrets.$secret.as_bytes());
+                )+
+                for public in public_bytes.iter() {
+                    nonce_hash.input(&public[..]);
+                }
+                for part in context {
+                    nonce_hash.input(part);
+                }
+                #[allow(unused_mut)]
+                let mut rand = __Scalars{
+                    $(
+                        $secret : {
+                            let mut hash = nonce_hash.clone();
+                            hash.input(stringify!($secret).as_bytes());
+                            Scalar::from_hash(hash)
+                        },
+                    )+
+                };
+                // Make the randomness satisfy the relations, so
+                // that the responses will too.
+                $(
+                    rand.$rel_lhs = __compute_relation!(
+                        (rand, Scalar::zero()) $relation
+                    );
+                )*
+                // $statement_rhs = `X * x + Y * y + Z * z`
+                // should become
+                // `publics.X * rand.x + publics.Y * rand.y + publics.Z * rand.z`
+                let commitments: Commitments = __compute_commitments_consttime!(
+                    (publics, rand) $($statement),*
+                );
+
+                let challenge = challenge_from_bytes(version, context, public_bytes, &commitments);
+
+                let responses = Responses::from(__Scalars{
+                    $(
+                        $secret : Scalar::multiply_add(
+                            &challenge,
+                            &secrets.$secret,
+                            &rand.$secret
+                        ),
+                    )+
+                });
+
+                Proof{ challenge: challenge, responses: responses }
+            }
+
+            /// Assemble a proof from a challenge and responses
+            /// computed outside of `create`, e.g., by a threshold
+            /// protocol.
+            #[allow(dead_code)]
+            pub fn from_parts(challenge: Scalar, responses: Responses) -> Proof {
+                Proof{ challenge: challenge, responses: responses }
+            }
+
+            /// Simulate a proof, without knowing the secrets, by
+            /// choosing a random challenge and responses and then
+            /// solving for the commitments.
+            ///
+            /// The result is distributed like an honest proof and
+            /// its commitments, and passes `verify_commitments`,
+            /// but not `verify`, since its challenge is not the
+            /// hash of the commitments.
+            #[allow(dead_code)]
+            pub fn simulate<R: Rng>(csprng: &mut R, publics: Publics) -> (Proof, Commitments) {
+                let challenge = Scalar::random(csprng);
+                Proof::simulate_with_challenge(csprng, publics, challenge)
+            }
+
+            /// Simulate a proof for a given challenge, e.g., for the
+            /// branches of an OR-proof that the prover cannot prove.
+            #[allow(dead_code)]
+            pub fn simulate_with_challenge<R: Rng>(
+                csprng: &mut R,
+                publics: Publics,
+                challenge: Scalar,
+            ) -> (Proof, Commitments) {
+                #[allow(unused_mut)]
+                let mut responses = __Scalars{
+                    $(
+                        $secret : Scalar::random(csprng),
+                    )+
+                };
+                $(
+                    responses.$rel_lhs = __compute_relation!(
+                        (responses, challenge) $relation
+                    );
+                )*
+                let proof = Proof{ challenge: challenge, responses: Responses::from(responses) };
+                let commitments = proof.recompute_commitments(publics);
+                (proof, commitments)
+            }
+
+            /// Verify the proof as a transcript of the interactive
+            /// protocol: check that the responses answer the
+            /// challenge for the given commitments, without
+            /// checking how the challenge was chosen.
+            #[allow(dead_code)]
+            pub fn verify_commitments(
+                &self,
+                publics: Publics,
+                commitments: &Commitments,
+            ) -> Result<(),()> {
+                self.check_relations()?;
+                let recomputed = self.recompute_commitments(publics);
+                for (a, b) in recomputed.iter().zip(commitments.iter()) {
+                    if a.compress() != b.compress() {
+                        return Err(());
+                    }
+                }
+                Ok(())
+            }
+
+            /// Verify the proof, first rejecting publics which are
+            /// the identity, for which statements can hold
+            /// trivially.
+            #[allow(dead_code)]
+            pub fn verify_strict(&self, publics: Publics) -> Result<(),()> {
+                $(
+                    if $crate::__is_identity(publics.$public) {
+                        return Err(());
+                    }
+                )+
+                self.verify(publics)
+            }
+
+            #[allow(dead_code)]
+            pub fn verify(&self, publics: Publics) -> Result<(),()> {
+                self.verify_with_bytes(
+                    publics,
+                    $crate::Version::CURRENT,
+                    &[],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Verify a proof made by `create_with_context` with the
+            /// same context.
+            #[allow(dead_code)]
+            pub fn verify_with_context(&self, context: &[u8], publics: Publics) -> Result<(),()> {
+                self.verify_with_bytes(
+                    publics,
+                    $crate::Version::CURRENT,
+                    &[ &$crate::__context_length(context)[..], context ],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Verify a proof made by `create_from_transcript` as the next step
+            /// of `transcript`, and if it is valid, add it to the
+            /// transcript.
+            #[allow(dead_code)]
+            pub fn verify_from_transcript(
+                &self,
+                transcript: &mut $crate::transcript::Transcript,
+                publics: Publics,
+            ) -> Result<(),()> {
+                self.verify_with_bytes(
+                    publics,
+                    $crate::Version::CURRENT,
+                    &[ transcript.__state() ],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )?;
+                transcript.__record_challenge(NAME, || self.absorbed(publics));
+                transcript.__append_proof(NAME, &self.chunks());
+                Ok(())
+            }
+
+            /// Verify a proof whose challenge was computed with an
+            /// earlier `version`, such as a proof stored before the
+            /// challenge changed.
+            #[allow(dead_code)]
+            pub fn verify_version(&self, version: $crate::Version, publics: Publics) -> Result<(),()> {
+                self.verify_with_bytes(
+                    publics,
+                    version,
+                    &[],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Verify a proof made by `create_with_context`, with the
+            /// same context, whose challenge was computed with an
+            /// earlier `version`.
+            #[allow(dead_code)]
+            pub fn verify_with_context_version(
+                &self,
+                version: $crate::Version,
+                context: &[u8],
+                publics: Publics,
+            ) -> Result<(),()> {
+                self.verify_with_bytes(
+                    publics,
+                    version,
+                    &[ &$crate::__context_length(context)[..], context ],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                )
+            }
+
+            /// Verify each of `proofs[i]` against `publics[i]`,
+            /// on a thread pool if `zkp` is built with the
+            /// `parallel` feature.
+            ///
+            /// Returns `Err(())` if any proof fails to verify, or
+            /// if the slices have different lengths.
+            #[allow(dead_code)]
+            pub fn verify_batch(proofs: &[Proof], publics: &[Publics]) -> Result<(),()> {
+                $crate::__check_all(proofs, publics, |proof, publics| {
+                    proof.verify(*publics).is_ok()
+                })
+            }
+
+            /// Find which of `candidates`, if any, the proof verifies
+            /// against, returning the index of the first.
+            ///
+            /// Much as in `create_batch`, publics which are the same (by
+            /// reference) as in the previous candidate are compressed
+            /// only once, and the challenge hash of the publics up to the
+            /// first which differs is kept, so that trying many issuer
+            /// keys with shared generators hashes each key and the
+            /// commitments, rather than every public each time.
+            #[allow(dead_code)]
+            pub fn verify_any_of(&self, candidates: &[Publics]) -> Option<usize> {
+                if self.check_relations().is_err() {
+                    return None;
+                }
+                // `hashes[i]` is the challenge hash of the first `i`
+                // publics of the previous candidate.
+                let mut hashes: Vec<ChallengeHash> = Vec::with_capacity(PUBLICS.len() + 1);
+                hashes.push(hash_publics($crate::Version::CURRENT, &[], &[]));
+                let mut previous: Option<(Publics, CompressedPublicsOwned)> = None;
+                for (index, p) in candidates.iter().enumerate() {
+                    let same = [
+                        $(
+                            match previous {
+                                Some((ref q, _)) => ::std::ptr::eq(q.$public, p.$public),
+                                None => false,
+                            },
+                        )+
+                    ];
+                    let encodings = CompressedPublicsOwned{
+                        $(
+                            $public : match previous {
+                                Some((ref q, ref e)) if ::std::ptr::eq(q.$public, p.$public)
+                                    => e.$public,
+                                _ => p.$public.compress(),
+                            },
+                        )+
+                    };
+                    let bytes: [&[u8; 32]; __count_tts!($($public)+)] = [
+                        $( encodings.$public.as_bytes() ),+
+                    ];
+                    let shared = same.iter().take_while(|&&same| same).count();
+                    hashes.truncate(shared + 1);
+                    for i in shared..bytes.len() {
+                        let mut hash = hashes[i].clone();
+                        $crate::__input_public($crate::Version::CURRENT, &mut hash, PUBLICS[i], bytes[i]);
+                        hashes.push(hash);
+                    }
+
+                    let commitments = self.recompute_commitments(*p);
+                    if challenge_from_hash($crate::Version::CURRENT, hashes[bytes.len()].clone(), &commitments) == self.challenge {
+                        return Some(index);
+                    }
+                    previous = Some((*p, encodings));
+                }
+                None
+            }
+
+            /// Verify the proof against compressed publics, which
+            /// are decompressed (and rejected if invalid) but not
+            /// recompressed for the challenge.
+            #[allow(dead_code)]
+            pub fn verify_compressed(&self, publics: CompressedPublics) -> Result<(),()> {
+                let points = PublicsOwned{
+                    $( $public : publics.$public.decompress().ok_or(())?, )+
+                };
+                self.verify_with_bytes(
+                    points.as_publics(),
+                    $crate::Version::CURRENT,
+                    &[],
+                    &[ $( publics.$public.as_bytes() ),+ ],
+                )
+            }
+
+            /// Verify the proof against the challenge of the given
+            /// version, given the transcript state and the encodings
+            /// of the publics to hash.
+            fn verify_with_bytes(
+                &self,
+                publics: Publics,
+                version: $crate::Version,
+                context: &[&[u8]],
+                public_bytes: &[&[u8; 32]],
+            ) -> Result<(),()> {
+                self.verify_with_hash(publics, version, hash_publics(version, context, public_bytes))
+            }
+
+            /// Verify the proof, given the challenge hash of the
+            /// context and the publics.
+            fn verify_with_hash(
+                &self,
+                publics: Publics,
+                version: $crate::Version,
+                hash: ChallengeHash,
+            ) -> Result<(),()> {
+                self.check_relations()?;
+                let commitments = self.recompute_commitments(publics);
+
+                // Recompute challenge
+                let challenge = challenge_from_hash(version, hash, &commitments);
+
+                if challenge == self.challenge { Ok(()) } else { Err(()) }
+            }
+
+            /// Verify the proof in constant time with respect to
+            /// which check fails, returning a `subtle::Choice`
+            /// which is true exactly when `verify` would accept.
+            #[allow(dead_code)]
+            pub fn verify_ct(&self, publics: Publics) -> $crate::subtle::Choice {
+                #[allow(unused_variables)]
+                let responses = __Scalars::from(self.responses);
+                #[allow(unused_mut)]
+                let mut valid = $crate::subtle::Choice::from(1u8);
+                $(
+                    valid &= $crate::__scalar_ct_eq(
+                        &responses.$rel_lhs,
+                        &__compute_relation!((responses, self.challenge) $relation),
+                    );
+                )*
+                let commitments = self.recompute_commitments(publics);
+                let challenge = challenge_from_bytes(
+                    $crate::Version::CURRENT,
+                    &[],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                    &commitments,
+                );
+                valid & $crate::__scalar_ct_eq(&challenge, &self.challenge)
+            }
+
+            /// Check that the responses satisfy the relations.
+            fn check_relations(&self) -> Result<(),()> {
+                #[allow(unused_variables)]
+                let responses = __Scalars::from(self.responses);
+                $(
+                    if responses.$rel_lhs != __compute_relation!(
+                        (responses, self.challenge) $relation
+                    ) {
+                        return Err(());
+                    }
+                )*
+                Ok(())
+            }
+
+            /// Recompute the prover's commitments from the
+            /// challenge and responses.
+            fn recompute_commitments(&self, publics: Publics) -> Commitments {
+                // `A = X * x + Y * y`
+                // should become
+                // `publics.X * responses.x + publics.Y * responses.y - publics.A * self.challenge`
+                let responses = __Scalars::from(self.responses);
+                [
+                    $(
+                        &__compute_formula_consttime!((publics, responses) $statement)
+                            - &(&__compute_lhs!(publics $lhs) * &self.challenge)
+                    ),+
+                ]
+            }
+
+            /// The labelled inputs to the challenge: the encoding
+            /// of each public, labelled with its name, then of each
+            /// commitment recomputed from this proof, labelled with
+            /// the label of its statement.
+            #[allow(dead_code)]
+            pub fn absorbed(&self, publics: Publics) -> Vec<$crate::transcript::Absorbed> {
+                let mut entries = vec![
+                    $( $crate::transcript::Absorbed::__new(
+                        "public",
+                        stringify!($public),
+                        publics.$public.compress().as_bytes(),
+                    ), )+
+                ];
+                let commitments = self.recompute_commitments(publics);
+                for (label, commitment) in LABELS.iter().zip(commitments.iter()) {
+                    entries.push($crate::transcript::Absorbed::__new(
+                        "commitment",
+                        label,
+                        commitment.compress().as_bytes(),
+                    ));
+                }
+                entries
+            }
+
+            /// The bytes hashed to compute the challenge: the
+            /// encodings of the publics, then of the commitments
+            /// recomputed from this proof, framed as described in
+            /// `zkp::Version::V2`.
+            #[allow(dead_code)]
+            pub fn transcript(&self, publics: Publics) -> Vec<u8> {
+                self.__transcript(&[], publics)
+            }
+
+            /// The bytes hashed to compute the challenge of a proof
+            /// made in `context`, such as the state of a transcript.
+            #[doc(hidden)]
+            pub fn __transcript(&self, context: &[u8], publics: Publics) -> Vec<u8> {
+                $crate::__challenge_input($crate::Version::CURRENT, context, &self.absorbed(publics))
+            }
+        }
+
+        /// Create a proof and record it, with its inputs and
+        /// transcript, as a test vector.
+        #[allow(dead_code)]
+        pub fn test_vector<R: Rng>(
+            csprng: &mut R,
+            publics: Publics,
+            secrets: Secrets,
+        ) -> $crate::testvectors::TestVector {
+            let proof = Proof::create(csprng, publics, secrets);
+            $crate::testvectors::TestVector{
+                name: stringify!($proof_module_name).to_string(),
+                publics: publics.to_bytes(),
+                secrets: secrets.to_bytes(),
+                proof: proof.to_bytes(),
+                transcript: proof.transcript(publics),
+            }
+        }
+
+        /// Generate random secrets satisfying the relations, and
+        /// random publics satisfying the statements with them.
+        ///
+        /// The publics are chosen uniformly at random, and then the
+        /// first point of each left-hand side is solved for, in
+        /// order.  So the instance is satisfying as long as no
+        /// statement's first left-hand side point is used by an
+        /// earlier statement.
+        #[allow(dead_code)]
+        pub fn random_instance<R: Rng>(csprng: &mut R) -> (PublicsOwned, SecretsOwned) {
+            #[allow(unused_mut)]
+            let mut secrets = SecretsOwned{
+                $(
+                    $secret : Scalar::random(csprng),
+                )+
+            };
+            $(
+                secrets.$rel_lhs = __compute_relation!(
+                    (secrets, Scalar::one()) $relation
+                );
+            )*
+            let mut points = PublicsOwned{
+                $(
+                    $public : Point::random(csprng),
+                )+
+            };
+            $(
+                let value = {
+                    let publics = points.as_publics();
+                    __compute_formula_consttime!((publics, secrets) $statement)
+                };
+                __solve_lhs!(points (value) $lhs);
+            )+
+            (points, secrets)
+        }
+
+        /// Check the proof system on a `random_instance`: that a proof
+        /// of it verifies, also after a round trip through its bytes,
+        /// and that changing its challenge or the response for any
+        /// secret the statements mention makes it fail.
+        ///
+        /// Returns `Err(())` if any of these checks fails.
+        #[allow(dead_code)]
+        pub fn selftest<R: Rng>(csprng: &mut R) -> Result<(),()> {
+            let (publics, secrets) = random_instance(csprng);
+            let publics = publics.as_publics();
+            let proof = Proof::create(csprng, publics, secrets.as_secrets());
+            proof.verify(publics)?;
+            Proof::from_bytes(&proof.to_bytes())?.verify(publics)?;
+
+            let mut corrupted = proof;
+            corrupted.challenge = &corrupted.challenge + &Scalar::one();
+            if corrupted.verify(publics).is_ok() {
+                return Err(());
+            }
+            $(
+                let used = $crate::__mentions(STATEMENTS, stringify!($secret))
+                    || $crate::__mentions(RELATIONS, stringify!($secret));
+                if used {
+                    let mut corrupted = proof;
+                    corrupted.responses.0[response_index::$secret] = &corrupted.responses.0[response_index::$secret] + &Scalar::one();
+                    if corrupted.verify(publics).is_ok() {
+                        return Err(());
+                    }
+                }
+            )+
+            Ok(())
+        }
+    };
+}
+
+/// Defines the `FischlinProof` of a statement, given its secrets,
+/// publics, right-hand sides and relations.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_fischlin {
+    (($($secret:ident),+) ($($public:ident),+) ($($statement:tt),+) ($($rel_lhs:ident = $relation:tt),*)) => {
+        /// A proof of the statement made with Fischlin's transform,
+        /// whose witness can be extracted without rewinding the
+        /// prover.
+        ///
+        /// It holds `zkp::FISCHLIN_REPETITIONS` runs of the sigma
+        /// protocol, each with its commitments, a small challenge and
+        /// the responses to it.
+        #[derive(Clone, Serialize, Deserialize)]
+        pub struct FischlinProof {
+            commitments: Vec<Commitments>,
+            challenges: Vec<u16>,
+            responses: Vec<Responses>,
+        }
+
+        impl FischlinProof {
+            /// Prove the statement with Fischlin's transform.
+            #[allow(dead_code)]
+            pub fn create<R: Rng>(csprng: &mut R, publics: Publics, secrets: Secrets) -> FischlinProof {
+                loop {
+                    if let Some(proof) = FischlinProof::try_create(csprng, publics, secrets) {
+                        return proof;
+                    }
+                }
+            }
+
+            /// Try to create a proof, failing (with probability about
+            /// `2^-19`) if some run has no accepted challenge.
+            fn try_create<R: Rng>(csprng: &mut R, publics: Publics, secrets: Secrets) -> Option<FischlinProof> {
+                // Hedge the nonces, as in `Proof::create`.
+                let mut seed = [0u8; 64];
+                csprng.fill_bytes(&mut seed);
+                let mut nonce_hash = Sha512::default();
+                nonce_hash.input(b"zkp fischlin nonce");
+                nonce_hash.input(&seed[..]);
+                $(
+                    nonce_hash.input(secrets.$secret.as_bytes());
+                )+
+                $(
+                    nonce_hash.input(publics.$public.compress().as_bytes());
+                )+
+                let rands: Vec<__Scalars> = (0..$crate::FISCHLIN_REPETITIONS).map(|index| {
+                    #[allow(unused_mut)]
+                    let mut rand = __Scalars{
+                        $(
+                            $secret : {
+                                let mut hash = nonce_hash.clone();
+                                hash.input(&[index as u8]);
+                                hash.input(stringify!($secret).as_bytes());
+                                Scalar::from_hash(hash)
+                            },
+                        )+
+                    };
+                    $(
+                        rand.$rel_lhs = __compute_relation!(
+                            (rand, Scalar::zero()) $relation
+                        );
+                    )*
+                    rand
+                }).collect();
+                let commitments: Vec<Commitments> = rands.iter().map(|rand| {
+                    __compute_commitments_consttime!((publics, rand) $($statement),*)
+                }).collect();
+
+                // Search each run for a challenge whose responses are
+                // accepted by the hash.
+                let base = FischlinProof::hash_commitments(publics, &commitments);
+                let mut challenges = Vec::with_capacity($crate::FISCHLIN_REPETITIONS);
+                let mut responses = Vec::with_capacity($crate::FISCHLIN_REPETITIONS);
+                for (index, rand) in rands.iter().enumerate() {
+                    let mut found = None;
+                    for challenge in 0..(1u32 << $crate::FISCHLIN_CHALLENGE_BITS) {
+                        let c = Scalar::from_u64(challenge as u64);
+                        let answer = Responses::from(__Scalars{
+                            $(
+                                $secret : Scalar::multiply_add(&c, &secrets.$secret, &rand.$secret),
+                            )+
+                        });
+                        if FischlinProof::accepts(&base, index, challenge as u16, &answer) {
+                            found = Some((challenge as u16, answer));
+                            break;
+                        }
+                    }
+                    let (challenge, answer) = found?;
+                    challenges.push(challenge);
+                    responses.push(answer);
+                }
+                Some(FischlinProof{ commitments: commitments, challenges: challenges, responses: responses })
+            }
+
+            #[allow(dead_code)]
+            pub fn verify(&self, publics: Publics) -> Result<(),()> {
+                let runs = $crate::FISCHLIN_REPETITIONS;
+                if self.commitments.len() != runs || self.challenges.len() != runs || self.responses.len() != runs {
+                    return Err(());
+                }
+                let base = FischlinProof::hash_commitments(publics, &self.commitments);
+                for index in 0..runs {
+                    let challenge = self.challenges[index];
+                    if (challenge as u32) >> $crate::FISCHLIN_CHALLENGE_BITS != 0 {
+                        return Err(());
+                    }
+                    if !FischlinProof::accepts(&base, index, challenge, &self.responses[index]) {
+                        return Err(());
+                    }
+                    let run = Proof{
+                        challenge: Scalar::from_u64(challenge as u64),
+                        responses: self.responses[index],
+                    };
+                    run.verify_commitments(publics, &self.commitments[index])?;
+                }
+                Ok(())
+            }
+
+            /// Start the hash of every run: the publics, in the
+            /// context `"zkp fischlin"`, then the commitments of all
+            /// the runs.
+            fn hash_commitments(publics: Publics, commitments: &[Commitments]) -> ChallengeHash {
+                let mut hash = hash_publics(
+                    $crate::Version::CURRENT,
+                    &[ &b"zkp fischlin"[..] ],
+                    &[ $( publics.$public.compress().as_bytes() ),+ ],
+                );
+                for run in commitments {
+                    for (label, commitment) in LABELS.iter().zip(run.iter()) {
+                        $crate::__input_commitment(
+                            $crate::Version::CURRENT, &mut hash, label, commitment.compress().as_bytes());
+                    }
+                }
+                hash
+            }
+
+            /// Whether the hash accepts `challenge` and `responses` for
+            /// the run `index`: the hash of `base`, then the index as
+            /// one byte, the challenge as two little-endian bytes and
+            /// the responses, must start with
+            /// `zkp::FISCHLIN_WORK_BITS` zero bits.
+            fn accepts(base: &ChallengeHash, index: usize, challenge: u16, responses: &Responses) -> bool {
+                let mut hash = base.clone();
+                hash.input(&[index as u8, challenge as u8, (challenge >> 8) as u8]);
+                $(
+                    hash.input(responses.0[response_index::$secret].as_bytes());
+                )+
+                $crate::__fischlin_accepts(&hash.result())
+            }
+        }
+    };
+}
+
+/// Implements `zkp::compose::Statement` for the `Publics` in scope,
+/// and defines the `Instance` implementing `zkp::compose::Witness`,
+/// unless the module was given a point type.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_compose {
+    (() $($secret:ident)+) => {
+        /// The publics and secrets of one instance of the
+        /// statement, for `zkp::compose`.
+        #[allow(dead_code)]
+        #[derive(Copy, Clone)]
+        pub struct Instance<'a> {
+            pub publics: Publics<'a>,
+            pub secrets: Secrets<'a>,
+        }
+
+        impl<'a> $crate::compose::Statement for Publics<'a> {
+            fn name(&self) -> &'static str {
+                NAME
+            }
+
+            fn public_bytes(&self) -> Vec<u8> {
+                self.to_bytes()
+            }
+
+            fn responses_len(&self) -> usize {
+                __count_tts!($($secret)+)
+            }
+
+            fn simulate(
+                &self,
+                mut csprng: &mut dyn Rng,
+                challenge: &Scalar,
+            ) -> (Vec<Scalar>, Vec<$crate::curve25519_dalek::decaf::DecafPoint>) {
+                let (proof, commitments) = Proof::simulate_with_challenge(&mut csprng, *self, *challenge);
+                (vec![ $( proof.responses.0[response_index::$secret] ),+ ], commitments.to_vec())
+            }
+
+            fn commitments(&self, challenge: &Scalar, responses: &[Scalar]) -> Result<Vec<$crate::curve25519_dalek::decaf::DecafPoint>,()> {
+                if responses.len() != __count_tts!($($secret)+) {
+                    return Err(());
+                }
+                let mut responses = responses.iter();
+                let proof = Proof{
+                    challenge: *challenge,
+                    responses: Responses::from(__Scalars{ $( $secret : *responses.next().unwrap(), )+ }),
+                };
+                proof.check_relations()?;
+                Ok(proof.recompute_commitments(*self).to_vec())
+            }
+        }
+
+        impl<'a> $crate::compose::Witness for Instance<'a> {
+            fn statement(&self) -> &dyn $crate::compose::Statement {
+                &self.publics
+            }
+
+            fn respond(&self, nonces: &[Scalar], challenge: &Scalar) -> Vec<Scalar> {
+                let mut nonces = nonces.iter();
+                vec![ $( Scalar::multiply_add(challenge, self.secrets.$secret, nonces.next().unwrap()) ),+ ]
+            }
+        }
+    };
+    // Composition is only implemented for Decaf points
+    (($point:ty) $($secret:ident)+) => {};
+}
+
+/// Defines `keygen`, and the proof of possession methods of `Proof`,
+/// for a key-shaped statement `A = (G * x)`, with no other secrets or
+/// publics; for any other statement, defines nothing.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_keygen {
+    (($x:ident) ($p1:ident, $p2:ident) $public:ident = ($base:ident * $secret:ident) ;) => {
+        /// Generate a uniformly random secret key, and its public key
+        /// for the given `base`.
+        #[allow(dead_code)]
+        pub fn keygen<R: Rng>(csprng: &mut R, base: &Point) -> (SecretsOwned, PublicsOwned) {
+            let $secret = Scalar::random(csprng);
+            let $public = base * &$secret;
+            (SecretsOwned{ $secret: $secret }, PublicsOwned{ $public: $public, $base: *base })
+        }
+
+        /// The transcript of a proof of possession: labelled
+        /// `"zkp proof of possession"`, after `context` as a message,
+        /// as for `zkp::keypair`.
+        fn possession_transcript(context: &[u8]) -> $crate::transcript::Transcript {
+            let mut transcript = $crate::transcript::Transcript::new(b"zkp proof of possession");
+            transcript.append_message(b"context", context);
+            transcript
+        }
+
+        impl Proof {
+            /// Prove possession of the secret key, bound to `context`.
+            #[allow(dead_code)]
+            pub fn prove_possession<R: Rng>(
+                csprng: &mut R,
+                context: &[u8],
+                publics: Publics,
+                secrets: Secrets,
+            ) -> Proof {
+                Proof::create_from_transcript(csprng, &mut possession_transcript(context), publics, secrets)
+            }
+
+            /// Verify a proof of possession bound to `context`.
+            ///
+            /// This also rejects the identity as a public key, since its
+            /// secret key is known to everyone.
+            #[allow(dead_code)]
+            pub fn verify_possession(&self, context: &[u8], publics: Publics) -> Result<(),()> {
+                if $crate::__is_identity(publics.$public) {
+                    return Err(());
+                }
+                self.verify_from_transcript(&mut possession_transcript(context), publics)
+            }
+        }
+    };
+    ($($tt:tt)*) => {};
+}
+
+/// Creates a module with code required to produce a non-interactive
+/// zero-knowledge proof statement, to serialize it to wire format, to
+/// parse from wire format, and to verify the proof statement.
+///
+/// The statement is specified in an embedded DSL resembling
+/// Camenisch-Stadler notation.  For instance, a proof of knowledge of
+/// two equal discrete logarithms ("DLEQ") is specified as:
+///
+/// ```rust,ignore
+/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+/// ```
+///
+/// This creates a module `dleq` with code for proving knowledge of a
+/// secret `x: Scalar` such that `A = G*x`, `B = H*x` for public
+/// parameters `A, B, G, H: DecafPoint`.  In general the syntax is
+///
+/// ```rust,ignore
+/// create_nipk!{
+///     module_name, // used to label proof statements
+///     (x,y,z,...), // secret variable names
+///     (A,B,C,...)  // public parameter names
+///     :
+///     LHS = (A * x + B * y + C * z + ... ),  // comma-seperated statements
+///     ...
+/// }
+/// ```
+///
+/// Statements have the form `LHS = (A * x + B * y - C * z + ... )`,
+/// where `LHS` is one of the points listed as a public parameter, and
+/// the right-hand side is a sum or difference of public points
+/// multiplied by secret scalars.
+///
+/// The left-hand side may also be a bracketed sum or difference of
+/// public points, as in `(C1 - C2) = (H * r)`, which saves the caller
+/// from computing the combined point themselves.
+///
+/// Each term may also be scaled by a known coefficient, written in
+/// parentheses in front of the term, as in `A = (G * x + (2) * H * y)`.
+/// The coefficient is any expression implementing `Coefficient` (a
+/// `Scalar` or an integer) and is evaluated inside the generated
+/// module, which imports everything from the module containing the
+/// macro invocation, so it may name constants or functions defined
+/// there.
+///
+/// Statements shared by several invocations can be defined once with
+/// `create_statement!`, and included among the statements with `use`,
+/// as in `create_nipk!{dleq, (x), (A, B, G, H) : use dleq_statement }`.
+///
+/// The statements may be followed by a semicolon and a list of
+/// affine relations between the secrets, such as
+///
+/// ```rust,ignore
+/// create_nipk!{sum, (a, b, c), (A, B, C, G) :
+///              A = (G * a), B = (G * b), C = (G * c) ; c = (a + (2) * b + (7)) }
+/// ```
+///
+/// Each relation gives one secret as a sum or difference of the other
+/// secrets, optionally multiplied by coefficients, and of bracketed
+/// constants.  The prover derives the randomness for the secret on the
+/// left from the randomness of the secrets on the right, and the
+/// verifier checks that the responses satisfy the same relation, with
+/// the constants multiplied by the challenge.  Relations are applied
+/// by the prover in order, so the right-hand side of a relation should
+/// not mention secrets defined by a later relation.  (A secret used in
+/// several statements, as in `A = (G * x + H * x)`, is always the same
+/// secret and needs no relation.)
+///
+/// A module can use another hash for its challenge, with a 64-byte
+/// output, given after its name, as in
+/// `create_nipk!{dleq<sha3::Sha3_512>, (x), (A, B, G, H) : ...}`, and
+/// statements over another group than Decaf give its point type after
+/// the hash, as in `create_nipk!{dleq<Sha512, RistrettoPoint>, ...}`;
+/// see `zkp::group`.
+///
+/// Inside the generated module `module_name`, the macro defines three
+/// structs:
+///
+/// A `Publics` struct corresponding to the public parameters, of the
+/// form
+///
+/// ```rust,ignore
+/// pub struct Publics<'a> { pub A: &'a DecafPoint, ... }
+/// ```
+///
+/// A `Secrets` struct corresponding to the secret parameters, of the
+/// form
+///
+/// ```rust,ignore
+/// pub struct Secrets<'a> { pub x: &'a Scalar, ... }
+/// ```
+///
+/// A `Proof` struct, of the form
+///
+/// ```rust,ignore
+/// #[derive(Serialize, Deserialize)]
+/// pub struct Proof { ... }
+///
+/// impl Proof {
+///     pub fn create<R: Rng>(
+///         csprng: &mut R,
+///         publics: Publics,
+///         secrets: Secrets,
+///     ) -> Proof { ... }
+///
+///     pub fn verify(&self, publics: Publics) -> Result<(),()> { ... }
+/// }
+/// ```
+///
+/// The `Proof` struct derives the Serde traits, so it can be
+/// serialized and deserialized to various wire formats.  Attributes
+/// written before the module name, such as `#[serde(deny_unknown_fields)]`,
+/// are attached to the `Proof` struct.
+///
+/// The `Publics` and `Secrets` structs are used to fake named
+/// arguments in the input to `create` and `verify`.  Proof creation
+/// is done in constant time.  The module has many more items, for
+/// contexts, transcripts, batches, encodings and protocols which build
+/// proofs by hand, each documented where it is defined.
+///
+/// Every point and scalar named in a statement or relation must be
+/// declared in the lists of publics and secrets; otherwise expansion
+/// fails with an error such as "no variant named `B` found for enum
+/// `DeclaredPublics`" pointing at the offending identifier:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # #[macro_use] extern crate zkp;
+/// # fn main() {}
+/// create_nipk!{m, (x), (A, G) : B = (G * x) }
+/// ```
+///
+/// Conversely, a public or secret which is declared but never used
+/// is an error ("non-exhaustive patterns: `DeclaredSecrets::y` not
+/// covered"): in particular, a proof would say nothing at all about
+/// an unused secret.
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # #[macro_use] extern crate zkp;
+/// # fn main() {}
+/// create_nipk!{m, (x, y), (A, G) : A = (G * x) }
+/// ```
+///
+/// Several statements about the same parameters can be defined
+/// together, each in its own submodule and with the name of its
+/// `Proof` re-exported, so that they all take the one `Publics` and
+/// `Secrets` of the enclosing module:
+///
+/// ```rust,ignore
+/// create_nipk!{params, (x, y), (A, B, G, H) {
+///     opening as OpeningProof { A = (G * x), B = (H * y) }
+///     dleq as DleqProof { A = (G * x), B = (H * y) ; y = x }
+/// }}
+///
+/// let publics = params::Publics{A: &A, B: &B, G: G, H: &H};
+/// let proof = params::OpeningProof::create(&mut csprng, publics, secrets);
+/// ```
+///
+/// Each submodule is a complete proof module, as above, whose
+/// `Publics`, `Secrets` and their variants are those of the enclosing
+/// module.  Each proof hashes all of the publics into its challenge,
+/// so its statements need not use every public, but it has a response
+/// for each of the secrets, so they must use every secret:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # #[macro_use] extern crate zkp;
+/// # fn main() {}
+/// create_nipk!{m, (x, y), (A, B, G) { a as AProof { A = (G * x) } }}
+/// ```
+///
+/// Since `zkp::compose` needs one statement per `Publics` type, these
+/// proofs cannot be composed.
+///
+/// As an example, we can create and verify a DLEQ proof as follows:
+///
+/// ```
+/// #[macro_use]
+/// extern crate serde_derive;
+///
+/// #[macro_use]
+/// extern crate zkp;
+///
+/// extern crate curve25519_dalek;
+/// use curve25519_dalek::constants as dalek_constants;
+/// use curve25519_dalek::decaf::DecafPoint;
+/// use curve25519_dalek::scalar::Scalar;
+///
+/// extern crate rand;
+/// use rand::OsRng;
+///
+/// extern crate sha2;
+/// use sha2::Sha256;
+///
+/// extern crate serde_cbor;
+///
+/// # fn main() {
+/// let mut csprng = OsRng::new().unwrap();
+/// let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+/// let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+///
+/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+///
+/// let x = Scalar::from_u64(89327492234);
 /// let A =  G * &x;
 /// let B = &H * &x;
 ///
-/// let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
-/// let secrets = dleq::Secrets{x: &x};
+/// let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
+/// let secrets = dleq::Secrets{x: &x};
+///
+/// let proof = dleq::Proof::create(&mut csprng, publics, secrets);
+///
+/// // Serialize to packed CBOR byte representation
+/// let proof_bytes = serde_cbor::ser::to_vec_packed(&proof).unwrap();
+///
+/// // Send bytes over the wire here ...
+///
+/// // Parse bytes back to in-memory representation
+/// let parsed_proof: dleq::Proof
+///     = serde_cbor::from_slice(&proof_bytes).unwrap();
+///
+/// // Check the proof.
+/// assert!(parsed_proof.verify(publics).is_ok());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! create_nipk {
+    // Statements including the equations of a `create_statement!`:
+    // `@include [header] [statements so far] [used] rest`, where
+    // `used` marks that a fragment was included, so that input which
+    // is invalid for other reasons is not munched again.
+    (@include $H:tt [$($done:tt)*] $U:tt use $fragment:ident , $($rest:tt)*) => {
+        $fragment!{ $H [$($done)*] [$($rest)*] }
+    };
+    (@include $H:tt [$($done:tt)*] $U:tt use $fragment:ident ; $($rest:tt)*) => {
+        $fragment!{ $H [$($done)*] [; $($rest)*] }
+    };
+    (@include $H:tt [$($done:tt)*] $U:tt use $fragment:ident) => {
+        $fragment!{ $H [$($done)*] [] }
+    };
+    (@include $H:tt [$($done:tt)*] $U:tt $lhs:tt = $statement:tt , $($rest:tt)*) => {
+        create_nipk!{ @include $H [$($done)* $lhs = $statement ,] $U $($rest)* }
+    };
+    (@include $H:tt [$($done:tt)*] $U:tt $lhs:tt = $statement:tt ; $($rest:tt)*) => {
+        create_nipk!{ @include $H [$($done)* $lhs = $statement ,] $U ; $($rest)* }
+    };
+    (@include $H:tt [$($done:tt)*] $U:tt $lhs:tt = $statement:tt) => {
+        create_nipk!{ @include $H [$($done)* $lhs = $statement ,] $U }
+    };
+    (@include [$($h:tt)*] [$($lhs:tt = $statement:tt ,)+] [used] ; $($rest:tt)*) => {
+        create_nipk!{ $($h)* : $($lhs = $statement),+ ; $($rest)* }
+    };
+    (@include [$($h:tt)*] [$($lhs:tt = $statement:tt ,)+] [used]) => {
+        create_nipk!{ $($h)* : $($lhs = $statement),+ }
+    };
+    // Several named proofs over the same publics and secrets, each in
+    // a submodule
+    (
+        $module_name:ident $(< $hash:ty $(, $point:ty)* >)*
+        ,
+        $secrets:tt
+        ,
+        $publics:tt
+        {
+            $(
+                $(#[$proof_attr:meta])*
+                $proof_module_name:ident as $proof_name:ident { $($body:tt)+ }
+            )+
+        }
+    ) => {
+        mod $module_name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::curve25519_dalek::scalar::Scalar;
+
+            __nipk_publics!{ $secrets $publics }
+            __challenge_hash!{ $($hash)* }
+            __group_types!{ $($($point)*)* }
+
+            /// The name of this module.
+            #[allow(dead_code)]
+            pub const NAME: &'static str = stringify!($module_name);
+
+            $(
+                create_nipk!{
+                    @shared
+                    $(#[$proof_attr])*
+                    $proof_module_name, $secrets, $publics : $($body)+
+                }
+                #[allow(unused_imports)]
+                pub use self::$proof_module_name::Proof as $proof_name;
+            )+
+        }
+    };
+    // One of several proofs, using the `Publics` and `Secrets` of the
+    // enclosing module
+    (
+        @shared
+        $(#[$proof_attr:meta])*
+        $proof_module_name:ident
+        ,
+        ( $($secret:ident),+ )
+        ,
+        ( $($public:ident),+ )
+        :
+        $($lhs:tt = $statement:tt),+
+        ;
+        $($rel_lhs:ident = $relation:tt),*
+    ) => {
+        pub mod $proof_module_name {
+            #[allow(unused_imports)]
+            use super::*;
+            #[allow(unused_imports)]
+            pub use super::{
+                Secrets, Publics, CompressedPublics, CompressedPublicsOwned,
+                SecretsOwned, PublicsOwned, SECRETS, PUBLICS, ChallengeHash,
+                Point, CompressedPoint,
+            };
+            use $crate::curve25519_dalek::scalar::Scalar;
+            use $crate::sha2::{Digest, Sha512};
+            use $crate::rand::Rng;
+            // Serde's `with` attribute can't refer to `$crate`.
+            use $crate::__serde_scalar as serde_scalar;
+
+            // Every public is hashed, so only the secrets need to be
+            // used.
+            __check_used!(@munch () ();
+                $( @lhs $public )+
+                $( @lhs $lhs @statement $statement )+
+                $( @secret $rel_lhs @relation $relation )*
+            );
+
+            __nipk_proof!{
+                $(#[$proof_attr])*
+                $proof_module_name, ( $($secret),+ ), ( $($public),+ ) :
+                $($lhs = $statement),+ ;
+                $($rel_lhs = $relation),*
+            }
+        }
+    };
+    (
+        @shared
+        $(#[$proof_attr:meta])*
+        $proof_module_name:ident
+        ,
+        ( $($secret:ident),+ )
+        ,
+        ( $($public:ident),+ )
+        :
+        $($lhs:tt = $statement:tt),+
+    ) => {
+        create_nipk!{
+            @shared
+            $(#[$proof_attr])*
+            $proof_module_name, ( $($secret),+ ), ( $($public),+ ) :
+            $($lhs = $statement),+ ;
+        }
+    };
+    (
+        @shared
+        $(#[$proof_attr:meta])*
+        $proof_module_name:ident
+        ,
+        ( $($secret:ident),+ )
+        ,
+        ( $($public:ident),+ )
+        :
+        $($body:tt)+
+    ) => {
+        create_nipk!{
+            @include
+            [@shared $(#[$proof_attr])* $proof_module_name, ( $($secret),+ ), ( $($public),+ )]
+            []
+            []
+            $($body)+
+        }
+    };
+    (
+        $(#[$proof_attr:meta])* // Attributes for the `Proof` struct
+        $proof_module_name:ident $(< $hash:ty $(, $point:ty)* >)* // Name of the module to create
+        ,
+        ( $($secret:ident),+ ) // Secret variables, sep by commas
+        ,
+        ( $($public:ident),+ ) // Public variables, sep by commas
+        :
+        // List of statements to prove
+        // Format: LHS = ( ... RHS expr ... ), 
+        $($lhs:tt = $statement:tt),+
+        ;
+        // List of relations between the secrets
+        // Format: x = ( ... linear combination of secrets ... ),
+        $($rel_lhs:ident = $relation:tt),*
+    ) => {
+        mod $proof_module_name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::curve25519_dalek::scalar::Scalar;
+            use $crate::sha2::{Digest, Sha512};
+            use $crate::rand::Rng;
+            // Serde's `with` attribute can't refer to `$crate`.
+            use $crate::__serde_scalar as serde_scalar;
+
+            __nipk_publics!{ ($($secret),+) ($($public),+) }
+
+            __check_used!(@munch () ();
+                $( @lhs $lhs @statement $statement )+
+                $( @secret $rel_lhs @relation $relation )*
+            );
+
+            __challenge_hash!{ $($hash)* }
+            __group_types!{ $($($point)*)* }
+
+            __nipk_proof!{
+                $(#[$proof_attr])*
+                $proof_module_name, ( $($secret),+ ), ( $($public),+ ) :
+                $($lhs = $statement),+ ;
+                $($rel_lhs = $relation),*
+            }
+
+            __impl_compose!{ ($($($point)*)*) $($secret)+ }
+            __impl_keygen!{ ($($secret),+) ($($public),+) $($lhs = $statement),+ ; $($rel_lhs = $relation),* }
+        }
+    };
+    // Statements without relations between the secrets
+    (
+        $(#[$proof_attr:meta])*
+        $proof_module_name:ident $(< $hash:ty $(, $point:ty)* >)*
+        ,
+        ( $($secret:ident),+ )
+        ,
+        ( $($public:ident),+ )
+        :
+        $($lhs:tt = $statement:tt),+
+    ) => {
+        create_nipk!{
+            $(#[$proof_attr])*
+            $proof_module_name $(< $hash $(, $point)* >)*, ( $($secret),+ ), ( $($public),+ ) :
+            $($lhs = $statement),+ ;
+        }
+    };
+    // Statements with `use fragment` among them
+    (
+        $(#[$proof_attr:meta])*
+        $proof_module_name:ident $(< $hash:ty $(, $point:ty)* >)*
+        ,
+        ( $($secret:ident),+ )
+        ,
+        ( $($public:ident),+ )
+        :
+        $($body:tt)+
+    ) => {
+        create_nipk!{
+            @include
+            [$(#[$proof_attr])* $proof_module_name $(< $hash $(, $point)* >)*, ( $($secret),+ ), ( $($public),+ )]
+            []
+            []
+            $($body)+
+        }
+    };
+}
+
+/// Defines a macro `name` holding a list of statements, which can be
+/// included in `create_nipk!` invocations with `use name`:
+///
+/// ```rust,ignore
+/// create_statement!{dleq_statement : A = (G * x), B = (H * x) }
+///
+/// create_nipk!{dleq, (x), (A, B, G, H) : use dleq_statement }
+/// create_nipk!{rerandomized, (x, r), (A, B, C, G, H)
+///              : use dleq_statement, C = (A * r + G * x) }
+/// ```
+///
+/// The statements are copied in place of the `use`, and their
+/// publics and secrets are those of the including invocation with the
+/// same names, so each of those must declare them.  Like any
+/// `macro_rules!` macro, the fragment must be defined before it is
+/// used, and may be exported from a crate with `#[macro_use]`.
+#[macro_export]
+macro_rules! create_statement {
+    ($name:ident : $($lhs:tt = $statement:tt),+) => {
+        __create_statement!{ ($) $name : $($lhs = $statement),+ }
+    };
+}
+
+/// Defines the macro for `create_statement!`, given a `$` token to
+/// write its metavariables with.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __create_statement {
+    (($d:tt) $name:ident : $($lhs:tt = $statement:tt),+) => {
+        macro_rules! $name {
+            ($d header:tt [$d($d done:tt)*] [$d($d rest:tt)*]) => {
+                create_nipk!{
+                    @include $d header [$d($d done)* $($lhs = $statement ,)+] [used] $d($d rest)*
+                }
+            };
+        }
+    };
+}
+
+/// Exports C functions creating and verifying proofs for a module
+/// generated by `create_nipk!`.
+///
+/// ```rust,ignore
+/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+/// create_nipk_ffi!{dleq, dleq_create, dleq_verify}
+/// ```
+///
+/// defines
+///
+/// ```c
+/// int32_t dleq_create(const uint8_t *publics, size_t publics_len,
+///                     const uint8_t *secrets, size_t secrets_len,
+///                     uint8_t *proof, size_t *proof_len);
+/// int32_t dleq_verify(const uint8_t *proof, size_t proof_len,
+///                     const uint8_t *publics, size_t publics_len);
+/// ```
+///
+/// The publics and secrets are passed as in `PublicsOwned::from_bytes`
+/// and `SecretsOwned::from_bytes`, and proofs as in `Proof::to_bytes`.
+/// On input `*proof_len` is the size of the `proof` buffer, and on
+/// success it is set to the length of the proof written.  Both
+/// functions return one of the codes in `zkp::ffi`.  Proof creation
+/// draws its randomness from `OsRng`.
+#[macro_export]
+macro_rules! create_nipk_ffi {
+    ($proof_module_name:ident, $create:ident, $verify:ident) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $create(
+            publics: *const u8,
+            publics_len: usize,
+            secrets: *const u8,
+            secrets_len: usize,
+            proof: *mut u8,
+            proof_len: *mut usize,
+        ) -> i32 {
+            let publics = match $crate::ffi::__slice(publics, publics_len) {
+                Ok(bytes) => bytes,
+                Err(code) => return code,
+            };
+            let secrets = match $crate::ffi::__slice(secrets, secrets_len) {
+                Ok(bytes) => bytes,
+                Err(code) => return code,
+            };
+            let publics = match $proof_module_name::PublicsOwned::from_bytes(publics) {
+                Ok(publics) => publics,
+                Err(()) => return $crate::ffi::ZKP_ERR_ENCODING,
+            };
+            let secrets = match $proof_module_name::SecretsOwned::from_bytes(secrets) {
+                Ok(secrets) => secrets,
+                Err(()) => return $crate::ffi::ZKP_ERR_ENCODING,
+            };
+            let bytes = match $proof_module_name::Proof::create_default(publics.as_publics(), secrets.as_secrets()) {
+                Ok(proof) => proof.to_bytes(),
+                Err(_) => return $crate::ffi::ZKP_ERR_RNG,
+            };
+            $crate::ffi::__copy_out(&bytes, proof, proof_len)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn $verify(
+            proof: *const u8,
+            proof_len: usize,
+            publics: *const u8,
+            publics_len: usize,
+        ) -> i32 {
+            let proof = match $crate::ffi::__slice(proof, proof_len) {
+                Ok(bytes) => bytes,
+                Err(code) => return code,
+            };
+            let publics = match $crate::ffi::__slice(publics, publics_len) {
+                Ok(bytes) => bytes,
+                Err(code) => return code,
+            };
+            let proof = match $proof_module_name::Proof::from_bytes(proof) {
+                Ok(proof) => proof,
+                Err(()) => return $crate::ffi::ZKP_ERR_ENCODING,
+            };
+            let publics = match $proof_module_name::PublicsOwned::from_bytes(publics) {
+                Ok(publics) => publics,
+                Err(()) => return $crate::ffi::ZKP_ERR_ENCODING,
+            };
+            match proof.verify(publics.as_publics()) {
+                Ok(()) => $crate::ffi::ZKP_OK,
+                Err(()) => $crate::ffi::ZKP_ERR_VERIFICATION,
+            }
+        }
+    };
+}
+
+/// Exports functions creating and verifying proofs for a module
+/// generated by `create_nipk!` to Kotlin and Swift, through UniFFI,
+/// with the `mobile` feature.
+///
+/// ```rust,ignore
+/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+/// create_nipk_uniffi!{dleq, dleq_create, dleq_verify}
+/// ```
 ///
-/// let proof = dleq::Proof::create(&mut csprng, publics, secrets);
+/// defines
 ///
-/// // Serialize to packed CBOR byte representation
-/// let proof_bytes = serde_cbor::ser::to_vec_packed(&proof).unwrap();
+/// ```rust,ignore
+/// pub fn dleq_create(publics: Vec<u8>, secrets: Vec<u8>) -> Result<Vec<u8>, zkp::mobile::ZkpError>;
+/// pub fn dleq_verify(proof: Vec<u8>, publics: Vec<u8>) -> Result<(), zkp::mobile::ZkpError>;
+/// ```
 ///
-/// // Send bytes over the wire here ...
+/// which appear in Kotlin as `dleqCreate(publics: ByteArray, ...)`,
+/// for instance, throwing `ZkpException`.  The byte strings are
+/// encoded as for `create_nipk_ffi!`, and proof creation likewise
+/// draws its randomness from `OsRng`.  See `zkp::mobile` for setting
+/// up the calling crate.
+#[cfg(feature = "mobile")]
+#[macro_export]
+macro_rules! create_nipk_uniffi {
+    ($proof_module_name:ident, $create:ident, $verify:ident) => {
+        #[$crate::uniffi::export]
+        pub fn $create(publics: Vec<u8>, secrets: Vec<u8>) -> Result<Vec<u8>, $crate::mobile::ZkpError> {
+            let publics = $proof_module_name::PublicsOwned::from_bytes(&publics)
+                .map_err(|()| $crate::mobile::ZkpError::Encoding)?;
+            let secrets = $proof_module_name::SecretsOwned::from_bytes(&secrets)
+                .map_err(|()| $crate::mobile::ZkpError::Encoding)?;
+            let proof = $proof_module_name::Proof::create_default(publics.as_publics(), secrets.as_secrets())
+                .map_err(|_| $crate::mobile::ZkpError::Rng)?;
+            Ok(proof.to_bytes())
+        }
+
+        #[$crate::uniffi::export]
+        pub fn $verify(proof: Vec<u8>, publics: Vec<u8>) -> Result<(), $crate::mobile::ZkpError> {
+            let proof = $proof_module_name::Proof::from_bytes(&proof)
+                .map_err(|()| $crate::mobile::ZkpError::Encoding)?;
+            let publics = $proof_module_name::PublicsOwned::from_bytes(&publics)
+                .map_err(|()| $crate::mobile::ZkpError::Encoding)?;
+            proof.verify(publics.as_publics()).map_err(|()| $crate::mobile::ZkpError::Verification)
+        }
+    };
+}
+
+/// Exports functions creating and verifying proofs for a module
+/// generated by `create_nipk!` to JavaScript, through wasm-bindgen,
+/// with the `wasm` feature.
 ///
-/// // Parse bytes back to in-memory representation
-/// let parsed_proof: dleq::Proof
-///     = serde_cbor::from_slice(&proof_bytes).unwrap();
+/// ```rust,ignore
+/// create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+/// create_nipk_wasm!{dleq, dleq_create, dleq_verify}
+/// ```
 ///
-/// // Check the proof.
-/// assert!(parsed_proof.verify(publics).is_ok());
-/// # }
+/// defines functions with the TypeScript definitions
+///
+/// ```text
+/// export function dleq_create(publics: Uint8Array, secrets: Uint8Array, seed: Uint8Array): Uint8Array;
+/// export function dleq_verify(proof: Uint8Array, publics: Uint8Array): boolean;
 /// ```
+///
+/// The byte strings are encoded as for `create_nipk_ffi!`.  The prover
+/// draws its randomness from a ChaCha RNG seeded with the
+/// `zkp::wasm::SEED_SIZE` bytes of `seed`, which should come from
+/// `crypto.getRandomValues()`; its nonces are hedged with the secrets
+/// as in `Proof::create`.  Both functions throw an `Error` for an
+/// invalid encoding or seed, and `verify` returns whether the proof is
+/// valid.  Since the proofs are made by the same Rust code, they have
+/// the same transcript as on every other platform.
+#[cfg(feature = "wasm")]
 #[macro_export]
-macro_rules! create_nipk {
-    (
-        $proof_module_name:ident // Name of the module to create
-        ,
-        ( $($secret:ident),+ ) // Secret variables, sep by commas
-        ,
-        ( $($public:ident),+ ) // Public variables, sep by commas
-        :
-        // List of statements to prove
-        // Format: LHS = ( ... RHS expr ... ), 
-        $($lhs:ident = $statement:tt),+
-    ) => {
-        mod $proof_module_name {
-            use $crate::curve25519_dalek::scalar::Scalar;
-            use $crate::curve25519_dalek::decaf::DecafPoint;
-            use $crate::sha2::{Digest, Sha512};
-            use $crate::rand::Rng;
+macro_rules! create_nipk_wasm {
+    ($proof_module_name:ident, $create:ident, $verify:ident) => {
+        /// Create a proof from the encodings of the publics and
+        /// secrets, with randomness from `seed`.
+        #[$crate::wasm_bindgen::prelude::wasm_bindgen]
+        pub fn $create(
+            publics: &[u8],
+            secrets: &[u8],
+            seed: &[u8],
+        ) -> Result<Vec<u8>, $crate::wasm_bindgen::JsValue> {
+            let encoding = |()| $crate::wasm_bindgen::JsValue::from_str("invalid encoding");
+            let publics = $proof_module_name::PublicsOwned::from_bytes(publics).map_err(encoding)?;
+            let secrets = $proof_module_name::SecretsOwned::from_bytes(secrets).map_err(encoding)?;
+            let mut csprng = $crate::wasm::__seeded_rng(seed)
+                .map_err(|()| $crate::wasm_bindgen::JsValue::from_str("invalid seed"))?;
+            Ok($proof_module_name::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets()).to_bytes())
+        }
 
-            #[derive(Copy, Clone)]
-            pub struct Secrets<'a> {
-                // Create a parameter for each secret value
-                $(
-                    pub $secret : &'a Scalar,
-                )+
-            }
+        /// Verify a proof against the encoding of the publics.
+        #[$crate::wasm_bindgen::prelude::wasm_bindgen]
+        pub fn $verify(proof: &[u8], publics: &[u8]) -> Result<bool, $crate::wasm_bindgen::JsValue> {
+            let encoding = |()| $crate::wasm_bindgen::JsValue::from_str("invalid encoding");
+            let proof = $proof_module_name::Proof::from_bytes(proof).map_err(encoding)?;
+            let publics = $proof_module_name::PublicsOwned::from_bytes(publics).map_err(encoding)?;
+            Ok(proof.verify(publics.as_publics()).is_ok())
+        }
+    };
+}
 
-            #[derive(Copy, Clone)]
-            pub struct Publics<'a> {
-                // Create a parameter for each public value
-                $(
-                    pub $public : &'a DecafPoint,
-                )+
-            }
+pub mod blind_schnorr;
+pub mod ring;
+pub mod pedersen;
+pub mod elgamal;
+pub mod shuffle;
+pub mod threshold;
+pub mod musig;
+pub mod range;
+pub mod membership;
+pub mod inequality;
+pub mod ffi;
+pub mod testvectors;
+pub mod transcript;
+pub mod testing;
+pub mod okamoto;
+pub mod protocols;
+pub mod credentials;
+pub mod rotation;
+pub mod batch_dleq;
+pub mod cbor;
+pub mod generators;
+pub mod group;
+pub mod any;
+pub mod compose;
+pub mod keypair;
+pub mod secret;
+pub mod cross_group;
+pub mod privacypass;
+pub mod voprf;
+pub mod vss;
+pub mod dkg;
+pub mod adaptor;
+pub mod rfc8235;
+pub mod runtime;
+pub mod envelope;
+#[cfg(feature = "text-encoding")]
+pub mod encoding;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "der")]
+pub mod der;
+#[cfg(feature = "mobile")]
+pub mod mobile;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+mod hex;
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_cbor;
+    extern crate serde_json;
+    extern crate test;
+
+    use rand::OsRng;
+    use sha2::Sha256;
+    use self::test::Bencher;
+    use hex;
+
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::Identity;
+
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts the heap allocations made by each thread, so that tests
+    /// running in parallel do not see each other's allocations.
+    struct CountingAllocator;
+
+    thread_local!{ static ALLOCATIONS: Cell<usize> = Cell::new(0) }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Runs `f`, and returns its result with the number of heap
+    /// allocations it made.
+    fn count_allocations<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+        let before = ALLOCATIONS.with(|n| n.get());
+        let result = f();
+        (result, ALLOCATIONS.with(|n| n.get()) - before)
+    }
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+    /// The `dlog` instance most tests prove: `A = G*x` for the
+    /// basepoint `G` and a fixed `x`.
+    fn dlog_instance() -> (dlog::PublicsOwned, dlog::SecretsOwned) {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::from_u64(89327492234);
+        let A = G * &x;
+        (dlog::PublicsOwned::from(dlog::Publics{A: &A, G: G}), dlog::SecretsOwned{x: x})
+    }
+
+    /// The `dlog_instance` publics, with a proof of them.
+    fn dlog_proof() -> (dlog::PublicsOwned, dlog::Proof) {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dlog_instance();
+        let proof = dlog::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        (publics, proof)
+    }
+
+    #[bench]
+    fn create_gen_dleq(b: &mut Bencher) {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let x = Scalar::from_u64(89327492234);
+        let A =  G * &x;
+        let B = &H * &x;
+
+        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
+        let secrets = dleq::Secrets{x: &x};
+
+        b.iter(|| dleq::Proof::create(&mut csprng, publics, secrets));
+    }
+    
+    #[bench]
+    fn verify_gen_dleq(b: &mut Bencher) {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let x = Scalar::from_u64(89327492234);
+        let A =  G * &x;
+        let B = &H * &x;
+
+        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
+        let secrets = dleq::Secrets{x: &x};
+
+        let proof = dleq::Proof::create(&mut csprng, publics, secrets);
+        b.iter(|| proof.verify(publics).is_ok());
+    }
+
+    #[test]
+    fn create_and_verify_gen_dleq() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let x = Scalar::from_u64(89327492234);
+        let A =  G * &x;
+        let B = &H * &x;
+
+        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
+        let secrets = dleq::Secrets{x: &x};
+
+        let proof = dleq::Proof::create(&mut csprng, publics, secrets);
+        // serialize to packed CBOR byte representation
+        let proof_bytes = serde_cbor::ser::to_vec_packed(&proof).unwrap();
+        // parse bytes back to memory
+        let parsed_proof: dleq::Proof
+            = serde_cbor::from_slice(&proof_bytes).unwrap();
+
+        assert!(parsed_proof.verify(publics).is_ok());
+    }
+
+    #[test]
+    fn json_hex_scalars() {
+        let (_, proof) = dlog_proof();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let challenge = hex::encode(&proof.to_bytes()[..32]);
+        assert!(json.starts_with(&format!("{{\"challenge\":\"{}\"", challenge)));
+        let parsed_proof: dlog::Proof = serde_json::from_str(&json).unwrap();
+        assert!(parsed_proof == proof);
+
+        let bad_json = json.replace(&challenge, "00");
+        assert!(serde_json::from_str::<dlog::Proof>(&bad_json).is_err());
+    }
+
+    #[test]
+    fn display_proof() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{dleq, (x, y), (A, B, G, H) : A = (G * x), B = (H * y) }
+
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let proof = dleq::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        let bytes = proof.to_bytes();
+        assert_eq!(proof.to_string(), format!(
+            "dleq proof\n  challenge: {}\n  x: {}\n  y: {}",
+            hex::encode(&bytes[..32]), hex::encode(&bytes[32..64]), hex::encode(&bytes[64..]),
+        ));
+    }
+
+    #[test]
+    fn non_canonical_scalars() {
+        let (_, proof) = dlog_proof();
+        let bytes = proof.to_bytes();
+
+        // Add the group order to the challenge, giving another
+        // encoding of the same scalar.
+        const L: [u8; 32] = [
+            0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58,
+            0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+        ];
+        let mut challenge = [0u8; 32];
+        let mut carry = 0u16;
+        for i in 0..32 {
+            let sum = bytes[i] as u16 + L[i] as u16 + carry;
+            challenge[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        let mut bad_bytes = challenge.to_vec();
+        bad_bytes.extend_from_slice(&bytes[32..]);
+        assert!(dlog::Proof::from_bytes(&bad_bytes).is_err());
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let bad_json = json.replace(&hex::encode(&bytes[..32]), &hex::encode(&challenge));
+        assert!(serde_json::from_str::<dlog::Proof>(&bad_json).is_err());
+    }
+
+    #[test]
+    fn proof_attributes() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        create_nipk!{#[serde(rename = "DlogProof", deny_unknown_fields)]
+                     #[cfg_attr(test, allow(dead_code))]
+                     dlog, (x), (A, G) : A = (G * x) }
+
+        let x = Scalar::from_u64(89327492234);
+        let A = G * &x;
+        let publics = dlog::Publics{A: &A, G: G};
+
+        let proof = dlog::Proof::create(&mut csprng, publics, dlog::Secrets{x: &x});
+        let proof_bytes = serde_cbor::ser::to_vec(&proof).unwrap();
+        let parsed_proof: dlog::Proof = serde_cbor::from_slice(&proof_bytes).unwrap();
+        assert!(parsed_proof.verify(publics).is_ok());
+    }
+
+    #[test]
+    fn proof_clone_and_eq() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dlog_instance();
+
+        let proof = dlog::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        let copy = proof;
+        assert!(format!("{:?}", copy).starts_with("Proof"));
+        assert!(copy == proof);
+
+        let other = dlog::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(other != proof);
+    }
+
+    #[test]
+    fn owned_parameters() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dlog_instance();
+
+        let proof = dlog::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify(publics.as_publics()).is_ok());
+
+        // Owned publics can be serialized along with the proof
+        let publics_bytes = serde_cbor::ser::to_vec_packed(&publics).unwrap();
+        let parsed_publics: dlog::PublicsOwned = serde_cbor::from_slice(&publics_bytes).unwrap();
+        assert!(proof.verify(parsed_publics.as_publics()).is_ok());
+    }
+
+    #[test]
+    fn verify_compressed() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let x = Scalar::from_u64(89327492234);
+        let A =  G * &x;
+        let B = &H * &x;
+
+        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
+        let proof = dleq::Proof::create(&mut csprng, publics, dleq::Secrets{x: &x});
+
+        let (cA, cB, cG, cH) = (A.compress(), B.compress(), G.compress(), H.compress());
+        let compressed = dleq::CompressedPublics{A: &cA, B: &cB, G: &cG, H: &cH};
+        assert!(proof.verify_compressed(compressed).is_ok());
+
+        let swapped = dleq::CompressedPublics{A: &cB, B: &cA, G: &cG, H: &cH};
+        assert!(proof.verify_compressed(swapped).is_err());
+
+        let invalid = CompressedDecaf([0xff; 32]);
+        let invalid = dleq::CompressedPublics{A: &invalid, B: &cB, G: &cG, H: &cH};
+        assert!(proof.verify_compressed(invalid).is_err());
+    }
+
+    #[test]
+    fn create_batch() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let xs: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut csprng)).collect();
+        let As: Vec<DecafPoint> = xs.iter().map(|x| G * x).collect();
+        let Bs: Vec<DecafPoint> = xs.iter().map(|x| &H * x).collect();
+
+        let publics: Vec<dleq::Publics> = As.iter().zip(Bs.iter())
+            .map(|(A, B)| dleq::Publics{A: A, B: B, G: G, H: &H})
+            .collect();
+        let secrets: Vec<dleq::Secrets> = xs.iter().map(|x| dleq::Secrets{x: x}).collect();
+
+        let proofs = dleq::Proof::create_batch(&mut csprng, &publics, &secrets).unwrap();
+        assert_eq!(proofs.len(), 4);
+        for (proof, publics) in proofs.iter().zip(publics.iter()) {
+            assert!(proof.verify(*publics).is_ok());
+        }
+        assert!(proofs[0].verify(publics[1]).is_err());
+
+        assert!(dleq::Proof::create_batch(&mut csprng, &publics, &secrets[1..]).is_err());
+    }
+
+    #[test]
+    fn verify_batch() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        let xs: Vec<Scalar> = (0..16).map(|_| Scalar::random(&mut csprng)).collect();
+        let As: Vec<DecafPoint> = xs.iter().map(|x| G * x).collect();
+        let publics: Vec<dlog::Publics> = As.iter().map(|A| dlog::Publics{A: A, G: G}).collect();
+        let mut proofs: Vec<dlog::Proof> = xs.iter().zip(publics.iter())
+            .map(|(x, publics)| dlog::Proof::create(&mut csprng, *publics, dlog::Secrets{x: x}))
+            .collect();
+
+        assert!(dlog::Proof::verify_batch(&proofs, &publics).is_ok());
+        assert!(dlog::Proof::verify_batch(&proofs[1..], &publics).is_err());
+
+        proofs.swap(3, 7);
+        assert!(dlog::Proof::verify_batch(&proofs, &publics).is_err());
+    }
+
+    #[test]
+    fn verify_any_of() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{dleq, (x), (G, H, A, B) : A = (G * x), B = (H * x) }
+
+        let xs: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut csprng)).collect();
+        let As: Vec<DecafPoint> = xs.iter().map(|x| G * x).collect();
+        let Bs: Vec<DecafPoint> = xs.iter().map(|x| &H * x).collect();
+        let candidates: Vec<dleq::Publics> = As.iter().zip(Bs.iter())
+            .map(|(A, B)| dleq::Publics{G: G, H: &H, A: A, B: B})
+            .collect();
+
+        for (i, x) in xs.iter().enumerate() {
+            let proof = dleq::Proof::create(&mut csprng, candidates[i], dleq::Secrets{x: x});
+            assert_eq!(proof.verify_any_of(&candidates), Some(i));
+            assert_eq!(proof.verify_any_of(&candidates[i..i + 1]), Some(0));
+            // Repeating a candidate reuses its whole hash.
+            let repeated = [candidates[i], candidates[i]];
+            assert_eq!(proof.verify_any_of(&repeated), Some(0));
+        }
+
+        let proof = dleq::Proof::create(&mut csprng, candidates[0], dleq::Secrets{x: &xs[0]});
+        assert_eq!(proof.verify_any_of(&candidates[1..]), None);
+        assert_eq!(proof.verify_any_of(&[]), None);
+    }
+
+    #[test]
+    fn fixed_sizes() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{opening, (x, r), (C, G, H) : C = (G * x + H * r) }
+
+        let (publics, secrets) = opening::random_instance(&mut csprng);
+        let proof = opening::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+
+        let bytes = proof.to_byte_array();
+        assert_eq!(bytes.len(), opening::Proof::SERIALIZED_SIZE);
+        assert_eq!(&bytes[..], &proof.to_bytes()[..]);
+        assert_eq!(count_allocations(|| proof.to_byte_array()).1, 0);
+
+        assert_eq!(opening::Responses::LEN, 2);
+        let responses = opening::Responses::from_array([Scalar::one(), Scalar::zero()]);
+        assert_eq!(responses.to_array(), [Scalar::one(), Scalar::zero()]);
+        assert!(responses.x() == Scalar::one() && responses.r() == Scalar::zero());
+    }
+
+    #[test]
+    fn fischlin() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let publics = publics.as_publics();
+        let proof = dleq::FischlinProof::create(&mut csprng, publics, secrets.as_secrets());
+        assert!(proof.verify(publics).is_ok());
+
+        let (other, _) = dleq::random_instance(&mut csprng);
+        assert!(proof.verify(other.as_publics()).is_err());
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let parsed: dleq::FischlinProof = serde_json::from_str(&json).unwrap();
+        assert!(parsed.verify(publics).is_ok());
+
+        // Changing any run's challenge or responses breaks the proof.
+        let tamper = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut value = serde_json::to_value(&proof).unwrap();
+            change(&mut value);
+            let tampered: dleq::FischlinProof = serde_json::from_value(value).unwrap();
+            tampered.verify(publics)
+        };
+        assert!(tamper(&|value| {
+            let challenge = value["challenges"][3].as_u64().unwrap();
+            value["challenges"][3] = serde_json::to_value(challenge ^ 1).unwrap();
+        }).is_err());
+        assert!(tamper(&|value| value["responses"].as_array_mut().unwrap().swap(0, 1)).is_err());
+        assert!(tamper(&|value| { value["commitments"].as_array_mut().unwrap().pop(); }).is_err());
+    }
+
+    #[test]
+    fn versions() {
+        let mut csprng = OsRng::new().unwrap();
 
-            // Hack because we can't concat identifiers,
-            // so do responses.x instead of responses_x
-            // rand.x instead of rand_x, etc.
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
 
-            struct Commitments {$($lhs: DecafPoint,)+ }
-            struct Randomnesses {$($secret : Scalar,)+}
-            #[derive(Serialize, Deserialize)]
-            struct Responses {$($secret : Scalar,)+}
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let publics = publics.as_publics();
 
-            #[derive(Serialize, Deserialize)]
-            pub struct Proof {
-                challenge: Scalar,
-                responses: Responses,
-            }
+        let proof = dleq::Proof::create_version(&mut csprng, ::Version::V1, publics, secrets.as_secrets());
+        assert!(proof.verify_version(::Version::V1, publics).is_ok());
+        assert!(proof.verify(publics).is_err());
+        assert!(proof.verify_version(::Version::V2, publics).is_err());
 
-            impl Proof {
-                #[allow(dead_code)]
-                pub fn create<R: Rng>(
-                    csprng: &mut R,
-                    publics: Publics,
-                    secrets: Secrets,
-                ) -> Proof {
-                    let rand = Randomnesses{
-                        $(
-                            $secret : Scalar::random(csprng),
-                        )+
-                    };
-                    // $statement_rhs = `X * x + Y * y + Z * z`
-                    // should become
-                    // `publics.X * rand.x + publics.Y * rand.y + publics.Z * rand.z`
-                    let commitments = __compute_commitments_consttime!(
-                        (publics, rand) $($lhs = $statement),*
-                    );
+        let proof = dleq::Proof::create(&mut csprng, publics, secrets.as_secrets());
+        assert!(proof.verify_version(::Version::V2, publics).is_ok());
+        assert!(proof.verify_version(::Version::V1, publics).is_err());
 
-                    let mut hash = Sha512::default();
+        let proof = dleq::Proof::create_with_context(&mut csprng, b"ctx", publics, secrets.as_secrets());
+        assert!(proof.verify_with_context_version(::Version::CURRENT, b"ctx", publics).is_ok());
+        assert!(proof.verify_with_context_version(::Version::CURRENT, b"other", publics).is_err());
 
-                    $(
-                        hash.input(publics.$public.compress().as_bytes());
-                    )+
-                    $(
-                        hash.input(commitments.$lhs.compress().as_bytes());
-                    )+
+        let proof = dleq::Proof::create_version(&mut csprng, ::Version::V0, publics, secrets.as_secrets());
+        assert!(proof.verify_version(::Version::V0, publics).is_ok());
+        assert!(proof.verify_version(::Version::V1, publics).is_err());
+    }
 
-                    let challenge = Scalar::from_hash(hash);
+    #[test]
+    fn version_0_proofs() {
+        use sha2::{Digest, Sha512};
 
-                    let responses = Responses{
-                        $(
-                            $secret : Scalar::multiply_add(
-                                &challenge,
-                                &secrets.$secret,
-                                &rand.$secret
-                            ),
-                        )+
-                    };
+        let mut csprng = OsRng::new().unwrap();
 
-                    Proof{ challenge: challenge, responses: responses }
-                }
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
 
-                #[allow(dead_code)]
-                pub fn verify(&self, publics: Publics) -> Result<(),()> {
-                    // `A = X * x + Y * y`
-                    // should become
-                    // `publics.X * responses.x + publics.Y * responses.y - publics.A * self.challenge`
-                    let responses = &self.responses;
-                    let mut commitments = __compute_commitments_consttime!(
-                        (publics, responses) $($lhs = $statement),*
-                    );
-                    $(
-                        commitments.$lhs -= &(publics.$lhs * &self.challenge);
-                    )*
-                    
-                    let mut hash = Sha512::default();
-                    // Add each public point into the hash
-                    $(
-                        hash.input(publics.$public.compress().as_bytes());
-                    )+
-                    // Add each (recomputed) commitment into the hash
-                    $(
-                        hash.input(commitments.$lhs.compress().as_bytes());
-                    )*
-                        
-                    // Recompute challenge
-                    let challenge = Scalar::from_hash(hash);
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
 
-                    if challenge == self.challenge { Ok(()) } else { Err(()) }
-                }
-            }
+        // A proof made as zkp 0.1 made them: the challenge hashes the
+        // publics and then the commitments, and nothing else.
+        let nonce = Scalar::random(&mut csprng);
+        let mut hash = Sha512::default();
+        for public in &[&publics.A, &publics.B, &publics.G, &publics.H] {
+            hash.input(public.compress().as_bytes());
         }
+        hash.input((&publics.G * &nonce).compress().as_bytes());
+        hash.input((&publics.H * &nonce).compress().as_bytes());
+        let challenge = Scalar::from_hash(hash);
+        let response = Scalar::multiply_add(&challenge, &secrets.x, &nonce);
+
+        let mut bytes = challenge.as_bytes().to_vec();
+        bytes.extend_from_slice(response.as_bytes());
+        let proof = dleq::Proof::from_bytes(&bytes).unwrap();
+        assert!(proof.verify_version(::Version::V0, publics.as_publics()).is_ok());
+        assert!(proof.verify(publics.as_publics()).is_err());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    extern crate serde_cbor;
-    extern crate test;
+    #[test]
+    fn introspection() {
+        create_nipk!{sum, (a, b, c), (A, B, C, D, G, H)
+                     : A = (G * a), (C - D) = (G * b + (2) * H * c), B = (H * a - H * b)
+                     ; c = (a + b) }
 
-    use rand::OsRng;
-    use sha2::Sha256;
-    use self::test::Bencher;
+        assert_eq!(sum::NAME, "sum");
+        assert_eq!(sum::SECRETS, &["a", "b", "c"]);
+        assert_eq!(sum::PUBLICS, &["A", "B", "C", "D", "G", "H"]);
+        assert_eq!(sum::STATEMENTS.len(), 3);
+        assert_eq!(sum::STATEMENT_PUBLICS[0], &["A", "G"]);
+        assert_eq!(sum::STATEMENT_PUBLICS[1], &["C", "D", "G", "H"]);
+        assert_eq!(sum::STATEMENT_PUBLICS[2], &["B", "H", "H"]);
+        assert_eq!(sum::RELATIONS.len(), 1);
+        assert_eq!(sum::Proof::SERIALIZED_SIZE, 32 * 4);
+        assert_eq!(sum::Publics::SERIALIZED_SIZE, 32 * 6);
+    }
 
-    use curve25519_dalek::constants as dalek_constants;
-    use curve25519_dalek::decaf::DecafPoint;
-    use curve25519_dalek::scalar::Scalar;
-    
-    #[bench]
-    fn create_gen_dleq(b: &mut Bencher) {
+    #[test]
+    fn create_and_verify_subtraction() {
         let mut csprng = OsRng::new().unwrap();
         let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
         let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
 
-        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+        create_nipk!{diff, (x, y), (A, B, G, H) : A = (G * x - H * y), B = (- H * x + G * y) }
 
         let x = Scalar::from_u64(89327492234);
-        let A =  G * &x;
-        let B = &H * &x;
+        let y = Scalar::from_u64(1234567);
+        let A = &(G * &x) - &(&H * &y);
+        let B = &(G * &y) - &(&H * &x);
 
-        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
-        let secrets = dleq::Secrets{x: &x};
+        let publics = diff::Publics{A: &A, B: &B, G: G, H: &H};
+        let secrets = diff::Secrets{x: &x, y: &y};
 
-        b.iter(|| dleq::Proof::create(&mut csprng, publics, secrets));
+        let proof = diff::Proof::create(&mut csprng, publics, secrets);
+        assert!(proof.verify(publics).is_ok());
+
+        let wrong = diff::Publics{A: &B, B: &A, G: G, H: &H};
+        assert!(proof.verify(wrong).is_err());
     }
-    
-    #[bench]
-    fn verify_gen_dleq(b: &mut Bencher) {
+
+    fn three() -> Scalar {
+        Scalar::from_u64(3)
+    }
+
+    #[test]
+    fn create_and_verify_coefficients() {
         let mut csprng = OsRng::new().unwrap();
         let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
         let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
 
-        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+        create_nipk!{scaled, (x, y), (A, B, G, H)
+                     : A = (G * x + (2) * H * y), B = ((three()) * G * x - (-5) * H * y) }
 
         let x = Scalar::from_u64(89327492234);
-        let A =  G * &x;
-        let B = &H * &x;
+        let y = Scalar::from_u64(1234567);
+        let A = &(G * &x) + &(&H * &(&Scalar::from_u64(2) * &y));
+        let B = &(G * &(&three() * &x)) + &(&H * &(&Scalar::from_u64(5) * &y));
 
-        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
-        let secrets = dleq::Secrets{x: &x};
+        let publics = scaled::Publics{A: &A, B: &B, G: G, H: &H};
+        let secrets = scaled::Secrets{x: &x, y: &y};
 
-        let proof = dleq::Proof::create(&mut csprng, publics, secrets);
-        b.iter(|| proof.verify(publics).is_ok());
+        let proof = scaled::Proof::create(&mut csprng, publics, secrets);
+        assert!(proof.verify(publics).is_ok());
+
+        let wrong = scaled::Publics{A: &B, B: &A, G: G, H: &H};
+        assert!(proof.verify(wrong).is_err());
     }
 
     #[test]
-    fn create_and_verify_gen_dleq() {
+    fn create_and_verify_lhs_expression() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{balance, (r), (C1, C2, C3, H) : (C1 + C2 - C3) = (H * r) }
+
+        let m = Scalar::from_u64(1000);
+        let r1 = Scalar::random(&mut csprng);
+        let r2 = Scalar::random(&mut csprng);
+        let r3 = Scalar::random(&mut csprng);
+        let C1 = &(G * &m) + &(&H * &r1);
+        let C2 = &(G * &m) + &(&H * &r2);
+        let C3 = &(G * &(&m + &m)) + &(&H * &r3);
+        let r = &(&r1 + &r2) - &r3;
+
+        let publics = balance::Publics{C1: &C1, C2: &C2, C3: &C3, H: &H};
+        let secrets = balance::Secrets{r: &r};
+
+        let proof = balance::Proof::create(&mut csprng, publics, secrets);
+        assert!(proof.verify(publics).is_ok());
+
+        let wrong = balance::Publics{C1: &C1, C2: &C1, C3: &C3, H: &H};
+        assert!(proof.verify(wrong).is_err());
+    }
+
+    #[test]
+    fn create_and_verify_relation() {
         let mut csprng = OsRng::new().unwrap();
         let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
         let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
 
+        create_nipk!{sum, (a, b, c, r), (A, B, C, G, H)
+                     : A = (G * a), B = (G * b), C = (G * c + H * r)
+                     ; c = (a + (2) * b - (7)) }
+
+        let a = Scalar::from_u64(11);
+        let b = Scalar::from_u64(13);
+        let c = &(&a + &(&b + &b)) - &Scalar::from_u64(7);
+        let r = Scalar::random(&mut csprng);
+        let A = G * &a;
+        let B = G * &b;
+        let C = &(G * &c) + &(&H * &r);
+
+        let publics = sum::Publics{A: &A, B: &B, C: &C, G: G, H: &H};
+        let proof = sum::Proof::create(&mut csprng, publics, sum::Secrets{a: &a, b: &b, c: &c, r: &r});
+        assert!(proof.verify(publics).is_ok());
+
+        // Secrets which open the statements but not the relation
+        let c = &c + &Scalar::one();
+        let C = &(G * &c) + &(&H * &r);
+        let publics = sum::Publics{A: &A, B: &B, C: &C, G: G, H: &H};
+        let proof = sum::Proof::create(&mut csprng, publics, sum::Secrets{a: &a, b: &b, c: &c, r: &r});
+        assert!(proof.verify(publics).is_err());
+    }
+
+    #[test]
+    fn create_default() {
+        let (publics, secrets) = dlog_instance();
+        let publics = publics.as_publics();
+        let proof = dlog::Proof::create_default(publics, secrets.as_secrets()).unwrap();
+        assert!(proof.verify(publics).is_ok());
+    }
+
+    #[test]
+    fn many_statements() {
+        let mut csprng = OsRng::new().unwrap();
+
+        // Enough statements for the commitments to be computed on a
+        // thread pool with the `parallel` feature.
+        create_nipk!{many, (x, y), (A, B, C, D, E, F, G, H, P, Q)
+                     : A = (P * x), B = (Q * x), C = (P * y), D = (Q * y),
+                       E = (P * x + Q * y), F = (Q * x + P * y),
+                       G = (P * x + P * y), H = (Q * x + Q * y) }
+        assert_eq!(many::STATEMENTS.len(), 8);
+
+        let (publics, secrets) = many::random_instance(&mut csprng);
+        let proof = many::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify(publics.as_publics()).is_ok());
+    }
+
+    #[test]
+    fn application_context() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (publics, secrets) = dlog::random_instance(&mut csprng);
+        let publics = publics.as_publics();
+        let proof = dlog::Proof::create_with_context(&mut csprng, b"app 1", publics, secrets.as_secrets());
+        assert!(proof.verify_with_context(b"app 1", publics).is_ok());
+        assert!(proof.verify_with_context(b"app 2", publics).is_err());
+        assert!(proof.verify(publics).is_err());
+
+        // An empty context is still a context.
+        let proof = dlog::Proof::create_with_context(&mut csprng, b"", publics, secrets.as_secrets());
+        assert!(proof.verify_with_context(b"", publics).is_ok());
+        assert!(proof.verify(publics).is_err());
+    }
+
+    #[test]
+    fn camenisch_stadler() {
         create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+        assert_eq!(dleq::camenisch_stadler(), "PK{(x): A = G^x ∧ B = H^x}");
 
-        let x = Scalar::from_u64(89327492234);
-        let A =  G * &x;
-        let B = &H * &x;
+        create_nipk!{sum, (a, b, c), (A, B, C, D, G, H)
+                     : A = (- G * a), (C - D) = (G * b + (2) * H * c), B = (H * a - H * b)
+                     ; c = (a - (3) * b + (7)), b = a }
+        assert_eq!(sum::camenisch_stadler(),
+                   "PK{(a, b, c): A = G^-a ∧ C · D^-1 = G^b · H^(2·c) ∧ B = H^a · H^-b \
+                    ∧ c = a - 3·b + 7 ∧ b = a}");
+    }
 
-        let publics = dleq::Publics{A: &A, B: &B, G: G, H: &H};
-        let secrets = dleq::Secrets{x: &x};
+    #[test]
+    fn labelled_inputs() {
+        let mut csprng = OsRng::new().unwrap();
 
-        let proof = dleq::Proof::create(&mut csprng, publics, secrets);
-        // serialize to packed CBOR byte representation
-        let proof_bytes = serde_cbor::ser::to_vec_packed(&proof).unwrap();
-        // parse bytes back to memory
-        let parsed_proof: dleq::Proof
-            = serde_cbor::from_slice(&proof_bytes).unwrap();
+        create_nipk!{labels, (x, y), (A, B, C, G, H)
+                     : A = (G * x), (B - C) = (- (2) * G * x + H * y) }
+        assert_eq!(labels::LABELS, &["A = G * x", "B - C = - (c) * G * x + H * y"]);
 
-        assert!(parsed_proof.verify(publics).is_ok());
+        // The same publics and commitments, with the roles of `G` and
+        // `H` swapped, give a different challenge.
+        create_nipk!{gh, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+        create_nipk!{hg, (x), (A, B, G, H) : A = (H * x), B = (G * x) }
+        let points: Vec<DecafPoint> = (0..6).map(|_| DecafPoint::random(&mut csprng)).collect();
+        let commitments = [points[4], points[5]];
+        assert!(gh::challenge(gh::Publics{A: &points[0], B: &points[1], G: &points[2], H: &points[3]}, &commitments)
+                != hg::challenge(hg::Publics{A: &points[0], B: &points[1], G: &points[2], H: &points[3]}, &commitments));
+    }
+
+    #[test]
+    fn included_statements() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        create_statement!{dleq_statement : A = (G * x), B = (H * x) }
+
+        create_nipk!{dleq, (x), (A, B, G, H) : use dleq_statement }
+        create_nipk!{both, (x, y, r), (A, B, C, D, G, H)
+                     : C = (G * y), use dleq_statement, D = (A * r + H * y)
+                     ; r = ((2) * x + (5)) }
+
+        assert_eq!(dleq::Proof::SERIALIZED_SIZE, 64);
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let proof = dleq::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify(publics.as_publics()).is_ok());
+
+        let H = DecafPoint::random(&mut csprng);
+        let (x, y) = (Scalar::random(&mut csprng), Scalar::random(&mut csprng));
+        let r = &(&Scalar::from_u64(2) * &x) + &Scalar::from_u64(5);
+        let (A, B, C) = (G * &x, &H * &x, G * &y);
+        let D = &(&A * &r) + &(&H * &y);
+        let publics = both::Publics{A: &A, B: &B, C: &C, D: &D, G: G, H: &H};
+        let proof = both::Proof::create(&mut csprng, publics, both::Secrets{x: &x, y: &y, r: &r});
+        assert!(proof.verify(publics).is_ok());
+        // The included statements are checked.
+        let other = G * &y;
+        assert!(proof.verify(both::Publics{B: &other, ..publics}).is_err());
+    }
+
+    #[test]
+    fn challenge_hash() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_statement!{dlog_statement : A = (G * x) }
+
+        create_nipk!{default, (x), (A, G) : A = (G * x) }
+        create_nipk!{explicit<::sha2::Sha512>, (x), (A, G) : A = (G * x) }
+        create_nipk!{included<::sha2::Sha512>, (x), (A, G) : use dlog_statement }
+        create_nipk!{shared<::sha2::Sha512>, (x), (A, G) {
+            dlog as DlogProof { A = (G * x) }
+        }}
+
+        // SHA-512 is the default, so the proofs are interchangeable.
+        let (publics, secrets) = default::random_instance(&mut csprng);
+        let proof = default::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        let bytes = proof.to_bytes();
+        let publics = explicit::PublicsOwned::from_bytes(&publics.as_publics().to_bytes()).unwrap();
+        assert!(explicit::Proof::from_bytes(&bytes).unwrap().verify(publics.as_publics()).is_ok());
+        let publics = included::PublicsOwned::from_bytes(&publics.as_publics().to_bytes()).unwrap();
+        assert!(included::Proof::from_bytes(&bytes).unwrap().verify(publics.as_publics()).is_ok());
+        let publics = shared::PublicsOwned::from_bytes(&publics.as_publics().to_bytes()).unwrap();
+        assert!(shared::DlogProof::from_bytes(&bytes).unwrap().verify(publics.as_publics()).is_ok());
+    }
+
+    #[test]
+    fn shared_publics() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_statement!{opening_statement : A = (G * x) }
+
+        create_nipk!{params, (x, y), (A, B, G, H) {
+            opening as OpeningProof { use opening_statement, B = (H * y) }
+            dleq as DleqProof { A = (G * x), B = (H * y) ; y = x }
+            sum as SumProof { B = (G * x + H * y) }
+        }}
+        assert_eq!(params::NAME, "params");
+        assert_eq!(params::opening::NAME, "opening");
+        assert_eq!(params::dleq::STATEMENTS.len(), 2);
+        assert_eq!(params::sum::SECRETS, &["x", "y"]);
+
+        let (publics, secrets) = params::dleq::random_instance(&mut csprng);
+        let publics: params::Publics = publics.as_publics();
+        let secrets: params::Secrets = secrets.as_secrets();
+
+        // All the proofs take the same publics.
+        let opening = params::OpeningProof::create(&mut csprng, publics, secrets);
+        let dleq = params::DleqProof::create(&mut csprng, publics, secrets);
+        assert!(opening.verify(publics).is_ok());
+        assert!(dleq.verify(publics).is_ok());
+
+        // They are different statements, with different challenges.
+        let sum = params::SumProof::create(&mut csprng, publics, secrets);
+        assert!(sum.verify(publics).is_err());
+        let bytes = opening.to_bytes();
+        assert!(params::DleqProof::from_bytes(&bytes).unwrap().verify(publics).is_err());
+    }
+
+    #[test]
+    fn selftest() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+        create_nipk!{related, (x, y), (A, B, G, H) : A = (G * x), B = (H * y) ; y = x }
+        create_nipk!{params, (x, y), (A, B, G, H) {
+            opening as OpeningProof { A = (G * x), B = (H * y) }
+            sum as SumProof { B = (G * x + H * y) }
+        }}
+
+        assert!(dleq::selftest(&mut csprng).is_ok());
+        assert!(related::selftest(&mut csprng).is_ok());
+        assert!(params::opening::selftest(&mut csprng).is_ok());
+        assert!(params::sum::selftest(&mut csprng).is_ok());
+    }
+
+    #[test]
+    fn keygen() {
+        use keypair;
+
+        let mut csprng = OsRng::new().unwrap();
+        let B = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        create_nipk!{pop, (x), (A, B) : A = (B * x) }
+
+        let (secrets, publics) = pop::keygen(&mut csprng, B);
+        assert_eq!(publics.A.compress(), (B * &secrets.x).compress());
+        let proof = pop::Proof::prove_possession(
+            &mut csprng, b"signer 1", publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify_possession(b"signer 1", publics.as_publics()).is_ok());
+        assert!(proof.verify_possession(b"signer 2", publics.as_publics()).is_err());
+        // The same proof of possession as `zkp::keypair`'s.
+        let proof = keypair::ProofOfPossession::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(keypair::verify_possession(&publics.A, b"signer 1", &proof).is_ok());
+    }
+
+    #[test]
+    fn nizk_proof_trait() {
+        use NizkProof;
+
+        fn round_trip<P: NizkProof>(publics: &P::Publics, secrets: &P::Secrets) -> &'static str {
+            let mut csprng = OsRng::new().unwrap();
+            let proof = P::create(&mut csprng, publics, secrets);
+            let parsed = P::from_bytes(&proof.to_bytes()).unwrap();
+            assert!(parsed.verify(publics).is_ok());
+            P::NAME
+        }
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dlog::random_instance(&mut csprng);
+        assert_eq!(round_trip::<dlog::Proof>(&publics, &secrets), "dlog");
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        assert_eq!(round_trip::<dleq::Proof>(&publics, &secrets), "dleq");
+
+        let (other, _) = dleq::random_instance(&mut csprng);
+        let proof = <dleq::Proof as NizkProof>::create(&mut csprng, &publics, &secrets);
+        assert!(NizkProof::verify(&proof, &other).is_err());
+    }
+
+    #[test]
+    fn hedged_nonces() {
+        use rand::Rng;
+        use transcript::Transcript;
+
+        struct ZeroRng;
+        impl Rng for ZeroRng {
+            fn next_u32(&mut self) -> u32 { 0 }
+        }
+
+        let (publics, secrets) = dlog_instance();
+        let publics = publics.as_publics();
+        let x = secrets.x;
+        // The nonce `s - c*x` of each proof.
+        let nonce = |context: &[u8]| {
+            let mut transcript = Transcript::new(context);
+            let proof = dlog::Proof::create_from_transcript(
+                &mut ZeroRng, &mut transcript, publics, dlog::Secrets{x: &x});
+            assert!(proof.verify_from_transcript(&mut Transcript::new(context), publics).is_ok());
+            let bytes = proof.to_bytes();
+            let mut c = [0u8; 32];
+            let mut s = [0u8; 32];
+            c.copy_from_slice(&bytes[..32]);
+            s.copy_from_slice(&bytes[32..]);
+            (&Scalar::from_bytes_mod_order(s) - &(&Scalar::from_bytes_mod_order(c) * &x)).to_bytes()
+        };
+
+        // Even with no randomness at all, proofs with different
+        // contexts do not share a nonce.
+        assert_eq!(nonce(b"one"), nonce(b"one"));
+        assert!(nonce(b"one") != nonce(b"two"));
+    }
+
+    #[test]
+    fn verify_strict() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dlog_instance();
+        let proof = dlog::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify_strict(publics.as_publics()).is_ok());
+
+        // Any response verifies when both publics are the identity.
+        let O = DecafPoint::identity();
+        let publics = dlog::Publics{A: &O, G: &O};
+        let proof = dlog::Proof::create(&mut csprng, publics, secrets.as_secrets());
+        assert!(proof.verify(publics).is_ok());
+        assert!(proof.verify_strict(publics).is_err());
+    }
+
+    #[test]
+    fn verify_ct() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{sum, (a, b, r), (C, D, G, H)
+                     : D = (G * b), (C - D) = (G * a + H * r)
+                     ; b = ((3) * a + (1)) }
+
+        let (publics, secrets) = sum::random_instance(&mut csprng);
+        let proof = sum::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert_eq!(proof.verify_ct(publics.as_publics()).unwrap_u8(), 1);
+
+        let corrupted = ::testing::corrupt(&mut csprng, &proof);
+        assert_eq!(corrupted.verify_ct(publics.as_publics()).unwrap_u8(), 0);
+
+        let (other, _) = sum::random_instance(&mut csprng);
+        assert_eq!(proof.verify_ct(other.as_publics()).unwrap_u8(), 0);
+
+        // A response which does not satisfy the relation.
+        let mut bytes = proof.to_bytes();
+        let b = &::__decode_scalar(&bytes[64..96]).unwrap() + &Scalar::one();
+        bytes[64..96].copy_from_slice(b.as_bytes());
+        let broken = sum::Proof::from_bytes(&bytes).unwrap();
+        assert!(broken.verify(publics.as_publics()).is_err());
+        assert_eq!(broken.verify_ct(publics.as_publics()).unwrap_u8(), 0);
+    }
+
+    #[test]
+    fn verifier() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let publics = publics.as_publics();
+        let proofs: Vec<dleq::Proof> = (0..4).map(|_| {
+            dleq::Proof::create(&mut csprng, publics, secrets.as_secrets())
+        }).collect();
+
+        let verifier = dleq::Verifier::new(publics);
+        for proof in &proofs {
+            assert!(verifier.verify(proof).is_ok());
+        }
+        assert!(verifier.verify_batch(&proofs).is_ok());
+
+        let (A, B, G, H) = (publics.A.compress(), publics.B.compress(), publics.G.compress(), publics.H.compress());
+        let verifier = dleq::Verifier::from_compressed(dleq::CompressedPublics{A: &A, B: &B, G: &G, H: &H}).unwrap();
+        assert!(verifier.verify(&proofs[0]).is_ok());
+
+        // A verifier for other publics rejects the proofs.
+        let swapped = dleq::Verifier::from_compressed(dleq::CompressedPublics{A: &B, B: &A, G: &H, H: &G}).unwrap();
+        assert!(swapped.verify(&proofs[0]).is_err());
+        let mut corrupted = proofs.clone();
+        corrupted[2] = ::testing::corrupt(&mut csprng, &proofs[2]);
+        assert!(verifier.verify_batch(&corrupted).is_err());
+    }
+
+    #[test]
+    fn multiscalar() {
+        let mut csprng = OsRng::new().unwrap();
+        let scalars: Vec<Scalar> = (0..6).map(|_| Scalar::random(&mut csprng)).collect();
+        let points: Vec<DecafPoint> = (0..6).map(|_| DecafPoint::random(&mut csprng)).collect();
+        let refs: Vec<&DecafPoint> = points.iter().collect();
+        for n in 1..7 {
+            let mut expected = &points[0] * &scalars[0];
+            for i in 1..n {
+                expected = &expected + &(&points[i] * &scalars[i]);
+            }
+            assert_eq!(::__multiscalar_mult(&scalars[..n], &refs[..n]).compress(), expected.compress());
+        }
+
+        create_nipk!{wide, (a, b, c, d, e), (A, G, H, J, K, L)
+                     : A = (G * a - H * b + (3) * J * c + K * d - (2) * L * e) }
+
+        let (publics, secrets) = wide::random_instance(&mut csprng);
+        let proof = wide::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        assert!(proof.verify(publics.as_publics()).is_ok());
+        let mut other = publics.clone();
+        other.A = &publics.A + &publics.G;
+        assert!(proof.verify(other.as_publics()).is_err());
+    }
+
+    #[test]
+    fn no_allocations() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{sum, (a, b, r), (A, C, G, H)
+                     : A = (G * a), C = (G * b + H * r)
+                     ; b = ((2) * a + (1)) }
+
+        let a = Scalar::random(&mut csprng);
+        let b = &(&Scalar::from_u64(2) * &a) + &Scalar::one();
+        let r = Scalar::random(&mut csprng);
+        let A = G * &a;
+        let C = &(G * &b) + &(&H * &r);
+        let publics = sum::Publics{A: &A, C: &C, G: G, H: &H};
+        let secrets = sum::Secrets{a: &a, b: &b, r: &r};
+        let (A_c, C_c, G_c, H_c) = (A.compress(), C.compress(), G.compress(), H.compress());
+        let compressed = sum::CompressedPublics{A: &A_c, C: &C_c, G: &G_c, H: &H_c};
+
+        let (proof, n) = count_allocations(|| sum::Proof::create(&mut csprng, publics, secrets));
+        assert_eq!(n, 0);
+        assert_eq!(count_allocations(|| proof.verify(publics)), (Ok(()), 0));
+        assert_eq!(count_allocations(|| proof.verify_strict(publics)), (Ok(()), 0));
+        assert_eq!(count_allocations(|| proof.verify_compressed(compressed)), (Ok(()), 0));
+        let (_, n) = count_allocations(|| sum::Proof::simulate(&mut csprng, publics));
+        assert_eq!(n, 0);
+
+        let mut transcript = ::transcript::Transcript::new(b"no allocations");
+        let (proof, n) = count_allocations(|| {
+            sum::Proof::create_from_transcript(&mut csprng, &mut transcript, publics, secrets)
+        });
+        assert_eq!(n, 0);
+        let mut transcript = ::transcript::Transcript::new(b"no allocations");
+        assert_eq!(count_allocations(|| proof.verify_from_transcript(&mut transcript, publics)), (Ok(()), 0));
+
+        let (proof, n) = count_allocations(|| {
+            sum::Proof::create_with_context(&mut csprng, b"no allocations", publics, secrets)
+        });
+        assert_eq!(n, 0);
+        assert_eq!(count_allocations(|| proof.verify_with_context(b"no allocations", publics)), (Ok(()), 0));
+        assert_eq!(
+            count_allocations(|| proof.verify_with_context_version(::Version::CURRENT, b"no allocations", publics)),
+            (Ok(()), 0)
+        );
+
+        // Serialization allocates, of course.
+        assert!(count_allocations(|| proof.to_bytes()).1 > 0);
+
+        // Long equations do not.
+        create_nipk!{wide, (a, b, c, d), (A, G, H, J, K)
+                     : A = (G * a + H * b + J * c + K * d) }
+        let (publics, secrets) = wide::random_instance(&mut csprng);
+        let (proof, n) = count_allocations(|| {
+            wide::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets())
+        });
+        let (result, m) = count_allocations(|| proof.verify(publics.as_publics()));
+        assert_eq!(result, Ok(()));
+        assert_eq!((n, m), (0, 0));
+    }
+
+    #[test]
+    fn batch_verifier() {
+        let mut csprng = OsRng::new().unwrap();
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let instances: Vec<(dleq::PublicsOwned, dleq::Proof)> = (0..4).map(|_| {
+            let (publics, secrets) = dleq::random_instance(&mut csprng);
+            let proof = dleq::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+            (publics, proof)
+        }).collect();
+
+        let mut batch = dleq::BatchVerifier::with_capacity(4);
+        for &(ref publics, ref proof) in &instances {
+            batch.push(proof, publics.as_publics());
+        }
+        assert_eq!(batch.len(), 4);
+        assert!(batch.verify().is_ok());
+        assert!(batch.is_empty());
+
+        // A bad proof fails the batch, which is cleared all the same.
+        batch.push(&instances[0].1, instances[1].0.as_publics());
+        batch.push(&instances[1].1, instances[1].0.as_publics());
+        assert!(batch.verify().is_err());
+        assert!(batch.is_empty());
+
+        // Later batches reuse the buffers.
+        #[cfg(not(feature = "parallel"))]
+        {
+            let (result, n) = count_allocations(|| {
+                for &(ref publics, ref proof) in &instances {
+                    batch.push(proof, publics.as_publics());
+                }
+                batch.verify()
+            });
+            assert_eq!((result, n), (Ok(()), 0));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "postcard-encoding")]
+    fn postcard() {
+        let (publics, proof) = dlog_proof();
+        let publics = publics.as_publics();
+
+        let mut buf = [0u8; dlog::Proof::POSTCARD_MAX_SIZE];
+        let len = proof.to_postcard(&mut buf).unwrap().len();
+        assert!(len <= dlog::Proof::POSTCARD_MAX_SIZE);
+        let parsed = dlog::Proof::from_postcard(&buf[..len]).unwrap();
+        assert!(parsed.verify(publics).is_ok());
+
+        assert!(dlog::Proof::from_postcard(&buf[..len - 1]).is_err());
+        assert!(proof.to_postcard(&mut buf[..len - 1]).is_err());
+        let mut trailing = buf[..len].to_vec();
+        trailing.push(0);
+        assert!(dlog::Proof::from_postcard(&trailing).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzing")]
+    fn arbitrary() {
+        use arbitrary::{Arbitrary, FiniteBuffer};
+        use rand::Rng;
+
+        create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+        let mut csprng = OsRng::new().unwrap();
+        let mut data = [0u8; 32 * (2 + 4)];
+        csprng.fill_bytes(&mut data);
+        let mut u = FiniteBuffer::new(&data, data.len()).unwrap();
+        let proof = dleq::Proof::arbitrary(&mut u).unwrap();
+        let publics = dleq::CompressedPublicsOwned::arbitrary(&mut u).unwrap();
+
+        assert_eq!(dleq::Proof::from_bytes(&proof.to_bytes()).unwrap().to_bytes(), proof.to_bytes());
+        assert!(proof.verify_compressed(publics.as_compressed_publics()).is_err());
+        assert!(dleq::Proof::arbitrary(&mut u).is_err());
+    }
+
+    #[test]
+    fn simulate() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        create_nipk!{sum, (a, b, c, r), (A, B, C, G, H)
+                     : A = (G * a), B = (G * b), C = (G * c + H * r)
+                     ; c = (a + (2) * b - (7)) }
+
+        // Publics for which we know no secrets
+        let A = DecafPoint::random(&mut csprng);
+        let B = DecafPoint::random(&mut csprng);
+        let C = DecafPoint::random(&mut csprng);
+        let publics = sum::Publics{A: &A, B: &B, C: &C, G: G, H: &H};
+
+        let (proof, commitments) = sum::Proof::simulate(&mut csprng, publics);
+        assert!(proof.verify_commitments(publics, &commitments).is_ok());
+        assert!(proof.verify(publics).is_err());
+
+        let challenge = Scalar::random(&mut csprng);
+        let (proof, mut commitments) = sum::Proof::simulate_with_challenge(&mut csprng, publics, challenge);
+        assert!(proof.verify_commitments(publics, &commitments).is_ok());
+        commitments[0] = G + &commitments[0];
+        assert!(proof.verify_commitments(publics, &commitments).is_err());
     }
 }
@@ -0,0 +1,313 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! One-of-many proofs with logarithmic size.
+//!
+//! A `OneOfManyProof` shows that one of the points `C_0, ..., C_{N-1}`
+//! is of the form `H*r` for a known `r` (a Pedersen commitment to
+//! zero), without revealing which.  It is the Groth-Kohlweiss
+//! construction: the prover commits to the bits `l_j` of the secret
+//! index, and proves that the polynomial
+//! `sum_i C_i * prod_j f_{j, i_j}(x)` collapses to `x^n * C_l` up to
+//! lower-order terms that it commits to in advance.  The proof has
+//! `O(log N)` elements; proving and verifying take `O(N log N)`
+//! scalar multiplications.
+//!
+//! Two applications are provided on top of it: proving that a
+//! Pedersen commitment opens to a member of a public set of values,
+//! and proving that one's public key belongs to a public list.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use pedersen::{Opening, PedersenCommitment, PedersenGenerators};
+
+/// Commitments to one bit `l_j` of the secret index.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+struct BitCommitment {
+    l: DecafPoint,
+    a: DecafPoint,
+    b: DecafPoint,
+}
+
+/// A proof that one of a list of points is a commitment to zero.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OneOfManyProof {
+    bits: Vec<BitCommitment>,
+    d: Vec<DecafPoint>,
+    f: Vec<Scalar>,
+    z_a: Vec<Scalar>,
+    z_b: Vec<Scalar>,
+    z_d: Scalar,
+}
+
+/// Pad `points` to a power of two (at least two) by repeating the
+/// last point, and return the padded list with its bit length.
+fn pad(points: &[DecafPoint]) -> (Vec<DecafPoint>, usize) {
+    let mut n = 1;
+    while (1usize << n) < points.len() {
+        n += 1;
+    }
+    let mut padded = points.to_vec();
+    let last = points[points.len() - 1];
+    while padded.len() < (1 << n) {
+        padded.push(last);
+    }
+    (padded, n)
+}
+
+fn challenge(
+    gens: &PedersenGenerators,
+    points: &[DecafPoint],
+    bits: &[BitCommitment],
+    d: &[DecafPoint],
+) -> Scalar {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp one-of-many");
+    hash.input(gens.G.compress().as_bytes());
+    hash.input(gens.H.compress().as_bytes());
+    for P in points {
+        hash.input(P.compress().as_bytes());
+    }
+    for bit in bits {
+        hash.input(bit.l.compress().as_bytes());
+        hash.input(bit.a.compress().as_bytes());
+        hash.input(bit.b.compress().as_bytes());
+    }
+    for D in d {
+        hash.input(D.compress().as_bytes());
+    }
+    Scalar::from_hash(hash)
+}
+
+impl OneOfManyProof {
+    /// Prove that `points[index] = gens.H * blinding`.
+    ///
+    /// Returns `Err(())` if `points` is empty or `index` is out of
+    /// range.  (The proof is only valid if `points[index]` really is
+    /// `gens.H * blinding`.)
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        gens: &PedersenGenerators,
+        points: &[DecafPoint],
+        index: usize,
+        blinding: &Scalar,
+    ) -> Result<OneOfManyProof, ()> {
+        if points.is_empty() || index >= points.len() {
+            return Err(());
+        }
+        let (points, n) = pad(points);
+        let one = Scalar::one();
+        let com = |v: &Scalar, r: &Scalar| &(&gens.G * v) + &(&gens.H * r);
+
+        let l: Vec<Scalar> = (0..n).map(|j| Scalar::from_u64(((index >> j) & 1) as u64)).collect();
+        let r: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let s: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let t: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let rho: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+
+        let bits: Vec<BitCommitment> = (0..n).map(|j| BitCommitment{
+            l: com(&l[j], &r[j]),
+            a: com(&a[j], &s[j]),
+            b: com(&(&l[j] * &a[j]), &t[j]),
+        }).collect();
+
+        // The coefficients of p_i(x) = prod_j f_{j, i_j}(x), where
+        // f_{j,1}(x) = l_j*x + a_j and f_{j,0}(x) = (1 - l_j)*x - a_j.
+        let coefficients: Vec<Vec<Scalar>> = (0..points.len()).map(|i| {
+            let mut p = vec![one];
+            for j in 0..n {
+                let (hi, lo) = if (i >> j) & 1 == 1 {
+                    (l[j], a[j])
+                } else {
+                    (&one - &l[j], -&a[j])
+                };
+                let mut q = vec![Scalar::zero(); p.len() + 1];
+                for (k, c) in p.iter().enumerate() {
+                    q[k] = Scalar::multiply_add(c, &lo, &q[k]);
+                    q[k + 1] = Scalar::multiply_add(c, &hi, &q[k + 1]);
+                }
+                p = q;
+            }
+            p
+        }).collect();
+
+        let d: Vec<DecafPoint> = (0..n).map(|k| {
+            let mut D = &gens.H * &rho[k];
+            for (P, p) in points.iter().zip(coefficients.iter()) {
+                D = &D + &(P * &p[k]);
+            }
+            D
+        }).collect();
+
+        let x = challenge(gens, &points, &bits, &d);
+
+        let f: Vec<Scalar> = (0..n).map(|j| Scalar::multiply_add(&l[j], &x, &a[j])).collect();
+        let z_a = (0..n).map(|j| Scalar::multiply_add(&r[j], &x, &s[j])).collect();
+        let z_b = (0..n).map(|j| Scalar::multiply_add(&r[j], &(&x - &f[j]), &t[j])).collect();
+
+        // z_d = r*x^n - sum_k rho_k*x^k
+        let mut z_d = Scalar::zero();
+        let mut x_k = Scalar::one();
+        for k in 0..n {
+            z_d = &z_d - &(&rho[k] * &x_k);
+            x_k = &x_k * &x;
+        }
+        z_d = Scalar::multiply_add(blinding, &x_k, &z_d);
+
+        Ok(OneOfManyProof{ bits: bits, d: d, f: f, z_a: z_a, z_b: z_b, z_d: z_d })
+    }
+
+    /// Verify that one of `points` is a commitment to zero.
+    pub fn verify(&self, gens: &PedersenGenerators, points: &[DecafPoint]) -> Result<(),()> {
+        if points.is_empty() {
+            return Err(());
+        }
+        let (points, n) = pad(points);
+        if self.bits.len() != n || self.d.len() != n || self.f.len() != n
+            || self.z_a.len() != n || self.z_b.len() != n
+        {
+            return Err(());
+        }
+        let com = |v: &Scalar, r: &Scalar| &(&gens.G * v) + &(&gens.H * r);
+        let eq = |P: &DecafPoint, Q: &DecafPoint| P.compress() == Q.compress();
+
+        let x = challenge(gens, &points, &self.bits, &self.d);
+
+        for j in 0..n {
+            let bit = &self.bits[j];
+            // c_l*x + c_a == Com(f; z_a)
+            if !eq(&(&(&bit.l * &x) + &bit.a), &com(&self.f[j], &self.z_a[j])) {
+                return Err(());
+            }
+            // c_l*(x - f) + c_b == Com(0; z_b)
+            if !eq(&(&(&bit.l * &(&x - &self.f[j])) + &bit.b), &(&gens.H * &self.z_b[j])) {
+                return Err(());
+            }
+        }
+
+        // sum_i C_i * prod_j f_{j, i_j} - sum_k D_k * x^k == Com(0; z_d)
+        let f0: Vec<Scalar> = self.f.iter().map(|f| &x - f).collect();
+        let mut total = -&self.d[0];
+        let mut x_k = x;
+        for D in &self.d[1..] {
+            total = &total - &(D * &x_k);
+            x_k = &x_k * &x;
+        }
+        for (i, P) in points.iter().enumerate() {
+            let mut e = Scalar::one();
+            for j in 0..n {
+                e = &e * if (i >> j) & 1 == 1 { &self.f[j] } else { &f0[j] };
+            }
+            total = &total + &(P * &e);
+        }
+
+        if eq(&total, &(&gens.H * &self.z_d)) { Ok(()) } else { Err(()) }
+    }
+}
+
+/// Prove that `commitment` opens to `set[index]`.
+pub fn prove_set_membership<R: Rng>(
+    csprng: &mut R,
+    gens: &PedersenGenerators,
+    commitment: &PedersenCommitment,
+    set: &[Scalar],
+    index: usize,
+    opening: &Opening,
+) -> Result<OneOfManyProof, ()> {
+    let points = set_points(gens, commitment, set);
+    OneOfManyProof::create(csprng, gens, &points, index, &opening.blinding)
+}
+
+/// Verify that `commitment` opens to some member of `set`.
+pub fn verify_set_membership(
+    gens: &PedersenGenerators,
+    commitment: &PedersenCommitment,
+    set: &[Scalar],
+    proof: &OneOfManyProof,
+) -> Result<(),()> {
+    proof.verify(gens, &set_points(gens, commitment, set))
+}
+
+/// The points `C - G*v_i`, one of which is `H*r` if `C` opens to a
+/// member of the set.
+fn set_points(gens: &PedersenGenerators, commitment: &PedersenCommitment, set: &[Scalar]) -> Vec<DecafPoint> {
+    set.iter().map(|v| &commitment.0 - &(&gens.G * v)).collect()
+}
+
+/// The generators used for key-list membership: keys are
+/// "commitments to zero" with the basepoint as the blinding
+/// generator.
+fn key_generators() -> PedersenGenerators {
+    let gens = PedersenGenerators::default();
+    PedersenGenerators{ G: gens.H, H: dalek_constants::DECAF_ED25519_BASEPOINT }
+}
+
+/// Prove that `G*secret` is one of `keys`, without revealing which.
+///
+/// Returns `Err(())` if it is not.
+pub fn prove_key_membership<R: Rng>(
+    csprng: &mut R,
+    keys: &[DecafPoint],
+    secret: &Scalar,
+) -> Result<OneOfManyProof, ()> {
+    let public = (&dalek_constants::DECAF_ED25519_BASEPOINT * secret).compress();
+    let index = keys.iter().position(|key| key.compress() == public).ok_or(())?;
+    OneOfManyProof::create(csprng, &key_generators(), keys, index, secret)
+}
+
+/// Verify that the prover knows the secret key of one of `keys`.
+pub fn verify_key_membership(keys: &[DecafPoint], proof: &OneOfManyProof) -> Result<(),()> {
+    proof.verify(&key_generators(), keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn set_membership() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+        let set: Vec<Scalar> = (0..5).map(|i| Scalar::from_u64(10 * i)).collect();
+
+        for index in 0..set.len() {
+            let opening = Opening::random(&mut csprng, set[index]);
+            let C = gens.commit(&opening);
+            let proof = prove_set_membership(&mut csprng, &gens, &C, &set, index, &opening).unwrap();
+            assert!(verify_set_membership(&gens, &C, &set, &proof).is_ok());
+        }
+
+        let opening = Opening::random(&mut csprng, Scalar::from_u64(15));
+        let C = gens.commit(&opening);
+        let proof = prove_set_membership(&mut csprng, &gens, &C, &set, 1, &opening).unwrap();
+        assert!(verify_set_membership(&gens, &C, &set, &proof).is_err());
+    }
+
+    #[test]
+    fn key_membership() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let secrets: Vec<Scalar> = (0..8).map(|_| Scalar::random(&mut csprng)).collect();
+        let keys: Vec<DecafPoint> = secrets.iter().map(|x| G * x).collect();
+
+        let proof = prove_key_membership(&mut csprng, &keys, &secrets[6]).unwrap();
+        assert!(verify_key_membership(&keys, &proof).is_ok());
+        assert!(verify_key_membership(&keys[..6], &proof).is_err());
+
+        assert!(prove_key_membership(&mut csprng, &keys[..6], &secrets[6]).is_err());
+    }
+}
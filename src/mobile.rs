@@ -0,0 +1,83 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Errors of the Kotlin and Swift functions exported by
+//! `create_nipk_uniffi!`, with the `mobile` feature.
+//!
+//! The bindings are generated with UniFFI.  This crate sets up its
+//! own UniFFI scaffolding, for `ZkpError`; the crate invoking
+//! `create_nipk_uniffi!` must depend on `uniffi` itself and call
+//! `uniffi::setup_scaffolding!()` at its root, and `uniffi-bindgen`
+//! then generates the Kotlin or Swift for both.
+
+use std::{error, fmt};
+
+/// Why a call to a function exported by `create_nipk_uniffi!` failed,
+/// as for the codes in `zkp::ffi`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, uniffi::Error)]
+pub enum ZkpError {
+    /// An input had the wrong length or an invalid encoding.
+    Encoding,
+    /// The proof did not verify.
+    Verification,
+    /// The operating system's random number generator was
+    /// unavailable.
+    Rng,
+}
+
+impl fmt::Display for ZkpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(error::Error::description(self))
+    }
+}
+
+impl error::Error for ZkpError {
+    fn description(&self) -> &str {
+        match *self {
+            ZkpError::Encoding => "invalid encoding",
+            ZkpError::Verification => "proof did not verify",
+            ZkpError::Rng => "OS random number generator unavailable",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+    create_nipk_uniffi!{dlog, zkp_test_dlog_create, zkp_test_dlog_verify}
+
+    #[test]
+    fn create_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+
+        let mut publics = Vec::new();
+        publics.extend_from_slice(A.compress().as_bytes());
+        publics.extend_from_slice(G.compress().as_bytes());
+        let secrets = x.as_bytes().to_vec();
+
+        let mut proof = zkp_test_dlog_create(publics.clone(), secrets.clone()).unwrap();
+        assert_eq!(proof.len(), 64);
+        assert_eq!(zkp_test_dlog_verify(proof.clone(), publics.clone()), Ok(()));
+
+        proof[40] ^= 1;
+        assert_eq!(zkp_test_dlog_verify(proof.clone(), publics.clone()), Err(ZkpError::Verification));
+        assert_eq!(zkp_test_dlog_verify(proof, publics[..32].to_vec()), Err(ZkpError::Encoding));
+        assert_eq!(zkp_test_dlog_create(publics, secrets[1..].to_vec()), Err(ZkpError::Encoding));
+    }
+}
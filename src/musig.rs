@@ -0,0 +1,266 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! MuSig2-style `n`-of-`n` joint proofs of knowledge of a discrete
+//! logarithm.
+//!
+//! Each prover `i` holds its own secret `x_i` with public key
+//! `X_i = G*x_i`.  The keys are aggregated into
+//! `X = sum_i a_i*X_i`, where `a_i = H(L, X_i)` and `L` commits to
+//! the whole key list, and the provers jointly produce a single
+//! normal `Proof` for the statement `X = (G * x)`:
+//!
+//! 1. each prover calls `commit` and publishes a `NonceCommitment`
+//!    with *two* nonce points `(R_i1, R_i2)`;
+//! 2. given all commitments, each prover computes the aggregate
+//!    nonces `R_1 = sum_i R_i1`, `R_2 = sum_i R_i2`, the binding
+//!    factor `b = H(X, R_1, R_2)`, the joint commitment
+//!    `R = R_1 + b*R_2`, the challenge `c`, and its `PartialResponse`
+//!    `s_i = r_i1 + b*r_i2 + c*a_i*x_i`;
+//! 3. anyone can check the partial responses and sum them into a
+//!    `Proof`.
+//!
+//! Using two nonces bound together by `b` is what makes the protocol
+//! safe to run concurrently: with a single nonce, an attacker opening
+//! many sessions can use Wagner's algorithm (the ROS attack) to forge
+//! a proof.  A `NonceState` is consumed when it is used, and must
+//! never be reused or copied.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+create_nipk!{dlog, (x), (X, G) : X = (G * x) }
+
+/// A joint proof of knowledge of the discrete log of the aggregate key.
+pub use self::dlog::Proof;
+
+/// One prover's key pair `(x_i, X_i = G*x_i)`.
+pub struct Prover {
+    secret: Scalar,
+    public: DecafPoint,
+}
+
+/// A prover's first-round message `(R_i1, R_i2)`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub R1: DecafPoint,
+    pub R2: DecafPoint,
+}
+
+/// A prover's secret nonces `(r_i1, r_i2)`, consumed by `respond`.
+pub struct NonceState {
+    r1: Scalar,
+    r2: Scalar,
+}
+
+/// A prover's second-round message `s_i`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PartialResponse(pub Scalar);
+
+fn key_list_hash(keys: &[DecafPoint]) -> Sha512 {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp musig key list");
+    for key in keys {
+        hash.input(key.compress().as_bytes());
+    }
+    hash
+}
+
+/// The key aggregation coefficients `a_i = H(L, X_i)`.
+fn key_coefficients(keys: &[DecafPoint]) -> Vec<Scalar> {
+    let L = Scalar::from_hash(key_list_hash(keys));
+    keys.iter().map(|key| {
+        let mut hash = Sha512::default();
+        hash.input(b"zkp musig key coefficient");
+        hash.input(L.as_bytes());
+        hash.input(key.compress().as_bytes());
+        Scalar::from_hash(hash)
+    }).collect()
+}
+
+/// Aggregate the provers' keys into the key `X = sum_i a_i*X_i`.
+///
+/// Returns `Err(())` if there are no keys.
+pub fn aggregate_keys(keys: &[DecafPoint]) -> Result<DecafPoint, ()> {
+    if keys.is_empty() {
+        return Err(());
+    }
+    let coefficients = key_coefficients(keys);
+    let mut X = &keys[0] * &coefficients[0];
+    for (key, a) in keys.iter().zip(coefficients.iter()).skip(1) {
+        X = &X + &(key * a);
+    }
+    Ok(X)
+}
+
+/// The data every prover derives from the keys and commitments.
+struct SessionContext {
+    coefficients: Vec<Scalar>,
+    binding: Scalar,
+    challenge: Scalar,
+}
+
+impl SessionContext {
+    fn new(keys: &[DecafPoint], commitments: &[NonceCommitment]) -> Result<SessionContext, ()> {
+        if commitments.len() != keys.len() {
+            return Err(());
+        }
+        let key = aggregate_keys(keys)?;
+        let coefficients = key_coefficients(keys);
+
+        let mut R1 = commitments[0].R1;
+        let mut R2 = commitments[0].R2;
+        for c in &commitments[1..] {
+            R1 = &R1 + &c.R1;
+            R2 = &R2 + &c.R2;
+        }
+
+        let mut hash = Sha512::default();
+        hash.input(b"zkp musig nonce binding");
+        hash.input(key.compress().as_bytes());
+        hash.input(R1.compress().as_bytes());
+        hash.input(R2.compress().as_bytes());
+        let binding = Scalar::from_hash(hash);
+
+        let R = &R1 + &(&R2 * &binding);
+        let challenge = dlog::challenge(
+            dlog::Publics{ X: &key, G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+            &[R],
+        );
+
+        Ok(SessionContext{
+            coefficients: coefficients,
+            binding: binding,
+            challenge: challenge,
+        })
+    }
+}
+
+impl Prover {
+    /// Generate a fresh prover key.
+    pub fn generate<R: Rng>(csprng: &mut R) -> Prover {
+        Prover::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct a prover from an existing secret scalar.
+    pub fn from_secret(secret: Scalar) -> Prover {
+        let public = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        Prover{ secret: secret, public: public }
+    }
+
+    /// This prover's public key `X_i = G*x_i`.
+    pub fn public_key(&self) -> DecafPoint {
+        self.public
+    }
+
+    /// Generate this prover's first-round nonces.
+    pub fn commit<R: Rng>(&self, csprng: &mut R) -> (NonceState, NonceCommitment) {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let r1 = Scalar::random(csprng);
+        let r2 = Scalar::random(csprng);
+        (NonceState{ r1: r1, r2: r2 }, NonceCommitment{ R1: G * &r1, R2: G * &r2 })
+    }
+}
+
+impl NonceState {
+    /// Compute this prover's partial response.
+    ///
+    /// `commitments[i]` must be the commitment of the owner of
+    /// `keys[i]`.  Returns `Err(())` if the lengths differ or this
+    /// prover's key is not in `keys`.
+    pub fn respond(
+        self,
+        prover: &Prover,
+        keys: &[DecafPoint],
+        commitments: &[NonceCommitment],
+    ) -> Result<PartialResponse, ()> {
+        let context = SessionContext::new(keys, commitments)?;
+        let public = prover.public.compress();
+        let i = keys.iter().position(|key| key.compress() == public).ok_or(())?;
+
+        let nonce = Scalar::multiply_add(&context.binding, &self.r2, &self.r1);
+        let c_a = &context.challenge * &context.coefficients[i];
+        Ok(PartialResponse(Scalar::multiply_add(&c_a, &prover.secret, &nonce)))
+    }
+}
+
+/// Check each prover's partial response and sum them into a `Proof`
+/// for the aggregate key.
+///
+/// `commitments[i]` and `responses[i]` must belong to the owner of
+/// `keys[i]`.
+pub fn aggregate(
+    keys: &[DecafPoint],
+    commitments: &[NonceCommitment],
+    responses: &[PartialResponse],
+) -> Result<Proof, ()> {
+    let context = SessionContext::new(keys, commitments)?;
+    if responses.len() != keys.len() {
+        return Err(());
+    }
+    let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+    let mut s = Scalar::zero();
+    for (i, ((key, commitment), response)) in
+        keys.iter().zip(commitments.iter()).zip(responses.iter()).enumerate()
+    {
+        // G*s_i == R_i1 + b*R_i2 + X_i*(c*a_i)
+        let c_a = &context.challenge * &context.coefficients[i];
+        let expected = &(&commitment.R1 + &(&commitment.R2 * &context.binding)) + &(key * &c_a);
+        if (G * &response.0).compress() != expected.compress() {
+            return Err(());
+        }
+        s = &s + &response.0;
+    }
+
+    Ok(Proof::from_parts(context.challenge, dlog::Responses::from_array([s])))
+}
+
+/// Verify a joint proof against the provers' keys.
+pub fn verify(keys: &[DecafPoint], proof: &Proof) -> Result<(),()> {
+    let key = aggregate_keys(keys)?;
+    proof.verify(dlog::Publics{ X: &key, G: &dalek_constants::DECAF_ED25519_BASEPOINT })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn three_provers() {
+        let mut csprng = OsRng::new().unwrap();
+        let provers: Vec<Prover> = (0..3).map(|_| Prover::generate(&mut csprng)).collect();
+        let keys: Vec<DecafPoint> = provers.iter().map(|p| p.public_key()).collect();
+
+        let (states, commitments): (Vec<_>, Vec<_>)
+            = provers.iter().map(|p| p.commit(&mut csprng)).unzip();
+        let mut responses: Vec<PartialResponse> = states.into_iter().zip(provers.iter())
+            .map(|(state, prover)| state.respond(prover, &keys, &commitments).unwrap())
+            .collect();
+
+        let proof = aggregate(&keys, &commitments, &responses).unwrap();
+        assert!(verify(&keys, &proof).is_ok());
+        assert!(verify(&keys[..2], &proof).is_err());
+
+        responses[1] = PartialResponse(Scalar::random(&mut csprng));
+        assert!(aggregate(&keys, &commitments, &responses).is_err());
+    }
+
+    #[test]
+    fn no_provers() {
+        assert!(aggregate_keys(&[]).is_err());
+        assert!(aggregate(&[], &[], &[]).is_err());
+    }
+}
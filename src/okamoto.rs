@@ -0,0 +1,104 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Okamoto's proof of knowledge of a representation `C = G*x + H*r`.
+//!
+//! This is the statement
+//!
+//! ```rust,ignore
+//! create_nipk!{representation, (x, r), (C, G, H) : C = (G * x + H * r) }
+//! ```
+//!
+//! over arbitrary generators `G` and `H`, so it is also a proof of
+//! knowledge of the opening of a Pedersen commitment; the opening
+//! proofs in `zkp::pedersen` are these proofs.  Defining it once here
+//! fixes the order of the secrets and publics, and so the encodings of
+//! proofs and their challenges.
+//!
+//! The `Proof` has the usual `create` and `verify` methods, taking
+//! `Publics{C, G, H}` and `Secrets{x, r}`.  For many commitments under
+//! the same generators, `create_batch` and `verify_batch` below avoid
+//! recompressing the generators for each proof.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+create_nipk!{representation, (x, r), (C, G, H) : C = (G * x + H * r) }
+
+/// A proof of knowledge of `x, r` with `C = G*x + H*r`.
+pub use self::representation::{Proof, Publics, PublicsOwned, Secrets, SecretsOwned};
+
+/// Prove knowledge of a representation `openings[i] = (x, r)` of each
+/// `commitments[i]`, under the generators `G` and `H`.
+///
+/// Returns `Err(())` if the slices have different lengths.
+pub fn create_batch<R: Rng>(
+    csprng: &mut R,
+    G: &DecafPoint,
+    H: &DecafPoint,
+    commitments: &[DecafPoint],
+    openings: &[(Scalar, Scalar)],
+) -> Result<Vec<Proof>, ()> {
+    let publics: Vec<Publics> = commitments.iter().map(|C| {
+        Publics{ C: C, G: G, H: H }
+    }).collect();
+    let secrets: Vec<Secrets> = openings.iter().map(|&(ref x, ref r)| {
+        Secrets{ x: x, r: r }
+    }).collect();
+    Proof::create_batch(csprng, &publics, &secrets)
+}
+
+/// Verify a proof for each of `commitments`, under the generators `G`
+/// and `H`.
+///
+/// Returns `Err(())` if any proof fails, or if the slices have
+/// different lengths.
+pub fn verify_batch(
+    G: &DecafPoint,
+    H: &DecafPoint,
+    commitments: &[DecafPoint],
+    proofs: &[Proof],
+) -> Result<(),()> {
+    let publics: Vec<Publics> = commitments.iter().map(|C| {
+        Publics{ C: C, G: G, H: H }
+    }).collect();
+    Proof::verify_batch(proofs, &publics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use rand::OsRng;
+
+    #[test]
+    fn batch() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::random(&mut csprng);
+
+        let openings: Vec<(Scalar, Scalar)> = (0..4).map(|_| {
+            (Scalar::random(&mut csprng), Scalar::random(&mut csprng))
+        }).collect();
+        let mut commitments: Vec<DecafPoint> = openings.iter().map(|&(ref x, ref r)| {
+            &(G * x) + &(&H * r)
+        }).collect();
+
+        let proofs = create_batch(&mut csprng, G, &H, &commitments, &openings).unwrap();
+        assert!(verify_batch(G, &H, &commitments, &proofs).is_ok());
+        assert!(proofs[1].verify(Publics{ C: &commitments[1], G: G, H: &H }).is_ok());
+        assert!(verify_batch(G, &H, &commitments[1..], &proofs).is_err());
+
+        commitments[2] = &commitments[2] + G;
+        assert!(verify_batch(G, &H, &commitments, &proofs).is_err());
+    }
+}
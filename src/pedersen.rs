@@ -0,0 +1,323 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Pedersen commitments `C = G*m + H*r` and proofs about their
+//! openings.
+//!
+//! The generators are public fields of `PedersenGenerators`, so they
+//! can be passed straight into the `Publics` of a user-defined
+//! `create_nipk!` statement.  For the common cases this module also
+//! provides ready-made proofs of knowledge of an opening, of equality
+//! of two committed values (under the same generators, or under two
+//! different sets of generators), and of a linear relation between
+//! committed values.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::Sha512;
+
+use generators;
+use okamoto;
+use protocols;
+
+create_nipk!{equality, (m, r1, r2), (C1, C2, G, H)
+             : C1 = (G * m + H * r1), C2 = (G * m + H * r2) }
+create_nipk!{zero, (r), (D, H) : D = (H * r) }
+
+/// A proof of knowledge of an opening of a commitment.
+pub use okamoto::Proof as OpeningProof;
+/// A proof that two commitments open to the same value.
+pub use self::equality::Proof as EqualityProof;
+/// A proof that two commitments, under different generators, open to
+/// the same value.
+pub use protocols::equality::Proof as CrossEqualityProof;
+/// A proof that a linear combination of committed values is zero.
+pub use self::zero::Proof as LinearRelationProof;
+
+/// The generators `G` (for values) and `H` (for blinding factors).
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PedersenGenerators {
+    pub G: DecafPoint,
+    pub H: DecafPoint,
+}
+
+/// A commitment `C = G*m + H*r`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PedersenCommitment(pub DecafPoint);
+
+/// The opening `(m, r)` of a commitment.
+#[derive(Copy, Clone)]
+pub struct Opening {
+    pub value: Scalar,
+    pub blinding: Scalar,
+}
+
+impl Opening {
+    /// Create an opening of `value` with a random blinding factor.
+    pub fn random<R: Rng>(csprng: &mut R, value: Scalar) -> Opening {
+        Opening{ value: value, blinding: Scalar::random(csprng) }
+    }
+}
+
+impl Default for PedersenGenerators {
+    /// Uses the Decaf basepoint for `G`, and the hash of its encoding
+    /// for `H`, so that nobody knows the discrete log of `H` to base
+    /// `G`.
+    fn default() -> PedersenGenerators {
+        let G = dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::hash_from_bytes::<Sha512>(G.compress().as_bytes());
+        PedersenGenerators{ G: G, H: H }
+    }
+}
+
+/// Compute `sum_i coefficients[i] * points[i]`.
+fn linear_combination(coefficients: &[Scalar], points: &[DecafPoint]) -> DecafPoint {
+    let mut sum = &points[0] * &coefficients[0];
+    for (a, P) in coefficients.iter().zip(points.iter()).skip(1) {
+        sum = &sum + &(P * a);
+    }
+    sum
+}
+
+impl PedersenGenerators {
+    /// Derive both generators from `label` with
+    /// `zkp::generators::derive`, for protocols which should not share
+    /// `H` with the default generators.
+    pub fn derive(label: &[u8]) -> PedersenGenerators {
+        let gens = generators::derive(label, 2);
+        PedersenGenerators{ G: gens[0], H: gens[1] }
+    }
+
+    /// Commit to the given opening.
+    pub fn commit(&self, opening: &Opening) -> PedersenCommitment {
+        PedersenCommitment(&(&self.G * &opening.value) + &(&self.H * &opening.blinding))
+    }
+
+    /// Prove knowledge of the `opening` of `commitment`.
+    pub fn prove_opening<R: Rng>(
+        &self,
+        csprng: &mut R,
+        commitment: &PedersenCommitment,
+        opening: &Opening,
+    ) -> OpeningProof {
+        let publics = okamoto::Publics{ C: &commitment.0, G: &self.G, H: &self.H };
+        let secrets = okamoto::Secrets{ x: &opening.value, r: &opening.blinding };
+        OpeningProof::create(csprng, publics, secrets)
+    }
+
+    /// Verify a proof of knowledge of an opening of `commitment`.
+    pub fn verify_opening(
+        &self,
+        commitment: &PedersenCommitment,
+        proof: &OpeningProof,
+    ) -> Result<(),()> {
+        proof.verify(okamoto::Publics{ C: &commitment.0, G: &self.G, H: &self.H })
+    }
+
+    /// Prove that `C1` and `C2` commit to the same value.
+    ///
+    /// The openings must have equal `value`s, or the resulting proof
+    /// will not verify.
+    pub fn prove_equality<R: Rng>(
+        &self,
+        csprng: &mut R,
+        C1: &PedersenCommitment,
+        opening1: &Opening,
+        C2: &PedersenCommitment,
+        opening2: &Opening,
+    ) -> EqualityProof {
+        let publics = equality::Publics{ C1: &C1.0, C2: &C2.0, G: &self.G, H: &self.H };
+        let secrets = equality::Secrets{
+            m: &opening1.value,
+            r1: &opening1.blinding,
+            r2: &opening2.blinding,
+        };
+        EqualityProof::create(csprng, publics, secrets)
+    }
+
+    /// Verify a proof that `C1` and `C2` commit to the same value.
+    pub fn verify_equality(
+        &self,
+        C1: &PedersenCommitment,
+        C2: &PedersenCommitment,
+        proof: &EqualityProof,
+    ) -> Result<(),()> {
+        proof.verify(equality::Publics{ C1: &C1.0, C2: &C2.0, G: &self.G, H: &self.H })
+    }
+
+    /// Prove that `C1`, a commitment under these generators, and `C2`,
+    /// a commitment under `other`, commit to the same value.
+    ///
+    /// This is the `zkp::protocols::equality` statement, with `G1, H1`
+    /// taken from `self` and `G2, H2` from `other`.  The openings must
+    /// have equal `value`s, or the resulting proof will not verify.
+    pub fn prove_equality_across<R: Rng>(
+        &self,
+        csprng: &mut R,
+        C1: &PedersenCommitment,
+        opening1: &Opening,
+        other: &PedersenGenerators,
+        C2: &PedersenCommitment,
+        opening2: &Opening,
+    ) -> CrossEqualityProof {
+        let publics = protocols::equality::Publics{
+            C1: &C1.0, C2: &C2.0, G1: &self.G, H1: &self.H, G2: &other.G, H2: &other.H,
+        };
+        let secrets = protocols::equality::Secrets{
+            m: &opening1.value,
+            r1: &opening1.blinding,
+            r2: &opening2.blinding,
+        };
+        protocols::equality::prove(csprng, publics, secrets)
+    }
+
+    /// Verify a proof that `C1`, under these generators, and `C2`,
+    /// under `other`, commit to the same value.
+    pub fn verify_equality_across(
+        &self,
+        C1: &PedersenCommitment,
+        other: &PedersenGenerators,
+        C2: &PedersenCommitment,
+        proof: &CrossEqualityProof,
+    ) -> Result<(),()> {
+        let publics = protocols::equality::Publics{
+            C1: &C1.0, C2: &C2.0, G1: &self.G, H1: &self.H, G2: &other.G, H2: &other.H,
+        };
+        protocols::equality::verify(proof, publics)
+    }
+
+    /// Prove that `sum_i coefficients[i] * m_i = 0`, where `m_i` is
+    /// the value committed to by `commitments[i]`.
+    ///
+    /// For instance, the coefficients `(1, 1, -1)` prove that
+    /// `m_1 + m_2 = m_3`.  This works by proving that the combined
+    /// commitment `sum_i coefficients[i] * C_i` is of the form `H*r`.
+    ///
+    /// Returns `Err(())` if the slices have different or zero lengths.
+    pub fn prove_linear_relation<R: Rng>(
+        &self,
+        csprng: &mut R,
+        coefficients: &[Scalar],
+        commitments: &[PedersenCommitment],
+        openings: &[Opening],
+    ) -> Result<LinearRelationProof, ()> {
+        let n = coefficients.len();
+        if n == 0 || commitments.len() != n || openings.len() != n {
+            return Err(());
+        }
+        let points: Vec<DecafPoint> = commitments.iter().map(|C| C.0).collect();
+        let blindings: Vec<Scalar> = openings.iter().map(|o| o.blinding).collect();
+
+        let D = linear_combination(coefficients, &points);
+        let mut r = Scalar::zero();
+        for (a, r_i) in coefficients.iter().zip(blindings.iter()) {
+            r = Scalar::multiply_add(a, r_i, &r);
+        }
+
+        let publics = zero::Publics{ D: &D, H: &self.H };
+        let secrets = zero::Secrets{ r: &r };
+        Ok(LinearRelationProof::create(csprng, publics, secrets))
+    }
+
+    /// Verify a proof that `sum_i coefficients[i] * m_i = 0`.
+    pub fn verify_linear_relation(
+        &self,
+        coefficients: &[Scalar],
+        commitments: &[PedersenCommitment],
+        proof: &LinearRelationProof,
+    ) -> Result<(),()> {
+        let n = coefficients.len();
+        if n == 0 || commitments.len() != n {
+            return Err(());
+        }
+        let points: Vec<DecafPoint> = commitments.iter().map(|C| C.0).collect();
+        let D = linear_combination(coefficients, &points);
+        proof.verify(zero::Publics{ D: &D, H: &self.H })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn opening_and_equality() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+
+        let o1 = Opening::random(&mut csprng, Scalar::from_u64(7));
+        let o2 = Opening::random(&mut csprng, Scalar::from_u64(7));
+        let o3 = Opening::random(&mut csprng, Scalar::from_u64(8));
+        let C1 = gens.commit(&o1);
+        let C2 = gens.commit(&o2);
+        let C3 = gens.commit(&o3);
+
+        let proof = gens.prove_opening(&mut csprng, &C1, &o1);
+        assert!(gens.verify_opening(&C1, &proof).is_ok());
+        assert!(gens.verify_opening(&C2, &proof).is_err());
+
+        let proof = gens.prove_equality(&mut csprng, &C1, &o1, &C2, &o2);
+        assert!(gens.verify_equality(&C1, &C2, &proof).is_ok());
+
+        let proof = gens.prove_equality(&mut csprng, &C1, &o1, &C3, &o3);
+        assert!(gens.verify_equality(&C1, &C3, &proof).is_err());
+    }
+
+    #[test]
+    fn equality_across() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens1 = PedersenGenerators::default();
+        let gens2 = PedersenGenerators{
+            G: DecafPoint::random(&mut csprng),
+            H: DecafPoint::random(&mut csprng),
+        };
+
+        let o1 = Opening::random(&mut csprng, Scalar::from_u64(7));
+        let o2 = Opening::random(&mut csprng, Scalar::from_u64(7));
+        let o3 = Opening::random(&mut csprng, Scalar::from_u64(8));
+        let C1 = gens1.commit(&o1);
+        let C2 = gens2.commit(&o2);
+        let C3 = gens2.commit(&o3);
+
+        let proof = gens1.prove_equality_across(&mut csprng, &C1, &o1, &gens2, &C2, &o2);
+        assert!(gens1.verify_equality_across(&C1, &gens2, &C2, &proof).is_ok());
+        assert!(gens2.verify_equality_across(&C1, &gens1, &C2, &proof).is_err());
+
+        let proof = gens1.prove_equality_across(&mut csprng, &C1, &o1, &gens2, &C3, &o3);
+        assert!(gens1.verify_equality_across(&C1, &gens2, &C3, &proof).is_err());
+    }
+
+    #[test]
+    fn linear_relation() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+
+        let openings = [
+            Opening::random(&mut csprng, Scalar::from_u64(2)),
+            Opening::random(&mut csprng, Scalar::from_u64(3)),
+            Opening::random(&mut csprng, Scalar::from_u64(5)),
+        ];
+        let commitments: Vec<PedersenCommitment>
+            = openings.iter().map(|o| gens.commit(o)).collect();
+        let coefficients = [Scalar::one(), Scalar::one(), -&Scalar::one()];
+
+        let proof = gens.prove_linear_relation(
+            &mut csprng, &coefficients, &commitments, &openings,
+        ).unwrap();
+        assert!(gens.verify_linear_relation(&coefficients, &commitments, &proof).is_ok());
+
+        let wrong = [Scalar::one(), Scalar::one(), Scalar::one()];
+        assert!(gens.verify_linear_relation(&wrong, &commitments, &proof).is_err());
+    }
+}
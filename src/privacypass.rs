@@ -0,0 +1,523 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Privacy Pass tokens in the Decaf group.
+//!
+//! The issuer has a key `k`, with public key `K = G*k`.  Issuance is
+//! one round trip:
+//!
+//! 1. the client picks a random seed `t` for each token, hashes it to
+//!    the point `T = H(t)`, and sends the `BlindedTokens` `M = T*r`, for
+//!    a fresh random `r` per token;
+//! 2. the issuer sends the `SignedTokens` `Z = M*k`, with one
+//!    `zkp::batch_dleq` proof that every `Z` was computed with the key
+//!    of `K`;
+//! 3. the client checks the proof against its own `M`, and unblinds
+//!    each `Z` to `N = Z*(1/r) = T*k`.
+//!
+//! The issuer cannot link a token to its issuance, since `M` is
+//! uniformly random, and the proof keeps it from tagging clients with
+//! a key of their own.  To redeem a token, the client sends its seed
+//! `t` with a MAC of the request it is attached to,
+//!
+//! ```text
+//! HMAC-SHA512(key, binding)[..32],  key = SHA-512("zkp privacypass key" || t || N)
+//! ```
+//!
+//! which the issuer recomputes from `t` and `k`.  A `SpentTokens` set
+//! rejects a seed redeemed twice.
+//!
+//! As in `zkp::blind_schnorr`, each side's state is a type whose
+//! methods consume it, so that pending tokens are unblinded once, and a
+//! token can only be redeemed once.
+//!
+//! # Private metadata
+//!
+//! A `MetadataKey` is a pair of issuer keys `(k_0, k_1)`, which issues
+//! a batch with the key `k_b` to hide one bit `b`, such as a suspicion
+//! flag, in its tokens.  Instead of a batch DLEQ proof, the issuer
+//! proves the `zkp::compose` composition
+//!
+//! ```text
+//! AND_i ( (K_0 = G*k and Z_i = M_i*k) OR (K_1 = G*k and Z_i = M_i*k) )
+//! ```
+//!
+//! for its public keys `(K_0, K_1)`, with the context
+//! `"zkp privacypass metadata"`, so the client learns that its tokens
+//! are valid for one of the keys but not which.  Clients blind tokens
+//! for such an issuer with `PendingTokens::blind_for_metadata`, and
+//! redeem them as before; the issuer learns the bit when it redeems
+//! them, with `MetadataKey::verify_redemption`.  The issuer's proof
+//! depends on the bit, so `MetadataKey::issue` is compiled out under
+//! the `ct-audit` feature.
+
+use std::collections::HashSet;
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+
+use batch_dleq::{self, BatchDleqProof};
+use compose::{Composition, CompositeProof};
+
+create_nipk!{metadata_dleq, (k), (K, Z, G, M) : K = (G * k), Z = (M * k) }
+
+/// The context of the proofs of `MetadataKey::issue`.
+const METADATA_CONTEXT: &'static [u8] = b"zkp privacypass metadata";
+
+/// The issuer's key `(k, K = G*k)`.
+pub struct IssuerKey {
+    secret: Scalar,
+    public: DecafPoint,
+}
+
+/// The client's first message, the blinded tokens `M = T*r`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlindedTokens(pub Vec<DecafPoint>);
+
+/// The issuer's response, `Z = M*k` with a proof that `k` is the key
+/// of `K`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignedTokens {
+    pub points: Vec<DecafPoint>,
+    pub proof: BatchDleqProof,
+}
+
+/// A pair of issuer keys `(k_0, k_1)`, which hides a bit in the
+/// tokens it issues.
+pub struct MetadataKey {
+    keys: [IssuerKey; 2],
+}
+
+/// The public keys `(K_0, K_1)` of a `MetadataKey`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct MetadataPublicKey(pub DecafPoint, pub DecafPoint);
+
+/// The response of a `MetadataKey`, `Z = M*k_b` with a proof that `k_b`
+/// is the key of `K_0` or of `K_1`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MetadataSignedTokens {
+    pub points: Vec<DecafPoint>,
+    pub proof: CompositeProof,
+}
+
+/// The public key tokens are blinded for.
+enum Issuer {
+    Plain(DecafPoint),
+    Metadata(MetadataPublicKey),
+}
+
+/// Client state after sending `BlindedTokens`, waiting for the
+/// issuer's response.
+pub struct PendingTokens {
+    issuer: Issuer,
+    seeds: Vec<[u8; 32]>,
+    blinds: Vec<Scalar>,
+    blinded: Vec<DecafPoint>,
+}
+
+/// An unblinded token, `(t, N = H(t)*k)`.
+pub struct Token {
+    seed: [u8; 32],
+    point: DecafPoint,
+}
+
+/// A token spent on the request `binding`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Redemption {
+    pub seed: [u8; 32],
+    pub mac: [u8; 32],
+}
+
+/// The seeds of the tokens an issuer has redeemed.
+pub struct SpentTokens {
+    seeds: HashSet<[u8; 32]>,
+}
+
+/// The point `T = H(t)` for the seed `t`.
+fn hash_to_point(seed: &[u8; 32]) -> DecafPoint {
+    let mut input = Vec::with_capacity(21 + 32);
+    input.extend_from_slice(b"zkp privacypass token");
+    input.extend_from_slice(seed);
+    DecafPoint::hash_from_bytes::<Sha512>(&input)
+}
+
+fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+    // SHA-512 has 128-byte blocks, so a 64-byte key is padded, not hashed.
+    let mut inner_pad = [0x36u8; 128];
+    let mut outer_pad = [0x5cu8; 128];
+    for (i, byte) in key.iter().enumerate() {
+        inner_pad[i] ^= *byte;
+        outer_pad[i] ^= *byte;
+    }
+    let mut inner = Sha512::default();
+    inner.input(&inner_pad[..]);
+    inner.input(message);
+    let mut outer = Sha512::default();
+    outer.input(&outer_pad[..]);
+    outer.input(&inner.result()[..]);
+    let mut mac = [0u8; 64];
+    mac.copy_from_slice(&outer.result()[..]);
+    mac
+}
+
+/// The MAC of `binding` for the token `(t, N)`.
+fn redemption_mac(seed: &[u8; 32], point: &DecafPoint, binding: &[u8]) -> [u8; 32] {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp privacypass key");
+    hash.input(seed);
+    hash.input(point.compress().as_bytes());
+    let mac = hmac_sha512(&hash.result()[..], binding);
+    let mut truncated = [0u8; 32];
+    truncated.copy_from_slice(&mac[..32]);
+    truncated
+}
+
+impl IssuerKey {
+    /// Generate a fresh issuer key.
+    pub fn generate<R: Rng>(csprng: &mut R) -> IssuerKey {
+        IssuerKey::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct an issuer key from an existing secret scalar.
+    pub fn from_secret(secret: Scalar) -> IssuerKey {
+        let public = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        IssuerKey{ secret: secret, public: public }
+    }
+
+    /// The public key `K = G*k`, which clients check issuance against.
+    pub fn public(&self) -> DecafPoint {
+        self.public
+    }
+
+    /// Sign a batch of blinded tokens.
+    ///
+    /// Returns `Err(())` if the batch is empty.
+    pub fn issue<R: Rng>(&self, csprng: &mut R, blinded: &BlindedTokens) -> Result<SignedTokens,()> {
+        let points: Vec<DecafPoint> = blinded.0.iter().map(|M| M * &self.secret).collect();
+        let proof = batch_dleq::prove(
+            csprng,
+            &dalek_constants::DECAF_ED25519_BASEPOINT,
+            &self.public,
+            &self.secret,
+            &blinded.0,
+            &points,
+        )?;
+        Ok(SignedTokens{ points: points, proof: proof })
+    }
+
+    /// Check the MAC of a redemption for the request `binding`, without
+    /// checking whether the token was already spent.
+    pub fn verify_redemption(&self, redemption: &Redemption, binding: &[u8]) -> Result<(),()> {
+        let point = &hash_to_point(&redemption.seed) * &self.secret;
+        let mac = redemption_mac(&redemption.seed, &point, binding);
+        if bool::from(mac[..].ct_eq(&redemption.mac[..])) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// The composition proved by `MetadataKey::issue`, given the
+/// statements for each token and key, and the index of the known key
+/// (if any).
+fn metadata_policy<'a>(
+    statements: &'a [[metadata_dleq::Publics<'a>; 2]],
+    witnesses: &'a [metadata_dleq::Instance<'a>],
+    bit: usize,
+) -> Composition<'a> {
+    Composition::and(statements.iter().enumerate().map(|(i, pair)| {
+        Composition::or((0..2).map(|j| {
+            if !witnesses.is_empty() && j == bit {
+                Composition::witness(&witnesses[i])
+            } else {
+                Composition::statement(&pair[j])
+            }
+        }).collect())
+    }).collect())
+}
+
+impl MetadataKey {
+    /// Generate a fresh pair of issuer keys.
+    pub fn generate<R: Rng>(csprng: &mut R) -> MetadataKey {
+        MetadataKey{ keys: [IssuerKey::generate(csprng), IssuerKey::generate(csprng)] }
+    }
+
+    pub fn public(&self) -> MetadataPublicKey {
+        MetadataPublicKey(self.keys[0].public, self.keys[1].public)
+    }
+
+    /// Sign a batch of blinded tokens with the key for `bit`.
+    ///
+    /// Returns `Err(())` if the batch is empty.
+    #[cfg(not(feature = "ct-audit"))]
+    pub fn issue<R: Rng>(
+        &self,
+        csprng: &mut R,
+        blinded: &BlindedTokens,
+        bit: bool,
+    ) -> Result<MetadataSignedTokens,()> {
+        if blinded.0.is_empty() {
+            return Err(());
+        }
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let key = &self.keys[bit as usize];
+        let publics = self.public();
+        let points: Vec<DecafPoint> = blinded.0.iter().map(|M| M * &key.secret).collect();
+        let statements: Vec<[metadata_dleq::Publics; 2]> = blinded.0.iter().zip(points.iter())
+            .map(|(M, Z)| [
+                metadata_dleq::Publics{ K: &publics.0, Z: Z, G: G, M: M },
+                metadata_dleq::Publics{ K: &publics.1, Z: Z, G: G, M: M },
+            ])
+            .collect();
+        let witnesses: Vec<metadata_dleq::Instance> = statements.iter()
+            .map(|pair| metadata_dleq::Instance{
+                publics: pair[bit as usize],
+                secrets: metadata_dleq::Secrets{ k: &key.secret },
+            })
+            .collect();
+        let proof = metadata_policy(&statements, &witnesses, bit as usize).prove(csprng, METADATA_CONTEXT)?;
+        Ok(MetadataSignedTokens{ points: points, proof: proof })
+    }
+
+    /// Check the MAC of a redemption for the request `binding`, and
+    /// return the bit of its token.
+    pub fn verify_redemption(&self, redemption: &Redemption, binding: &[u8]) -> Result<bool,()> {
+        let zero = self.keys[0].verify_redemption(redemption, binding).is_ok();
+        let one = self.keys[1].verify_redemption(redemption, binding).is_ok();
+        match (zero, one) {
+            (true, false) => Ok(false),
+            (false, true) => Ok(true),
+            _ => Err(()),
+        }
+    }
+}
+
+impl PendingTokens {
+    /// Blind `n` fresh tokens for the issuer with public key `public`,
+    /// producing the message to send to the issuer.
+    pub fn blind<R: Rng>(csprng: &mut R, public: &DecafPoint, n: usize) -> (PendingTokens, BlindedTokens) {
+        PendingTokens::blind_for(csprng, Issuer::Plain(*public), n)
+    }
+
+    /// Blind `n` fresh tokens for the `MetadataKey` with public keys
+    /// `public`.
+    pub fn blind_for_metadata<R: Rng>(
+        csprng: &mut R,
+        public: &MetadataPublicKey,
+        n: usize,
+    ) -> (PendingTokens, BlindedTokens) {
+        PendingTokens::blind_for(csprng, Issuer::Metadata(*public), n)
+    }
+
+    fn blind_for<R: Rng>(csprng: &mut R, issuer: Issuer, n: usize) -> (PendingTokens, BlindedTokens) {
+        let mut seeds = Vec::with_capacity(n);
+        let mut blinds = Vec::with_capacity(n);
+        let mut blinded = Vec::with_capacity(n);
+        for _ in 0..n {
+            let mut seed = [0u8; 32];
+            csprng.fill_bytes(&mut seed);
+            let r = Scalar::random(csprng);
+            blinded.push(&hash_to_point(&seed) * &r);
+            seeds.push(seed);
+            blinds.push(r);
+        }
+        let pending = PendingTokens{
+            issuer: issuer,
+            seeds: seeds,
+            blinds: blinds,
+            blinded: blinded.clone(),
+        };
+        (pending, BlindedTokens(blinded))
+    }
+
+    /// Check the issuer's proof and unblind the tokens, ending the
+    /// session.
+    ///
+    /// Returns `Err(())` if the response is for a different number of
+    /// tokens, or its proof does not verify against the issuer's
+    /// public key and the tokens this client blinded.
+    ///
+    /// Also returns `Err(())` if the tokens were blinded for a
+    /// `MetadataKey`.
+    pub fn unblind(self, signed: &SignedTokens) -> Result<Vec<Token>,()> {
+        let public = match self.issuer {
+            Issuer::Plain(public) => public,
+            Issuer::Metadata(_) => return Err(()),
+        };
+        batch_dleq::verify(
+            &dalek_constants::DECAF_ED25519_BASEPOINT,
+            &public,
+            &self.blinded,
+            &signed.points,
+            &signed.proof,
+        )?;
+        Ok(self.finish(&signed.points))
+    }
+
+    /// Check the proof of a `MetadataKey` and unblind the tokens,
+    /// ending the session.
+    ///
+    /// Returns `Err(())` as `unblind` does, or if the tokens were not
+    /// blinded for a `MetadataKey`.
+    pub fn unblind_metadata(self, signed: &MetadataSignedTokens) -> Result<Vec<Token>,()> {
+        let public = match self.issuer {
+            Issuer::Metadata(public) => public,
+            Issuer::Plain(_) => return Err(()),
+        };
+        if signed.points.len() != self.blinded.len() {
+            return Err(());
+        }
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let statements: Vec<[metadata_dleq::Publics; 2]> = self.blinded.iter().zip(signed.points.iter())
+            .map(|(M, Z)| [
+                metadata_dleq::Publics{ K: &public.0, Z: Z, G: G, M: M },
+                metadata_dleq::Publics{ K: &public.1, Z: Z, G: G, M: M },
+            ])
+            .collect();
+        metadata_policy(&statements, &[], 0).verify(METADATA_CONTEXT, &signed.proof)?;
+        Ok(self.finish(&signed.points))
+    }
+
+    fn finish(self, points: &[DecafPoint]) -> Vec<Token> {
+        self.seeds.into_iter()
+            .zip(self.blinds.iter())
+            .zip(points.iter())
+            .map(|((seed, r), Z)| Token{ seed: seed, point: Z * &r.invert() })
+            .collect()
+    }
+}
+
+impl Token {
+    /// Spend the token on the request `binding`, such as the method,
+    /// host and path of an HTTP request.
+    pub fn redeem(self, binding: &[u8]) -> Redemption {
+        Redemption{ seed: self.seed, mac: redemption_mac(&self.seed, &self.point, binding) }
+    }
+}
+
+impl SpentTokens {
+    pub fn new() -> SpentTokens {
+        SpentTokens{ seeds: HashSet::new() }
+    }
+
+    /// Check a redemption for the request `binding`, and record its
+    /// token as spent.
+    ///
+    /// Returns `Err(())` if the MAC is invalid, or the token was
+    /// already spent.
+    pub fn redeem(
+        &mut self,
+        key: &IssuerKey,
+        redemption: &Redemption,
+        binding: &[u8],
+    ) -> Result<(),()> {
+        key.verify_redemption(redemption, binding)?;
+        self.spend(redemption)
+    }
+
+    /// Check a redemption of a token issued by a `MetadataKey`, record
+    /// it as spent, and return its bit.
+    pub fn redeem_metadata(
+        &mut self,
+        key: &MetadataKey,
+        redemption: &Redemption,
+        binding: &[u8],
+    ) -> Result<bool,()> {
+        let bit = key.verify_redemption(redemption, binding)?;
+        self.spend(redemption)?;
+        Ok(bit)
+    }
+
+    fn spend(&mut self, redemption: &Redemption) -> Result<(),()> {
+        if self.seeds.insert(redemption.seed) { Ok(()) } else { Err(()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn issue_and_redeem() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = IssuerKey::generate(&mut csprng);
+
+        let (pending, blinded) = PendingTokens::blind(&mut csprng, &key.public(), 3);
+        let signed = key.issue(&mut csprng, &blinded).unwrap();
+        let mut tokens = pending.unblind(&signed).unwrap();
+        assert_eq!(tokens.len(), 3);
+
+        let mut spent = SpentTokens::new();
+        let redemption = tokens.pop().unwrap().redeem(b"GET example.com /");
+        assert!(key.verify_redemption(&redemption, b"GET example.com /other").is_err());
+        assert!(spent.redeem(&key, &redemption, b"GET example.com /").is_ok());
+        assert!(spent.redeem(&key, &redemption, b"GET example.com /").is_err());
+
+        let other = IssuerKey::generate(&mut csprng);
+        let redemption = tokens.pop().unwrap().redeem(b"GET example.com /");
+        assert!(spent.redeem(&other, &redemption, b"GET example.com /").is_err());
+        assert!(spent.redeem(&key, &redemption, b"GET example.com /").is_ok());
+    }
+
+    #[test]
+    fn tagged_issuance() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = IssuerKey::generate(&mut csprng);
+        let tagging = IssuerKey::generate(&mut csprng);
+
+        // Signed with another key than the advertised one.
+        let (pending, blinded) = PendingTokens::blind(&mut csprng, &key.public(), 2);
+        let signed = tagging.issue(&mut csprng, &blinded).unwrap();
+        assert!(pending.unblind(&signed).is_err());
+
+        // Signed for a different batch.
+        let (pending, blinded) = PendingTokens::blind(&mut csprng, &key.public(), 2);
+        let mut signed = key.issue(&mut csprng, &blinded).unwrap();
+        signed.points.pop();
+        assert!(pending.unblind(&signed).is_err());
+
+        assert!(key.issue(&mut csprng, &BlindedTokens(Vec::new())).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "ct-audit"))]
+    fn private_metadata() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = MetadataKey::generate(&mut csprng);
+        let mut spent = SpentTokens::new();
+
+        for &bit in &[false, true] {
+            let (pending, blinded) = PendingTokens::blind_for_metadata(&mut csprng, &key.public(), 2);
+            let signed = key.issue(&mut csprng, &blinded, bit).unwrap();
+            let mut tokens = pending.unblind_metadata(&signed).unwrap();
+            let redemption = tokens.pop().unwrap().redeem(b"POST /login");
+            assert_eq!(spent.redeem_metadata(&key, &redemption, b"POST /login"), Ok(bit));
+            assert!(spent.redeem_metadata(&key, &redemption, b"POST /login").is_err());
+        }
+
+        // Signed with a key outside the advertised pair.
+        let other = MetadataKey::generate(&mut csprng);
+        let (pending, blinded) = PendingTokens::blind_for_metadata(&mut csprng, &key.public(), 2);
+        let signed = other.issue(&mut csprng, &blinded, false).unwrap();
+        assert!(pending.unblind_metadata(&signed).is_err());
+
+        // Metadata responses only unblind metadata requests.
+        let (pending, blinded) = PendingTokens::blind(&mut csprng, &key.public().0, 1);
+        let signed = key.issue(&mut csprng, &blinded, false).unwrap();
+        assert!(pending.unblind_metadata(&signed).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Protocol Buffers encodings of proofs and compressed publics.
+//!
+//! The messages are the same for every statement, and are defined in
+//! `proto/zkp.proto`:
+//!
+//! ```text
+//! message Proof {
+//!   string statement = 1;
+//!   bytes challenge = 2;
+//!   repeated bytes responses = 3;
+//! }
+//!
+//! message CompressedPublics {
+//!   string statement = 1;
+//!   repeated bytes points = 2;
+//! }
+//! ```
+//!
+//! where `statement` is the name of the `create_nipk!` module, the
+//! responses are in the order the secrets were declared, and the
+//! points in the order the publics were declared, as 32 bytes each.
+//! Code generated from that file (e.g. by `prost`) can exchange these
+//! messages with this module.
+//!
+//! With the `proto` feature, each `create_nipk!` module also has
+//! `Proof::to_proto` and `Proof::from_proto`, and
+//! `CompressedPublicsOwned::to_proto` and
+//! `CompressedPublicsOwned::from_proto`, which check the statement name
+//! and the number and encoding of the fields.  Decoding follows the
+//! protobuf rules: fields may come in any order, unknown fields are
+//! skipped, and the last occurrence of a singular field wins.
+
+use group::PrimeGroup;
+
+/// A `zkp.Proof` message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofMessage {
+    pub statement: String,
+    pub challenge: Vec<u8>,
+    pub responses: Vec<Vec<u8>>,
+}
+
+/// A `zkp.CompressedPublics` message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressedPublicsMessage {
+    pub statement: String,
+    pub points: Vec<Vec<u8>>,
+}
+
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+fn put_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn put_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, (field << 3) | LENGTH_DELIMITED);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn get_varint(bytes: &mut &[u8]) -> Result<u64,()> {
+    let mut n = 0u64;
+    for i in 0..10 {
+        let (&byte, rest) = bytes.split_first().ok_or(())?;
+        *bytes = rest;
+        n |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: u64) -> Result<&'a [u8],()> {
+    if n > bytes.len() as u64 {
+        return Err(());
+    }
+    let (field, rest) = bytes.split_at(n as usize);
+    *bytes = rest;
+    Ok(field)
+}
+
+/// Calls `field(number, contents)` for each length-delimited field
+/// of `bytes`, skipping fields of other wire types.
+fn for_each_field<'a, F>(mut bytes: &'a [u8], mut field: F) -> Result<(),()>
+    where F: FnMut(u64, &'a [u8]) -> Result<(),()>
+{
+    while !bytes.is_empty() {
+        let key = get_varint(&mut bytes)?;
+        match key & 7 {
+            VARINT => { get_varint(&mut bytes)?; }
+            FIXED64 => { take(&mut bytes, 8)?; }
+            LENGTH_DELIMITED => {
+                let length = get_varint(&mut bytes)?;
+                field(key >> 3, take(&mut bytes, length)?)?;
+            }
+            FIXED32 => { take(&mut bytes, 4)?; }
+            // Groups are deprecated, and not used by these messages.
+            _ => return Err(()),
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a field holding a compressed point.
+#[doc(hidden)]
+pub fn __compressed_point<P: PrimeGroup>(bytes: &[u8]) -> Result<P::Compressed,()> {
+    if bytes.len() != 32 {
+        return Err(());
+    }
+    let mut encoding = [0u8; 32];
+    encoding.copy_from_slice(bytes);
+    Ok(P::compressed(encoding))
+}
+
+fn string(bytes: &[u8]) -> Result<String,()> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| ())
+}
+
+impl ProofMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_bytes(&mut out, 1, self.statement.as_bytes());
+        put_bytes(&mut out, 2, &self.challenge);
+        for response in &self.responses {
+            put_bytes(&mut out, 3, response);
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<ProofMessage,()> {
+        let mut message = ProofMessage::default();
+        for_each_field(bytes, |number, contents| {
+            match number {
+                1 => message.statement = string(contents)?,
+                2 => message.challenge = contents.to_vec(),
+                3 => message.responses.push(contents.to_vec()),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+impl CompressedPublicsMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_bytes(&mut out, 1, self.statement.as_bytes());
+        for point in &self.points {
+            put_bytes(&mut out, 2, point);
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<CompressedPublicsMessage,()> {
+        let mut message = CompressedPublicsMessage::default();
+        for_each_field(bytes, |number, contents| {
+            match number {
+                1 => message.statement = string(contents)?,
+                2 => message.points.push(contents.to_vec()),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+    #[test]
+    fn round_trip() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+        let publics = dlog::Publics{A: &A, G: G};
+        let proof = dlog::Proof::create(&mut csprng, publics, dlog::Secrets{x: &x});
+
+        let bytes = proof.to_proto();
+        let message = ProofMessage::decode(&bytes).unwrap();
+        assert_eq!(message.statement, "dlog");
+        assert_eq!(message.encode(), bytes);
+        let parsed = dlog::Proof::from_proto(&bytes).unwrap();
+        assert_eq!(parsed.to_bytes(), proof.to_bytes());
+
+        let compressed = dlog::CompressedPublicsOwned{A: A.compress(), G: G.compress()};
+        let parsed = dlog::CompressedPublicsOwned::from_proto(&compressed.to_proto()).unwrap();
+        assert!(proof.verify_compressed(parsed.as_compressed_publics()).is_ok());
+
+        // Unknown fields are skipped, in any position.
+        let mut extended = vec![0x20, 0x96, 0x01, 0x2a, 0x01, 0xff];
+        extended.extend_from_slice(&bytes);
+        assert!(dlog::Proof::from_proto(&extended).is_ok());
+
+        // The statement name and the number of responses are checked.
+        let mut other = message.clone();
+        other.statement = "dleq".to_string();
+        assert!(dlog::Proof::from_proto(&other.encode()).is_err());
+        let mut other = message.clone();
+        other.responses.push(vec![0; 32]);
+        assert!(dlog::Proof::from_proto(&other.encode()).is_err());
+        assert!(dlog::Proof::from_proto(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
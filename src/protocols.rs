@@ -0,0 +1,279 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Common statements, with fixed transcript labels.
+//!
+//! Each submodule defines one statement with `create_nipk!`, and
+//! re-exports its `Proof`, `Publics` and `Secrets`.  The free
+//! functions `prove` and `verify` then make and check proofs in a
+//! `Transcript::new(LABEL)`, so that proofs of different statements,
+//! or made by other libraries using the same statement, cannot be
+//! confused.  Interoperable implementations should use these
+//! functions, rather than `Proof::create` and `Proof::verify`.
+//!
+//! * `dlog`: knowledge of `x` with `A = G*x`;
+//! * `dleq`: Chaum-Pedersen equality of discrete logarithms, `A = G*x`
+//!   and `B = H*x`;
+//! * `opening`: knowledge of an opening `(x, r)` of a Pedersen
+//!   commitment `C = G*x + H*r` (the `zkp::okamoto` statement);
+//! * `equality`: two Pedersen commitments `C1 = G1*m + H1*r1` and
+//!   `C2 = G2*m + H2*r2` to the same value, possibly under different
+//!   generators;
+//! * `dh_triple`: a Diffie-Hellman triple `A = G*a`, `B = G*b`,
+//!   `C = G*a*b`, given `a` (or `b`), bound to a context such as a
+//!   ciphertext.
+//!
+//! The `test_vector` function of each submodule records a labelled
+//! proof as a `zkp::testvectors::TestVector`.  Its transcript starts
+//! with the 64-byte initial state of the labelled transcript, so that
+//! the challenge is still the hash of the transcript.
+
+/// Defines `LABEL`, `prove`, `verify` and `test_vector` for the
+/// statement in scope.
+macro_rules! labelled_protocol {
+    ($name:expr, $label:expr) => {
+        use rand::Rng;
+        use testvectors::TestVector;
+        use transcript::Transcript;
+
+        /// The label of the transcripts in which proofs are made.
+        pub const LABEL: &'static [u8] = $label;
+
+        /// Create a proof in a transcript labelled with `LABEL`.
+        pub fn prove<R: Rng>(csprng: &mut R, publics: Publics, secrets: Secrets) -> Proof {
+            Proof::create_from_transcript(csprng, &mut Transcript::new(LABEL), publics, secrets)
+        }
+
+        /// Verify a proof made by `prove`.
+        pub fn verify(proof: &Proof, publics: Publics) -> Result<(),()> {
+            proof.verify_from_transcript(&mut Transcript::new(LABEL), publics)
+        }
+
+        /// Create a proof with `prove`, and record it as a test vector.
+        pub fn test_vector<R: Rng>(
+            csprng: &mut R,
+            publics: Publics,
+            secrets: Secrets,
+        ) -> TestVector {
+            let proof = prove(csprng, publics, secrets);
+            let transcript = proof.__transcript(Transcript::new(LABEL).__state(), publics);
+            TestVector{
+                name: $name.to_string(),
+                publics: publics.to_bytes(),
+                secrets: secrets.to_bytes(),
+                proof: proof.to_bytes(),
+                transcript: transcript,
+            }
+        }
+    };
+}
+
+/// Knowledge of a discrete logarithm.
+pub mod dlog {
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+    pub use self::dlog::{Proof, Publics, PublicsOwned, Secrets, SecretsOwned};
+
+    labelled_protocol!("dlog", b"zkp protocols dlog");
+}
+
+/// Equality of two discrete logarithms (Chaum-Pedersen).
+pub mod dleq {
+    create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+    pub use self::dleq::{Proof, Publics, PublicsOwned, Secrets, SecretsOwned};
+
+    labelled_protocol!("dleq", b"zkp protocols dleq");
+}
+
+/// Knowledge of an opening of a Pedersen commitment.
+pub mod opening {
+    pub use okamoto::{Proof, Publics, PublicsOwned, Secrets, SecretsOwned};
+
+    labelled_protocol!("opening", b"zkp protocols opening");
+}
+
+/// Equality of the values in two Pedersen commitments.
+pub mod equality {
+    create_nipk!{equality, (m, r1, r2), (C1, C2, G1, H1, G2, H2)
+                 : C1 = (G1 * m + H1 * r1), C2 = (G2 * m + H2 * r2) }
+
+    pub use self::equality::{Proof, Publics, PublicsOwned, Secrets, SecretsOwned};
+
+    labelled_protocol!("equality", b"zkp protocols equality");
+}
+
+/// Diffie-Hellman triples (Chaum-Pedersen), bound to a context.
+///
+/// A proof for the triple `(G, A, B, C)` shows knowledge of `a` with
+/// `A = G*a` and `C = B*a`, in a transcript labelled with `LABEL`
+/// after the context as a message.  Since `(G, B, A, C)` is also a
+/// Diffie-Hellman triple, the holder of `b` proves and verifies with
+/// `triple.swapped()` instead.
+pub mod dh_triple {
+    use curve25519_dalek::decaf::DecafPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::Rng;
+
+    use testvectors::TestVector;
+    use transcript::Transcript;
+
+    create_nipk!{dh_triple, (a), (A, C, G, B) : A = (G * a), C = (B * a) }
+
+    pub use self::dh_triple::{Proof, Publics, PublicsOwned, Secrets, SecretsOwned};
+
+    /// The label of the transcripts in which proofs are made.
+    pub const LABEL: &'static [u8] = b"zkp protocols dh_triple";
+
+    /// A claimed Diffie-Hellman triple `A = G*a`, `B = G*b`, `C = G*a*b`.
+    #[derive(Copy, Clone)]
+    pub struct Triple<'a> {
+        pub G: &'a DecafPoint,
+        pub A: &'a DecafPoint,
+        pub B: &'a DecafPoint,
+        pub C: &'a DecafPoint,
+    }
+
+    impl<'a> Triple<'a> {
+        /// The same triple with `A` and `B` exchanged, for proofs
+        /// knowing `b`.
+        pub fn swapped(&self) -> Triple<'a> {
+            Triple{ G: self.G, A: self.B, B: self.A, C: self.C }
+        }
+
+        pub fn publics(&self) -> Publics<'a> {
+            Publics{ A: self.A, C: self.C, G: self.G, B: self.B }
+        }
+    }
+
+    fn transcript(context: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new(LABEL);
+        transcript.append_message(b"context", context);
+        transcript
+    }
+
+    /// Prove that `triple` is a Diffie-Hellman triple, knowing `a`,
+    /// bound to `context`.
+    pub fn prove<R: Rng>(csprng: &mut R, context: &[u8], triple: Triple, a: &Scalar) -> Proof {
+        Proof::create_from_transcript(csprng, &mut transcript(context), triple.publics(), Secrets{ a: a })
+    }
+
+    /// Verify a proof made by `prove` with the same context.
+    pub fn verify(proof: &Proof, context: &[u8], triple: Triple) -> Result<(),()> {
+        proof.verify_from_transcript(&mut transcript(context), triple.publics())
+    }
+
+    /// Create a proof with `prove`, and record it as a test vector.
+    pub fn test_vector<R: Rng>(
+        csprng: &mut R,
+        context: &[u8],
+        triple: Triple,
+        a: &Scalar,
+    ) -> TestVector {
+        let proof = prove(csprng, context, triple, a);
+        let bytes = proof.__transcript(transcript(context).__state(), triple.publics());
+        TestVector{
+            name: "dh_triple".to_string(),
+            publics: triple.publics().to_bytes(),
+            secrets: Secrets{ a: a }.to_bytes(),
+            proof: proof.to_bytes(),
+            transcript: bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::decaf::DecafPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::{ChaChaRng, OsRng, SeedableRng};
+    use sha2::{Digest, Sha512};
+
+    #[test]
+    fn labelled() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+        let publics = dlog::Publics{A: &A, G: G};
+        let secrets = dlog::Secrets{x: &x};
+
+        let proof = dlog::prove(&mut csprng, publics, secrets);
+        assert!(dlog::verify(&proof, publics).is_ok());
+        assert!(proof.verify(publics).is_err());
+        assert!(dlog::verify(&dlog::Proof::create(&mut csprng, publics, secrets), publics).is_err());
+    }
+
+    #[test]
+    fn test_vectors() {
+        let mut csprng = ChaChaRng::from_seed(&[570]);
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::random(&mut csprng);
+        let G2 = DecafPoint::random(&mut csprng);
+        let H2 = DecafPoint::random(&mut csprng);
+        let x = Scalar::random(&mut csprng);
+        let r = Scalar::random(&mut csprng);
+        let r2 = Scalar::random(&mut csprng);
+        let (A, B) = (G * &x, &H * &x);
+        let C = &A + &(&H * &r);
+        let C2 = &(&G2 * &x) + &(&H2 * &r2);
+
+        let vectors = vec![
+            dlog::test_vector(&mut csprng, dlog::Publics{A: &A, G: G}, dlog::Secrets{x: &x}),
+            dleq::test_vector(&mut csprng, dleq::Publics{A: &A, B: &B, G: G, H: &H}, dleq::Secrets{x: &x}),
+            opening::test_vector(&mut csprng, opening::Publics{C: &C, G: G, H: &H}, opening::Secrets{x: &x, r: &r}),
+            equality::test_vector(
+                &mut csprng,
+                equality::Publics{C1: &C, C2: &C2, G1: G, H1: &H, G2: &G2, H2: &H2},
+                equality::Secrets{m: &x, r1: &r, r2: &r2},
+            ),
+            dh_triple::test_vector(
+                &mut csprng,
+                b"ciphertext",
+                dh_triple::Triple{G: G, A: &A, B: &H, C: &B},
+                &x,
+            ),
+        ];
+        for vector in vectors.iter() {
+            let mut hash = Sha512::default();
+            hash.input(&vector.transcript);
+            assert_eq!(&vector.proof[..32], Scalar::from_hash(hash).as_bytes());
+        }
+        assert_eq!(vectors[3].name, "equality");
+        assert_eq!(vectors[3].transcript.len(), 16 + 17 + 64 + 6 * (17 + 2 + 32) + 2 * (17 + 21 + 32));
+    }
+
+    #[test]
+    fn dh_triples() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let a = Scalar::random(&mut csprng);
+        let b = Scalar::random(&mut csprng);
+        let (A, B) = (G * &a, G * &b);
+        let C = &A * &b;
+        let triple = dh_triple::Triple{G: G, A: &A, B: &B, C: &C};
+
+        let proof = dh_triple::prove(&mut csprng, b"ciphertext 1", triple, &a);
+        assert!(dh_triple::verify(&proof, b"ciphertext 1", triple).is_ok());
+        assert!(dh_triple::verify(&proof, b"ciphertext 2", triple).is_err());
+        assert!(dh_triple::verify(&proof, b"ciphertext 1", triple.swapped()).is_err());
+
+        let proof = dh_triple::prove(&mut csprng, b"ciphertext 1", triple.swapped(), &b);
+        assert!(dh_triple::verify(&proof, b"ciphertext 1", triple.swapped()).is_ok());
+
+        let D = &C + G;
+        let wrong = dh_triple::Triple{G: G, A: &A, B: &B, C: &D};
+        let proof = dh_triple::prove(&mut csprng, b"ciphertext 1", wrong, &a);
+        assert!(dh_triple::verify(&proof, b"ciphertext 1", wrong).is_err());
+    }
+}
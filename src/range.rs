@@ -0,0 +1,218 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Range proofs showing that a Pedersen commitment `C = G*v + H*r`
+//! opens to a value `v` in `[0, 2^n)`.
+//!
+//! The prover commits to each bit `b_i` of `v` as
+//! `C_i = G*b_i + H*r_i`, choosing the `r_i` so that
+//! `sum_i 2^i * C_i = C`, and proves for each `C_i` the OR-statement
+//! `C_i = H*r_i` or `C_i - G = H*r_i`.  All the OR-proofs share one
+//! Fiat-Shamir challenge.
+//!
+//! Proofs are linear in `n`; this is much larger than a Bulletproof,
+//! but needs nothing beyond the sigma-protocol machinery used
+//! elsewhere in the crate.  Proof creation is constant-time with
+//! respect to the committed value: the real and simulated branches of
+//! each OR-proof are selected arithmetically rather than by branching.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use pedersen::{PedersenCommitment, PedersenGenerators};
+
+/// The largest supported bit length of a range proof.
+pub const MAX_BITS: usize = 64;
+
+/// A proof that a commitment opens to one bit.
+#[derive(Clone, Serialize, Deserialize)]
+struct BitProof {
+    commitment: DecafPoint,
+    c0: Scalar,
+    c1: Scalar,
+    s0: Scalar,
+    s1: Scalar,
+}
+
+/// A proof that a commitment opens to a value in `[0, 2^n)`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RangeProof {
+    bits: Vec<BitProof>,
+}
+
+fn challenge(
+    gens: &PedersenGenerators,
+    commitment: &PedersenCommitment,
+    n: usize,
+    points: &[(DecafPoint, DecafPoint, DecafPoint)],
+) -> Scalar {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp range proof");
+    hash.input(gens.G.compress().as_bytes());
+    hash.input(gens.H.compress().as_bytes());
+    hash.input(commitment.0.compress().as_bytes());
+    hash.input(&[n as u8]);
+    for &(ref C_i, ref A0, ref A1) in points {
+        hash.input(C_i.compress().as_bytes());
+        hash.input(A0.compress().as_bytes());
+        hash.input(A1.compress().as_bytes());
+    }
+    Scalar::from_hash(hash)
+}
+
+impl RangeProof {
+    /// Prove that the commitment `G*value + H*blinding` opens to a
+    /// value in `[0, 2^n)`.
+    ///
+    /// Returns `Err(())` if `n` is zero or larger than `MAX_BITS`, or
+    /// if `value` is out of range.
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        gens: &PedersenGenerators,
+        n: usize,
+        value: u64,
+        blinding: &Scalar,
+    ) -> Result<RangeProof, ()> {
+        if n == 0 || n > MAX_BITS || (n < 64 && value >> n != 0) {
+            return Err(());
+        }
+        let commitment = PedersenCommitment(
+            &(&gens.G * &Scalar::from_u64(value)) + &(&gens.H * blinding)
+        );
+
+        // Pick r_1, ..., r_{n-1} at random and r_0 = r - sum 2^i r_i.
+        let mut blindings: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let mut r_0 = *blinding;
+        let mut power = Scalar::one();
+        for r_i in blindings.iter().skip(1) {
+            power = &power + &power;
+            r_0 = &r_0 - &(&power * r_i);
+        }
+        blindings[0] = r_0;
+
+        let one = Scalar::one();
+        let mut bits = Vec::with_capacity(n);
+        let mut nonces = Vec::with_capacity(n);
+        let mut points = Vec::with_capacity(n);
+        for i in 0..n {
+            let b = Scalar::from_u64((value >> i) & 1);
+            let not_b = &one - &b;
+            let C_i = &(&gens.G * &b) + &(&gens.H * &blindings[i]);
+
+            // The real branch commits to H*k; the other branch is
+            // simulated from random (c_sim, s_sim).
+            let k = Scalar::random(csprng);
+            let c_sim = Scalar::random(csprng);
+            let s_sim = Scalar::random(csprng);
+            let A_real = &gens.H * &k;
+            let A_sim0 = &(&gens.H * &s_sim) - &(&C_i * &c_sim);
+            let A_sim1 = &(&gens.H * &s_sim) - &(&(&C_i - &gens.G) * &c_sim);
+
+            // A0 = b ? A_sim0 : A_real, A1 = b ? A_real : A_sim1
+            let A0 = &(&A_real * &not_b) + &(&A_sim0 * &b);
+            let A1 = &(&A_real * &b) + &(&A_sim1 * &not_b);
+
+            points.push((C_i, A0, A1));
+            nonces.push((b, not_b, k, c_sim, s_sim));
+        }
+
+        let c = challenge(gens, &commitment, n, &points);
+
+        for (i, &(b, not_b, k, c_sim, s_sim)) in nonces.iter().enumerate() {
+            let c_real = &c - &c_sim;
+            let s_real = Scalar::multiply_add(&c_real, &blindings[i], &k);
+            bits.push(BitProof{
+                commitment: points[i].0,
+                c0: &(&not_b * &c_real) + &(&b * &c_sim),
+                c1: &(&b * &c_real) + &(&not_b * &c_sim),
+                s0: &(&not_b * &s_real) + &(&b * &s_sim),
+                s1: &(&b * &s_real) + &(&not_b * &s_sim),
+            });
+        }
+
+        Ok(RangeProof{ bits: bits })
+    }
+
+    /// Verify that `commitment` opens to a value in `[0, 2^n)`.
+    pub fn verify(
+        &self,
+        gens: &PedersenGenerators,
+        commitment: &PedersenCommitment,
+        n: usize,
+    ) -> Result<(),()> {
+        if n == 0 || n > MAX_BITS || self.bits.len() != n {
+            return Err(());
+        }
+
+        // Check that the bit commitments add up to the commitment.
+        let mut sum = self.bits[n - 1].commitment;
+        for bit in self.bits.iter().rev().skip(1) {
+            sum = &(&sum + &sum) + &bit.commitment;
+        }
+        if sum.compress() != commitment.0.compress() {
+            return Err(());
+        }
+
+        let points: Vec<(DecafPoint, DecafPoint, DecafPoint)> = self.bits.iter().map(|bit| {
+            let A0 = &(&gens.H * &bit.s0) - &(&bit.commitment * &bit.c0);
+            let A1 = &(&gens.H * &bit.s1) - &(&(&bit.commitment - &gens.G) * &bit.c1);
+            (bit.commitment, A0, A1)
+        }).collect();
+        let c = challenge(gens, commitment, n, &points);
+
+        for bit in &self.bits {
+            if &bit.c0 + &bit.c1 != c {
+                return Err(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn create_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+        let r = Scalar::random(&mut csprng);
+
+        for &value in &[0u64, 1, 200, 255] {
+            let C = PedersenCommitment(&(&gens.G * &Scalar::from_u64(value)) + &(&gens.H * &r));
+            let proof = RangeProof::create(&mut csprng, &gens, 8, value, &r).unwrap();
+            assert!(proof.verify(&gens, &C, 8).is_ok());
+            assert!(proof.verify(&gens, &C, 7).is_err());
+        }
+
+        assert!(RangeProof::create(&mut csprng, &gens, 8, 256, &r).is_err());
+    }
+
+    #[test]
+    fn full_width() {
+        let mut csprng = OsRng::new().unwrap();
+        let gens = PedersenGenerators::default();
+        let r = Scalar::random(&mut csprng);
+        let value = 0xffff_ffff_ffff_fffe;
+
+        let C = PedersenCommitment(&(&gens.G * &Scalar::from_u64(value)) + &(&gens.H * &r));
+        let proof = RangeProof::create(&mut csprng, &gens, 64, value, &r).unwrap();
+        assert!(proof.verify(&gens, &C, 64).is_ok());
+
+        let other = PedersenCommitment(&C.0 + &gens.G);
+        assert!(proof.verify(&gens, &other, 64).is_err());
+    }
+}
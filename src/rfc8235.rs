@@ -0,0 +1,277 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Schnorr non-interactive proofs of knowledge of a discrete
+//! logarithm, as in [RFC 8235], for interoperating with other
+//! implementations of it.
+//!
+//! Knowing `a` with `A = G*a`, the prover picks `v`, and sends
+//! `V = G*v` and `r = v - a*c`, where
+//!
+//! ```text
+//! c = H(G || V || A || UserID || OtherInfo)
+//! ```
+//!
+//! read as an integer and reduced modulo the group order.  The
+//! verifier checks that `A` is not the identity and that
+//! `V = G*r + A*c`.
+//!
+//! The RFC leaves some of the encoding to the implementation, so the
+//! conventions are gathered in a `Profile`: the order of the inputs
+//! to the hash, whether each is prefixed with its length, how points
+//! are encoded, and whether scalars and hash outputs are big-endian.
+//! `Profile::rfc8235()` follows the RFC, with the 32-byte Decaf
+//! encoding of points and big-endian integers; the hash function is a
+//! type parameter, as in `Proof::create::<Sha256, _>(...)`.  Unlike
+//! `create_nipk!` proofs, these are not bound to a statement label, so
+//! the `UserID` and `OtherInfo` should identify the protocol.
+//!
+//! [RFC 8235]: https://tools.ietf.org/html/rfc8235
+
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::Digest;
+
+/// An input to the challenge hash.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Input {
+    /// The generator `G`.
+    Generator,
+    /// The prover's commitment `V`.
+    Commitment,
+    /// The public key `A`.
+    Public,
+    /// The prover's `UserID`.
+    UserId,
+    /// The `OtherInfo` of the protocol.
+    OtherInfo,
+}
+
+/// The conventions of a Schnorr NIZK implementation.
+#[derive(Copy, Clone)]
+pub struct Profile {
+    /// The inputs to the challenge hash, in the order they are hashed.
+    pub order: &'static [Input],
+    /// Whether each input is prefixed with its length in bytes, as a
+    /// 4-byte big-endian integer.
+    pub length_prefixed: bool,
+    /// Whether the hash output is read as a big-endian integer, and the
+    /// response encoded as one; otherwise both are little-endian.
+    pub big_endian: bool,
+    /// Encode a point, for the hash and for `Proof::to_bytes`.
+    pub encode_point: fn(&DecafPoint) -> Vec<u8>,
+    /// Decode a point encoded by `encode_point`, returning `None` if
+    /// the encoding is invalid.
+    pub decode_point: fn(&[u8]) -> Option<DecafPoint>,
+}
+
+/// The statement `A = G*a`, with the context of the proof.
+#[derive(Copy, Clone)]
+pub struct Statement<'a> {
+    pub generator: &'a DecafPoint,
+    pub public: &'a DecafPoint,
+    pub user_id: &'a [u8],
+    pub other_info: &'a [u8],
+}
+
+/// A proof `(V, r)` of knowledge of the discrete logarithm of `A`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Proof {
+    commitment: DecafPoint,
+    response: Scalar,
+}
+
+const RFC8235_ORDER: &'static [Input] = &[
+    Input::Generator,
+    Input::Commitment,
+    Input::Public,
+    Input::UserId,
+    Input::OtherInfo,
+];
+
+fn encode_decaf(point: &DecafPoint) -> Vec<u8> {
+    point.compress().as_bytes().to_vec()
+}
+
+fn decode_decaf(bytes: &[u8]) -> Option<DecafPoint> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    let mut encoding = [0u8; 32];
+    encoding.copy_from_slice(bytes);
+    CompressedDecaf(encoding).decompress()
+}
+
+impl Profile {
+    /// The conventions of RFC 8235: the inputs in the order
+    /// `G || V || A || UserID || OtherInfo`, without lengths, and
+    /// big-endian integers.
+    pub fn rfc8235() -> Profile {
+        Profile{
+            order: RFC8235_ORDER,
+            length_prefixed: false,
+            big_endian: true,
+            encode_point: encode_decaf,
+            decode_point: decode_decaf,
+        }
+    }
+
+    /// Compute the challenge for `statement` and the commitment `V`.
+    pub fn challenge<D: Digest + Default>(&self, statement: Statement, commitment: &DecafPoint) -> Scalar {
+        let mut hash = D::default();
+        for input in self.order {
+            let bytes = match *input {
+                Input::Generator => (self.encode_point)(statement.generator),
+                Input::Commitment => (self.encode_point)(commitment),
+                Input::Public => (self.encode_point)(statement.public),
+                Input::UserId => statement.user_id.to_vec(),
+                Input::OtherInfo => statement.other_info.to_vec(),
+            };
+            if self.length_prefixed {
+                let length = bytes.len() as u32;
+                hash.input(&[(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8, length as u8]);
+            }
+            hash.input(&bytes);
+        }
+        let output = hash.result();
+        if self.big_endian {
+            reduce(output.iter())
+        } else {
+            reduce(output.iter().rev())
+        }
+    }
+}
+
+/// Read big-endian bytes as an integer modulo the group order.
+fn reduce<'a, I: Iterator<Item = &'a u8>>(bytes: I) -> Scalar {
+    let base = Scalar::from_u64(256);
+    let mut scalar = Scalar::zero();
+    for byte in bytes {
+        scalar = Scalar::multiply_add(&scalar, &base, &Scalar::from_u64(*byte as u64));
+    }
+    scalar
+}
+
+impl Proof {
+    /// Prove knowledge of `secret`, the discrete logarithm of
+    /// `statement.public` to the base `statement.generator`.
+    pub fn create<D: Digest + Default, R: Rng>(
+        csprng: &mut R,
+        profile: &Profile,
+        statement: Statement,
+        secret: &Scalar,
+    ) -> Proof {
+        let nonce = Scalar::random(csprng);
+        let commitment = statement.generator * &nonce;
+        let challenge = profile.challenge::<D>(statement, &commitment);
+        Proof{ commitment: commitment, response: &nonce - &(secret * &challenge) }
+    }
+
+    /// Verify the proof, rejecting a public key which is the
+    /// identity.
+    pub fn verify<D: Digest + Default>(&self, profile: &Profile, statement: Statement) -> Result<(),()> {
+        if ::__is_identity(statement.public) {
+            return Err(());
+        }
+        let challenge = profile.challenge::<D>(statement, &self.commitment);
+        let expected = &(statement.generator * &self.response) + &(statement.public * &challenge);
+        if expected.compress() == self.commitment.compress() { Ok(()) } else { Err(()) }
+    }
+
+    /// Encode the proof as `V` followed by `r`, in the conventions of
+    /// `profile`.
+    pub fn to_bytes(&self, profile: &Profile) -> Vec<u8> {
+        let mut bytes = (profile.encode_point)(&self.commitment);
+        let mut response = *self.response.as_bytes();
+        if profile.big_endian {
+            response.reverse();
+        }
+        bytes.extend_from_slice(&response);
+        bytes
+    }
+
+    /// Parse a proof encoded by `to_bytes`, rejecting invalid points
+    /// and non-canonical scalars.
+    pub fn from_bytes(bytes: &[u8], profile: &Profile) -> Result<Proof,()> {
+        if bytes.len() < 32 {
+            return Err(());
+        }
+        let (point, scalar) = bytes.split_at(bytes.len() - 32);
+        let commitment = (profile.decode_point)(point).ok_or(())?;
+        let mut response = [0u8; 32];
+        response.copy_from_slice(scalar);
+        if profile.big_endian {
+            response.reverse();
+        }
+        Ok(Proof{ commitment: commitment, response: ::__decode_scalar(&response)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use rand::OsRng;
+    use sha2::{Sha256, Sha512};
+
+    #[test]
+    fn create_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let a = Scalar::random(&mut csprng);
+        let A = G * &a;
+        let profile = Profile::rfc8235();
+        let statement = Statement{ generator: G, public: &A, user_id: b"alice", other_info: b"test" };
+
+        let proof = Proof::create::<Sha256, _>(&mut csprng, &profile, statement, &a);
+        assert!(proof.verify::<Sha256>(&profile, statement).is_ok());
+        assert!(proof.verify::<Sha512>(&profile, statement).is_err());
+        assert!(proof.verify::<Sha256>(&profile, Statement{ user_id: b"bob", ..statement }).is_err());
+
+        let bytes = proof.to_bytes(&profile);
+        assert_eq!(bytes.len(), 64);
+        let parsed = Proof::from_bytes(&bytes, &profile).unwrap();
+        assert!(parsed.verify::<Sha256>(&profile, statement).is_ok());
+
+        // Another hashing order gives another challenge.
+        let other = Profile{ order: &[Input::Public, Input::Generator, Input::Commitment], length_prefixed: true, ..profile };
+        assert!(proof.verify::<Sha256>(&other, statement).is_err());
+        let proof = Proof::create::<Sha256, _>(&mut csprng, &other, statement, &a);
+        assert!(proof.verify::<Sha256>(&other, statement).is_ok());
+    }
+
+    #[test]
+    fn challenge_is_big_endian_hash() {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let statement = Statement{ generator: G, public: G, user_id: b"", other_info: b"" };
+        let profile = Profile{ order: &[Input::UserId], ..Profile::rfc8235() };
+
+        let mut digest = Sha256::default().result();
+        digest.reverse();
+        let mut little_endian = [0u8; 32];
+        little_endian.copy_from_slice(&digest);
+        assert_eq!(profile.challenge::<Sha256>(statement, G), Scalar::from_bytes_mod_order(little_endian));
+    }
+
+    #[test]
+    fn identity_public_is_rejected() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let zero = Scalar::zero();
+        let A = G * &zero;
+        let profile = Profile::rfc8235();
+        let statement = Statement{ generator: G, public: &A, user_id: b"alice", other_info: b"" };
+
+        let proof = Proof::create::<Sha256, _>(&mut csprng, &profile, statement, &zero);
+        assert!(proof.verify::<Sha256>(&profile, statement).is_err());
+    }
+}
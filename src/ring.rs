@@ -0,0 +1,151 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Ring signatures built from OR-proofs.
+//!
+//! A ring signature on a message `m` for the keys `P_0, ..., P_{n-1}`
+//! is a non-interactive proof of knowledge of *one* `x` such that
+//! `P_j = G*x` for some `j`, bound to `m`.  It uses the
+//! Cramer-Damgård-Schoenmakers OR-composition: the signer simulates
+//! the Schnorr transcripts for every other key, and the per-key
+//! challenges are constrained to sum to the Fiat-Shamir challenge.
+//!
+//! Signatures are linear in the size of the ring.  Signing is not
+//! constant-time with respect to the signer's position in the ring,
+//! so `sign` is compiled out under the `ct-audit` feature.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+#[cfg(not(feature = "ct-audit"))]
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+/// A ring signature: one challenge and response per ring member.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Signature {
+    challenges: Vec<Scalar>,
+    responses: Vec<Scalar>,
+}
+
+fn challenge(message: &[u8], keys: &[DecafPoint], commitments: &[DecafPoint]) -> Scalar {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp ring signature");
+    for key in keys {
+        hash.input(key.compress().as_bytes());
+    }
+    for commitment in commitments {
+        hash.input(commitment.compress().as_bytes());
+    }
+    hash.input(message);
+    Scalar::from_hash(hash)
+}
+
+/// Sign `message` for the ring `keys` using `secret`.
+///
+/// Returns `Err(())` if `G*secret` is not one of the `keys`.
+#[cfg(not(feature = "ct-audit"))]
+pub fn sign<R: Rng>(
+    csprng: &mut R,
+    message: &[u8],
+    keys: &[DecafPoint],
+    secret: &Scalar,
+) -> Result<Signature, ()> {
+    let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+    let public = (G * secret).compress();
+    let mut index = None;
+    for (i, key) in keys.iter().enumerate() {
+        if key.compress() == public {
+            index = Some(i);
+        }
+    }
+    let j = index.ok_or(())?;
+
+    // Simulate a transcript for every other member of the ring.
+    let mut challenges: Vec<Scalar> = (0..keys.len()).map(|_| Scalar::random(csprng)).collect();
+    let mut responses: Vec<Scalar> = (0..keys.len()).map(|_| Scalar::random(csprng)).collect();
+    let nonce = Scalar::random(csprng);
+
+    let commitments: Vec<DecafPoint> = keys.iter().enumerate().map(|(i, key)| {
+        if i == j {
+            G * &nonce
+        } else {
+            &(G * &responses[i]) - &(key * &challenges[i])
+        }
+    }).collect();
+
+    // The real challenge is whatever is left over.
+    let mut c_j = challenge(message, keys, &commitments);
+    for (i, c_i) in challenges.iter().enumerate() {
+        if i != j {
+            c_j = &c_j - c_i;
+        }
+    }
+    responses[j] = Scalar::multiply_add(&c_j, secret, &nonce);
+    challenges[j] = c_j;
+
+    Ok(Signature{ challenges: challenges, responses: responses })
+}
+
+/// Verify a ring signature on `message` for the ring `keys`.
+pub fn verify(message: &[u8], keys: &[DecafPoint], signature: &Signature) -> Result<(),()> {
+    let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+    if signature.challenges.len() != keys.len() || signature.responses.len() != keys.len() {
+        return Err(());
+    }
+
+    let commitments: Vec<DecafPoint> = keys.iter()
+        .zip(signature.challenges.iter().zip(signature.responses.iter()))
+        .map(|(key, (c, s))| &(G * s) - &(key * c))
+        .collect();
+
+    let mut sum = Scalar::zero();
+    for c in &signature.challenges {
+        sum = &sum + c;
+    }
+
+    if challenge(message, keys, &commitments) == sum { Ok(()) } else { Err(()) }
+}
+
+#[cfg(all(test, not(feature = "ct-audit")))]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn sign_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        let secrets: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut csprng)).collect();
+        let keys: Vec<DecafPoint> = secrets.iter().map(|x| G * x).collect();
+
+        for x in &secrets {
+            let signature = sign(&mut csprng, b"msg", &keys, x).unwrap();
+            assert!(verify(b"msg", &keys, &signature).is_ok());
+            assert!(verify(b"other msg", &keys, &signature).is_err());
+            assert!(verify(b"msg", &keys[1..], &signature).is_err());
+        }
+    }
+
+    #[test]
+    fn sign_rejects_secret_outside_ring() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+        let keys: Vec<DecafPoint> = (0..3).map(|_| G * &Scalar::random(&mut csprng)).collect();
+        let x = Scalar::random(&mut csprng);
+
+        assert!(sign(&mut csprng, b"msg", &keys, &x).is_err());
+    }
+}
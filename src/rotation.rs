@@ -0,0 +1,115 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs of key rotation.
+//!
+//! A key `A` is rotated to `B = A*delta` for a rotation factor `delta`
+//! known to the prover.  A `RotationProof` proves knowledge of `delta`
+//! for the statement
+//!
+//! ```rust,ignore
+//! create_nipk!{rotation, (delta), (B, A) : B = (A * delta) }
+//! ```
+//!
+//! in a transcript labelled `"zkp key rotation"`, after the epoch of
+//! the new key as an 8-byte little-endian message.  Both keys are
+//! publics of the statement, so the proof is bound to the keys and the
+//! epoch, and cannot be replayed to justify a rotation in another
+//! epoch.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+use __is_identity;
+use transcript::Transcript;
+
+create_nipk!{rotation, (delta), (B, A) : B = (A * delta) }
+
+/// A proof that a key is a known multiple of the previous key.
+pub use self::rotation::Proof as RotationProof;
+
+fn rotation_transcript(epoch: u64) -> Transcript {
+    let mut epoch_bytes = [0u8; 8];
+    for i in 0..8 {
+        epoch_bytes[i] = (epoch >> (8 * i)) as u8;
+    }
+    let mut transcript = Transcript::new(b"zkp key rotation");
+    transcript.append_message(b"epoch", &epoch_bytes);
+    transcript
+}
+
+/// Rotate the key `old` by a random factor for `epoch`, returning the
+/// new key, the factor, and a proof of the rotation.
+pub fn rotate<R: Rng>(
+    csprng: &mut R,
+    old: &DecafPoint,
+    epoch: u64,
+) -> (DecafPoint, Scalar, RotationProof) {
+    let delta = Scalar::random(csprng);
+    let new = old * &delta;
+    let proof = prove_rotation(csprng, old, &new, &delta, epoch);
+    (new, delta, proof)
+}
+
+/// Prove that `new = old * delta`, for the key of `epoch`.
+pub fn prove_rotation<R: Rng>(
+    csprng: &mut R,
+    old: &DecafPoint,
+    new: &DecafPoint,
+    delta: &Scalar,
+    epoch: u64,
+) -> RotationProof {
+    RotationProof::create_from_transcript(
+        csprng,
+        &mut rotation_transcript(epoch),
+        rotation::Publics{ B: new, A: old },
+        rotation::Secrets{ delta: delta },
+    )
+}
+
+/// Verify a proof that `new` is a known multiple of `old`, for the key
+/// of `epoch`.
+///
+/// This also rejects rotations to or from the identity, which any
+/// factor proves.
+pub fn verify_rotation(
+    old: &DecafPoint,
+    new: &DecafPoint,
+    epoch: u64,
+    proof: &RotationProof,
+) -> Result<(),()> {
+    if __is_identity(old) || __is_identity(new) {
+        return Err(());
+    }
+    proof.verify_from_transcript(&mut rotation_transcript(epoch), rotation::Publics{ B: new, A: old })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn rotate_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let A = DecafPoint::random(&mut csprng);
+
+        let (B, _, proof) = rotate(&mut csprng, &A, 7);
+        assert!(verify_rotation(&A, &B, 7, &proof).is_ok());
+        assert!(verify_rotation(&A, &B, 8, &proof).is_err());
+        assert!(verify_rotation(&B, &A, 7, &proof).is_err());
+
+        let (C, _, proof2) = rotate(&mut csprng, &B, 8);
+        assert!(verify_rotation(&B, &C, 8, &proof2).is_ok());
+        assert!(verify_rotation(&A, &C, 8, &proof2).is_err());
+    }
+}
@@ -0,0 +1,433 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Statements chosen at runtime, such as loaded from a database.
+//!
+//! A `Statement` is a list of `Equation`s, each a public point equal
+//! to a sum of `Term`s `point * secret`.  It can be built from these
+//! directly, or parsed from the notation of `create_nipk!`:
+//!
+//! ```rust,ignore
+//! let statement = Statement::parse("A = G * x, B = H * x")?;
+//! let proof = statement.prove(&mut csprng, &points, &scalars)?;
+//! statement.verify(&points, &proof)?;
+//! ```
+//!
+//! where `points` maps the name of each public to its point, and
+//! `scalars` the name of each secret to its value, as
+//! `HashMap<String, _>`s.  In each term the point comes first, and
+//! parentheses are ignored, so that `(G * x)` and `G * x` are the same
+//! term; unlike `create_nipk!`, there are no coefficients, negations
+//! or relations between the secrets.
+//!
+//! The publics are taken in order of first appearance, each equation
+//! contributing its left-hand side and then the points of its terms,
+//! and the secrets likewise.  The challenge is that of `create_nipk!`,
+//! with this order of publics, and the proof has the same encoding.
+//! So a proof of `"A = G * x, B = H * x"` is also a proof for
+//!
+//! ```rust,ignore
+//! create_nipk!{dleq, (x), (A, G, B, H) : A = (G * x), B = (H * x) }
+//! ```
+//!
+//! and the other way around.
+
+use std::collections::HashMap;
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::Sha512;
+
+use Version;
+
+/// A term `point * secret` of an equation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Term {
+    pub point: String,
+    pub secret: String,
+}
+
+/// An equation `lhs = point * secret + ...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Equation {
+    pub lhs: String,
+    pub terms: Vec<Term>,
+}
+
+/// Equations which a proof shows that some secrets satisfy.
+#[derive(Clone, Debug)]
+pub struct Statement {
+    equations: Vec<Equation>,
+    publics: Vec<String>,
+    secrets: Vec<String>,
+    /// For each equation, the index of its left-hand side in
+    /// `publics`, and of the point and secret of each term.
+    indices: Vec<(usize, Vec<(usize, usize)>)>,
+}
+
+/// A proof of a runtime `Statement`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Proof {
+    challenge: Scalar,
+    responses: Vec<Scalar>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Token<'a> {
+    Name(&'a str),
+    Symbol(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>,()> {
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => {}
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1).ok_or(())?,
+            '=' | '+' | '*' | ',' => tokens.push(Token::Symbol(c)),
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                tokens.push(Token::Name(&source[start..end]));
+            }
+            _ => return Err(()),
+        }
+    }
+    if depth != 0 {
+        return Err(());
+    }
+    Ok(tokens)
+}
+
+fn parse_equation(tokens: &[Token]) -> Result<Equation,()> {
+    if tokens.len() < 2 || tokens[1] != Token::Symbol('=') {
+        return Err(());
+    }
+    let lhs = match tokens[0] {
+        Token::Name(name) => name.to_string(),
+        _ => return Err(()),
+    };
+    let mut terms = Vec::new();
+    for term in tokens[2..].split(|&token| token == Token::Symbol('+')) {
+        if term.len() != 3 || term[1] != Token::Symbol('*') {
+            return Err(());
+        }
+        match (term[0], term[2]) {
+            (Token::Name(point), Token::Name(secret)) => {
+                terms.push(Term{ point: point.to_string(), secret: secret.to_string() });
+            }
+            _ => return Err(()),
+        }
+    }
+    Ok(Equation{ lhs: lhs, terms: terms })
+}
+
+fn index_of(names: &mut Vec<String>, name: &str) -> usize {
+    match names.iter().position(|n| n == name) {
+        Some(i) => i,
+        None => {
+            names.push(name.to_string());
+            names.len() - 1
+        }
+    }
+}
+
+impl Statement {
+    /// The statement made of `equations`.
+    ///
+    /// Returns `Err(())` if there are no equations, an equation has no
+    /// terms, or a name is used both as a public and as a secret.
+    pub fn new(equations: Vec<Equation>) -> Result<Statement,()> {
+        if equations.is_empty() || equations.iter().any(|e| e.terms.is_empty()) {
+            return Err(());
+        }
+        let mut publics = Vec::new();
+        let mut secrets = Vec::new();
+        let mut indices = Vec::new();
+        for equation in &equations {
+            let lhs = index_of(&mut publics, &equation.lhs);
+            let terms = equation.terms.iter().map(|term| {
+                (index_of(&mut publics, &term.point), index_of(&mut secrets, &term.secret))
+            }).collect();
+            indices.push((lhs, terms));
+        }
+        if publics.iter().any(|name| secrets.contains(name)) {
+            return Err(());
+        }
+        Ok(Statement{ equations: equations, publics: publics, secrets: secrets, indices: indices })
+    }
+
+    /// Parse equations such as `"A = G * x, B = H * x"`, separated by
+    /// commas.
+    ///
+    /// Returns `Err(())` if the source is not in this notation, or as
+    /// for `new`.
+    pub fn parse(source: &str) -> Result<Statement,()> {
+        let tokens = tokenize(source)?;
+        let equations = tokens
+            .split(|&token| token == Token::Symbol(','))
+            .map(parse_equation)
+            .collect::<Result<Vec<_>,()>>()?;
+        Statement::new(equations)
+    }
+
+    pub fn equations(&self) -> &[Equation] {
+        &self.equations
+    }
+
+    /// The names of the publics, in the order they are hashed.
+    pub fn publics(&self) -> &[String] {
+        &self.publics
+    }
+
+    /// The names of the secrets, in the order of the responses.
+    pub fn secrets(&self) -> &[String] {
+        &self.secrets
+    }
+
+    /// The label of each equation in the challenge hash, as in the
+    /// `LABELS` of `create_nipk!`, e.g. `"A = G * x + H * y"`.
+    pub fn labels(&self) -> Vec<String> {
+        self.equations.iter().map(|equation| {
+            let terms: Vec<String> = equation.terms.iter()
+                .map(|term| format!("{} * {}", term.point, term.secret))
+                .collect();
+            format!("{} = {}", equation.lhs, terms.join(" + "))
+        }).collect()
+    }
+
+    /// Prove the statement for the given values of the publics and
+    /// secrets.
+    ///
+    /// Returns `Err(())` if a public or secret is missing, or the
+    /// secrets do not satisfy the equations.
+    pub fn prove<R: Rng>(
+        &self,
+        csprng: &mut R,
+        points: &HashMap<String, DecafPoint>,
+        scalars: &HashMap<String, Scalar>,
+    ) -> Result<Proof,()> {
+        self.prove_bytes(csprng, &[], points, scalars)
+    }
+
+    /// Prove the statement bound to `context`, as
+    /// `Proof::create_with_context` does.
+    pub fn prove_with_context<R: Rng>(
+        &self,
+        csprng: &mut R,
+        context: &[u8],
+        points: &HashMap<String, DecafPoint>,
+        scalars: &HashMap<String, Scalar>,
+    ) -> Result<Proof,()> {
+        self.prove_bytes(csprng, &[ &::__context_length(context)[..], context ], points, scalars)
+    }
+
+    /// Verify a proof made by `prove`.
+    ///
+    /// Returns `Err(())` if a public is missing, or the proof is
+    /// invalid.
+    pub fn verify(&self, points: &HashMap<String, DecafPoint>, proof: &Proof) -> Result<(),()> {
+        self.verify_bytes(&[], points, proof)
+    }
+
+    /// Verify a proof made by `prove_with_context` with the same
+    /// context.
+    pub fn verify_with_context(
+        &self,
+        context: &[u8],
+        points: &HashMap<String, DecafPoint>,
+        proof: &Proof,
+    ) -> Result<(),()> {
+        self.verify_bytes(&[ &::__context_length(context)[..], context ], points, proof)
+    }
+
+    fn prove_bytes<R: Rng>(
+        &self,
+        csprng: &mut R,
+        context: &[&[u8]],
+        points: &HashMap<String, DecafPoint>,
+        scalars: &HashMap<String, Scalar>,
+    ) -> Result<Proof,()> {
+        let points = self.lookup_points(points)?;
+        let secrets = self.secrets.iter()
+            .map(|name| scalars.get(name).cloned().ok_or(()))
+            .collect::<Result<Vec<_>,()>>()?;
+        for (value, &(lhs, _)) in self.combine(&points, &secrets).iter().zip(self.indices.iter()) {
+            if value.compress() != points[lhs].compress() {
+                return Err(());
+            }
+        }
+
+        let nonces: Vec<Scalar> = self.secrets.iter().map(|_| Scalar::random(csprng)).collect();
+        let commitments = self.combine(&points, &nonces);
+        let challenge = self.challenge(context, &points, &commitments);
+        let responses = nonces.iter().zip(secrets.iter())
+            .map(|(nonce, secret)| Scalar::multiply_add(&challenge, secret, nonce))
+            .collect();
+        Ok(Proof{ challenge: challenge, responses: responses })
+    }
+
+    fn verify_bytes(
+        &self,
+        context: &[&[u8]],
+        points: &HashMap<String, DecafPoint>,
+        proof: &Proof,
+    ) -> Result<(),()> {
+        let points = self.lookup_points(points)?;
+        if proof.responses.len() != self.secrets.len() {
+            return Err(());
+        }
+        // `A = G * x` gives the commitment `G * r - A * c`.
+        let commitments: Vec<DecafPoint> = self.combine(&points, &proof.responses).iter()
+            .zip(self.indices.iter())
+            .map(|(value, &(lhs, _))| value - &(&points[lhs] * &proof.challenge))
+            .collect();
+        if self.challenge(context, &points, &commitments) == proof.challenge {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    fn lookup_points(&self, points: &HashMap<String, DecafPoint>) -> Result<Vec<DecafPoint>,()> {
+        self.publics.iter().map(|name| points.get(name).cloned().ok_or(())).collect()
+    }
+
+    /// The right-hand side of each equation, for the given values of
+    /// the secrets.
+    fn combine(&self, points: &[DecafPoint], scalars: &[Scalar]) -> Vec<DecafPoint> {
+        self.indices.iter().map(|&(_, ref terms)| {
+            let scalars: Vec<Scalar> = terms.iter().map(|&(_, s)| scalars[s]).collect();
+            let points: Vec<&DecafPoint> = terms.iter().map(|&(p, _)| &points[p]).collect();
+            ::__multiscalar_mult(&scalars, &points)
+        }).collect()
+    }
+
+    fn challenge(&self, context: &[&[u8]], points: &[DecafPoint], commitments: &[DecafPoint]) -> Scalar {
+        let version = Version::CURRENT;
+        let mut hash: Sha512 = ::__start_challenge(version, context);
+        for (name, point) in self.publics.iter().zip(points.iter()) {
+            ::__input_public(version, &mut hash, name, point.compress().as_bytes());
+        }
+        for (label, commitment) in self.labels().iter().zip(commitments.iter()) {
+            ::__input_commitment(version, &mut hash, label, commitment.compress().as_bytes());
+        }
+        Scalar::from_hash(hash)
+    }
+}
+
+impl Proof {
+    /// The challenge followed by the responses, as 32 bytes each, as
+    /// in `Proof::to_bytes` of `create_nipk!`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 * (1 + self.responses.len()));
+        bytes.extend_from_slice(self.challenge.as_bytes());
+        for response in &self.responses {
+            bytes.extend_from_slice(response.as_bytes());
+        }
+        bytes
+    }
+
+    /// Parse a proof encoded by `to_bytes`, rejecting non-canonical
+    /// scalars.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Proof,()> {
+        if bytes.len() < 32 || bytes.len() % 32 != 0 {
+            return Err(());
+        }
+        let mut scalars = bytes.chunks(32).map(::__decode_scalar).collect::<Result<Vec<_>,()>>()?;
+        let challenge = scalars.remove(0);
+        Ok(Proof{ challenge: challenge, responses: scalars })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    create_nipk!{dleq, (x), (A, G, B, H) : A = (G * x), B = (H * x) }
+
+    fn names<T: Clone>(values: &[(&str, T)]) -> HashMap<String, T> {
+        values.iter().map(|&(name, ref value)| (name.to_string(), value.clone())).collect()
+    }
+
+    #[test]
+    fn parse() {
+        let statement = Statement::parse("A = (G * x), B = H*x + (J * y)").unwrap();
+        assert_eq!(statement.publics(), &["A", "G", "B", "H", "J"]);
+        assert_eq!(statement.secrets(), &["x", "y"]);
+        assert_eq!(statement.labels(), vec!["A = G * x", "B = H * x + J * y"]);
+        assert_eq!(statement.equations()[1].terms[1], Term{ point: "J".to_string(), secret: "y".to_string() });
+
+        for source in &["", "A = G * x,", "A = G", "A = x * G * y", "A = (G * x", "A = G * x)",
+                        "A = G * 2", "A = G * A", "A - B = G * x", "= G * x"] {
+            assert!(Statement::parse(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn prove_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let points = names(&[("A", publics.A), ("G", publics.G), ("B", publics.B), ("H", publics.H)]);
+        let scalars = names(&[("x", secrets.x)]);
+        let statement = Statement::parse("A = G * x, B = H * x").unwrap();
+
+        let proof = statement.prove(&mut csprng, &points, &scalars).unwrap();
+        assert!(statement.verify(&points, &proof).is_ok());
+        let parsed = Proof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(statement.verify(&points, &parsed).is_ok());
+        assert!(statement.verify_with_context(b"other", &points, &proof).is_err());
+
+        let mut swapped = points.clone();
+        swapped.insert("A".to_string(), publics.B);
+        swapped.insert("B".to_string(), publics.A);
+        assert!(statement.verify(&swapped, &proof).is_err());
+        assert!(statement.prove(&mut csprng, &swapped, &scalars).is_err());
+        assert!(statement.prove(&mut csprng, &points, &HashMap::new()).is_err());
+        let mut missing = points.clone();
+        missing.remove("H");
+        assert!(statement.verify(&missing, &proof).is_err());
+
+        let proof = statement.prove_with_context(&mut csprng, b"app", &points, &scalars).unwrap();
+        assert!(statement.verify_with_context(b"app", &points, &proof).is_ok());
+        assert!(statement.verify(&points, &proof).is_err());
+    }
+
+    #[test]
+    fn same_proofs_as_macro() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let points = names(&[("A", publics.A), ("G", publics.G), ("B", publics.B), ("H", publics.H)]);
+        let scalars = names(&[("x", secrets.x)]);
+        let statement = Statement::parse("A = G * x, B = H * x").unwrap();
+        assert_eq!(statement.labels(), dleq::LABELS);
+
+        let proof = statement.prove(&mut csprng, &points, &scalars).unwrap();
+        let parsed = dleq::Proof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(parsed.verify(publics.as_publics()).is_ok());
+
+        let proof = dleq::Proof::create_with_context(
+            &mut csprng, b"app", publics.as_publics(), secrets.as_secrets());
+        let parsed = Proof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(statement.verify_with_context(b"app", &points, &parsed).is_ok());
+    }
+}
@@ -0,0 +1,99 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! A wrapper for witnesses, which keeps them out of logs and encodings.
+//!
+//! A `Secret<T>` holds a value which should not be printed, copied
+//! around, or serialized, such as the secret scalars passed to
+//! `Proof::create`.  It implements `Debug` without showing the value,
+//! so a derived `Debug` on a struct holding secrets is safe to log,
+//! and it implements neither `Clone` nor the Serde traits.  The value
+//! is only reached through `expose_secret()`, which marks each place
+//! it is used:
+//!
+//! ```rust,ignore
+//! let x = Secret::new(Scalar::random(&mut csprng));
+//! let proof = dlog::Proof::create(&mut csprng, publics, dlog::Secrets{ x: x.expose_secret() });
+//! ```
+//!
+//! The generated `Secrets` and `SecretsOwned` structs likewise
+//! implement `Debug` without their scalars.
+//!
+//! A `Secret` does not wipe its value when it is dropped.
+
+use std::fmt;
+
+/// A value which is only reached through `expose_secret()`.
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    /// Borrow the secret value.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Secret<T> {
+        Secret(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Secret").field(&__Redacted).finish()
+    }
+}
+
+/// Stands in for a secret value in `Debug` output.
+#[doc(hidden)]
+pub struct __Redacted;
+
+impl fmt::Debug for __Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+    #[derive(Debug)]
+    struct Signer {
+        key: Secret<Scalar>,
+    }
+
+    #[test]
+    fn redacted() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let signer = Signer{ key: Secret::new(Scalar::from_u64(1234567)) };
+        let A = G * signer.key.expose_secret();
+        let publics = dlog::Publics{A: &A, G: G};
+        let secrets = dlog::Secrets{x: signer.key.expose_secret()};
+        let proof = dlog::Proof::create(&mut csprng, publics, secrets);
+        assert!(proof.verify(publics).is_ok());
+
+        assert_eq!(format!("{:?}", signer), "Signer { key: Secret([REDACTED]) }");
+        assert_eq!(format!("{:?}", secrets), "Secrets { x: [REDACTED] }");
+        assert_eq!(format!("{:?}", dlog::SecretsOwned::from(secrets)), "SecretsOwned { x: [REDACTED] }");
+    }
+}
@@ -0,0 +1,244 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Verifiable shuffles of ElGamal ciphertexts.
+//!
+//! A shuffle maps the inputs `e_0, ..., e_{n-1}` to the outputs
+//! `e'_j = rerandomize(e_{pi(j)}, r_j)` for a secret permutation `pi`.
+//! The `ShuffleProof` shows that the outputs are such a permuted
+//! re-encryption of the inputs, without revealing `pi` or the `r_j`.
+//!
+//! This uses the Sako-Kilian cut-and-choose construction made
+//! non-interactive with Fiat-Shamir: the prover publishes `ROUNDS`
+//! "shadow" shuffles of the inputs, and for each one a challenge bit
+//! selects whether to open it as a shuffle of the inputs or as a
+//! shuffle of the outputs.  A cheating prover survives each round
+//! with probability 1/2, so the soundness error is `2^-ROUNDS`.
+//!
+//! Proofs are `ROUNDS` times the size of the ciphertext list.  This is
+//! much larger than Bayer-Groth-style proofs, but the construction is
+//! simple and only uses re-encryptions.
+//!
+//! Shuffling and proving index memory by the secret permutation, so
+//! they are compiled out under the `ct-audit` feature; verification
+//! is still available.
+
+use curve25519_dalek::scalar::Scalar;
+#[cfg(not(feature = "ct-audit"))]
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use elgamal::{Ciphertext, PublicKey};
+
+/// The number of cut-and-choose rounds in a `ShuffleProof`.
+pub const ROUNDS: usize = 128;
+
+/// The secret permutation and re-encryption randomness of a shuffle.
+#[cfg(not(feature = "ct-audit"))]
+pub struct ShuffleWitness {
+    permutation: Vec<usize>,
+    randomness: Vec<Scalar>,
+}
+
+/// The opening of one shadow shuffle, as a permutation `phi` and
+/// randomness `rho` with `shadow_i = rerandomize(source_{phi(i)}, rho_i)`.
+#[derive(Clone, Serialize, Deserialize)]
+struct ShadowOpening {
+    permutation: Vec<usize>,
+    randomness: Vec<Scalar>,
+}
+
+/// A proof that one list of ciphertexts is a shuffle of another.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShuffleProof {
+    shadows: Vec<Vec<Ciphertext>>,
+    openings: Vec<ShadowOpening>,
+}
+
+#[cfg(not(feature = "ct-audit"))]
+fn random_permutation<R: Rng>(csprng: &mut R, n: usize) -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..n).collect();
+    csprng.shuffle(&mut permutation);
+    permutation
+}
+
+fn ciphertexts_eq(a: &Ciphertext, b: &Ciphertext) -> bool {
+    a.C1.compress() == b.C1.compress() && a.C2.compress() == b.C2.compress()
+}
+
+fn is_permutation(permutation: &[usize], n: usize) -> bool {
+    if permutation.len() != n {
+        return false;
+    }
+    let mut seen = vec![false; n];
+    for &i in permutation {
+        if i >= n || seen[i] {
+            return false;
+        }
+        seen[i] = true;
+    }
+    true
+}
+
+/// Compute the challenge bits for every round.
+fn challenge_bits(
+    pk: &PublicKey,
+    inputs: &[Ciphertext],
+    outputs: &[Ciphertext],
+    shadows: &[Vec<Ciphertext>],
+) -> Vec<bool> {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp elgamal shuffle");
+    hash.input(pk.0.compress().as_bytes());
+    for ct in inputs.iter().chain(outputs.iter()).chain(shadows.iter().flat_map(|s| s.iter())) {
+        hash.input(ct.C1.compress().as_bytes());
+        hash.input(ct.C2.compress().as_bytes());
+    }
+    let digest = hash.result();
+    (0..ROUNDS).map(|t| (digest[t / 8] >> (t % 8)) & 1 == 1).collect()
+}
+
+/// Shuffle and re-encrypt `inputs` under `pk`.
+///
+/// Returns the outputs together with the witness needed to prove the
+/// shuffle was done correctly.
+#[cfg(not(feature = "ct-audit"))]
+pub fn shuffle<R: Rng>(
+    csprng: &mut R,
+    pk: &PublicKey,
+    inputs: &[Ciphertext],
+) -> (Vec<Ciphertext>, ShuffleWitness) {
+    let permutation = random_permutation(csprng, inputs.len());
+    let randomness: Vec<Scalar> = (0..inputs.len()).map(|_| Scalar::random(csprng)).collect();
+    let outputs = permutation.iter().zip(randomness.iter())
+        .map(|(&i, r)| pk.rerandomize(&inputs[i], r))
+        .collect();
+    (outputs, ShuffleWitness{ permutation: permutation, randomness: randomness })
+}
+
+impl ShuffleProof {
+    /// Prove that `outputs` is the shuffle of `inputs` described by
+    /// `witness`.
+    #[cfg(not(feature = "ct-audit"))]
+    pub fn create<R: Rng>(
+        csprng: &mut R,
+        pk: &PublicKey,
+        inputs: &[Ciphertext],
+        outputs: &[Ciphertext],
+        witness: &ShuffleWitness,
+    ) -> ShuffleProof {
+        let n = inputs.len();
+
+        // inverse[i] = j such that permutation[j] = i
+        let mut inverse = vec![0; n];
+        for (j, &i) in witness.permutation.iter().enumerate() {
+            inverse[i] = j;
+        }
+
+        let mut shadows = Vec::with_capacity(ROUNDS);
+        let mut shadow_openings = Vec::with_capacity(ROUNDS);
+        for _ in 0..ROUNDS {
+            let permutation = random_permutation(csprng, n);
+            let randomness: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+            shadows.push(permutation.iter().zip(randomness.iter())
+                         .map(|(&i, r)| pk.rerandomize(&inputs[i], r))
+                         .collect());
+            shadow_openings.push(ShadowOpening{ permutation: permutation, randomness: randomness });
+        }
+
+        let bits = challenge_bits(pk, inputs, outputs, &shadows);
+
+        let openings = shadow_openings.into_iter().zip(bits.into_iter()).map(|(opening, bit)| {
+            if !bit {
+                // Open the shadow as a shuffle of the inputs.
+                opening
+            } else {
+                // Open the shadow as a shuffle of the outputs:
+                // psi(i) = pi^-1(phi(i)), delta_i = rho_i - r_{psi(i)}.
+                let permutation: Vec<usize>
+                    = opening.permutation.iter().map(|&i| inverse[i]).collect();
+                let randomness = permutation.iter().zip(opening.randomness.iter())
+                    .map(|(&j, rho)| rho - &witness.randomness[j])
+                    .collect();
+                ShadowOpening{ permutation: permutation, randomness: randomness }
+            }
+        }).collect();
+
+        ShuffleProof{ shadows: shadows, openings: openings }
+    }
+
+    /// Verify that `outputs` is a shuffle of `inputs` under `pk`.
+    pub fn verify(
+        &self,
+        pk: &PublicKey,
+        inputs: &[Ciphertext],
+        outputs: &[Ciphertext],
+    ) -> Result<(),()> {
+        let n = inputs.len();
+        if outputs.len() != n || self.shadows.len() != ROUNDS || self.openings.len() != ROUNDS {
+            return Err(());
+        }
+
+        let bits = challenge_bits(pk, inputs, outputs, &self.shadows);
+
+        for ((shadow, opening), bit) in self.shadows.iter().zip(self.openings.iter()).zip(bits) {
+            if shadow.len() != n
+                || opening.randomness.len() != n
+                || !is_permutation(&opening.permutation, n)
+            {
+                return Err(());
+            }
+            let source = if bit { outputs } else { inputs };
+            for (i, ct) in shadow.iter().enumerate() {
+                let expected = pk.rerandomize(&source[opening.permutation[i]], &opening.randomness[i]);
+                if !ciphertexts_eq(ct, &expected) {
+                    return Err(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "ct-audit")))]
+mod tests {
+    use super::*;
+    use curve25519_dalek::decaf::DecafPoint;
+    use elgamal::SecretKey;
+    use rand::OsRng;
+
+    #[test]
+    fn shuffle_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let sk = SecretKey::generate(&mut csprng);
+        let pk = sk.public_key();
+
+        let plaintexts: Vec<DecafPoint> = (0..4).map(|_| DecafPoint::random(&mut csprng)).collect();
+        let inputs: Vec<Ciphertext> = plaintexts.iter().map(|M| pk.encrypt(&mut csprng, M).0).collect();
+
+        let (outputs, witness) = shuffle(&mut csprng, &pk, &inputs);
+        let proof = ShuffleProof::create(&mut csprng, &pk, &inputs, &outputs, &witness);
+        assert!(proof.verify(&pk, &inputs, &outputs).is_ok());
+
+        // The outputs decrypt to a permutation of the plaintexts.
+        for ct in &outputs {
+            let M = sk.decrypt(ct).compress();
+            assert!(plaintexts.iter().any(|P| P.compress() == M));
+        }
+
+        // Replacing an output breaks the proof.
+        let mut tampered = outputs.clone();
+        let M = DecafPoint::random(&mut csprng);
+        tampered[0] = pk.encrypt(&mut csprng, &M).0;
+        assert!(proof.verify(&pk, &inputs, &tampered).is_err());
+    }
+}
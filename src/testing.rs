@@ -0,0 +1,368 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Generators for property tests.
+//!
+//! These take any `rand::Rng`, such as a seeded `rand::ChaChaRng`.
+//! Satisfying instances of a statement are generated by the
+//! `random_instance(csprng)` function in each module created by
+//! `create_nipk!`.
+//!
+//! With the `property-testing` feature, `testing::strategies` has
+//! `proptest` strategies for scalars, points, seeded generators (from
+//! which to draw instances) and tampered proofs:
+//!
+//! ```rust,ignore
+//! proptest! {
+//!     #[test]
+//!     fn dleq_proofs(seed in strategies::seeds()) {
+//!         let mut rng = strategies::rng(seed);
+//!         let (publics, secrets) = dleq::random_instance(&mut rng);
+//!         let proof = dleq::Proof::create(&mut rng, publics.as_publics(), secrets.as_secrets());
+//!         prop_assert!(proof.verify(publics.as_publics()).is_ok());
+//!     }
+//! }
+//! ```
+//!
+//! There are no `quickcheck` generators: quickcheck's `Gen` is an
+//! `Rng` of a later version of `rand` than this crate's, so they
+//! would have to go through a seed in the same way.
+//!
+//! For negative tests, `tampered(proof)` lists the proofs made from a
+//! valid one by each single change of its encoding, and
+//! `check_tampering(proof, verify)` checks that `verify` rejects every
+//! one of them, so that a statement's tests can make sure no part of
+//! its proofs is ignored:
+//!
+//! ```rust,ignore
+//! assert_eq!(check_tampering(&proof, |p: &dleq::Proof| p.verify(publics)), Ok(()));
+//! ```
+//!
+//! For zero knowledge, `compare_distributions` checks that the
+//! encodings of real proofs, made with the secrets, look like those of
+//! simulated ones, made without, as a guard against biased nonces:
+//!
+//! ```rust,ignore
+//! assert!(compare_distributions(&mut csprng, 2000,
+//!     |rng| dleq::Proof::create(rng, publics, secrets).to_bytes(),
+//!     |rng| dleq::Proof::simulate(rng, publics).0.to_bytes(),
+//! ).is_ok());
+//! ```
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+use ProofBytes;
+
+/// A uniformly random scalar.
+///
+/// This reduces 64 random bytes modulo the group order, so the bias is
+/// negligible; reducing only 32 bytes would not be uniform.
+pub fn random_scalar<R: Rng>(csprng: &mut R) -> Scalar {
+    Scalar::random(csprng)
+}
+
+/// A uniformly random point, with unknown discrete logarithm.
+pub fn random_point<R: Rng>(csprng: &mut R) -> DecafPoint {
+    DecafPoint::random(csprng)
+}
+
+/// Corrupt a proof, by replacing one of its scalars (the challenge or
+/// a response) with a different random scalar.
+///
+/// The result is still a well-formed proof, so it can be used to check
+/// that verification, rather than parsing, rejects it.
+pub fn corrupt<P: ProofBytes, R: Rng>(csprng: &mut R, proof: &P) -> P {
+    let mut bytes = proof.to_bytes();
+    let i = 32 * csprng.gen_range(0, bytes.len() / 32);
+    loop {
+        let scalar = Scalar::random(csprng);
+        if &bytes[i..i + 32] != scalar.as_bytes() {
+            bytes[i..i + 32].copy_from_slice(scalar.as_bytes());
+            break;
+        }
+    }
+    P::from_bytes(&bytes).expect("scalars are canonically encoded")
+}
+
+/// One change to the encoding of a proof, made by `tampered`.  The
+/// scalars are numbered in the order of `to_bytes`, so scalar 0 is the
+/// challenge and the responses follow.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tamper {
+    /// Flip the lowest bit of the byte at this index.
+    FlipByte(usize),
+    /// Exchange two different scalars.
+    SwapScalars(usize, usize),
+    /// Replace a non-zero scalar with zero.
+    ZeroScalar(usize),
+}
+
+/// Every proof made from `proof` by one `Tamper`, with the change.
+///
+/// Changes which make the encoding invalid (a flipped byte giving a
+/// non-canonical scalar) are left out, since parsing already rejects
+/// them, as are swaps of equal scalars and zeroing a zero scalar,
+/// which change nothing.
+pub fn tampered<P: ProofBytes>(proof: &P) -> Vec<(Tamper, P)> {
+    let bytes = proof.to_bytes();
+    let scalars = bytes.len() / 32;
+    let mut changes = Vec::new();
+    for i in 0..bytes.len() {
+        changes.push(Tamper::FlipByte(i));
+    }
+    for i in 0..scalars {
+        for j in i + 1..scalars {
+            changes.push(Tamper::SwapScalars(i, j));
+        }
+    }
+    for i in 0..scalars {
+        changes.push(Tamper::ZeroScalar(i));
+    }
+
+    changes.into_iter().filter_map(|change| {
+        let mut changed = bytes.clone();
+        match change {
+            Tamper::FlipByte(i) => changed[i] ^= 1,
+            Tamper::SwapScalars(i, j) => {
+                for k in 0..32 {
+                    changed.swap(32 * i + k, 32 * j + k);
+                }
+            }
+            Tamper::ZeroScalar(i) => {
+                for byte in changed[32 * i..32 * i + 32].iter_mut() {
+                    *byte = 0;
+                }
+            }
+        }
+        if changed == bytes {
+            return None;
+        }
+        P::from_bytes(&changed).ok().map(|tampered| (change, tampered))
+    }).collect()
+}
+
+/// Check that `verify` rejects every proof in `tampered(proof)`,
+/// returning the first change it accepts otherwise.
+pub fn check_tampering<P: ProofBytes, F: Fn(&P) -> Result<(),()>>(proof: &P, verify: F) -> Result<(), Tamper> {
+    for (change, tampered) in tampered(proof) {
+        if verify(&tampered).is_ok() {
+            return Err(change);
+        }
+    }
+    Ok(())
+}
+
+/// A byte of the proof encodings whose values are distributed
+/// differently in the two samples of `compare_distributions`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Difference {
+    /// The index of the byte in the encodings.
+    pub byte: usize,
+    /// The chi-squared statistic of the two histograms of its values.
+    pub statistic: f64,
+    /// The statistic above which the histograms are flagged.
+    pub threshold: f64,
+}
+
+/// Compare `samples` encodings from `real` with as many from
+/// `simulated`, byte by byte, returning the first byte whose values
+/// differ detectably.
+///
+/// For each byte of the encodings, the histograms of its values in the
+/// two samples are compared with a two-sample chi-squared test, over
+/// the values which occur, with `k - 1` degrees of freedom for `k`
+/// such values.  A byte is flagged when the statistic exceeds the mean
+/// of `k - 1` by six standard deviations, which honest proofs do with
+/// negligible probability even over every byte.  About ten samples per
+/// value, so a few thousand, catch a bias of a few percent in any
+/// byte.  Panics if the encodings have different lengths.
+pub fn compare_distributions<R, F, G>(
+    csprng: &mut R,
+    samples: usize,
+    mut real: F,
+    mut simulated: G,
+) -> Result<(), Difference>
+    where R: Rng, F: FnMut(&mut R) -> Vec<u8>, G: FnMut(&mut R) -> Vec<u8>
+{
+    let mut histograms: Vec<[[u32; 256]; 2]> = Vec::new();
+    for _ in 0..samples {
+        for (side, bytes) in [real(csprng), simulated(csprng)].iter().enumerate() {
+            if histograms.is_empty() {
+                histograms = vec![[[0; 256]; 2]; bytes.len()];
+            }
+            assert_eq!(bytes.len(), histograms.len());
+            for (histogram, &byte) in histograms.iter_mut().zip(bytes.iter()) {
+                histogram[side][byte as usize] += 1;
+            }
+        }
+    }
+
+    for (byte, histogram) in histograms.iter().enumerate() {
+        let mut statistic = 0.0;
+        let mut values = 0;
+        for (&r, &s) in histogram[0].iter().zip(histogram[1].iter()) {
+            if r + s > 0 {
+                let difference = r as f64 - s as f64;
+                statistic += difference * difference / (r + s) as f64;
+                values += 1;
+            }
+        }
+        let freedom = (values as f64 - 1.0).max(1.0);
+        let threshold = freedom + 6.0 * (2.0 * freedom).sqrt();
+        if statistic > threshold {
+            return Err(Difference{ byte: byte, statistic: statistic, threshold: threshold });
+        }
+    }
+    Ok(())
+}
+
+/// `proptest` strategies, with the `property-testing` feature.
+///
+/// Each value is drawn from a `ChaChaRng` seeded by proptest, so it is
+/// distributed as with the generators above.  Shrinking changes the
+/// seed, so it gives unrelated values rather than smaller ones.
+#[cfg(feature = "property-testing")]
+pub mod strategies {
+    use std::fmt::Debug;
+
+    use curve25519_dalek::decaf::DecafPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use rand::{ChaChaRng, SeedableRng};
+
+    use ProofBytes;
+    use super::{tampered, Tamper};
+
+    /// Seeds for `rng`.
+    pub fn seeds() -> impl Strategy<Value = [u32; 8]> {
+        any::<[u32; 8]>()
+    }
+
+    /// The generator with the seed `seed`.
+    pub fn rng(seed: [u32; 8]) -> ChaChaRng {
+        ChaChaRng::from_seed(&seed[..])
+    }
+
+    /// Uniformly random scalars.
+    pub fn scalars() -> impl Strategy<Value = Scalar> {
+        seeds().prop_map(|seed| Scalar::random(&mut rng(seed)))
+    }
+
+    /// Uniformly random points, with unknown discrete logarithms.
+    pub fn points() -> impl Strategy<Value = DecafPoint> {
+        seeds().prop_map(|seed| DecafPoint::random(&mut rng(seed)))
+    }
+
+    /// The proofs in `tampered(proof)`, with their changes.
+    ///
+    /// Panics if there are none, as for a proof with no valid
+    /// tampering.
+    pub fn tampered_proofs<P>(proof: &P) -> impl Strategy<Value = (Tamper, P)>
+        where P: ProofBytes + Clone + Debug + 'static
+    {
+        select(tampered(proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+    create_nipk!{sum, (a, b, r), (C, D, G, H)
+                 : D = (G * b), (C - D) = (G * a + H * r)
+                 ; b = ((3) * a + (1)) }
+
+    #[test]
+    fn instances_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        for _ in 0..8 {
+            let (publics, secrets) = dleq::random_instance(&mut csprng);
+            let proof = dleq::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+            assert!(proof.verify(publics.as_publics()).is_ok());
+            assert!(corrupt(&mut csprng, &proof).verify(publics.as_publics()).is_err());
+
+            let (publics, secrets) = sum::random_instance(&mut csprng);
+            let proof = sum::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+            assert!(proof.verify(publics.as_publics()).is_ok());
+            assert!(corrupt(&mut csprng, &proof).verify(publics.as_publics()).is_err());
+        }
+    }
+
+    #[test]
+    fn zero_knowledge() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let publics = publics.as_publics();
+        let secrets = secrets.as_secrets();
+
+        let real = |rng: &mut OsRng| dleq::Proof::create(rng, publics, secrets).to_bytes();
+        let simulated = |rng: &mut OsRng| dleq::Proof::simulate(rng, publics).0.to_bytes();
+        assert_eq!(compare_distributions(&mut csprng, 1000, real, simulated), Ok(()));
+
+        // Responses whose low byte is always even are caught.
+        let biased = |rng: &mut OsRng| {
+            let mut bytes = dleq::Proof::create(rng, publics, secrets).to_bytes();
+            bytes[32] &= 0xfe;
+            bytes
+        };
+        let difference = compare_distributions(&mut csprng, 1000, biased, simulated).unwrap_err();
+        assert_eq!(difference.byte, 32);
+    }
+
+    #[test]
+    fn tampering() {
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = sum::random_instance(&mut csprng);
+        let publics = publics.as_publics();
+        let proof = sum::Proof::create(&mut csprng, publics, secrets.as_secrets());
+
+        // Every byte is flipped, and every pair of scalars swapped.
+        let changes: Vec<Tamper> = tampered(&proof).into_iter().map(|(change, _)| change).collect();
+        assert!(changes.len() >= 4 * 32 - 4 + 6 + 4 - 1);
+        assert!(changes.contains(&Tamper::SwapScalars(0, 3)));
+        assert!(changes.contains(&Tamper::ZeroScalar(0)));
+
+        assert_eq!(check_tampering(&proof, |p: &sum::Proof| p.verify(publics)), Ok(()));
+
+        // A verifier which only checks the challenge is caught.
+        let challenge = proof.to_bytes()[..32].to_vec();
+        let lax = |p: &sum::Proof| if p.to_bytes()[..32] == challenge[..] { Ok(()) } else { Err(()) };
+        assert_eq!(check_tampering(&proof, lax), Err(Tamper::FlipByte(32)));
+    }
+
+    #[cfg(feature = "property-testing")]
+    #[test]
+    fn strategies() {
+        use proptest::test_runner::TestRunner;
+
+        let mut runner = TestRunner::default();
+        runner.run(&strategies::seeds(), |seed| {
+            let mut rng = strategies::rng(seed);
+            let (publics, secrets) = sum::random_instance(&mut rng);
+            let proof = sum::Proof::create(&mut rng, publics.as_publics(), secrets.as_secrets());
+            assert!(proof.verify(publics.as_publics()).is_ok());
+            Ok(())
+        }).unwrap();
+
+        let mut csprng = OsRng::new().unwrap();
+        let (publics, secrets) = dleq::random_instance(&mut csprng);
+        let proof = dleq::Proof::create(&mut csprng, publics.as_publics(), secrets.as_secrets());
+        runner.run(&strategies::tampered_proofs(&proof), |(_, tampered)| {
+            assert!(tampered.verify(publics.as_publics()).is_err());
+            Ok(())
+        }).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Deterministic test vectors, for checking other implementations of
+//! the proofs generated by `create_nipk!`.
+//!
+//! Each module generated by `create_nipk!` has a function
+//! `test_vector(csprng, publics, secrets)` which creates a proof and
+//! records it as a `TestVector`.  Passing a seeded `Rng`, such as
+//! `rand::ChaChaRng::from_seed(...)`, makes the vectors reproducible:
+//!
+//! ```rust,ignore
+//! let mut csprng = ChaChaRng::from_seed(&[1, 2, 3, 4]);
+//! let vectors = vec![
+//!     dleq::test_vector(&mut csprng, publics, secrets),
+//!     ...
+//! ];
+//! println!("{}", testvectors::to_json(&vectors));
+//! ```
+//!
+//! All byte strings are written as lowercase hex.  The publics and
+//! secrets are the concatenations of their 32-byte encodings, in the
+//! order they were declared, the proof is as in `Proof::to_bytes`,
+//! and the transcript is as in `Proof::transcript`: the challenge is
+//! the SHA-512 hash of the transcript, reduced modulo the group
+//! order.
+
+use hex;
+
+/// One proof, together with its inputs and transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestVector {
+    /// The name of the module generated by `create_nipk!`.
+    pub name: String,
+    pub publics: Vec<u8>,
+    pub secrets: Vec<u8>,
+    pub proof: Vec<u8>,
+    pub transcript: Vec<u8>,
+}
+
+impl TestVector {
+    /// Write this vector as a JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"name\":\"{}\",\"publics\":\"{}\",\"secrets\":\"{}\",\"proof\":\"{}\",\"transcript\":\"{}\"}}",
+            self.name,
+            hex::encode(&self.publics),
+            hex::encode(&self.secrets),
+            hex::encode(&self.proof),
+            hex::encode(&self.transcript),
+        )
+    }
+}
+
+/// Write a list of vectors as a JSON array.
+pub fn to_json(vectors: &[TestVector]) -> String {
+    let objects: Vec<String> = vectors.iter().map(|v| v.to_json()).collect();
+    format!("[{}]", objects.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::decaf::DecafPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::{ChaChaRng, SeedableRng};
+    use sha2::{Digest, Sha512};
+
+    create_nipk!{dleq, (x), (A, B, G, H) : A = (G * x), B = (H * x) }
+
+    fn vectors(seed: u32) -> Vec<TestVector> {
+        let mut csprng = ChaChaRng::from_seed(&[seed]);
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let H = DecafPoint::random(&mut csprng);
+        (0..3).map(|_| {
+            let x = Scalar::random(&mut csprng);
+            let (A, B) = (G * &x, &H * &x);
+            dleq::test_vector(&mut csprng, dleq::Publics{A: &A, B: &B, G: G, H: &H}, dleq::Secrets{x: &x})
+        }).collect()
+    }
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(vectors(1), vectors(1));
+        assert!(vectors(1) != vectors(2));
+
+        let json = to_json(&vectors(1));
+        assert!(json.starts_with("[{\"name\":\"dleq\",\"publics\":\""));
+    }
+
+    #[test]
+    fn challenge_is_transcript_hash() {
+        for vector in vectors(1) {
+            let mut hash = Sha512::default();
+            hash.input(&vector.transcript);
+            let challenge = Scalar::from_hash(hash);
+            assert_eq!(&vector.proof[..32], challenge.as_bytes());
+            // The domain and the empty context, then each public and
+            // commitment framed with its kind, its label, as in
+            // `"A = G * x"`, and the lengths of both.
+            assert_eq!(vector.transcript.len(), 16 + 17 + 4 * (17 + 1 + 32) + 2 * (17 + 9 + 32));
+        }
+    }
+}
@@ -0,0 +1,337 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! FROST-style `t`-of-`n` threshold proofs of knowledge of a discrete
+//! logarithm.
+//!
+//! The secret `x` of the group key `X = G*x` is Shamir-shared among
+//! `n` participants, so that any `t` of them can jointly produce a
+//! normal `Proof` for the statement `X = (G * x)` without ever
+//! reconstructing `x`:
+//!
+//! 1. each participating `Share` calls `commit` and publishes its
+//!    `NonceCommitment` `(D_i, E_i)`;
+//! 2. given the list of all commitments, each participant computes
+//!    its binding factor `rho_i`, the group commitment
+//!    `R = sum_i (D_i + E_i*rho_i)`, the challenge `c`, and its
+//!    `PartialResponse` `z_i = d_i + e_i*rho_i + lambda_i*s_i*c`;
+//! 3. the coordinator checks each partial response against the
+//!    participant's `PublicShare` and aggregates them into a `Proof`.
+//!
+//! The binding factors tie each participant's nonces to the full set
+//! of commitments, preventing the Drijvers et al. attack on
+//! two-round threshold Schnorr.  Shares are created by a trusted
+//! dealer with `deal`.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+create_nipk!{dlog, (x), (X, G) : X = (G * x) }
+
+/// A proof of knowledge of the discrete log of the group key.
+pub use self::dlog::Proof;
+
+/// A participant's secret share `s_i = f(i)` of the group secret.
+pub struct Share {
+    index: u32,
+    secret: Scalar,
+}
+
+/// A participant's public share `Y_i = G*s_i`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PublicShare {
+    pub index: u32,
+    pub point: DecafPoint,
+}
+
+/// A participant's first-round message `(D_i, E_i) = (G*d_i, G*e_i)`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub index: u32,
+    pub D: DecafPoint,
+    pub E: DecafPoint,
+}
+
+/// A participant's secret nonces `(d_i, e_i)`, consumed by `respond`.
+pub struct NonceState {
+    index: u32,
+    d: Scalar,
+    e: Scalar,
+}
+
+/// A participant's second-round message `z_i`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PartialResponse {
+    pub index: u32,
+    pub z: Scalar,
+}
+
+/// Split `secret` into `n` shares, any `t` of which can prove
+/// knowledge of it.  Participants are numbered `1..n`.
+///
+/// Returns the group key `X = G*secret`, the secret shares, and the
+/// corresponding public shares.
+pub fn deal<R: Rng>(
+    csprng: &mut R,
+    secret: &Scalar,
+    t: usize,
+    n: usize,
+) -> (DecafPoint, Vec<Share>, Vec<PublicShare>) {
+    assert!(t >= 1 && t <= n, "threshold must be between 1 and n");
+    let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+    let mut coefficients = vec![*secret];
+    for _ in 1..t {
+        coefficients.push(Scalar::random(csprng));
+    }
+
+    let shares: Vec<Share> = (1..(n as u32 + 1)).map(|i| {
+        // Horner's rule for f(i)
+        let x = Scalar::from_u64(i as u64);
+        let mut y = Scalar::zero();
+        for a in coefficients.iter().rev() {
+            y = Scalar::multiply_add(&y, &x, a);
+        }
+        Share{ index: i, secret: y }
+    }).collect();
+    let public_shares = shares.iter().map(|s| s.public_share()).collect();
+
+    (G * secret, shares, public_shares)
+}
+
+/// The Lagrange coefficient of participant `i` for the set `indices`,
+/// evaluated at zero.
+fn lagrange_coefficient(i: u32, indices: &[u32]) -> Scalar {
+    let x_i = Scalar::from_u64(i as u64);
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for &j in indices {
+        if j == i {
+            continue;
+        }
+        let x_j = Scalar::from_u64(j as u64);
+        numerator = &numerator * &x_j;
+        denominator = &denominator * &(&x_j - &x_i);
+    }
+    &numerator * &denominator.invert()
+}
+
+fn binding_factor(index: u32, group_key: &DecafPoint, commitments: &[NonceCommitment]) -> Scalar {
+    let mut hash = Sha512::default();
+    hash.input(b"zkp frost binding");
+    hash.input(&[index as u8, (index >> 8) as u8, (index >> 16) as u8, (index >> 24) as u8]);
+    hash.input(group_key.compress().as_bytes());
+    for c in commitments {
+        let i = c.index;
+        hash.input(&[i as u8, (i >> 8) as u8, (i >> 16) as u8, (i >> 24) as u8]);
+        hash.input(c.D.compress().as_bytes());
+        hash.input(c.E.compress().as_bytes());
+    }
+    Scalar::from_hash(hash)
+}
+
+/// The data every participant derives from the list of commitments:
+/// the signing set, the binding factors, and the challenge.
+struct SigningPackage {
+    indices: Vec<u32>,
+    binding_factors: Vec<Scalar>,
+    challenge: Scalar,
+}
+
+impl SigningPackage {
+    fn new(group_key: &DecafPoint, commitments: &[NonceCommitment]) -> Result<SigningPackage, ()> {
+        if commitments.is_empty() {
+            return Err(());
+        }
+        let indices: Vec<u32> = commitments.iter().map(|c| c.index).collect();
+        for (k, i) in indices.iter().enumerate() {
+            if *i == 0 || indices[..k].contains(i) {
+                return Err(());
+            }
+        }
+
+        let binding_factors: Vec<Scalar> = indices.iter()
+            .map(|&i| binding_factor(i, group_key, commitments))
+            .collect();
+
+        let mut R = &commitments[0].D + &(&commitments[0].E * &binding_factors[0]);
+        for (c, rho) in commitments.iter().zip(binding_factors.iter()).skip(1) {
+            R = &R + &(&c.D + &(&c.E * rho));
+        }
+
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let challenge = dlog::challenge(
+            dlog::Publics{ X: group_key, G: G },
+            &[R],
+        );
+
+        Ok(SigningPackage{ indices: indices, binding_factors: binding_factors, challenge: challenge })
+    }
+
+    fn position(&self, index: u32) -> Option<usize> {
+        self.indices.iter().position(|&i| i == index)
+    }
+}
+
+impl Share {
+    /// This participant's index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// This participant's public share `Y_i = G*s_i`.
+    pub fn public_share(&self) -> PublicShare {
+        PublicShare{ index: self.index, point: &dalek_constants::DECAF_ED25519_BASEPOINT * &self.secret }
+    }
+
+    /// Generate this participant's first-round nonces.
+    pub fn commit<R: Rng>(&self, csprng: &mut R) -> (NonceState, NonceCommitment) {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let d = Scalar::random(csprng);
+        let e = Scalar::random(csprng);
+        let commitment = NonceCommitment{ index: self.index, D: G * &d, E: G * &e };
+        (NonceState{ index: self.index, d: d, e: e }, commitment)
+    }
+}
+
+impl NonceState {
+    /// Compute this participant's partial response, given the
+    /// commitments of every participant (including this one).
+    ///
+    /// Returns `Err(())` if `commitments` is empty, if this
+    /// participant's commitment is missing from it, or if two
+    /// commitments share an index.
+    pub fn respond(
+        self,
+        share: &Share,
+        group_key: &DecafPoint,
+        commitments: &[NonceCommitment],
+    ) -> Result<PartialResponse, ()> {
+        if share.index != self.index {
+            return Err(());
+        }
+        let package = SigningPackage::new(group_key, commitments)?;
+        let k = package.position(self.index).ok_or(())?;
+
+        let lambda = lagrange_coefficient(self.index, &package.indices);
+        let hiding = Scalar::multiply_add(&self.e, &package.binding_factors[k], &self.d);
+        let z = Scalar::multiply_add(&(&lambda * &package.challenge), &share.secret, &hiding);
+
+        Ok(PartialResponse{ index: self.index, z: z })
+    }
+}
+
+/// Check each participant's partial response and aggregate them into
+/// a `Proof` for the statement `X = (G * x)`.
+///
+/// `responses` must contain one response for each commitment, in any
+/// order.  Returns `Err(())` if any partial response is invalid, or if
+/// too few participants took part for the result to verify.
+pub fn aggregate(
+    group_key: &DecafPoint,
+    public_shares: &[PublicShare],
+    commitments: &[NonceCommitment],
+    responses: &[PartialResponse],
+) -> Result<Proof, ()> {
+    if responses.len() != commitments.len() {
+        return Err(());
+    }
+    let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+    let package = SigningPackage::new(group_key, commitments)?;
+
+    let mut z = Scalar::zero();
+    for (k, commitment) in commitments.iter().enumerate() {
+        let i = commitment.index;
+        let response = responses.iter().find(|r| r.index == i).ok_or(())?;
+        let Y = public_shares.iter().find(|y| y.index == i).ok_or(())?;
+
+        // G*z_i == D_i + E_i*rho_i + Y_i*(lambda_i*c)
+        let lambda = lagrange_coefficient(i, &package.indices);
+        let expected = &(&commitment.D + &(&commitment.E * &package.binding_factors[k]))
+            + &(&Y.point * &(&lambda * &package.challenge));
+        if (G * &response.z).compress() != expected.compress() {
+            return Err(());
+        }
+
+        z = &z + &response.z;
+    }
+
+    let proof = Proof::from_parts(package.challenge, dlog::Responses::from_array([z]));
+    proof.verify(dlog::Publics{ X: group_key, G: G })?;
+    Ok(proof)
+}
+
+/// Verify a threshold-generated proof against the group key.
+pub fn verify(group_key: &DecafPoint, proof: &Proof) -> Result<(),()> {
+    proof.verify(dlog::Publics{ X: group_key, G: &dalek_constants::DECAF_ED25519_BASEPOINT })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    fn run(shares: &[&Share], X: &DecafPoint, public_shares: &[PublicShare]) -> Result<Proof, ()> {
+        let mut csprng = OsRng::new().unwrap();
+        let (states, commitments): (Vec<_>, Vec<_>)
+            = shares.iter().map(|s| s.commit(&mut csprng)).unzip();
+        let responses: Vec<PartialResponse> = states.into_iter().zip(shares.iter())
+            .map(|(state, share)| state.respond(share, X, &commitments).unwrap())
+            .collect();
+        aggregate(X, public_shares, &commitments, &responses)
+    }
+
+    #[test]
+    fn two_of_three() {
+        let mut csprng = OsRng::new().unwrap();
+        let x = Scalar::random(&mut csprng);
+        let (X, shares, public_shares) = deal(&mut csprng, &x, 2, 3);
+
+        let proof = run(&[&shares[0], &shares[2]], &X, &public_shares).unwrap();
+        assert!(verify(&X, &proof).is_ok());
+
+        let proof = run(&[&shares[2], &shares[1], &shares[0]], &X, &public_shares).unwrap();
+        assert!(verify(&X, &proof).is_ok());
+
+        // A single share is below the threshold.
+        assert!(run(&[&shares[1]], &X, &public_shares).is_err());
+    }
+
+    #[test]
+    fn aggregate_rejects_bad_partial() {
+        let mut csprng = OsRng::new().unwrap();
+        let x = Scalar::random(&mut csprng);
+        let (X, shares, public_shares) = deal(&mut csprng, &x, 2, 2);
+
+        let (s0, c0) = shares[0].commit(&mut csprng);
+        let (s1, c1) = shares[1].commit(&mut csprng);
+        let commitments = [c0, c1];
+        let r0 = s0.respond(&shares[0], &X, &commitments).unwrap();
+        let mut r1 = s1.respond(&shares[1], &X, &commitments).unwrap();
+        r1.z = &r1.z + &Scalar::one();
+
+        assert!(aggregate(&X, &public_shares, &commitments, &[r0, r1]).is_err());
+    }
+
+    #[test]
+    fn no_commitments() {
+        let mut csprng = OsRng::new().unwrap();
+        let x = Scalar::random(&mut csprng);
+        let (X, shares, public_shares) = deal(&mut csprng, &x, 2, 2);
+
+        let (state, _) = shares[0].commit(&mut csprng);
+        assert!(state.respond(&shares[0], &X, &[]).is_err());
+        assert!(aggregate(&X, &public_shares, &[], &[]).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Transcripts binding proofs to their context, and to each other.
+//!
+//! A `Transcript` carries a 64-byte chaining value through a
+//! protocol.  The protocol can absorb its own context, such as session
+//! identifiers or earlier messages, with `append_message`.  Each proof
+//! created with `Proof::create_from_transcript` hashes the current
+//! state in front of its publics and commitments, and is then
+//! appended to the transcript itself, so every challenge depends on
+//! everything before it.  Appending replaces the state by
+//!
+//! ```text
+//! H(domain || state || len(label) || label || len(message) || message)
+//! ```
+//!
+//! where the domain is `"zkp transcript message"` or `"zkp transcript
+//! proof"`, the label of a proof is the name of its module, and the
+//! lengths are 8-byte little-endian integers.
+//!
+//! The verifier replays the same sequence with `append_message` and
+//! `Proof::verify_from_transcript`, which only advances the transcript
+//! when a proof is valid.
+//!
+//! # Multi-round protocols
+//!
+//! Protocols with more rounds than a single sigma protocol can be
+//! made non-interactive on the same transcript.  The prover appends
+//! its earlier messages, such as auxiliary commitments, with
+//! `append_point`, and draws each intermediate verifier challenge with
+//! `challenge_scalar`, which replaces the state by
+//!
+//! ```text
+//! H("zkp transcript challenge" || state || len(label) || label || len(0))
+//! ```
+//!
+//! and returns `H("zkp transcript scalar" || state)` reduced modulo the
+//! group order.  The last round is then a `create_nipk!` proof, made
+//! with `create_from_transcript`, over publics which may depend on the
+//! earlier challenges:
+//!
+//! ```rust,ignore
+//! let mut transcript = Transcript::new(b"my protocol");
+//! transcript.append_point(b"C", &C);
+//! let z = transcript.challenge_scalar(b"z");
+//! let D = &C * &z;
+//! let proof = dlog::Proof::create_from_transcript(
+//!     &mut csprng, &mut transcript, dlog::Publics{A: &D, G: G}, secrets);
+//! ```
+//!
+//! The protocol's message is then the auxiliary commitments together
+//! with the final proof, which both have serde and byte encodings.
+//!
+//! # Audit logs
+//!
+//! A transcript started with `Transcript::recording(label)` also keeps
+//! a log of everything absorbed into it, as a list of `Absorbed`
+//! entries, returned by `log()`.  Besides the messages, challenges and
+//! proofs appended to the transcript, the log holds each proof's
+//! challenge inputs: the transcript state it was bound to (`"context"`),
+//! then its publics (`"public"`) and commitments (`"commitment"`) in
+//! the order they were hashed.  For a proof made outside of a
+//! transcript, `Proof::absorbed(publics)` returns the same public and
+//! commitment entries.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+
+/// The state of a sequence of proofs.
+#[derive(Clone)]
+pub struct Transcript {
+    state: [u8; 64],
+    log: Option<Vec<Absorbed>>,
+}
+
+/// One labelled input absorbed into a transcript or a challenge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Absorbed {
+    /// What the input is: `"label"`, `"message"`, `"challenge"`,
+    /// `"proof"`, `"context"`, `"public"` or `"commitment"`.
+    pub kind: &'static str,
+    /// The label of a message or challenge, the name of a proof's
+    /// module, or the name of a public or statement.
+    pub label: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Absorbed {
+    #[doc(hidden)]
+    pub fn __new(kind: &'static str, label: &str, bytes: &[u8]) -> Absorbed {
+        Absorbed{ kind: kind, label: label.to_string(), bytes: bytes.to_vec() }
+    }
+}
+
+impl Transcript {
+    /// Start a transcript, with a label identifying the protocol.
+    pub fn new(label: &[u8]) -> Transcript {
+        let mut hash = Sha512::default();
+        hash.input(b"zkp transcript");
+        hash.input(label);
+        let mut state = [0u8; 64];
+        state.copy_from_slice(&hash.result()[..]);
+        Transcript{ state: state, log: None }
+    }
+
+    /// Start a transcript, like `new`, which also records everything
+    /// absorbed into it.
+    pub fn recording(label: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new(label);
+        transcript.log = Some(vec![Absorbed{
+            kind: "label",
+            label: String::from_utf8_lossy(label).into_owned(),
+            bytes: Vec::new(),
+        }]);
+        transcript
+    }
+
+    /// The entries absorbed so far, if this transcript is recording.
+    pub fn log(&self) -> Option<&[Absorbed]> {
+        self.log.as_ref().map(|log| &log[..])
+    }
+
+    /// Record the inputs to a proof's challenge, if this transcript
+    /// is recording.
+    #[doc(hidden)]
+    pub fn __record_challenge<F: FnOnce() -> Vec<Absorbed>>(&mut self, name: &str, inputs: F) {
+        let state = self.state;
+        if let Some(ref mut log) = self.log {
+            log.push(Absorbed::__new("context", name, &state[..]));
+            log.extend(inputs());
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __state(&self) -> &[u8] {
+        &self.state[..]
+    }
+
+    /// Absorb a labelled message into the transcript.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        self.record("message", label, message);
+        self.append(b"zkp transcript message", label, &[message]);
+    }
+
+    /// Absorb a labelled point, such as an auxiliary commitment, into
+    /// the transcript.
+    pub fn append_point(&mut self, label: &[u8], point: &DecafPoint) {
+        self.append_message(label, point.compress().as_bytes());
+    }
+
+    /// Draw a challenge scalar depending on everything absorbed so far,
+    /// and advance the transcript.
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> Scalar {
+        self.record("challenge", label, &[]);
+        self.append::<&[u8]>(b"zkp transcript challenge", label, &[]);
+        let mut hash = Sha512::default();
+        hash.input(b"zkp transcript scalar");
+        hash.input(&self.state[..]);
+        Scalar::from_hash(hash)
+    }
+
+    /// Absorb a proof, given as the 32-byte chunks of its encoding, so
+    /// that callers need not allocate the encoding.
+    #[doc(hidden)]
+    pub fn __append_proof(&mut self, name: &str, proof: &[&[u8; 32]]) {
+        if self.log.is_some() {
+            let bytes: Vec<u8> = proof.iter().flat_map(|chunk| chunk.iter().cloned()).collect();
+            self.record("proof", name.as_bytes(), &bytes);
+        }
+        self.append(b"zkp transcript proof", name.as_bytes(), proof);
+    }
+
+    fn record(&mut self, kind: &'static str, label: &[u8], bytes: &[u8]) {
+        if let Some(ref mut log) = self.log {
+            log.push(Absorbed{
+                kind: kind,
+                label: String::from_utf8_lossy(label).into_owned(),
+                bytes: bytes.to_vec(),
+            });
+        }
+    }
+
+    /// Absorb a message given as the concatenation of `chunks`.
+    fn append<M: AsRef<[u8]>>(&mut self, domain: &[u8], label: &[u8], chunks: &[M]) {
+        let length = chunks.iter().map(|chunk| chunk.as_ref().len()).sum();
+        let mut hash = Sha512::default();
+        hash.input(domain);
+        hash.input(&self.state[..]);
+        hash.input(&::length_bytes(label.len()));
+        hash.input(label);
+        hash.input(&::length_bytes(length));
+        for chunk in chunks {
+            hash.input(chunk.as_ref());
+        }
+        self.state.copy_from_slice(&hash.result()[..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+
+    #[test]
+    fn chained_proofs() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let y = Scalar::random(&mut csprng);
+        let (A, B) = (G * &x, G * &y);
+        let first = dlog::Publics{A: &A, G: G};
+        let second = dlog::Publics{A: &B, G: G};
+
+        let mut prover = Transcript::new(b"test protocol");
+        let proof1 = dlog::Proof::create_from_transcript(&mut csprng, &mut prover, first, dlog::Secrets{x: &x});
+        let proof2 = dlog::Proof::create_from_transcript(&mut csprng, &mut prover, second, dlog::Secrets{x: &y});
+
+        let mut verifier = Transcript::new(b"test protocol");
+        assert!(proof1.verify_from_transcript(&mut verifier, first).is_ok());
+        assert!(proof2.verify_from_transcript(&mut verifier, second).is_ok());
+
+        // The second proof is bound to the first.
+        assert!(proof2.verify(second).is_err());
+        let mut verifier = Transcript::new(b"test protocol");
+        assert!(proof2.verify_from_transcript(&mut verifier, second).is_err());
+
+        let mut verifier = Transcript::new(b"other protocol");
+        assert!(proof1.verify_from_transcript(&mut verifier, first).is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+        let publics = dlog::Publics{A: &A, G: G};
+
+        let mut prover = Transcript::new(b"test protocol");
+        prover.append_message(b"session", b"1234");
+        let proof = dlog::Proof::create_from_transcript(&mut csprng, &mut prover, publics, dlog::Secrets{x: &x});
+
+        let mut verifier = Transcript::new(b"test protocol");
+        verifier.append_message(b"session", b"1234");
+        assert!(proof.verify_from_transcript(&mut verifier, publics).is_ok());
+
+        let mut verifier = Transcript::new(b"test protocol");
+        verifier.append_message(b"session", b"1235");
+        assert!(proof.verify_from_transcript(&mut verifier, publics).is_err());
+
+        // Moving bytes between the label and the message changes the
+        // transcript.
+        let mut verifier = Transcript::new(b"test protocol");
+        verifier.append_message(b"session1", b"234");
+        assert!(proof.verify_from_transcript(&mut verifier, publics).is_err());
+    }
+
+    #[test]
+    fn two_rounds() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let y = Scalar::random(&mut csprng);
+        let (A, B) = (G * &x, G * &y);
+
+        // Prove knowledge of both discrete logarithms at once, with a
+        // random linear combination chosen after committing to them.
+        let prove = |csprng: &mut OsRng| {
+            let mut transcript = Transcript::new(b"two dlogs");
+            transcript.append_point(b"A", &A);
+            transcript.append_point(b"B", &B);
+            let z = transcript.challenge_scalar(b"z");
+            let D = &A + &(&B * &z);
+            let w = Scalar::multiply_add(&z, &y, &x);
+            dlog::Proof::create_from_transcript(csprng, &mut transcript, dlog::Publics{A: &D, G: G}, dlog::Secrets{x: &w})
+        };
+        let verify = |proof: &dlog::Proof, B: &DecafPoint| {
+            let mut transcript = Transcript::new(b"two dlogs");
+            transcript.append_point(b"A", &A);
+            transcript.append_point(b"B", B);
+            let z = transcript.challenge_scalar(b"z");
+            let D = &A + &(B * &z);
+            proof.verify_from_transcript(&mut transcript, dlog::Publics{A: &D, G: G})
+        };
+
+        let proof = prove(&mut csprng);
+        assert!(verify(&proof, &B).is_ok());
+        assert!(verify(&proof, &(&B + G)).is_err());
+
+        let mut transcript = Transcript::new(b"two dlogs");
+        let z1 = transcript.challenge_scalar(b"z");
+        let z2 = transcript.challenge_scalar(b"z");
+        assert!(z1 != z2);
+    }
+
+    #[test]
+    fn recording() {
+        let mut csprng = OsRng::new().unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+        let publics = dlog::Publics{A: &A, G: G};
+
+        let mut transcript = Transcript::recording(b"test protocol");
+        transcript.append_message(b"session", b"1234");
+        let proof = dlog::Proof::create_from_transcript(&mut csprng, &mut transcript, publics, dlog::Secrets{x: &x});
+        assert!(Transcript::new(b"test protocol").log().is_none());
+
+        let kinds: Vec<&str> = transcript.log().unwrap().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec!["label", "message", "context", "public", "public", "commitment", "proof"]);
+
+        // The verifier hashes the same inputs.
+        let mut verifier = Transcript::recording(b"test protocol");
+        verifier.append_message(b"session", b"1234");
+        assert!(proof.verify_from_transcript(&mut verifier, publics).is_ok());
+        assert_eq!(verifier.log(), transcript.log());
+
+        let log = transcript.log().unwrap();
+        assert_eq!(log[3].label, "A");
+        assert_eq!(log[5].label, "A = G * x");
+        assert_eq!(&log[3..6], &proof.absorbed(publics)[..]);
+        let challenge_input = ::__challenge_input(::Version::CURRENT, &log[2].bytes, &log[3..6]);
+        let mut hash = Sha512::default();
+        hash.input(&challenge_input);
+        assert_eq!(&proof.to_bytes()[..32], Scalar::from_hash(hash).as_bytes());
+    }
+}
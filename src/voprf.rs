@@ -0,0 +1,255 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! A verifiable oblivious PRF, following the CFRG VOPRF design, in the
+//! Decaf group with SHA-512.
+//!
+//! The server has a key `k`, with public key `K = G*k`, and the PRF is
+//!
+//! ```text
+//! F(k, x) = SHA-512(len(x) || x || len(N) || N || "Finalize"),  N = H(x)*k
+//! ```
+//!
+//! with the lengths as 2-byte big-endian integers, so that inputs are
+//! at most `MAX_INPUT_LEN` bytes, and `H` hashing to the group with
+//! the domain separation tag `"HashToGroup-" || CONTEXT`.  A client learns `F(k, x)` for its
+//! inputs without revealing them, in one round trip:
+//!
+//! 1. the client sends the `BlindedElements` `M = H(x)*r`, for a fresh
+//!    random `r` per input;
+//! 2. the server sends the `Evaluations` `Z = M*k`, with one
+//!    `zkp::batch_dleq` proof that every `Z` was computed with the key
+//!    of `K`;
+//! 3. the client checks the proof against its own `M`, unblinds each
+//!    `Z` to `N = Z*(1/r)`, and hashes it into the output.
+//!
+//! The server computes `F(k, x)` for inputs it knows with
+//! `ServerKey::evaluate_input`.  As in `zkp::privacypass`, the client's
+//! state is consumed when it finalizes.  Since the hash to the group
+//! and the DLEQ proofs are this crate's, outputs only agree with other
+//! implementations of this suite, `CONTEXT`.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use batch_dleq::{self, BatchDleqProof};
+
+/// The context string of this suite: the protocol version, the mode
+/// (verifiable, `0x01`), and the suite name.
+pub const CONTEXT: &'static [u8] = b"VOPRF08-\x01zkp-decaf-SHA512";
+
+/// The length of the longest input, whose length fits in the 2 bytes
+/// hashed before it.
+pub const MAX_INPUT_LEN: usize = 0xffff;
+
+/// The server's key `(k, K = G*k)`.
+pub struct ServerKey {
+    secret: Scalar,
+    public: DecafPoint,
+}
+
+/// The client's first message, the blinded inputs `M = H(x)*r`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlindedElements(pub Vec<DecafPoint>);
+
+/// The server's response, `Z = M*k` with a proof that `k` is the key
+/// of `K`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Evaluations {
+    pub points: Vec<DecafPoint>,
+    pub proof: BatchDleqProof,
+}
+
+/// Client state after sending `BlindedElements`, waiting for the
+/// server's response.
+pub struct PendingEvaluations {
+    public: DecafPoint,
+    inputs: Vec<Vec<u8>>,
+    blinds: Vec<Scalar>,
+    blinded: Vec<DecafPoint>,
+}
+
+/// Hash an input to the group.
+fn hash_to_group(input: &[u8]) -> DecafPoint {
+    let mut bytes = Vec::with_capacity(12 + CONTEXT.len() + input.len());
+    bytes.extend_from_slice(b"HashToGroup-");
+    bytes.extend_from_slice(CONTEXT);
+    bytes.extend_from_slice(input);
+    DecafPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+fn input_length(hash: &mut Sha512, length: usize) {
+    debug_assert!(length <= MAX_INPUT_LEN);
+    hash.input(&[(length >> 8) as u8, length as u8]);
+}
+
+/// The PRF output for `input`, given `N = H(input)*k`.
+fn finalize(input: &[u8], point: &DecafPoint) -> [u8; 64] {
+    let mut hash = Sha512::default();
+    input_length(&mut hash, input.len());
+    hash.input(input);
+    input_length(&mut hash, 32);
+    hash.input(point.compress().as_bytes());
+    hash.input(b"Finalize");
+    let mut output = [0u8; 64];
+    output.copy_from_slice(&hash.result()[..]);
+    output
+}
+
+impl ServerKey {
+    /// Generate a fresh server key.
+    pub fn generate<R: Rng>(csprng: &mut R) -> ServerKey {
+        ServerKey::from_secret(Scalar::random(csprng))
+    }
+
+    /// Construct a server key from an existing secret scalar.
+    pub fn from_secret(secret: Scalar) -> ServerKey {
+        let public = &dalek_constants::DECAF_ED25519_BASEPOINT * &secret;
+        ServerKey{ secret: secret, public: public }
+    }
+
+    /// The public key `K = G*k`, which clients check evaluations
+    /// against.
+    pub fn public(&self) -> DecafPoint {
+        self.public
+    }
+
+    /// Evaluate a batch of blinded inputs.
+    ///
+    /// Returns `Err(())` if the batch is empty.
+    pub fn evaluate<R: Rng>(&self, csprng: &mut R, blinded: &BlindedElements) -> Result<Evaluations,()> {
+        let points: Vec<DecafPoint> = blinded.0.iter().map(|M| M * &self.secret).collect();
+        let proof = batch_dleq::prove(
+            csprng,
+            &dalek_constants::DECAF_ED25519_BASEPOINT,
+            &self.public,
+            &self.secret,
+            &blinded.0,
+            &points,
+        )?;
+        Ok(Evaluations{ points: points, proof: proof })
+    }
+
+    /// The PRF output for an input the server knows.
+    ///
+    /// Returns `Err(())` if the input is longer than `MAX_INPUT_LEN`.
+    pub fn evaluate_input(&self, input: &[u8]) -> Result<[u8; 64],()> {
+        if input.len() > MAX_INPUT_LEN {
+            return Err(());
+        }
+        Ok(finalize(input, &(&hash_to_group(input) * &self.secret)))
+    }
+}
+
+impl PendingEvaluations {
+    /// Blind `inputs` for evaluation by the server with public key
+    /// `public`, producing the message to send to the server.
+    ///
+    /// Returns `Err(())` if an input is longer than `MAX_INPUT_LEN`.
+    pub fn blind<R: Rng>(
+        csprng: &mut R,
+        public: &DecafPoint,
+        inputs: &[&[u8]],
+    ) -> Result<(PendingEvaluations, BlindedElements),()> {
+        if inputs.iter().any(|input| input.len() > MAX_INPUT_LEN) {
+            return Err(());
+        }
+        let blinds: Vec<Scalar> = inputs.iter().map(|_| Scalar::random(csprng)).collect();
+        let blinded: Vec<DecafPoint> = inputs.iter().zip(blinds.iter())
+            .map(|(input, r)| &hash_to_group(input) * r)
+            .collect();
+        let pending = PendingEvaluations{
+            public: *public,
+            inputs: inputs.iter().map(|input| input.to_vec()).collect(),
+            blinds: blinds,
+            blinded: blinded.clone(),
+        };
+        Ok((pending, BlindedElements(blinded)))
+    }
+
+    /// Check the server's proof and compute the PRF outputs, in the
+    /// order of the inputs, ending the session.
+    ///
+    /// Returns `Err(())` if the response is for a different number of
+    /// inputs, or its proof does not verify against the server's
+    /// public key and the elements this client blinded.
+    pub fn finalize(self, evaluations: &Evaluations) -> Result<Vec<[u8; 64]>,()> {
+        batch_dleq::verify(
+            &dalek_constants::DECAF_ED25519_BASEPOINT,
+            &self.public,
+            &self.blinded,
+            &evaluations.points,
+            &evaluations.proof,
+        )?;
+        let outputs = self.inputs.iter()
+            .zip(self.blinds.iter())
+            .zip(evaluations.points.iter())
+            .map(|((input, r), Z)| finalize(input, &(Z * &r.invert())))
+            .collect();
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn oblivious_evaluation() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = ServerKey::generate(&mut csprng);
+        let inputs: &[&[u8]] = &[b"alice@example.com", b"bob@example.com"];
+
+        let (pending, blinded) = PendingEvaluations::blind(&mut csprng, &key.public(), inputs).unwrap();
+        let evaluations = key.evaluate(&mut csprng, &blinded).unwrap();
+        let outputs = pending.finalize(&evaluations).unwrap();
+        assert_eq!(&outputs[0][..], &key.evaluate_input(inputs[0]).unwrap()[..]);
+        assert_eq!(&outputs[1][..], &key.evaluate_input(inputs[1]).unwrap()[..]);
+        assert!(outputs[0][..] != outputs[1][..]);
+
+        // Blinding is randomized, but the outputs are not.
+        let (pending, blinded_again) = PendingEvaluations::blind(&mut csprng, &key.public(), inputs).unwrap();
+        assert!(blinded_again.0[0].compress() != blinded.0[0].compress());
+        let outputs_again = pending.finalize(&key.evaluate(&mut csprng, &blinded_again).unwrap()).unwrap();
+        assert_eq!(&outputs_again[0][..], &outputs[0][..]);
+    }
+
+    #[test]
+    fn unverifiable_evaluation() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = ServerKey::generate(&mut csprng);
+        let other = ServerKey::generate(&mut csprng);
+        let inputs: &[&[u8]] = &[b"input"];
+
+        let (pending, blinded) = PendingEvaluations::blind(&mut csprng, &key.public(), inputs).unwrap();
+        let evaluations = other.evaluate(&mut csprng, &blinded).unwrap();
+        assert!(pending.finalize(&evaluations).is_err());
+    }
+
+    #[test]
+    fn long_inputs() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = ServerKey::generate(&mut csprng);
+        let longest = vec![0u8; MAX_INPUT_LEN];
+        let too_long = vec![0u8; MAX_INPUT_LEN + 1];
+
+        let (pending, blinded) = PendingEvaluations::blind(&mut csprng, &key.public(), &[&longest[..]]).unwrap();
+        let outputs = pending.finalize(&key.evaluate(&mut csprng, &blinded).unwrap()).unwrap();
+        assert_eq!(&outputs[0][..], &key.evaluate_input(&longest).unwrap()[..]);
+
+        assert!(PendingEvaluations::blind(&mut csprng, &key.public(), &[&b"short"[..], &too_long[..]]).is_err());
+        assert!(key.evaluate_input(&too_long).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Feldman verifiable secret sharing.
+//!
+//! A dealer shares a secret `a_0` among `n` participants, numbered
+//! `1..n`, with a random polynomial `f(z) = a_0 + a_1*z + ... +
+//! a_{t-1}*z^{t-1}`, publishing the `VssCommitment` `A_j = G*a_j` to its
+//! coefficients and sending participant `i` its `SecretShare`
+//! `s_i = f(i)`.  Anyone can compute the public share
+//!
+//! ```text
+//! Y_i = G*s_i = sum_j A_j * i^j
+//! ```
+//!
+//! from the commitment, so a participant checks its share with
+//! `VssCommitment::verify_share` before accepting it, and any `t`
+//! shares determine `a_0`.
+//!
+//! Two proofs of knowledge go with a dealing, both of the statement
+//! `X = (G * x)`, in a transcript labelled `"zkp vss"` to which the
+//! caller's context and every `A_j` are appended:
+//!
+//! * the dealer proves knowledge of `a_0` for `A_0` with
+//!   `prove_dealing`, so that in a distributed key generation a
+//!   dealer cannot choose its `A_0` as a function of the others';
+//! * a participant proves knowledge of its share `s_i` for `Y_i` with
+//!   `SecretShare::prove`, e.g. to show the others that it has
+//!   accepted it, without revealing it.
+//!
+//! In a distributed key generation, every participant deals, and
+//! `VssCommitment::sum` and `SecretShare::sum` add up the dealings,
+//! into the commitment to the group key and each participant's share
+//! of it.
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use serde::{Deserialize, Deserializer};
+use serde::de::Error;
+
+use transcript::Transcript;
+
+create_nipk!{dlog, (x), (X, G) : X = (G * x) }
+
+/// A proof of knowledge of a dealt secret or a share.
+pub use self::dlog::Proof as VssProof;
+
+/// The public commitments `A_j = G*a_j` to the coefficients of a
+/// dealer's polynomial.
+///
+/// There is always at least one, `A_0`: an empty commitment is
+/// refused by `VssCommitment::new` and by deserialization.
+#[derive(Clone, Serialize)]
+pub struct VssCommitment(Vec<DecafPoint>);
+
+impl<'de> Deserialize<'de> for VssCommitment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<VssCommitment, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "VssCommitment")]
+        struct Points(Vec<DecafPoint>);
+
+        let Points(points) = Points::deserialize(deserializer)?;
+        VssCommitment::new(points).map_err(|_| D::Error::custom("empty commitment"))
+    }
+}
+
+/// Participant `index`'s share `s_i = f(i)` of a secret.
+pub struct SecretShare {
+    index: u32,
+    value: Scalar,
+}
+
+fn transcript(commitment: &VssCommitment, role: &[u8], context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"zkp vss");
+    transcript.append_message(b"context", context);
+    for A_j in &commitment.0 {
+        transcript.append_point(b"A", A_j);
+    }
+    transcript.append_message(b"role", role);
+    transcript
+}
+
+/// The role of participant `index` in its share proofs.
+fn share_role(index: u32) -> [u8; 5] {
+    [b's', index as u8, (index >> 8) as u8, (index >> 16) as u8, (index >> 24) as u8]
+}
+
+/// Share `secret` among `n` participants, any `t` of whom can recover
+/// it.
+///
+/// Returns the commitment to the polynomial, and the shares of
+/// participants `1..n`.
+pub fn deal<R: Rng>(
+    csprng: &mut R,
+    secret: &Scalar,
+    t: usize,
+    n: usize,
+) -> (VssCommitment, Vec<SecretShare>) {
+    assert!(t >= 1 && t <= n, "threshold must be between 1 and n");
+    let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+
+    let mut coefficients = vec![*secret];
+    for _ in 1..t {
+        coefficients.push(Scalar::random(csprng));
+    }
+
+    let shares = (1..(n as u32 + 1)).map(|i| {
+        // Horner's rule for f(i)
+        let x = Scalar::from_u64(i as u64);
+        let mut y = Scalar::zero();
+        for a in coefficients.iter().rev() {
+            y = Scalar::multiply_add(&y, &x, a);
+        }
+        SecretShare{ index: i, value: y }
+    }).collect();
+    let commitment = VssCommitment(coefficients.iter().map(|a| G * a).collect());
+
+    (commitment, shares)
+}
+
+/// Prove knowledge of the secret `a_0` dealt with `commitment`.
+pub fn prove_dealing<R: Rng>(
+    csprng: &mut R,
+    commitment: &VssCommitment,
+    secret: &Scalar,
+    context: &[u8],
+) -> VssProof {
+    VssProof::create_from_transcript(
+        csprng,
+        &mut transcript(commitment, b"dealer", context),
+        dlog::Publics{ X: &commitment.0[0], G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+        dlog::Secrets{ x: secret },
+    )
+}
+
+/// Verify a dealer's proof of knowledge of the secret dealt with
+/// `commitment`.
+pub fn verify_dealing(commitment: &VssCommitment, context: &[u8], proof: &VssProof) -> Result<(),()> {
+    proof.verify_from_transcript(
+        &mut transcript(commitment, b"dealer", context),
+        dlog::Publics{ X: &commitment.0[0], G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+    )
+}
+
+impl VssCommitment {
+    /// The commitment to the coefficients `A_0, A_1, ...`, in order.
+    ///
+    /// Returns `Err(())` if there are none.
+    pub fn new(points: Vec<DecafPoint>) -> Result<VssCommitment,()> {
+        if points.is_empty() {
+            return Err(());
+        }
+        Ok(VssCommitment(points))
+    }
+
+    /// The commitments `A_j`, in order.
+    pub fn points(&self) -> &[DecafPoint] {
+        &self.0
+    }
+
+    /// The number of shares needed to recover the secret.
+    pub fn threshold(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The commitment `A_0 = G*a_0` to the secret.
+    pub fn public_key(&self) -> DecafPoint {
+        self.0[0]
+    }
+
+    /// The public share `Y_i = sum_j A_j * i^j` of participant `index`.
+    pub fn public_share(&self, index: u32) -> DecafPoint {
+        // Horner's rule for sum_j A_j * i^j
+        let x = Scalar::from_u64(index as u64);
+        let mut Y = self.0[self.0.len() - 1];
+        for A_j in self.0.iter().rev().skip(1) {
+            Y = &(&Y * &x) + A_j;
+        }
+        Y
+    }
+
+    /// Check that `share` is consistent with this commitment.
+    ///
+    /// Returns `Err(())` for the share of participant zero, which
+    /// would be the secret itself.
+    pub fn verify_share(&self, share: &SecretShare) -> Result<(),()> {
+        if share.index == 0 {
+            return Err(());
+        }
+        let Y = &dalek_constants::DECAF_ED25519_BASEPOINT * &share.value;
+        if Y.compress() == self.public_share(share.index).compress() { Ok(()) } else { Err(()) }
+    }
+
+    /// Verify participant `index`'s proof of knowledge of its share.
+    pub fn verify_share_proof(&self, index: u32, context: &[u8], proof: &VssProof) -> Result<(),()> {
+        if index == 0 {
+            return Err(());
+        }
+        proof.verify_from_transcript(
+            &mut transcript(self, &share_role(index), context),
+            dlog::Publics{ X: &self.public_share(index), G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+        )
+    }
+
+    /// Add up the commitments of several dealings with the same
+    /// threshold, into the commitment to the sum of their secrets.
+    ///
+    /// Returns `Err(())` if there are none, or their thresholds differ.
+    pub fn sum(commitments: &[VssCommitment]) -> Result<VssCommitment,()> {
+        let t = commitments.first().ok_or(())?.threshold();
+        if commitments.iter().any(|c| c.threshold() != t) {
+            return Err(());
+        }
+        let sums = (0..t).map(|j| {
+            let mut sum = commitments[0].0[j];
+            for c in &commitments[1..] {
+                sum = &sum + &c.0[j];
+            }
+            sum
+        }).collect();
+        Ok(VssCommitment(sums))
+    }
+}
+
+impl SecretShare {
+    /// Construct participant `index`'s share with the value `value`.
+    pub fn from_parts(index: u32, value: Scalar) -> SecretShare {
+        SecretShare{ index: index, value: value }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn value(&self) -> &Scalar {
+        &self.value
+    }
+
+    /// Prove knowledge of this share, for the public share computed
+    /// from `commitment`.
+    pub fn prove<R: Rng>(&self, csprng: &mut R, commitment: &VssCommitment, context: &[u8]) -> VssProof {
+        let Y = &dalek_constants::DECAF_ED25519_BASEPOINT * &self.value;
+        VssProof::create_from_transcript(
+            csprng,
+            &mut transcript(commitment, &share_role(self.index), context),
+            dlog::Publics{ X: &Y, G: &dalek_constants::DECAF_ED25519_BASEPOINT },
+            dlog::Secrets{ x: &self.value },
+        )
+    }
+
+    /// Add up one participant's shares of several dealings.
+    ///
+    /// Returns `Err(())` if there are none, or they are not all for the
+    /// same participant.
+    pub fn sum(shares: &[SecretShare]) -> Result<SecretShare,()> {
+        let index = shares.first().ok_or(())?.index;
+        if shares.iter().any(|s| s.index != index) {
+            return Err(());
+        }
+        let mut value = Scalar::zero();
+        for share in shares {
+            value = &value + &share.value;
+        }
+        Ok(SecretShare{ index: index, value: value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn deal_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+        let secret = Scalar::random(&mut csprng);
+        let (commitment, shares) = deal(&mut csprng, &secret, 3, 5);
+        assert_eq!(commitment.threshold(), 3);
+        assert_eq!(commitment.public_key().compress(),
+                   (&dalek_constants::DECAF_ED25519_BASEPOINT * &secret).compress());
+
+        for share in &shares {
+            assert!(commitment.verify_share(share).is_ok());
+            let proof = share.prove(&mut csprng, &commitment, b"dkg 1");
+            assert!(commitment.verify_share_proof(share.index(), b"dkg 1", &proof).is_ok());
+            assert!(commitment.verify_share_proof(share.index() % 5 + 1, b"dkg 1", &proof).is_err());
+            assert!(commitment.verify_share_proof(share.index(), b"dkg 2", &proof).is_err());
+        }
+        let bad = SecretShare::from_parts(2, shares[1].value() + &Scalar::one());
+        assert!(commitment.verify_share(&bad).is_err());
+        assert!(commitment.verify_share(&SecretShare::from_parts(0, secret)).is_err());
+
+        let proof = prove_dealing(&mut csprng, &commitment, &secret, b"dkg 1");
+        assert!(verify_dealing(&commitment, b"dkg 1", &proof).is_ok());
+        assert!(verify_dealing(&commitment, b"dkg 2", &proof).is_err());
+        // A dealer proof is not a share proof.
+        assert!(commitment.verify_share_proof(1, b"dkg 1", &proof).is_err());
+    }
+
+    #[test]
+    fn distributed_key_generation() {
+        let mut csprng = OsRng::new().unwrap();
+        let dealings: Vec<(Scalar, VssCommitment, Vec<SecretShare>)> = (0..3).map(|_| {
+            let secret = Scalar::random(&mut csprng);
+            let (commitment, shares) = deal(&mut csprng, &secret, 2, 3);
+            (secret, commitment, shares)
+        }).collect();
+
+        let commitments: Vec<VssCommitment> = dealings.iter().map(|d| d.1.clone()).collect();
+        let group = VssCommitment::sum(&commitments).unwrap();
+        let group_secret = &(&dealings[0].0 + &dealings[1].0) + &dealings[2].0;
+        assert_eq!(group.public_key().compress(),
+                   (&dalek_constants::DECAF_ED25519_BASEPOINT * &group_secret).compress());
+
+        for i in 0..3 {
+            let received: Vec<SecretShare> = dealings.iter()
+                .map(|d| SecretShare::from_parts(d.2[i].index(), *d.2[i].value()))
+                .collect();
+            let share = SecretShare::sum(&received).unwrap();
+            assert!(group.verify_share(&share).is_ok());
+        }
+
+        let (other, _) = deal(&mut csprng, &group_secret, 3, 3);
+        assert!(VssCommitment::sum(&[group, other]).is_err());
+        assert!(VssCommitment::sum(&[]).is_err());
+    }
+
+    #[test]
+    fn empty_commitment() {
+        extern crate serde_json;
+
+        let mut csprng = OsRng::new().unwrap();
+        let (commitment, _) = deal(&mut csprng, &Scalar::random(&mut csprng), 2, 3);
+        assert!(VssCommitment::new(commitment.points().to_vec()).is_ok());
+        assert!(VssCommitment::new(Vec::new()).is_err());
+
+        let json = serde_json::to_string(&commitment).unwrap();
+        let parsed: VssCommitment = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.public_key().compress(), commitment.public_key().compress());
+        assert!(serde_json::from_str::<VssCommitment>("[]").is_err());
+    }
+}
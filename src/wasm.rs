@@ -0,0 +1,78 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to zkp,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Helpers for the JavaScript functions exported by
+//! `create_nipk_wasm!`, with the `wasm` feature.
+//!
+//! The functions are exported with `wasm-bindgen`, which also writes
+//! their TypeScript definitions, with byte strings as `Uint8Array`s
+//! and the doc comments of the Rust functions.  The crate invoking
+//! `create_nipk_wasm!` must depend on `wasm-bindgen` itself.  Since
+//! `OsRng` is unavailable in the browser, provers take a seed of
+//! `SEED_SIZE` bytes, which the page should draw from
+//! `crypto.getRandomValues()`.
+
+use rand::{ChaChaRng, SeedableRng};
+
+/// The length of the seed of the provers exported by
+/// `create_nipk_wasm!`.
+pub const SEED_SIZE: usize = 32;
+
+/// Seed a ChaCha RNG from `SEED_SIZE` bytes, as little-endian words.
+#[doc(hidden)]
+pub fn __seeded_rng(seed: &[u8]) -> Result<ChaChaRng, ()> {
+    if seed.len() != SEED_SIZE {
+        return Err(());
+    }
+    let mut words = [0u32; SEED_SIZE / 4];
+    for (word, bytes) in words.iter_mut().zip(seed.chunks(4)) {
+        *word = bytes.iter().rev().fold(0, |word, &byte| (word << 8) | byte as u32);
+    }
+    Ok(ChaChaRng::from_seed(&words[..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants as dalek_constants;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::Rng;
+
+    create_nipk!{dlog, (x), (A, G) : A = (G * x) }
+    create_nipk_wasm!{dlog, zkp_test_dlog_create, zkp_test_dlog_verify}
+
+    #[test]
+    fn seeded_rng() {
+        let seed = [7u8; SEED_SIZE];
+        assert_eq!(__seeded_rng(&seed).unwrap().next_u64(), __seeded_rng(&seed).unwrap().next_u64());
+        assert!(__seeded_rng(&seed).unwrap().next_u64() != __seeded_rng(&[8u8; SEED_SIZE]).unwrap().next_u64());
+        assert!(__seeded_rng(&seed[1..]).is_err());
+    }
+
+    #[test]
+    fn create_and_verify() {
+        let mut csprng = __seeded_rng(&[1u8; SEED_SIZE]).unwrap();
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT;
+        let x = Scalar::random(&mut csprng);
+        let A = G * &x;
+
+        let mut publics = Vec::new();
+        publics.extend_from_slice(A.compress().as_bytes());
+        publics.extend_from_slice(G.compress().as_bytes());
+
+        let mut proof = zkp_test_dlog_create(&publics, x.as_bytes(), &[2u8; SEED_SIZE]).unwrap();
+        assert_eq!(proof.len(), 64);
+        assert_eq!(zkp_test_dlog_verify(&proof, &publics).ok(), Some(true));
+
+        proof[40] ^= 1;
+        assert_eq!(zkp_test_dlog_verify(&proof, &publics).ok(), Some(false));
+    }
+}